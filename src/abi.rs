@@ -0,0 +1,238 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::solidity::{Function, Param, SourceUnit};
+
+/// How deep to look for a nested `abi` key in an artifact.
+const MAX_ARTIFACT_DEPTH: usize = 4;
+
+/// User-defined types known across a set of files, used to canonicalize signatures.
+#[derive(Default)]
+pub struct TypeTable {
+    structs: HashMap<String, Vec<String>>,
+    enums: HashSet<String>,
+    value_types: HashMap<String, String>,
+}
+
+impl TypeTable {
+    pub fn add(&mut self, unit: &SourceUnit) {
+        for definition in &unit.structs {
+            self.structs.insert(
+                definition.name.clone(),
+                definition.fields.iter().map(|field| field.ty.clone()).collect(),
+            );
+        }
+        self.enums.extend(unit.enums.iter().map(|definition| definition.name.clone()));
+        self.value_types.extend(unit.value_types.iter().cloned());
+    }
+
+    /// Returns the canonical ABI type for a type as written in source, e.g. `uint` becomes
+    /// `uint256`, structs become tuples, and contracts become `address`.
+    pub fn canonical_type(&self, ty: &str) -> String {
+        self.canonicalize(ty, 0)
+    }
+
+    fn canonicalize(&self, ty: &str, depth: usize) -> String {
+        let (base, arrays) = match ty.find('[') {
+            Some(bracket) => ty.split_at(bracket),
+            None => (ty, ""),
+        };
+        let base = base.trim();
+        // Qualified names such as `IPool.Config` resolve by their final segment.
+        let base = base.rsplit('.').next().unwrap_or(base);
+
+        let canonical = match base {
+            "uint" => "uint256".to_string(),
+            "int" => "int256".to_string(),
+            "byte" => "bytes1".to_string(),
+            "ufixed" => "ufixed128x18".to_string(),
+            "fixed" => "fixed128x18".to_string(),
+            _ if is_elementary(base) => base.to_string(),
+            _ if base.starts_with("function") => "function".to_string(),
+            _ if self.enums.contains(base) => "uint8".to_string(),
+            _ if depth < 16 && self.structs.contains_key(base) => format!(
+                "({})",
+                self.structs[base]
+                    .iter()
+                    .map(|field| self.canonicalize(field, depth + 1))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            _ => match self.value_types.get(base) {
+                Some(underlying) if depth < 16 => self.canonicalize(underlying, depth + 1),
+                // Anything else is a contract or interface type.
+                _ => "address".to_string(),
+            },
+        };
+
+        format!("{}{}", canonical, arrays.replace(' ', ""))
+    }
+
+    /// Returns the canonical signature of a function, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self, function: &Function) -> String {
+        format!("{}({})", function.name, self.type_list(&function.params))
+    }
+
+    fn type_list(&self, params: &[Param]) -> String {
+        params
+            .iter()
+            .map(|param| self.canonical_type(&param.ty))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn is_elementary(ty: &str) -> bool {
+    let sized = |prefix: &str| {
+        ty.strip_prefix(prefix)
+            .is_some_and(|bits| !bits.is_empty() && bits.chars().all(|c| c.is_ascii_digit()))
+    };
+    matches!(ty, "address" | "bool" | "string" | "bytes")
+        || sized("uint")
+        || sized("int")
+        || sized("bytes")
+        || ty.starts_with("fixed")
+        || ty.starts_with("ufixed")
+}
+
+/// Computes the 4-byte selector of a canonical signature.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let mut hasher = Keccak::v256();
+    let mut hash = [0u8; 32];
+    hasher.update(signature.as_bytes());
+    hasher.finalize(&mut hash);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Formats a selector as `0x`-prefixed hex.
+pub fn format_selector(selector: [u8; 4]) -> String {
+    format!(
+        "0x{:02x}{:02x}{:02x}{:02x}",
+        selector[0], selector[1], selector[2], selector[3]
+    )
+}
+
+/// A function exposed by an ABI JSON file.
+#[derive(Serialize)]
+pub struct AbiFunction {
+    /// The canonical signature, e.g. `transfer(address,uint256)`.
+    pub signature: String,
+    pub file: PathBuf,
+}
+
+/// Reads the functions of ABI JSON files matching the `--abi` globs, e.g. the published
+/// ABIs under `deployments/`.
+#[derive(Default)]
+pub struct AbiExports {
+    pub functions: Vec<AbiFunction>,
+    signatures: HashSet<String>,
+}
+
+impl AbiExports {
+    pub fn load(globs: &[String]) -> Result<Self> {
+        let mut exports = AbiExports::default();
+        for pattern in globs {
+            let paths = glob::glob(pattern).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid --abi glob {:?}: {}", pattern, e),
+                )
+            })?;
+            let mut matched = false;
+            for path in paths {
+                let path = path.map_err(|e| Error::new(e.error().kind(), e.to_string()))?;
+                if path.is_file() {
+                    matched = true;
+                    exports.functions.extend(read_abi_functions(&path)?);
+                }
+            }
+            if !matched {
+                eprintln!("Warning: --abi {:?} matched no files.", pattern);
+            }
+        }
+        exports.functions.sort_by(|a, b| a.file.cmp(&b.file).then(a.signature.cmp(&b.signature)));
+        exports.signatures = exports.functions.iter().map(|function| function.signature.clone()).collect();
+        Ok(exports)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Whether an ABI exposes a function with this canonical signature.
+    pub fn exports(&self, signature: &str) -> bool {
+        self.signatures.contains(signature)
+    }
+}
+
+/// Reads the functions of an ABI JSON file: either a raw ABI array or an artifact, as
+/// written by Foundry, Hardhat or hardhat-deploy, that nests it under an `abi` key.
+pub fn read_abi_functions(path: &Path) -> Result<Vec<AbiFunction>> {
+    let content = fs::read_to_string(path)?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Failed to parse ABI file {:?}: {}", path, e),
+        )
+    })?;
+    let abi = find_abi(&value, 0).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{:?} is neither an ABI array nor an artifact with an `abi` key.", path),
+        )
+    })?;
+
+    Ok(abi
+        .iter()
+        // Entries without a `type` are functions, as in the original ABI specification.
+        .filter(|entry| entry.get("type").and_then(Value::as_str).unwrap_or("function") == "function")
+        .filter_map(|entry| {
+            let name = entry.get("name")?.as_str()?;
+            let inputs = entry.get("inputs").and_then(Value::as_array).map_or(&[][..], Vec::as_slice);
+            Some(AbiFunction {
+                signature: format!("{}({})", name, abi_type_list(inputs)),
+                file: path.to_path_buf(),
+            })
+        })
+        .collect())
+}
+
+fn find_abi(value: &Value, depth: usize) -> Option<&Vec<Value>> {
+    match value {
+        Value::Array(entries) => Some(entries),
+        Value::Object(object) => match object.get("abi") {
+            Some(Value::Array(entries)) => Some(entries),
+            _ if depth < MAX_ARTIFACT_DEPTH => object.values().find_map(|nested| {
+                nested.is_object().then(|| find_abi(nested, depth + 1)).flatten()
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Formats ABI parameters as canonical types, expanding tuples from their `components`.
+fn abi_type_list(params: &[Value]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let ty = param.get("type").and_then(Value::as_str).unwrap_or_default();
+            match ty.strip_prefix("tuple") {
+                Some(arrays) => {
+                    let components = param
+                        .get("components")
+                        .and_then(Value::as_array)
+                        .map_or(&[][..], Vec::as_slice);
+                    format!("({}){}", abi_type_list(components), arrays)
+                }
+                None => ty.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
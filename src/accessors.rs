@@ -0,0 +1,80 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::solidity::SourceUnit;
+use crate::source::SourceFile;
+
+/// A state variable with both an explicit getter and a setter in its contract.
+pub struct Accessors {
+    pub variable: String,
+    pub contract: String,
+    pub line: usize,
+    /// A function without parameters whose body only returns the variable.
+    pub getter: String,
+    /// A function named `set` and the variable, e.g. `setFee` for `fee` or `_fee`, that
+    /// assigns it.
+    pub setter: String,
+}
+
+fn getter_body_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\{\s*return\s+([A-Za-z_$][\w$]*)\s*;\s*\}$").unwrap())
+}
+
+/// Finds the state variables of `unit` that have both a getter and a setter, pairing
+/// the first of each in the variable's contract.
+pub fn find(source: &SourceFile, unit: &SourceUnit) -> Vec<Accessors> {
+    let mut found = Vec::new();
+    for variable in &unit.state_variables {
+        let functions = unit
+            .functions
+            .iter()
+            .filter(|function| function.contract.as_deref() == Some(variable.contract.as_str()));
+        let mut getter = None;
+        let mut setter = None;
+        for function in functions {
+            let Some(body) = function.body else {
+                continue;
+            };
+            let code = source.code_only(body);
+            if getter.is_none() && function.params.is_empty() {
+                let returned = getter_body_pattern().captures(code.trim()).map(|captures| captures[1].to_string());
+                if returned.as_deref() == Some(variable.name.as_str()) {
+                    getter = Some(function.name.clone());
+                }
+            }
+            if setter.is_none()
+                && !function.params.is_empty()
+                && names_setter(&function.name, &variable.name)
+                && assigns(&code, &variable.name)
+            {
+                setter = Some(function.name.clone());
+            }
+        }
+        if let (Some(getter), Some(setter)) = (getter, setter) {
+            found.push(Accessors {
+                variable: variable.name.clone(),
+                contract: variable.contract.clone(),
+                line: variable.line,
+                getter,
+                setter,
+            });
+        }
+    }
+    found
+}
+
+/// Whether `function` is `set` followed by the name of `variable`, ignoring case and
+/// underscores, e.g. `setFee` or `set_fee` for `_fee`.
+fn names_setter(function: &str, variable: &str) -> bool {
+    let normalize = |name: &str| name.replace('_', "").to_ascii_lowercase();
+    normalize(function)
+        .strip_prefix("set")
+        .is_some_and(|rest| !rest.is_empty() && rest == normalize(variable))
+}
+
+/// Whether `code` assigns `name` with `=` or a compound assignment such as `+=`.
+fn assigns(code: &str, name: &str) -> bool {
+    let pattern = format!(r"(?:^|[^\w$.]){}\s*(?:[-+*/%|&^]|<<|>>)?=(?:[^=>]|$)", regex::escape(name));
+    Regex::new(&pattern).unwrap().is_match(code)
+}
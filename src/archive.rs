@@ -0,0 +1,158 @@
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Archive formats that can be analyzed in place of a directory.
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// Archive extensions that are recognized but can't be extracted.
+const UNSUPPORTED: [&str; 9] = [
+    ".tar.bz2", ".tbz2", ".tar.xz", ".txz", ".tar.zst", ".7z", ".rar", ".bz2", ".xz",
+];
+
+/// Sources extracted from an archive, or downloaded, into a temporary directory, which is
+/// removed when this is dropped unless it was asked to be kept.
+pub struct Extracted {
+    /// The archive or contract address the sources came from.
+    pub origin: PathBuf,
+    /// The directory the sources were written to.
+    dir: PathBuf,
+    /// The extracted project: the directory, or the single directory at the top of the
+    /// archive, e.g. `project-1.0/`.
+    pub root: PathBuf,
+    /// Whether `dir` was given by the user, so it is kept and read like any directory.
+    pub kept: bool,
+    /// The temporary directory otherwise, removed when dropped.
+    _temporary: Option<TempDir>,
+}
+
+impl Extracted {
+    /// Creates a new, empty temporary directory for the sources of `origin`. It's created
+    /// under a random name that no other directory has, so nobody else can have prepared it.
+    pub fn temporary(origin: &Path) -> Result<Self> {
+        let temporary = tempfile::Builder::new().prefix("wand-").tempdir()?;
+        let dir = temporary.path().to_path_buf();
+        Ok(Extracted {
+            origin: origin.to_path_buf(),
+            root: dir.clone(),
+            dir,
+            kept: false,
+            _temporary: Some(temporary),
+        })
+    }
+
+    /// Uses `dir`, created if needed, for the sources of `origin`, keeping it afterwards.
+    pub fn kept(origin: &Path, dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Extracted {
+            origin: origin.to_path_buf(),
+            dir: dir.to_path_buf(),
+            root: dir.to_path_buf(),
+            kept: true,
+            _temporary: None,
+        })
+    }
+
+    /// Returns the path of an extracted file as it is inside the archive, e.g.
+    /// `audit.zip/src/Vault.sol`, which stays the same between runs. Files in a kept
+    /// directory keep their path.
+    pub fn display_path(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.dir) {
+            Ok(inner) if !self.kept => self.origin.join(inner),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+/// Whether `path` names an archive, supported or not, rather than a Solidity file.
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && (kind(path).is_some() || unsupported(path).is_some())
+}
+
+fn kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else {
+        None
+    }
+}
+
+fn unsupported(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    UNSUPPORTED.into_iter().find(|extension| name.ends_with(extension))
+}
+
+/// Extracts `archive` into a new temporary directory.
+pub fn extract(archive: &Path) -> Result<Extracted> {
+    let Some(kind) = kind(archive) else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unsupported archive type {:?} for {:?}; expected .zip, .tar, .tar.gz or .tgz.",
+                unsupported(archive).unwrap_or_default(),
+                archive
+            ),
+        ));
+    };
+
+    // Created before extracting, so that a failed extraction is cleaned up too.
+    let mut extracted = Extracted::temporary(archive)?;
+
+    let file = File::open(archive)?;
+    let unpacked = match kind {
+        ArchiveKind::Zip => zip::ZipArchive::new(file)
+            .and_then(|mut zip| zip.extract(&extracted.dir))
+            .map_err(Error::from),
+        ArchiveKind::Tar => tar::Archive::new(file).unpack(&extracted.dir),
+        ArchiveKind::TarGz => tar::Archive::new(GzDecoder::new(file)).unpack(&extracted.dir),
+    };
+    unpacked.map_err(|e| Error::new(e.kind(), format!("Failed to extract {:?}: {}", archive, e)))?;
+
+    let entries: Vec<PathBuf> = fs::read_dir(&extracted.dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<_>>()?;
+    if let [single] = entries.as_slice() {
+        if single.is_dir() {
+            extracted.root = single.clone();
+        }
+    }
+
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_directories_are_new_and_removed_when_dropped() {
+        let first = Extracted::temporary(Path::new("a.zip")).unwrap();
+        let second = Extracted::temporary(Path::new("a.zip")).unwrap();
+        assert_ne!(first.dir, second.dir);
+        assert!(first.dir.is_dir());
+        assert_eq!(fs::read_dir(&first.dir).unwrap().count(), 0);
+
+        let dir = first.dir.clone();
+        drop(first);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn kept_directories_stay() {
+        let parent = tempfile::tempdir().unwrap();
+        let dir = parent.path().join("sources");
+        drop(Extracted::kept(Path::new("a.zip"), &dir).unwrap());
+        assert!(dir.is_dir());
+    }
+}
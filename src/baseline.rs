@@ -0,0 +1,289 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::findings::{Finding, FindingKind};
+use crate::output;
+use crate::solidity;
+use crate::source::{canonical, portable_path, SourceFile};
+
+/// Version 2 added the creation date of each entry. Version 1 baselines are still read,
+/// with their entries' dates unknown.
+const VERSION: u32 = 2;
+
+/// Entries older than this many days are reported as stale.
+pub const STALE_AFTER_DAYS: i64 = 90;
+
+/// A snapshot of known findings, so that only new ones are reported.
+#[derive(Serialize, Deserialize)]
+pub struct Baseline {
+    version: u32,
+    pub entries: Vec<Entry>,
+    /// The root of the project, which the files of the entries are relative to, so that
+    /// the baseline applies from any directory.
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+/// A known finding. Entries are matched by fingerprint, which doesn't depend on the file
+/// or line, so moving a function keeps it baselined. One whose code changed too is matched
+/// by its contract, signature and kind instead, in its file or, once it moved out of it,
+/// in any other, see `moved`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Entry {
+    pub fingerprint: String,
+    pub file: String,
+    pub contract: Option<String>,
+    pub signature: String,
+    pub kind: FindingKind,
+    /// The day the entry was recorded, as `YYYY-MM-DD`. Empty for entries read from a
+    /// version 1 baseline.
+    #[serde(default)]
+    pub created: String,
+    /// The entry's file no longer declares its function, e.g. since a refactor moved it
+    /// to another file. Noted when the baseline is read, never recorded.
+    #[serde(skip)]
+    pub moved: bool,
+}
+
+impl Entry {
+    /// Records `finding` of the project at `root`.
+    pub fn new(finding: &Finding, root: &Path) -> Self {
+        Entry {
+            fingerprint: finding.fingerprint(),
+            file: root_relative(&finding.file, root),
+            contract: finding.contract.clone(),
+            signature: finding.signature.clone(),
+            kind: finding.kind,
+            created: format_date(today()),
+            moved: false,
+        }
+    }
+
+    /// Whether the entry records the finding that `found` was made from: one with the
+    /// same fingerprint, or one of the same contract, signature and kind in the entry's
+    /// file, or in any file once the entry's function moved out of its own.
+    fn records(&self, found: &Entry) -> bool {
+        self.fingerprint == found.fingerprint
+            || ((self.moved || self.file == found.file)
+                && self.kind == found.kind
+                && self.contract == found.contract
+                && self.signature == found.signature)
+    }
+
+    /// Number of days since the entry was recorded, if its date is known.
+    pub fn age_days(&self) -> Option<i64> {
+        parse_date(&self.created).map(|created| today() - created)
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.age_days().is_some_and(|age| age > STALE_AFTER_DAYS)
+    }
+}
+
+impl Baseline {
+    pub fn new(findings: &[Finding], root: &Path) -> Self {
+        let mut seen = HashSet::new();
+        Baseline {
+            version: VERSION,
+            entries: findings
+                .iter()
+                .map(|finding| Entry::new(finding, root))
+                .filter(|entry| seen.insert(entry.fingerprint.clone()))
+                .collect(),
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Records `findings` in a new baseline at `path`. Entries already in the baseline
+    /// there keep their creation date, see `read` for `root`.
+    pub fn record(path: &Path, findings: &[Finding], root: &Path) -> Result<Self> {
+        let mut baseline = Baseline::new(findings, root);
+        if let Ok(previous) = Baseline::read(path, root) {
+            for entry in &mut baseline.entries {
+                if let Some(known) = previous.lookup(entry) {
+                    entry.created = known.created.clone();
+                }
+            }
+        }
+        baseline.write(path)?;
+        Ok(baseline)
+    }
+
+    /// Reads the baseline at `path` of the project at `root`. The entries of a baseline
+    /// recorded before their files were relative to the root, from another directory than
+    /// the root, are made relative to it.
+    pub fn read(path: &Path, root: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::new(e.kind(), format!("Failed to read baseline {:?}: {}", path, e))
+        })?;
+        let mut baseline: Baseline = serde_json::from_str(&content).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid baseline {:?}: {}", path, e),
+            )
+        })?;
+        if !(1..=VERSION).contains(&baseline.version) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Baseline {:?} has version {}, expected {}.",
+                    path, baseline.version, VERSION
+                ),
+            ));
+        }
+        baseline.root = root.to_path_buf();
+        for entry in &mut baseline.entries {
+            let file = Path::new(&entry.file);
+            if !root.join(file).exists() && file.exists() {
+                entry.file = root_relative(file, root);
+            }
+        }
+        baseline.note_moves();
+        Ok(baseline)
+    }
+
+    /// Notes the entries whose file no longer declares their function, by contract and
+    /// signature as written. Entries of other symbols, e.g. `--pattern` ones, stay tied to
+    /// their file.
+    fn note_moves(&mut self) {
+        let mut declared: HashMap<String, HashSet<(Option<String>, String)>> = HashMap::new();
+        for entry in &mut self.entries {
+            if !entry.signature.contains('(') {
+                continue;
+            }
+            let functions = declared.entry(entry.file.clone()).or_insert_with(|| {
+                let Ok(source) = SourceFile::read(&self.root.join(&entry.file)) else {
+                    return HashSet::new();
+                };
+                solidity::parse(&source)
+                    .functions
+                    .iter()
+                    .map(|function| (function.contract.clone(), function.written_signature()))
+                    .collect()
+            });
+            entry.moved = !functions.contains(&(entry.contract.clone(), entry.signature.clone()));
+        }
+    }
+
+    /// Writes the baseline in the current version, keeping the entries' dates.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let current = Baseline {
+            version: VERSION,
+            entries: self.entries.clone(),
+            root: PathBuf::new(),
+        };
+        fs::write(path, output::to_pretty_json(&current)? + "\n")
+    }
+
+    /// Returns the entry recording `finding`, if there is one.
+    pub fn find(&self, finding: &Finding) -> Option<&Entry> {
+        self.lookup(&Entry::new(finding, &self.root))
+    }
+
+    /// Returns the entry recording the finding `found` was made from, preferring one with
+    /// its fingerprint over one that moved.
+    fn lookup(&self, found: &Entry) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.fingerprint == found.fingerprint)
+            .or_else(|| self.entries.iter().find(|entry| entry.records(found)))
+    }
+
+    pub fn contains(&self, finding: &Finding) -> bool {
+        self.find(finding).is_some()
+    }
+
+    /// Removes the entries that don't record any of `findings`, returning them. Entries
+    /// whose function moved to another file or changed are kept, and recorded as found.
+    pub fn trim(&mut self, findings: &[Finding]) -> Vec<Entry> {
+        let current: Vec<Entry> = findings.iter().map(|finding| Entry::new(finding, &self.root)).collect();
+        let mut kept: Vec<Entry> = Vec::new();
+        let mut removed = Vec::new();
+        for mut entry in self.entries.drain(..) {
+            let found = current
+                .iter()
+                .find(|found| found.fingerprint == entry.fingerprint)
+                .or_else(|| current.iter().find(|found| entry.records(found)));
+            let Some(found) = found else {
+                removed.push(entry);
+                continue;
+            };
+            entry.file = found.file.clone();
+            entry.fingerprint = found.fingerprint.clone();
+            entry.moved = false;
+            // A moved entry may now be recorded twice.
+            if !kept.iter().any(|known| known.fingerprint == entry.fingerprint) {
+                kept.push(entry);
+            }
+        }
+        self.entries = kept;
+        removed
+    }
+}
+
+/// The path of `file` relative to `root`, with `/` separators, or `file` as given when
+/// it isn't under `root`.
+fn root_relative(file: &Path, root: &Path) -> String {
+    match canonical(file).strip_prefix(canonical(root)) {
+        Ok(relative) => portable_path(relative),
+        Err(_) => portable_path(file),
+    }
+}
+
+/// Hashes what identifies a finding wherever it is, leaving out its file and line: its
+/// contract, signature, kind, and `context`, the code of its function if it has one.
+pub fn fingerprint(contract: Option<&str>, signature: &str, kind: FindingKind, context: &str) -> String {
+    let mut hasher = Keccak::v256();
+    let mut hash = [0u8; 32];
+    for part in [contract.unwrap_or_default(), signature, kind.as_str(), context] {
+        hasher.update(part.as_bytes());
+        hasher.update(&[0]);
+    }
+    hasher.finalize(&mut hash);
+    hash[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Days since the Unix epoch, in UTC.
+fn today() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    (seconds / 86_400) as i64
+}
+
+/// Formats days since the Unix epoch as a `YYYY-MM-DD` date.
+pub fn format_date(days: i64) -> String {
+    // Howard Hinnant's civil_from_days.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Parses a `YYYY-MM-DD` date as days since the Unix epoch.
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
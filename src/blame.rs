@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::baseline::format_date;
+
+/// Who last changed a line, according to `git blame`.
+#[derive(Serialize, Clone, Debug)]
+pub struct Blame {
+    pub commit: String,
+    pub author: String,
+    /// The author date, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    /// The author date in seconds since the Unix epoch, for sorting.
+    #[serde(skip)]
+    pub time: i64,
+}
+
+impl Blame {
+    /// Whether the line has changes that aren't committed yet, for which git names the
+    /// author `Not Committed Yet`.
+    pub fn is_uncommitted(&self) -> bool {
+        self.commit.chars().all(|c| c == '0')
+    }
+}
+
+/// Blames the given lines of `file` with a single `git blame` run. Returns `None` when
+/// git isn't installed, the file isn't tracked or blame fails for another reason, so that
+/// callers can go on without blame.
+pub fn blame_lines(file: &Path, lines: &[usize]) -> Option<HashMap<usize, Blame>> {
+    if lines.is_empty() {
+        return Some(HashMap::new());
+    }
+    // Running git in the file's directory finds the repository the file belongs to.
+    let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(["blame", "--line-porcelain"]);
+    for line in lines {
+        command.arg("-L").arg(format!("{},{}", line, line));
+    }
+    let output = command.arg("--").arg(file.file_name()?).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(parse_porcelain(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses `git blame --line-porcelain` output, in which each line has a header of the
+/// commit and line numbers, followed by `key value` fields and the line itself after a
+/// tab.
+fn parse_porcelain(output: &str) -> HashMap<usize, Blame> {
+    let mut blamed = HashMap::new();
+    let mut current: Option<(usize, Blame)> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            if let Some((number, blame)) = current.take() {
+                blamed.insert(number, blame);
+            }
+            continue;
+        }
+        match &mut current {
+            None => {
+                let mut fields = line.split(' ');
+                let commit = fields.next().unwrap_or_default();
+                let Some(number) = fields.nth(1).and_then(|number| number.parse().ok()) else {
+                    continue;
+                };
+                current = Some((
+                    number,
+                    Blame {
+                        commit: commit.chars().take(12).collect(),
+                        author: String::new(),
+                        date: String::new(),
+                        time: 0,
+                    },
+                ));
+            }
+            Some((_, blame)) => {
+                if let Some(author) = line.strip_prefix("author ") {
+                    blame.author = author.to_string();
+                } else if let Some(time) = line.strip_prefix("author-time ") {
+                    blame.time = time.parse().unwrap_or_default();
+                    blame.date = format_date(blame.time.div_euclid(86_400));
+                }
+            }
+        }
+    }
+    blamed
+}
@@ -0,0 +1,121 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::source::{Context, SourceFile};
+
+/// Words that keep their meaning when identifiers are normalized away.
+const KEYWORDS: &[&str] = &[
+    "abi", "address", "assembly", "assert", "block", "bool", "break", "bytes", "calldata",
+    "catch", "continue", "delete", "do", "else", "emit", "false", "for", "if", "keccak256",
+    "mapping", "memory", "msg", "new", "payable", "require", "return", "returns", "revert",
+    "storage", "string", "this", "true", "try", "tx", "type", "unchecked", "while",
+];
+
+/// A function body reduced to its code tokens, free of comments and formatting.
+pub struct NormalizedBody {
+    pub tokens: Vec<String>,
+    pub hash: u64,
+}
+
+impl NormalizedBody {
+    /// Normalizes the body spanning `body` (including its braces). When `identifiers`
+    /// is set, identifiers are replaced by placeholders numbered in order of first use,
+    /// so bodies that only differ by naming normalize identically.
+    pub fn new(source: &SourceFile, body: (usize, usize), identifiers: bool) -> Self {
+        let mut tokens = tokenize(source, body);
+
+        if identifiers {
+            let mut placeholders: HashMap<String, usize> = HashMap::new();
+            for token in &mut tokens {
+                if is_identifier(token) && !is_keyword(token) {
+                    let next = placeholders.len();
+                    let index = *placeholders.entry(token.clone()).or_insert(next);
+                    *token = format!("${}", index);
+                }
+            }
+        }
+
+        let mut hasher = DefaultHasher::new();
+        tokens.hash(&mut hasher);
+        NormalizedBody {
+            hash: hasher.finish(),
+            tokens,
+        }
+    }
+
+    /// Jaccard similarity between the token shingles of two bodies.
+    pub fn similarity(&self, other: &NormalizedBody) -> f64 {
+        let ours = self.shingles();
+        let theirs = other.shingles();
+        if ours.is_empty() && theirs.is_empty() {
+            return 1.0;
+        }
+        let shared = ours.intersection(&theirs).count();
+        shared as f64 / (ours.len() + theirs.len() - shared) as f64
+    }
+
+    fn shingles(&self) -> HashSet<&[String]> {
+        const SHINGLE: usize = 3;
+        if self.tokens.len() < SHINGLE {
+            return std::iter::once(self.tokens.as_slice()).collect();
+        }
+        self.tokens.windows(SHINGLE).collect()
+    }
+}
+
+/// Splits the code within `span` into identifier, number, string, and symbol tokens.
+fn tokenize(source: &SourceFile, span: (usize, usize)) -> Vec<String> {
+    let content = &source.content;
+    let mut tokens = Vec::new();
+    let mut pos = span.0;
+
+    while pos < span.1 {
+        let c = content[pos..].chars().next().unwrap();
+        match source.context_at(pos) {
+            Context::Comment => pos = source.span_end(pos),
+            Context::String => {
+                let end = source.span_end(pos).min(span.1);
+                tokens.push(content[pos..end].to_string());
+                pos = end;
+            }
+            Context::Code if c.is_whitespace() => pos += c.len_utf8(),
+            Context::Code if is_word_char(c) => {
+                let end = content[pos..span.1]
+                    .find(|c: char| !is_word_char(c))
+                    .map_or(span.1, |len| pos + len);
+                tokens.push(content[pos..end].to_string());
+                pos = end;
+            }
+            Context::Code => {
+                tokens.push(c.to_string());
+                pos += c.len_utf8();
+            }
+        }
+    }
+
+    tokens
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn is_identifier(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+}
+
+fn is_keyword(token: &str) -> bool {
+    if KEYWORDS.contains(&token) {
+        return true;
+    }
+    // Elementary types such as `uint256`, `int8`, and `bytes32`.
+    ["uint", "int", "bytes"].iter().any(|prefix| {
+        token
+            .strip_prefix(prefix)
+            .is_some_and(|bits| bits.chars().all(|c| c.is_ascii_digit()))
+    })
+}
@@ -0,0 +1,149 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+
+use crate::abi;
+use crate::source::collect_files;
+
+/// How deep to look for `transactions` arrays, which multi-chain broadcasts nest under
+/// `deployments`.
+const MAX_BROADCAST_DEPTH: usize = 4;
+
+/// The selectors called by the transactions of Foundry broadcasts, i.e. the
+/// `broadcast/**/*.json` files written by `forge script --broadcast`.
+#[derive(Default)]
+pub struct Broadcasts {
+    /// The first broadcast file calling each selector.
+    selectors: HashMap<[u8; 4], PathBuf>,
+}
+
+impl Broadcasts {
+    /// Reads every broadcast run under `dir`, i.e. its `run-latest.json` and
+    /// `run-<timestamp>.json` files, leaving out the other JSON files, e.g. the `cache`
+    /// that `forge` may write there, and the `dry-run` directories of runs that sent
+    /// nothing. A file that can't be read or parsed is skipped with a warning, so that one
+    /// interrupted run doesn't fail the analysis.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let paths = collect_files(dir, &["json"])?.into_iter().filter(|path| {
+            is_run_file(path) && !path.strip_prefix(dir).unwrap_or(path).iter().any(|name| name == "dry-run")
+        });
+
+        let mut broadcasts = Broadcasts::default();
+        for path in paths {
+            let value = match fs::read_to_string(&path)
+                .and_then(|content| serde_json::from_str::<Value>(&content).map_err(Error::from))
+            {
+                Ok(value) => value,
+                Err(e) => {
+                    eprintln!("Warning: skipping broadcast file {:?}, which can't be read as JSON: {}.", path, e);
+                    continue;
+                }
+            };
+            let mut selectors = Vec::new();
+            collect_selectors(&value, 0, &mut selectors);
+            for selector in selectors {
+                broadcasts.selectors.entry(selector).or_insert_with(|| path.clone());
+            }
+        }
+        Ok(broadcasts)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty()
+    }
+
+    /// Returns every selector called, formatted as `0x`-prefixed hex, with the first
+    /// broadcast calling it.
+    pub fn calls(&self) -> impl Iterator<Item = (String, &Path)> {
+        self.selectors
+            .iter()
+            .map(|(selector, path)| (abi::format_selector(*selector), path.as_path()))
+    }
+
+    /// Returns the broadcast calling the function with this canonical signature, if any.
+    pub fn caller(&self, signature: &str) -> Option<&Path> {
+        self.selectors.get(&abi::selector(signature)).map(PathBuf::as_path)
+    }
+}
+
+/// Whether `path` is a broadcast run of `forge script`: `run-latest.json`, or
+/// `run-<timestamp>.json` for the earlier runs.
+fn is_run_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    name.strip_prefix("run-")
+        .and_then(|name| name.strip_suffix(".json"))
+        .is_some_and(|run| run == "latest" || (!run.is_empty() && run.bytes().all(|byte| byte.is_ascii_digit())))
+}
+
+/// Collects the selectors of the calls in the `transactions` arrays of a broadcast. The
+/// input of a transaction that deploys a contract is creation code, not a call.
+fn collect_selectors(value: &Value, depth: usize, selectors: &mut Vec<[u8; 4]>) {
+    let Value::Object(object) = value else {
+        if let Value::Array(values) = value {
+            if depth < MAX_BROADCAST_DEPTH {
+                values.iter().for_each(|nested| collect_selectors(nested, depth + 1, selectors));
+            }
+        }
+        return;
+    };
+
+    if let Some(Value::Array(transactions)) = object.get("transactions") {
+        for transaction in transactions {
+            let kind = transaction.get("transactionType").and_then(Value::as_str);
+            if matches!(kind, Some("CREATE" | "CREATE2")) {
+                continue;
+            }
+            let Some(fields) = transaction.get("transaction") else {
+                continue;
+            };
+            let input = fields.get("input").or_else(|| fields.get("data")).and_then(Value::as_str);
+            if let Some(selector) = input.and_then(parse_selector) {
+                selectors.push(selector);
+            }
+        }
+    }
+    if depth < MAX_BROADCAST_DEPTH {
+        for (key, nested) in object {
+            if key != "transactions" {
+                collect_selectors(nested, depth + 1, selectors);
+            }
+        }
+    }
+}
+
+/// Parses the selector from the start of `0x`-prefixed calldata.
+fn parse_selector(input: &str) -> Option<[u8; 4]> {
+    let hex = input.strip_prefix("0x")?.get(..8)?;
+    let mut selector = [0u8; 4];
+    for (index, byte) in selector.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(selector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_run_files_are_broadcasts() {
+        assert!(is_run_file(Path::new("broadcast/Deploy.s.sol/1/run-latest.json")));
+        assert!(is_run_file(Path::new("broadcast/Deploy.s.sol/1/run-1700000000.json")));
+        assert!(!is_run_file(Path::new("broadcast/Deploy.s.sol/1/run-.json")));
+        assert!(!is_run_file(Path::new("broadcast/Deploy.s.sol/1/run-latest.json.bak")));
+        assert!(!is_run_file(Path::new("broadcast/Deploy.s.sol/1/cache.json")));
+        assert!(!is_run_file(Path::new("broadcast/Deploy.s.sol/1/run-latest-sensitive.json")));
+    }
+
+    #[test]
+    fn selectors_are_parsed_from_calldata() {
+        assert_eq!(parse_selector("0xa9059cbb000000"), Some([0xa9, 0x05, 0x9c, 0xbb]));
+        assert_eq!(parse_selector("0xa905"), None);
+        assert_eq!(parse_selector("a9059cbb"), None);
+        assert_eq!(parse_selector("0xzz059cbb"), None);
+    }
+}
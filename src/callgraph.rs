@@ -0,0 +1,239 @@
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::references::{CountOptions, NameMatcher};
+use crate::solidity::{self, Visibility};
+use crate::source::{canonical, collect_sol_files, SourceFile};
+
+/// A function in the call graph, identified by its contract-qualified name.
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    pub contract: Option<String>,
+    pub file: PathBuf,
+    pub line: usize,
+    pub visibility: Visibility,
+    /// Referenced from somewhere other than the body of an analyzed function.
+    pub externally_referenced: bool,
+    /// Listed as an entry point by the user, e.g. because a router calls it by selector.
+    pub assumed_entry_point: bool,
+    pub reachable: bool,
+}
+
+impl Node {
+    /// Entry points are the roots of reachability.
+    pub fn is_entry_point(&self) -> bool {
+        self.visibility.is_externally_visible() || self.externally_referenced || self.assumed_entry_point
+    }
+}
+
+/// Function-level call graph over the functions declared in the analyzed files.
+pub struct CallGraph {
+    pub nodes: Vec<Node>,
+    /// Directed `(caller, callee)` edges as indices into `nodes`.
+    pub edges: BTreeSet<(usize, usize)>,
+}
+
+/// Where a function's body lives, used to attribute references to callers.
+struct BodySpan {
+    node: usize,
+    start: usize,
+    name_offset: usize,
+    body: Option<(usize, usize)>,
+}
+
+impl CallGraph {
+    /// Builds the call graph for `analyzed_files`, resolving references across every
+    /// Solidity file under `root`.
+    pub fn build(analyzed_files: &[PathBuf], root: &Path, options: &CountOptions) -> Result<Self> {
+        let analyzed = analyzed_files
+            .iter()
+            .map(|path| SourceFile::read(path))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The analyzed files are always searched, even when they live outside the root,
+        // so that calls between analyzed functions are never lost.
+        let seen: HashSet<PathBuf> = analyzed_files.iter().map(|path| canonical(path)).collect();
+        let others: Vec<SourceFile> = collect_sol_files(root)?
+            .into_par_iter()
+            .filter(|path| !seen.contains(&canonical(path)))
+            .map(|path| {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                SourceFile::new(path, content)
+            })
+            .collect();
+
+        Self::from_sources(&analyzed, &others, options)
+    }
+
+    /// Builds the call graph for the functions declared in `analyzed`, resolving
+    /// references across `analyzed` and `others`.
+    pub fn from_sources(analyzed: &[SourceFile], others: &[SourceFile], options: &CountOptions) -> Result<Self> {
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut spans: Vec<Vec<BodySpan>> = Vec::new();
+
+        for source in analyzed {
+            let mut file_spans = Vec::new();
+            for function in solidity::parse(source).functions {
+                let id = function.qualified_name();
+                let node = *ids.entry(id.clone()).or_insert_with(|| {
+                    nodes.push(Node {
+                        id,
+                        name: function.name.clone(),
+                        contract: function.contract.clone(),
+                        file: source.path.clone(),
+                        line: function.line,
+                        visibility: function.visibility,
+                        externally_referenced: false,
+                        assumed_entry_point: false,
+                        reachable: false,
+                    });
+                    nodes.len() - 1
+                });
+                file_spans.push(BodySpan {
+                    node,
+                    start: function.start,
+                    name_offset: function.name_offset,
+                    body: function.body,
+                });
+            }
+            spans.push(file_spans);
+        }
+
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, node) in nodes.iter().enumerate() {
+            by_name.entry(node.name.as_str()).or_default().push(index);
+        }
+
+        let matcher = NameMatcher::new(by_name.keys().copied())?;
+        let targets_by_id: Vec<&Vec<usize>> = matcher.names().iter().map(|name| &by_name[name.as_str()]).collect();
+
+        // Every reference is attributed either to the analyzed function whose body
+        // contains it, or to the outside world.
+        let no_spans = Vec::new();
+        let search: Vec<(&SourceFile, &Vec<BodySpan>)> = analyzed
+            .iter()
+            .zip(&spans)
+            .chain(others.iter().map(|source| (source, &no_spans)))
+            .collect();
+        let references: Vec<(Option<usize>, usize)> = search
+            .par_iter()
+            .flat_map_iter(|(source, file_spans)| {
+                let mut found = Vec::new();
+                for (id, occurrence) in matcher.find_occurrences(source) {
+                    let targets = targets_by_id[id];
+                    if !occurrence.counts(options) {
+                        continue;
+                    }
+                    let is_declaration =
+                        file_spans.iter().any(|span| span.name_offset == occurrence.offset);
+                    if is_declaration {
+                        continue;
+                    }
+                    let caller = file_spans
+                        .iter()
+                        .filter(|span| {
+                            span.body.is_some_and(|(start, end)| {
+                                start <= occurrence.offset && occurrence.offset < end
+                            })
+                        })
+                        .max_by_key(|span| span.start)
+                        .map(|span| span.node);
+                    // A call qualified by the contract of some targets, e.g.
+                    // `Math.mulDiv(...)`, only calls those.
+                    let qualified: Vec<usize> = targets
+                        .iter()
+                        .copied()
+                        .filter(|&target| {
+                            occurrence.qualifier.is_some() && nodes[target].contract == occurrence.qualifier
+                        })
+                        .collect();
+                    let targets = if qualified.is_empty() { targets } else { &qualified };
+                    found.extend(targets.iter().map(|&target| (caller, target)));
+                }
+                found
+            })
+            .collect();
+
+        let mut edges = BTreeSet::new();
+        for (caller, callee) in references {
+            match caller {
+                Some(caller) => {
+                    edges.insert((caller, callee));
+                }
+                None => nodes[callee].externally_referenced = true,
+            }
+        }
+
+        let mut graph = CallGraph { nodes, edges };
+        graph.compute_reachability();
+        Ok(graph)
+    }
+
+    /// Makes the functions matching `entry_points`, given as `name` or `Contract.name`,
+    /// entry points too, and returns the entries that match no function.
+    pub fn assume_entry_points<'a>(&mut self, entry_points: &'a [String]) -> Vec<&'a str> {
+        let mut unmatched = Vec::new();
+        for entry in entry_points {
+            let found = self.find(entry);
+            if found.is_empty() {
+                unmatched.push(entry.as_str());
+            }
+            for index in found {
+                self.nodes[index].assumed_entry_point = true;
+            }
+        }
+        self.compute_reachability();
+        unmatched
+    }
+
+    fn compute_reachability(&mut self) {
+        let mut queue: VecDeque<usize> = (0..self.nodes.len())
+            .filter(|&index| self.nodes[index].is_entry_point())
+            .collect();
+        for &index in &queue {
+            self.nodes[index].reachable = true;
+        }
+
+        while let Some(index) = queue.pop_front() {
+            for &(_, callee) in self.edges.range((index, 0)..=(index, usize::MAX)) {
+                if !self.nodes[callee].reachable {
+                    self.nodes[callee].reachable = true;
+                    queue.push_back(callee);
+                }
+            }
+        }
+    }
+
+    /// Returns the indices of nodes matching `function`, given as `name` or `Contract.name`.
+    pub fn find(&self, function: &str) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&index| self.nodes[index].id == function || self.nodes[index].name == function)
+            .collect()
+    }
+
+    /// Returns the indices of nodes within `depth` hops of `start`, following edges in
+    /// either direction.
+    pub fn neighborhood(&self, start: &[usize], depth: usize) -> HashSet<usize> {
+        let mut visited: HashSet<usize> = start.iter().copied().collect();
+        let mut frontier: Vec<usize> = start.to_vec();
+
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for &(caller, callee) in &self.edges {
+                for (from, to) in [(caller, callee), (callee, caller)] {
+                    if frontier.contains(&from) && visited.insert(to) {
+                        next.push(to);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        visited
+    }
+}
@@ -0,0 +1,154 @@
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use super::vacuum::{self, AnalysisArgs};
+use crate::baseline::{Baseline, Entry, STALE_AFTER_DAYS};
+use crate::output::{self, Format};
+use crate::source::expand_path;
+
+#[derive(Parser, Debug)]
+pub struct BaselineArgs {
+    #[command(subcommand)]
+    command: BaselineCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum BaselineCommand {
+    /// Runs the vacuum analysis and records every unused function in a baseline.
+    Create(CreateArgs),
+    /// Removes the entries that no longer correspond to an unused function.
+    Trim(TrimArgs),
+    /// Lists the entries of a baseline and how long they have been baselined.
+    Show(ShowArgs),
+}
+
+#[derive(Parser, Debug)]
+struct CreateArgs {
+    /// Baseline file to write. Entries already in it keep their creation date.
+    #[arg(long, value_name = "FILE", default_value = "wand-baseline.json", value_parser = expand_path)]
+    output: PathBuf,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+#[derive(Parser, Debug)]
+struct TrimArgs {
+    /// Baseline file to trim in place.
+    #[arg(long, value_name = "FILE", default_value = "wand-baseline.json", value_parser = expand_path)]
+    baseline: PathBuf,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+#[derive(Parser, Debug)]
+struct ShowArgs {
+    /// Baseline file to list.
+    #[arg(value_name = "FILE", default_value = "wand-baseline.json", value_parser = expand_path)]
+    baseline: PathBuf,
+
+    /// Output format of the listing.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Serialize)]
+struct ShownEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a Entry,
+    age_days: Option<i64>,
+    stale: bool,
+}
+
+pub fn run(args: BaselineArgs) -> Result<()> {
+    match args.command {
+        BaselineCommand::Create(args) => create(args),
+        BaselineCommand::Trim(args) => trim(args),
+        BaselineCommand::Show(args) => show(args),
+    }
+}
+
+fn create(args: CreateArgs) -> Result<()> {
+    let findings = vacuum::find_unused(&args.analysis)?;
+    let baseline = Baseline::record(&args.output, &findings, &args.analysis.root)?;
+    println!(
+        "Wrote {} finding(s) to baseline {:?}.",
+        baseline.entries.len(),
+        args.output
+    );
+    Ok(())
+}
+
+fn trim(args: TrimArgs) -> Result<()> {
+    let mut baseline = Baseline::read(&args.baseline, &args.analysis.root)?;
+    let findings = vacuum::find_unused(&args.analysis)?;
+    let removed = baseline.trim(&findings);
+    baseline.write(&args.baseline)?;
+
+    for entry in &removed {
+        println!("Removed {}", describe(entry));
+    }
+    println!(
+        "\nRemoved {} entr{} from baseline {:?}; {} remain.",
+        removed.len(),
+        if removed.len() == 1 { "y" } else { "ies" },
+        args.baseline,
+        baseline.entries.len()
+    );
+    Ok(())
+}
+
+fn show(args: ShowArgs) -> Result<()> {
+    // Listing doesn't depend on which entries moved.
+    let baseline = Baseline::read(&args.baseline, Path::new("."))?;
+    let stale = baseline.entries.iter().filter(|entry| entry.is_stale()).count();
+
+    match args.format {
+        Format::Text => {
+            println!("\nBaseline {:?}:", args.baseline);
+            for entry in &baseline.entries {
+                let age = match entry.age_days() {
+                    Some(days) => format!("recorded {} ({} days ago)", entry.created, days),
+                    None => "recorded on an unknown date".to_string(),
+                };
+                let marker = if entry.is_stale() { " [stale]" } else { "" };
+                println!("  {} {}{}", describe(entry), age, marker);
+            }
+            if baseline.entries.is_empty() {
+                println!("  (no entries)");
+            }
+            println!("\nTotal entries: {}", baseline.entries.len());
+            if stale > 0 {
+                println!(
+                    "Entries baselined for more than {} days: {}",
+                    STALE_AFTER_DAYS, stale
+                );
+            }
+        }
+        Format::Json => {
+            let entries: Vec<ShownEntry> = baseline
+                .entries
+                .iter()
+                .map(|entry| ShownEntry {
+                    entry,
+                    age_days: entry.age_days(),
+                    stale: entry.is_stale(),
+                })
+                .collect();
+            println!("{}", output::to_json(&entries)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(entry: &Entry) -> String {
+    let symbol = match &entry.contract {
+        Some(contract) => format!("{}.{}", contract, entry.signature),
+        None => entry.signature.clone(),
+    };
+    format!("{}: {} ({})", entry.file, symbol, entry.kind.as_str())
+}
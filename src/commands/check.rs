@@ -0,0 +1,445 @@
+use clap::Parser;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::natspec::{self, NatspecArgs};
+use super::vacuum::{self, AnalysisArgs};
+use crate::baseline::Baseline;
+use crate::config::{self, Config};
+use crate::findings::{self, Finding, FindingKind, Severity, Suppressions};
+use crate::imports::{self, ImportGraph, Resolver};
+use crate::output::{self, Format, FindingsFormat};
+use crate::project::Project;
+use crate::ignore::{self, should_ignore_function};
+use crate::solidity;
+use crate::source::{canonical, collect_sol_files, expand_path, portable_path, SourceFile};
+use crate::unreachable;
+
+/// The rules `wand check` can run, in the order they are reported.
+pub(crate) const RULES: [FindingKind; 4] = [
+    FindingKind::Unused,
+    FindingKind::ImportCycle,
+    FindingKind::MissingNatspec,
+    FindingKind::UnreachableCode,
+];
+
+#[derive(Parser, Debug)]
+pub struct CheckArgs {
+    /// Project root containing `wand.toml`. The configured paths are relative to it.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    root: PathBuf,
+
+    /// Output format, instead of the one configured in `wand.toml`.
+    #[arg(long, value_enum)]
+    format: Option<FindingsFormat>,
+
+    /// Only report findings in files changed since the merge base with this git
+    /// revision, e.g. `origin/main`, including uncommitted and untracked files.
+    #[arg(long, value_name = "REV")]
+    since: Option<String>,
+
+    /// File of finding fingerprints, one per line, that SARIF output marks as suppressed,
+    /// e.g. those dismissed in GitHub code scanning.
+    #[arg(long, value_name = "FILE", value_parser = expand_path)]
+    suppressions: Option<PathBuf>,
+
+    /// Directory that the file URIs of SARIF results are relative to, instead of the root
+    /// of the git repository containing --root.
+    #[arg(long, value_name = "DIR", value_parser = expand_path)]
+    relative_to: Option<PathBuf>,
+
+    /// Fail when an `ignore` pattern of `wand.toml` matches no function, instead of only
+    /// warning.
+    #[arg(long)]
+    strict_ignores: bool,
+}
+
+/// The options of `wand vacuum` that decide which functions are unused, for the `unused`
+/// rule to run with their defaults.
+#[derive(Parser)]
+struct AnalysisDefaults {
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+#[derive(Serialize)]
+struct RuleReport<'a> {
+    rule: &'static str,
+    findings: Vec<ReportedFinding<'a>>,
+}
+
+#[derive(Serialize)]
+struct ReportedFinding<'a> {
+    file: &'a Path,
+    line: usize,
+    severity: Severity,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    rules: Vec<RuleReport<'a>>,
+    suppressed_by_baseline: usize,
+    out_of_scope: usize,
+    failing: usize,
+    max_findings: usize,
+    passed: bool,
+}
+
+pub fn run(args: CheckArgs) -> Result<()> {
+    let config = Config::load(&args.root)?;
+    let check = &config.check;
+    let rules = check
+        .rules
+        .iter()
+        .map(|rule| {
+            RULES
+                .into_iter()
+                .find(|kind| kind.rule_id() == rule)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Unknown rule {:?} in the [check] table of wand.toml, expected one of: {}.",
+                            rule,
+                            RULES.map(FindingKind::rule_id).join(", ")
+                        ),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let relative = |path: &Path| {
+        if args.root == Path::new(".") {
+            path.to_path_buf()
+        } else {
+            args.root.join(path)
+        }
+    };
+    let paths: Vec<PathBuf> = match &check.paths {
+        Some(paths) => paths.iter().map(|path| relative(path)).collect(),
+        None => vec![Project::detect(&args.root, None, None)
+            .sources()
+            .unwrap_or_else(|| relative(Path::new("src")))],
+    };
+
+    let defaults = AnalysisDefaults::try_parse_from(["vacuum"]).map_err(Error::other)?.analysis;
+    let mut all = Vec::new();
+    for path in &paths {
+        if rules.contains(&FindingKind::Unused) {
+            all.extend(vacuum::find_unused(&AnalysisArgs {
+                path: Some(path.clone()),
+                root: args.root.clone(),
+                ignore: check.ignore.clone(),
+                ..defaults.clone()
+            })?);
+        }
+        if rules.contains(&FindingKind::MissingNatspec) {
+            all.extend(natspec::find_undocumented(&NatspecArgs {
+                path: path.clone(),
+                root: args.root.clone(),
+                ignore: check.ignore.clone(),
+                events: false,
+                errors: false,
+                check: false,
+                format: Format::Text,
+            })?);
+        }
+    }
+    if rules.contains(&FindingKind::ImportCycle) {
+        all.extend(import_cycles(&paths, &args.root)?);
+    }
+    if rules.contains(&FindingKind::UnreachableCode) {
+        all.extend(unreachable_code(&paths, &check.ignore)?);
+    }
+    // The default pattern isn't checked unless it's configured, and import cycles don't
+    // depend on any.
+    let configured = config::locate_key(&args.root, "check", "ignore");
+    let unmatched = if configured.is_none() || rules == [FindingKind::ImportCycle] {
+        Vec::new()
+    } else {
+        unmatched_ignores(&paths, &check.ignore)?
+    };
+    let file = relative(Path::new("wand.toml"));
+    for pattern in &unmatched {
+        let line = config::locate_value(&args.root, pattern).or(configured).unwrap_or(1);
+        eprintln!(
+            "Warning: ignore pattern {:?} from {}:{} (check.ignore) matched no function.",
+            pattern,
+            file.display(),
+            line
+        );
+    }
+
+    let baseline = check
+        .baseline
+        .as_ref()
+        .map(|path| Baseline::read(&args.root.join(path), &args.root))
+        .transpose()?;
+    let (all, baselined): (Vec<Finding>, Vec<Finding>) = all
+        .into_iter()
+        .partition(|finding| baseline.as_ref().is_none_or(|baseline| !baseline.contains(finding)));
+    let (findings, out_of_scope) = match &args.since {
+        Some(rev) => {
+            let changed = changed_files(&args.root, rev)?;
+            all.into_iter()
+                .partition(|finding| changed.contains(&canonical(&finding.file)))
+        }
+        None => (all, Vec::new()),
+    };
+
+    let failing = findings
+        .iter()
+        .filter(|finding| config.severity.severity(finding) <= check.fail_on)
+        .count();
+    let passed = failing <= check.max_findings;
+
+    match args.format.unwrap_or(check.format) {
+        FindingsFormat::Text => {
+            for &rule in &rules {
+                let matching: Vec<&Finding> = findings.iter().filter(|finding| finding.kind == rule).collect();
+                println!("\n{} ({}):", rule.rule_id(), matching.len());
+                for finding in &matching {
+                    println!(
+                        "  {}:{} [{}] {}",
+                        finding.file.display(),
+                        finding.line,
+                        config.severity.severity(finding).as_str(),
+                        finding.message()
+                    );
+                }
+                if matching.is_empty() {
+                    println!("  (no findings)");
+                }
+            }
+
+            let count = |severity: Severity| {
+                findings
+                    .iter()
+                    .filter(|finding| config.severity.severity(finding) == severity)
+                    .count()
+            };
+            println!(
+                "\nFindings: {} ({} error, {} warning, {} note)",
+                findings.len(),
+                count(Severity::Error),
+                count(Severity::Warning),
+                count(Severity::Note)
+            );
+            if !baselined.is_empty() {
+                println!("Suppressed by the baseline: {}", baselined.len());
+            }
+            if let Some(rev) = &args.since {
+                println!("Outside the files changed since {}: {}", rev, out_of_scope.len());
+            }
+            println!(
+                "Check {}: {} finding(s) at {} or above, {} allowed.",
+                if passed { "passed" } else { "failed" },
+                failing,
+                check.fail_on.as_str(),
+                check.max_findings
+            );
+        }
+        FindingsFormat::Json => println!(
+            "{}",
+            output::to_json(&Report {
+                rules: rules
+                    .iter()
+                    .map(|&rule| RuleReport {
+                        rule: rule.rule_id(),
+                        findings: findings
+                            .iter()
+                            .filter(|finding| finding.kind == rule)
+                            .map(|finding| ReportedFinding {
+                                file: &finding.file,
+                                line: finding.line,
+                                severity: config.severity.severity(finding),
+                                message: finding.message(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+                suppressed_by_baseline: baselined.len(),
+                out_of_scope: out_of_scope.len(),
+                failing,
+                max_findings: check.max_findings,
+                passed,
+            })?
+        ),
+        format => {
+            let suppressions = Suppressions::load(args.suppressions.as_deref())?;
+            let uri_base = findings::uri_base(&args.root, args.relative_to.as_deref());
+            if let Some(rendered) = findings::render(format, &findings, &config.severity, &suppressions, &uri_base)? {
+                println!("{}", rendered);
+            }
+        }
+    }
+
+    if !passed {
+        return Err(Error::other(format!(
+            "Check failed with {} finding(s) at {} or above.",
+            failing,
+            check.fail_on.as_str()
+        )));
+    }
+    if args.strict_ignores && !unmatched.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} ignore pattern(s) of wand.toml matched no function: {}.",
+                unmatched.len(),
+                unmatched.join(", ")
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The patterns of `ignore` that match no function declared in the files under `paths`.
+fn unmatched_ignores<'a>(paths: &[PathBuf], ignore: &'a [String]) -> Result<Vec<&'a str>> {
+    let mut names = Vec::new();
+    for path in paths {
+        let files = if path.is_file() { vec![path.clone()] } else { collect_sol_files(path)? };
+        for file in files {
+            let source = SourceFile::read(&file)?;
+            names.extend(solidity::parse(&source).functions.into_iter().map(|function| function.name));
+        }
+    }
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let matched = ignore::matched_patterns(ignore, &names);
+    Ok(ignore
+        .iter()
+        .map(String::as_str)
+        .filter(|pattern| !matched.contains(pattern))
+        .collect())
+}
+
+/// Reports each import cycle among the files under `paths` once, at the import that
+/// starts it.
+fn import_cycles(paths: &[PathBuf], root: &Path) -> Result<Vec<Finding>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+        } else {
+            files.extend(collect_sol_files(path)?);
+        }
+    }
+    files.sort();
+
+    let resolver = Resolver::new(root)?;
+    let graph = ImportGraph::build(&files, &resolver)?;
+    graph
+        .cycles()
+        .iter()
+        .map(|cycle| {
+            let file = &graph.files[cycle[0]];
+            let next = canonical(&graph.files[cycle[1]]);
+            let line = imports::parse_imports(&SourceFile::read(file)?)
+                .iter()
+                .find(|import| {
+                    resolver
+                        .resolve(file, &import.path)
+                        .is_some_and(|target| canonical(&target) == next)
+                })
+                .map_or(1, |import| import.line);
+            let chain = cycle
+                .iter()
+                .map(|&member| portable_path(&graph.files[member]))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            Ok(Finding {
+                file: file.clone(),
+                line,
+                contract: None,
+                function: portable_path(file),
+                signature: chain.clone(),
+                context: String::new(),
+                visibility: None,
+                kind: FindingKind::ImportCycle,
+                suggestion: None,
+                coverage_hits: None,
+                detail: Some(chain),
+            })
+        })
+        .collect()
+}
+
+/// Reports the code that can never run in the function bodies of the files under `paths`.
+fn unreachable_code(paths: &[PathBuf], ignore: &[String]) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for path in paths {
+        let files = if path.is_file() { vec![path.clone()] } else { collect_sol_files(path)? };
+        for file in files {
+            let source = SourceFile::read(&file)?;
+            let unit = solidity::parse(&source);
+            findings.extend(
+                unreachable::scan(&source, &unit)
+                    .into_iter()
+                    .filter(|code| !should_ignore_function(&code.function, ignore))
+                    .map(|code| Finding {
+                        file: file.clone(),
+                        line: code.line,
+                        contract: code.contract.clone(),
+                        suggestion: None,
+                        coverage_hits: None,
+                        detail: Some(code.describe()),
+                        function: code.function,
+                        signature: code.signature,
+                        context: code.context,
+                        visibility: Some(code.visibility),
+                        kind: FindingKind::UnreachableCode,
+                    }),
+            );
+        }
+    }
+    Ok(findings)
+}
+
+/// Files under `root` changed since the merge base with `rev`, or not tracked by git.
+fn changed_files(root: &Path, rev: &str) -> Result<HashSet<PathBuf>> {
+    let mut changed = HashSet::new();
+    for git_args in [
+        vec!["diff", "--name-only", "--relative", "--merge-base", rev],
+        vec!["ls-files", "--others", "--exclude-standard"],
+    ] {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(&git_args)
+            .output()
+            .map_err(|e| Error::new(e.kind(), format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(Error::other(format!(
+                "`git {}` failed: {}",
+                git_args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        changed.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| canonical(&root.join(line))),
+        );
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exclusions::SCRIPT_DIRS;
+
+    #[test]
+    fn the_unused_rule_runs_with_the_defaults_of_vacuum() {
+        let defaults = AnalysisDefaults::try_parse_from(["vacuum"]).unwrap().analysis;
+        assert_eq!(defaults.ignore, ["^test"]);
+        assert_eq!(defaults.chain, "mainnet");
+        assert_eq!(defaults.script_dirs, SCRIPT_DIRS);
+        assert_eq!(defaults.generated_lines, 10);
+        assert_eq!(defaults.root, Path::new("."));
+    }
+}
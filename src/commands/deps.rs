@@ -0,0 +1,283 @@
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use crate::imports::{ImportGraph, Resolver};
+use crate::output;
+use crate::source::{collect_sol_files, expand_path};
+
+#[derive(Parser, Debug)]
+pub struct DepsArgs {
+    /// Path to a Solidity file or directory whose imports to graph.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    path: PathBuf,
+
+    /// Project root that imports and `remappings.txt` are resolved against.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    root: PathBuf,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = DepsFormat::Text)]
+    format: DepsFormat,
+
+    /// Number of files to list for the highest fan-in and fan-out.
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+
+    /// Exit with an error if any import cycle is found.
+    #[arg(long)]
+    fail_on_cycles: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum DepsFormat {
+    Text,
+    Dot,
+    Json,
+    Mermaid,
+}
+
+#[derive(Serialize)]
+struct JsonGraph {
+    files: Vec<JsonFile>,
+    edges: Vec<JsonEdge>,
+    cycles: Vec<Vec<String>>,
+    orphans: Vec<String>,
+    unresolved: Vec<JsonUnresolved>,
+}
+
+#[derive(Serialize)]
+struct JsonFile {
+    file: String,
+    /// Whether the file is outside PATH and was only reached as an import target.
+    external: bool,
+    fan_in: usize,
+    fan_out: usize,
+}
+
+#[derive(Serialize)]
+struct JsonEdge {
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize)]
+struct JsonUnresolved {
+    file: String,
+    line: usize,
+    import: String,
+}
+
+pub fn run(args: DepsArgs) -> Result<()> {
+    let files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        let mut files = collect_sol_files(&args.path)?;
+        files.sort();
+        files
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    let resolver = Resolver::new(&args.root)?;
+    let graph = ImportGraph::build(&files, &resolver)?;
+    let cycles = graph.cycles();
+
+    let output = match args.format {
+        DepsFormat::Text => render_text(&graph, &cycles, args.top),
+        DepsFormat::Dot => render_dot(&graph, &cycles),
+        DepsFormat::Mermaid => render_mermaid(&graph, &cycles),
+        DepsFormat::Json => render_json(&graph, &cycles)?,
+    };
+    println!("{}", output);
+
+    if args.fail_on_cycles && !cycles.is_empty() {
+        return Err(Error::other(format!(
+            "Found {} import cycle(s).",
+            cycles.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Analyzed files that no analyzed file imports.
+fn orphans(graph: &ImportGraph) -> Vec<usize> {
+    (0..graph.analyzed)
+        .filter(|&file| graph.fan_in(file) == 0)
+        .collect()
+}
+
+fn display(graph: &ImportGraph, file: usize) -> String {
+    graph.files[file].display().to_string()
+}
+
+fn render_text(graph: &ImportGraph, cycles: &[Vec<usize>], top: usize) -> String {
+    let mut out = String::new();
+
+    out.push_str("Import cycles:\n");
+    for cycle in cycles {
+        let chain: Vec<String> = cycle.iter().map(|&file| display(graph, file)).collect();
+        out.push_str(&format!("- {}\n", chain.join(" -> ")));
+    }
+    if cycles.is_empty() {
+        out.push_str("  (none)\n");
+    }
+
+    let mut ranked = |title: &str, degree: &dyn Fn(usize) -> usize| {
+        let mut files: Vec<usize> = (0..graph.files.len()).filter(|&file| degree(file) > 0).collect();
+        files.sort_by_key(|&file| std::cmp::Reverse(degree(file)));
+        out.push_str(&format!("\n{}:\n", title));
+        for &file in files.iter().take(top) {
+            out.push_str(&format!("  {:>3}  {}\n", degree(file), display(graph, file)));
+        }
+        if files.is_empty() {
+            out.push_str("  (none)\n");
+        }
+    };
+    ranked("Highest fan-in (imported by)", &|file| graph.fan_in(file));
+    ranked("Highest fan-out (imports)", &|file| graph.fan_out(file));
+
+    let orphans = orphans(graph);
+    out.push_str("\nOrphans (imported by nothing):\n");
+    for &file in &orphans {
+        out.push_str(&format!("- {}\n", display(graph, file)));
+    }
+    if orphans.is_empty() {
+        out.push_str("  (none)\n");
+    }
+
+    if !graph.unresolved.is_empty() {
+        out.push_str("\nUnresolved imports:\n");
+        for unresolved in &graph.unresolved {
+            out.push_str(&format!(
+                "- {}:{} {:?}\n",
+                unresolved.file.display(),
+                unresolved.line,
+                unresolved.import
+            ));
+        }
+    }
+
+    out.push_str(&format!(
+        "\nFiles: {} ({} external), imports: {}, cycles: {}, orphans: {}",
+        graph.files.len(),
+        graph.files.len() - graph.analyzed,
+        graph.edges.len(),
+        cycles.len(),
+        orphans.len()
+    ));
+    out
+}
+
+/// Files and edges that take part in a cycle.
+fn cycle_members(cycles: &[Vec<usize>]) -> (HashSet<usize>, HashSet<(usize, usize)>) {
+    let files = cycles.iter().flatten().copied().collect();
+    let edges = cycles
+        .iter()
+        .flat_map(|cycle| cycle.windows(2).map(|pair| (pair[0], pair[1])))
+        .collect();
+    (files, edges)
+}
+
+fn render_dot(graph: &ImportGraph, cycles: &[Vec<usize>]) -> String {
+    let (cyclic_files, cyclic_edges) = cycle_members(cycles);
+
+    let mut out = String::from("digraph imports {\n    node [shape=box];\n");
+    for file in 0..graph.files.len() {
+        let mut style = String::new();
+        if cyclic_files.contains(&file) {
+            style.push_str(" color=red fontcolor=red");
+        }
+        if file >= graph.analyzed {
+            style.push_str(" style=dashed");
+        }
+        let name = display(graph, file);
+        out.push_str(&format!("    \"{}\" [label=\"{}\"{}];\n", name, name, style));
+    }
+    for &(importer, imported) in &graph.edges {
+        let color = if cyclic_edges.contains(&(importer, imported)) {
+            " [color=red]"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "    \"{}\" -> \"{}\"{};\n",
+            display(graph, importer),
+            display(graph, imported),
+            color
+        ));
+    }
+    out.push('}');
+    out
+}
+
+fn render_mermaid(graph: &ImportGraph, cycles: &[Vec<usize>]) -> String {
+    let (cyclic_files, _) = cycle_members(cycles);
+    let mermaid_id = |index: usize| format!("f{}", index);
+
+    let mut out = String::from("graph LR\n");
+    out.push_str("    classDef cycle stroke:#d00,color:#d00;\n");
+    out.push_str("    classDef external stroke-dasharray:4;\n");
+    for file in 0..graph.files.len() {
+        let class = if cyclic_files.contains(&file) {
+            ":::cycle"
+        } else if file >= graph.analyzed {
+            ":::external"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "    {}[\"{}\"]{}\n",
+            mermaid_id(file),
+            display(graph, file),
+            class
+        ));
+    }
+    for &(importer, imported) in &graph.edges {
+        out.push_str(&format!("    {} --> {}\n", mermaid_id(importer), mermaid_id(imported)));
+    }
+    out.trim_end().to_string()
+}
+
+fn render_json(graph: &ImportGraph, cycles: &[Vec<usize>]) -> Result<String> {
+    let json = JsonGraph {
+        files: (0..graph.files.len())
+            .map(|file| JsonFile {
+                file: display(graph, file),
+                external: file >= graph.analyzed,
+                fan_in: graph.fan_in(file),
+                fan_out: graph.fan_out(file),
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .iter()
+            .map(|&(importer, imported)| JsonEdge {
+                from: display(graph, importer),
+                to: display(graph, imported),
+            })
+            .collect(),
+        cycles: cycles
+            .iter()
+            .map(|cycle| cycle.iter().map(|&file| display(graph, file)).collect())
+            .collect(),
+        orphans: orphans(graph).into_iter().map(|file| display(graph, file)).collect(),
+        unresolved: graph
+            .unresolved
+            .iter()
+            .map(|unresolved| JsonUnresolved {
+                file: unresolved.file.display().to_string(),
+                line: unresolved.line,
+                import: unresolved.import.clone(),
+            })
+            .collect(),
+    };
+
+    output::to_json(&json)
+}
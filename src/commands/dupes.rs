@@ -0,0 +1,447 @@
+use clap::Parser;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use super::vacuum;
+use crate::body::NormalizedBody;
+use crate::inheritance::{self, Hierarchy, Parsed};
+use crate::output::{self, Format};
+use crate::solidity::{self, Function};
+use crate::source::{self, collect_sol_files, expand_path, SourceFile};
+
+#[derive(Parser, Debug)]
+pub struct DupesArgs {
+    /// Path to a Solidity file or directory to scan for duplicate functions.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    path: PathBuf,
+
+    /// Treat bodies that only differ by identifier names as duplicates.
+    #[arg(long)]
+    normalize_identifiers: bool,
+
+    /// Also group highly similar bodies whose token-shingle similarity is at least this (0 to 1).
+    #[arg(long, value_name = "THRESHOLD")]
+    similarity: Option<f64>,
+
+    /// Ignore function bodies with fewer tokens than this.
+    #[arg(long, default_value_t = 10)]
+    min_tokens: usize,
+
+    /// Instead of clusters, report overrides whose body repeats the implementation they
+    /// override in a base contract.
+    #[arg(long, conflicts_with = "similarity")]
+    inherited: bool,
+
+    /// Root directory searched for the base contracts of the analyzed contracts, with
+    /// --inherited.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    root: PathBuf,
+
+    /// Delete the redundant overrides found with --inherited, which leaves the contracts
+    /// inheriting the implementation they repeated.
+    #[arg(long, requires = "inherited")]
+    delete: bool,
+
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+/// A function body considered for duplicate detection.
+struct Candidate {
+    file: PathBuf,
+    function: String,
+    line: usize,
+    lines: usize,
+    body: NormalizedBody,
+}
+
+#[derive(Serialize)]
+struct Cluster {
+    kind: &'static str,
+    functions: Vec<ClusterMember>,
+}
+
+#[derive(Serialize)]
+struct ClusterMember {
+    file: PathBuf,
+    function: String,
+    line: usize,
+    lines: usize,
+    tokens: usize,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    clusters: &'a [Cluster],
+}
+
+#[derive(Serialize)]
+struct Location {
+    file: PathBuf,
+    function: String,
+    line: usize,
+}
+
+/// A function repeating the implementation it overrides, so that removing it wouldn't
+/// change the contract.
+#[derive(Serialize)]
+struct RedundantOverride {
+    function: Location,
+    base: Location,
+    tokens: usize,
+    /// Whether --delete removed the override.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    removed: bool,
+}
+
+#[derive(Serialize)]
+struct InheritedReport<'a> {
+    redundant_overrides: &'a [RedundantOverride],
+}
+
+pub fn run(args: DupesArgs) -> Result<()> {
+    if let Some(threshold) = args.similarity {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("--similarity must be between 0 and 1, got {}.", threshold),
+            ));
+        }
+    }
+
+    let sol_files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        collect_sol_files(&args.path)?
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    if args.inherited {
+        return run_inherited(&args, &sol_files);
+    }
+
+    let candidates: Vec<Candidate> = sol_files
+        .par_iter()
+        .map(|path| {
+            let source = SourceFile::read(path)?;
+            Ok(solidity::parse(&source)
+                .functions
+                .into_iter()
+                .filter_map(|function| {
+                    let body = function.body?;
+                    Some(Candidate {
+                        file: path.clone(),
+                        function: function.qualified_name(),
+                        line: function.line,
+                        lines: source.line_of(body.1) - source.line_of(function.start) + 1,
+                        body: NormalizedBody::new(&source, body, args.normalize_identifiers),
+                    })
+                })
+                .filter(|candidate| candidate.body.tokens.len() >= args.min_tokens)
+                .collect::<Vec<_>>())
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let clusters = cluster(&candidates, args.similarity);
+
+    match args.format {
+        Format::Text => print_text_report(&clusters),
+        Format::Json => println!("{}", output::to_json(&Report { clusters: &clusters })?),
+    }
+
+    Ok(())
+}
+
+/// Reports the functions of the analyzed contracts whose body and header match the
+/// nearest inherited implementation with the same signature, and which can be removed:
+/// no other base declares the signature apart from those the implementation overrides,
+/// and no function names the contract in its `override(...)` list. Removes them too
+/// with `--delete`.
+fn run_inherited(args: &DupesArgs, sol_files: &[PathBuf]) -> Result<()> {
+    let files = inheritance::load(sol_files, &args.root)?;
+    let hierarchy = Hierarchy::new(&files);
+
+    let mut redundant = Vec::new();
+    for file in &files[..sol_files.len()] {
+        for contract in &file.unit.contracts {
+            let bases = hierarchy.ancestors(contract);
+            for function in declared_in(file, &contract.name) {
+                let Some(body) = function.body else { continue };
+                let signature = function.written_signature();
+                let declaring: Vec<_> = bases
+                    .iter()
+                    .filter(|&&(index, base)| {
+                        declared_in(&files[index], &base.name).any(|candidate| candidate.written_signature() == signature)
+                    })
+                    .collect();
+                let Some(&&(index, nearest)) = declaring.first() else {
+                    continue;
+                };
+                let base_file = &files[index];
+                let Some((base, base_body)) = declared_in(base_file, &nearest.name)
+                    .find(|candidate| candidate.written_signature() == signature)
+                    .and_then(|base| Some((base, base.body?)))
+                else {
+                    continue;
+                };
+
+                let normalized = NormalizedBody::new(&file.source, body, args.normalize_identifiers);
+                let base_body = NormalizedBody::new(&base_file.source, base_body, args.normalize_identifiers);
+                if normalized.hash != base_body.hash
+                    || header_modifiers(&file.source, function) != header_modifiers(&base_file.source, base)
+                {
+                    continue;
+                }
+                // Another base declaring the signature, e.g. an interface the contract also
+                // inherits, makes the override required.
+                let overridden = hierarchy.ancestors(nearest);
+                let required = declaring[1..].iter().any(|&&(_, other)| {
+                    !overridden.iter().any(|&(_, ancestor)| ancestor.name == other.name)
+                });
+                if required || named_by_override(&files, &signature, &contract.name) {
+                    continue;
+                }
+                redundant.push(RedundantOverride {
+                    function: Location {
+                        file: file.source.path.clone(),
+                        function: function.qualified_name(),
+                        line: function.line,
+                    },
+                    base: Location {
+                        file: base_file.source.path.clone(),
+                        function: base.qualified_name(),
+                        line: base.line,
+                    },
+                    tokens: normalized.tokens.len(),
+                    removed: false,
+                });
+            }
+        }
+    }
+    redundant.sort_by(|a, b| {
+        a.function
+            .file
+            .cmp(&b.function.file)
+            .then(a.function.line.cmp(&b.function.line))
+    });
+
+    let mut updated = Vec::new();
+    if args.delete {
+        // A function naming the contract in its `override(...)` list in an unreadable
+        // directory would no longer compile.
+        let skipped = source::skipped_dirs();
+        if !skipped.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Skipped {} unreadable director(ies), starting with {:?}, whose contracts might require the overrides; nothing was deleted.",
+                    skipped.len(),
+                    skipped[0].path
+                ),
+            ));
+        }
+        let mut by_file: BTreeMap<PathBuf, Vec<(String, usize)>> = BTreeMap::new();
+        for entry in &redundant {
+            let name = entry.function.function.rsplit('.').next().unwrap_or_default();
+            by_file
+                .entry(entry.function.file.clone())
+                .or_default()
+                .push((name.to_string(), entry.function.line));
+        }
+        for (path, functions) in by_file {
+            let mut removed = vacuum::remove_unused_functions(&path, &functions)?;
+            updated.push((path.clone(), removed.len()));
+            for entry in redundant.iter_mut().filter(|entry| entry.function.file == path) {
+                let name = entry.function.function.rsplit('.').next().unwrap_or_default();
+                if let Some(position) = removed.iter().position(|removed| removed == name) {
+                    removed.swap_remove(position);
+                    entry.removed = true;
+                }
+            }
+        }
+    }
+
+    match args.format {
+        Format::Text => {
+            for entry in &redundant {
+                println!(
+                    "{}:{} {} repeats {} ({}:{}) verbatim ({} tokens)",
+                    entry.function.file.display(),
+                    entry.function.line,
+                    entry.function.function,
+                    entry.base.function,
+                    entry.base.file.display(),
+                    entry.base.line,
+                    entry.tokens
+                );
+            }
+            println!("\nRedundant overrides found: {}", redundant.len());
+            for (path, removed) in &updated {
+                println!("Updated {:?} with {} redundant override(s) removed.", path, removed);
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            output::to_json(&InheritedReport {
+                redundant_overrides: &redundant,
+            })?
+        ),
+    }
+
+    Ok(())
+}
+
+/// Whether a function with `signature` names `contract` in its `override(...)` list, which
+/// requires the contract to keep declaring the function.
+fn named_by_override(files: &[Parsed], signature: &str, contract: &str) -> bool {
+    files.iter().any(|file| {
+        file.unit
+            .functions
+            .iter()
+            .filter(|function| function.written_signature() == signature)
+            .any(|function| {
+                let header = file.source.code_only((function.params_span.1, function.header_end));
+                override_list_pattern()
+                    .captures_iter(&header)
+                    .any(|list| list[1].split(',').any(|base| base.trim() == contract))
+            })
+    })
+}
+
+fn override_list_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\boverride\s*\(([^)]*)\)").unwrap())
+}
+
+/// Returns the functions declared in the contract `name` of `file`.
+fn declared_in<'a>(file: &'a Parsed, name: &'a str) -> impl Iterator<Item = &'a Function> {
+    file.unit
+        .functions
+        .iter()
+        .filter(move |function| function.contract.as_deref() == Some(name))
+}
+
+fn override_keyword_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bvirtual\b|\boverride\b(?:\s*\([^)]*\))?").unwrap())
+}
+
+/// Returns the header of `function` after its parameters, i.e. its visibility,
+/// mutability, modifiers and returns, without `virtual` and `override`, which an
+/// override changes without changing behavior.
+fn header_modifiers(source: &SourceFile, function: &Function) -> String {
+    let header = source.code_only((function.params_span.1, function.header_end));
+    let header = override_keyword_pattern().replace_all(&header, " ");
+    header.split_whitespace().collect()
+}
+
+/// Groups candidates with identical bodies and, given a threshold, similar ones, largest
+/// clusters first.
+fn cluster(candidates: &[Candidate], similarity: Option<f64>) -> Vec<Cluster> {
+    let mut parent: Vec<usize> = (0..candidates.len()).collect();
+    fn find(parent: &mut [usize], mut index: usize) -> usize {
+        while parent[index] != index {
+            parent[index] = parent[parent[index]];
+            index = parent[index];
+        }
+        index
+    }
+
+    let mut by_hash: HashMap<u64, usize> = HashMap::new();
+    for (index, candidate) in candidates.iter().enumerate() {
+        let first = *by_hash.entry(candidate.body.hash).or_insert(index);
+        let (a, b) = (find(&mut parent, first), find(&mut parent, index));
+        parent[b] = a;
+    }
+
+    if let Some(threshold) = similarity {
+        for i in 0..candidates.len() {
+            for j in i + 1..candidates.len() {
+                if find(&mut parent, i) != find(&mut parent, j)
+                    && candidates[i].body.similarity(&candidates[j].body) >= threshold
+                {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                    parent[b] = a;
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<&Candidate>> = HashMap::new();
+    for (index, candidate) in candidates.iter().enumerate() {
+        groups.entry(find(&mut parent, index)).or_default().push(candidate);
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+            let identical = members.iter().all(|m| m.body.hash == members[0].body.hash);
+            Cluster {
+                kind: if identical { "identical" } else { "similar" },
+                functions: members
+                    .into_iter()
+                    .map(|m| ClusterMember {
+                        file: m.file.clone(),
+                        function: m.function.clone(),
+                        line: m.line,
+                        lines: m.lines,
+                        tokens: m.body.tokens.len(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| {
+        b.functions
+            .len()
+            .cmp(&a.functions.len())
+            .then(b.functions[0].tokens.cmp(&a.functions[0].tokens))
+            .then(a.functions[0].file.cmp(&b.functions[0].file))
+            .then(a.functions[0].line.cmp(&b.functions[0].line))
+    });
+    clusters
+}
+
+fn print_text_report(clusters: &[Cluster]) {
+    for (index, cluster) in clusters.iter().enumerate() {
+        println!(
+            "\nCluster {}: {} {} functions",
+            index + 1,
+            cluster.functions.len(),
+            cluster.kind
+        );
+        for member in &cluster.functions {
+            println!(
+                "  {}:{} {} ({} tokens, {} lines)",
+                member.file.display(),
+                member.line,
+                member.function,
+                member.tokens,
+                member.lines
+            );
+        }
+    }
+
+    let duplicated: usize = clusters.iter().map(|cluster| cluster.functions.len()).sum();
+    println!(
+        "\nDuplicate clusters found: {} ({} functions)",
+        clusters.len(),
+        duplicated
+    );
+}
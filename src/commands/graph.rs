@@ -0,0 +1,194 @@
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use crate::callgraph::CallGraph;
+use crate::output;
+use crate::references::CountOptions;
+use crate::source::{collect_sol_files, expand_path};
+
+#[derive(Parser, Debug)]
+pub struct GraphArgs {
+    /// Path to a Solidity file or directory to graph.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    path: PathBuf,
+
+    /// Root directory to search for function references.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    root: PathBuf,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+
+    /// Only emit the subgraph around this function (`name` or `Contract.name`).
+    #[arg(long, value_name = "FUNCTION")]
+    focus: Option<String>,
+
+    /// Number of call hops around the focused function to include.
+    #[arg(long, default_value_t = 1, requires = "focus")]
+    depth: usize,
+
+    /// Treat occurrences inside string literals as references.
+    #[arg(long)]
+    count_strings: bool,
+
+    /// Treat occurrences inside comments as references, as `vacuum --include-commented` does.
+    #[arg(long)]
+    include_commented: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Json,
+    Mermaid,
+}
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    nodes: Vec<JsonNode<'a>>,
+    edges: Vec<JsonEdge<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    id: &'a str,
+    file: String,
+    line: usize,
+    visibility: &'a str,
+    entry_point: bool,
+    reachable: bool,
+}
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+pub fn run(args: GraphArgs) -> Result<()> {
+    let analyzed_files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        collect_sol_files(&args.path)?
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    let options = CountOptions {
+        count_strings: args.count_strings,
+        include_commented: args.include_commented,
+        log_functions: Vec::new(),
+    };
+    let graph = CallGraph::build(&analyzed_files, &args.root, &options)?;
+
+    let mut included: Vec<usize> = match &args.focus {
+        Some(function) => {
+            let start = graph.find(function);
+            if start.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("Function {:?} is not declared in {:?}.", function, args.path),
+                ));
+            }
+            graph.neighborhood(&start, args.depth).into_iter().collect()
+        }
+        None => (0..graph.nodes.len()).collect(),
+    };
+    included.sort_by(|&a, &b| graph.nodes[a].id.cmp(&graph.nodes[b].id));
+
+    let edges: Vec<(usize, usize)> = graph
+        .edges
+        .iter()
+        .copied()
+        .filter(|(caller, callee)| included.contains(caller) && included.contains(callee))
+        .collect();
+
+    let output = match args.format {
+        GraphFormat::Dot => render_dot(&graph, &included, &edges),
+        GraphFormat::Mermaid => render_mermaid(&graph, &included, &edges),
+        GraphFormat::Json => render_json(&graph, &included, &edges)?,
+    };
+    println!("{}", output);
+
+    Ok(())
+}
+
+fn render_dot(graph: &CallGraph, included: &[usize], edges: &[(usize, usize)]) -> String {
+    let mut out = String::from("digraph calls {\n    node [shape=box];\n");
+    for &index in included {
+        let node = &graph.nodes[index];
+        let color = if !node.reachable {
+            " color=red fontcolor=red"
+        } else if node.is_entry_point() {
+            " color=green fontcolor=green"
+        } else {
+            ""
+        };
+        out.push_str(&format!("    \"{}\" [label=\"{}\"{}];\n", node.id, node.id, color));
+    }
+    for &(caller, callee) in edges {
+        out.push_str(&format!(
+            "    \"{}\" -> \"{}\";\n",
+            graph.nodes[caller].id, graph.nodes[callee].id
+        ));
+    }
+    out.push('}');
+    out
+}
+
+fn render_mermaid(graph: &CallGraph, included: &[usize], edges: &[(usize, usize)]) -> String {
+    let mermaid_id = |index: usize| format!("n{}", index);
+
+    let mut out = String::from("graph LR\n");
+    out.push_str("    classDef unreachable stroke:#d00,color:#d00;\n");
+    out.push_str("    classDef entry stroke:#0a0,color:#0a0;\n");
+    for &index in included {
+        let node = &graph.nodes[index];
+        let class = if !node.reachable {
+            ":::unreachable"
+        } else if node.is_entry_point() {
+            ":::entry"
+        } else {
+            ""
+        };
+        out.push_str(&format!("    {}[\"{}\"]{}\n", mermaid_id(index), node.id, class));
+    }
+    for &(caller, callee) in edges {
+        out.push_str(&format!("    {} --> {}\n", mermaid_id(caller), mermaid_id(callee)));
+    }
+    out.trim_end().to_string()
+}
+
+fn render_json(graph: &CallGraph, included: &[usize], edges: &[(usize, usize)]) -> Result<String> {
+    let json = JsonGraph {
+        nodes: included
+            .iter()
+            .map(|&index| {
+                let node = &graph.nodes[index];
+                JsonNode {
+                    id: &node.id,
+                    file: node.file.display().to_string(),
+                    line: node.line,
+                    visibility: node.visibility.as_str(),
+                    entry_point: node.is_entry_point(),
+                    reachable: node.reachable,
+                }
+            })
+            .collect(),
+        edges: edges
+            .iter()
+            .map(|&(caller, callee)| JsonEdge {
+                from: &graph.nodes[caller].id,
+                to: &graph.nodes[callee].id,
+            })
+            .collect(),
+    };
+
+    output::to_json(&json)
+}
@@ -0,0 +1,412 @@
+use clap::Parser;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::natspec::NatSpec;
+use crate::solidity::{self, Contract, Function, SourceUnit, StateVariable, Visibility};
+use crate::source::{expand_path, Context, SourceFile};
+
+#[derive(Parser, Debug)]
+pub struct InterfaceArgs {
+    /// Solidity file declaring the contract.
+    #[arg(value_name = "FILE", value_parser = expand_path)]
+    file: PathBuf,
+
+    /// Name of the contract to generate an interface for.
+    #[arg(long)]
+    contract: String,
+
+    /// Patterns for function names to leave out of the interface.
+    #[arg(long, value_name = "REGEX")]
+    exclude: Vec<String>,
+
+    /// Version constraint for the `pragma solidity` line, instead of the source file's.
+    #[arg(long, value_name = "VERSION")]
+    pragma: Option<String>,
+
+    /// Write the interface to this file instead of printing it.
+    #[arg(long, value_name = "FILE", value_parser = expand_path)]
+    output: Option<PathBuf>,
+}
+
+fn pragma_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bpragma\s+solidity\s+([^;]+);").unwrap())
+}
+
+fn license_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"SPDX-License-Identifier:\s*([^\s*]+)").unwrap())
+}
+
+fn mutability_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(pure|view|payable)\b").unwrap())
+}
+
+fn returns_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\breturns\s*\(").unwrap())
+}
+
+pub fn run(args: InterfaceArgs) -> Result<()> {
+    let exclude = args
+        .exclude
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid --exclude {:?}: {}", pattern, e),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let source = SourceFile::read(&args.file)?;
+    let unit = solidity::parse(&source);
+    let contract = unit
+        .contracts
+        .iter()
+        .find(|contract| contract.name == args.contract)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("Contract {:?} is not declared in {:?}.", args.contract, args.file),
+            )
+        })?;
+
+    let functions: Vec<&Function> = unit
+        .functions
+        .iter()
+        .filter(|function| function.contract.as_deref() == Some(contract.name.as_str()))
+        .filter(|function| function.visibility.is_externally_visible())
+        .filter(|function| !exclude.iter().any(|regex| regex.is_match(&function.name)))
+        .collect();
+
+    let getters: Vec<&StateVariable> = unit
+        .state_variables
+        .iter()
+        .filter(|variable| variable.contract == contract.name && variable.visibility == Visibility::Public)
+        .filter(|variable| !exclude.iter().any(|regex| regex.is_match(&variable.name)))
+        .collect();
+
+    let interface = render(&source, &unit, contract, &functions, &getters, args.pragma.as_deref());
+
+    match &args.output {
+        Some(path) => {
+            fs::write(path, interface)?;
+            println!("Wrote interface I{} to {:?}.", contract.name, path);
+        }
+        None => print!("{}", interface),
+    }
+
+    Ok(())
+}
+
+fn render(
+    source: &SourceFile,
+    unit: &SourceUnit,
+    contract: &Contract,
+    functions: &[&Function],
+    getters: &[&StateVariable],
+    pragma: Option<&str>,
+) -> String {
+    let content = &source.content;
+    let in_contract = |span: (usize, usize)| contract.body.0 < span.0 && span.1 <= contract.body.1;
+    let in_scope = |span: (usize, usize)| {
+        in_contract(span) || !unit.contracts.iter().any(|c| c.body.0 < span.0 && span.1 <= c.body.1)
+    };
+
+    let license = license_pattern()
+        .captures(content)
+        .map_or("UNLICENSED".to_string(), |cap| cap[1].to_string());
+    let pragma = pragma.map(str::to_string).unwrap_or_else(|| {
+        pragma_pattern()
+            .captures(content)
+            .map_or("^0.8.0".to_string(), |cap| cap[1].trim().to_string())
+    });
+
+    // The functions and getters, in the order they're declared.
+    let mut headers: Vec<(usize, FunctionHeader)> = functions
+        .iter()
+        .map(|function| (function.start, header(source, function)))
+        .chain(getters.iter().map(|variable| (variable.start, getter(unit, variable))))
+        .collect();
+    headers.sort_by_key(|(start, _)| *start);
+
+    // Structs and enums used by the signatures, including those nested in used structs.
+    let mut used: BTreeSet<String> = headers.iter().flat_map(|(_, header)| header.types.clone()).collect();
+    loop {
+        let nested: Vec<String> = unit
+            .structs
+            .iter()
+            .filter(|definition| used.contains(&definition.name) && in_scope(definition.span))
+            .flat_map(|definition| definition.fields.iter().map(|field| base_type(&field.ty)))
+            .filter(|ty| !used.contains(ty))
+            .collect();
+        if nested.is_empty() {
+            break;
+        }
+        used.extend(nested);
+    }
+
+    let mut out = format!(
+        "// SPDX-License-Identifier: {}\npragma solidity {};\n\n",
+        license, pragma
+    );
+    for line in natspec(source, contract.start) {
+        out.push_str(&format!("{}\n", line));
+    }
+    out.push_str(&format!("interface I{} {{\n", contract.name));
+
+    let mut sections: Vec<Vec<String>> = Vec::new();
+    let declarations = |spans: Vec<(usize, usize)>| {
+        spans
+            .into_iter()
+            .map(|span| {
+                let mut text = natspec(source, span.0).join("\n");
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&verbatim(source, span));
+                text
+            })
+            .collect::<Vec<_>>()
+    };
+    sections.push(declarations(
+        unit.enums
+            .iter()
+            .filter(|definition| used.contains(&definition.name) && in_scope(definition.span))
+            .map(|definition| definition.span)
+            .collect(),
+    ));
+    sections.push(declarations(
+        unit.structs
+            .iter()
+            .filter(|definition| used.contains(&definition.name) && in_scope(definition.span))
+            .map(|definition| definition.span)
+            .collect(),
+    ));
+    sections.push(declarations(
+        unit.events
+            .iter()
+            .filter(|definition| in_contract(definition.span))
+            .map(|definition| definition.span)
+            .collect(),
+    ));
+    sections.push(declarations(
+        unit.errors
+            .iter()
+            .filter(|definition| in_contract(definition.span))
+            .map(|definition| definition.span)
+            .collect(),
+    ));
+    sections.push(
+        headers
+            .iter()
+            .map(|(start, header)| {
+                let mut text = natspec(source, *start).join("\n");
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&header.text);
+                text
+            })
+            .collect(),
+    );
+
+    let body = sections
+        .into_iter()
+        .filter(|section| !section.is_empty())
+        .map(|section| section.join("\n\n"))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    for line in body.lines() {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(&format!("    {}\n", line));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// A function declaration rewritten for an interface.
+struct FunctionHeader {
+    text: String,
+    /// Base names of the parameter and return types.
+    types: Vec<String>,
+}
+
+fn header(source: &SourceFile, function: &Function) -> FunctionHeader {
+    let content = &source.content;
+    let params = collapse(&content[function.params_span.0..function.params_span.1]);
+    let rest_start = (function.params_span.1 + 1).min(function.header_end);
+    let rest = &content[rest_start..function.header_end];
+
+    let returns_keyword = returns_pattern()
+        .find_iter(rest)
+        .find(|found| source.context_at(rest_start + found.start()) == Context::Code);
+    let returns = returns_keyword.and_then(|found| {
+        let open = rest_start + found.end() - 1;
+        let close = source.group_end(open)?;
+        Some(collapse(&content[open + 1..close - 1]))
+    });
+    let modifiers_end = returns_keyword.map_or(rest.len(), |found| found.start());
+    let mutability = mutability_pattern()
+        .captures(&rest[..modifiers_end])
+        .map(|cap| format!(" {}", &cap[1]))
+        .unwrap_or_default();
+
+    let mut types: Vec<String> = function.params.iter().map(|param| base_type(&param.ty)).collect();
+    types.extend(function.returns.iter().map(|param| base_type(&param.ty)));
+    let mut text = format!("function {}({}) external{}", function.name, params, mutability);
+    if let Some(returns) = returns {
+        text.push_str(&format!(" returns ({})", returns));
+    }
+    text.push(';');
+
+    FunctionHeader { text, types }
+}
+
+/// Returns the getter the compiler generates for a public state variable: a parameter
+/// for each mapping key and array index, returning the value, or the members of a struct
+/// other than its arrays and mappings.
+fn getter(unit: &SourceUnit, variable: &StateVariable) -> FunctionHeader {
+    let mut params = Vec::new();
+    let mut ty = variable.ty.as_str();
+    loop {
+        if let Some(element) = ty.strip_suffix(']').and_then(|rest| rest.rfind('[').map(|open| &rest[..open])) {
+            params.push("uint256".to_string());
+            ty = element.trim_end();
+        } else if let Some(inner) = ty.strip_prefix("mapping").and_then(|rest| {
+            rest.trim_start().strip_prefix('(').and_then(|rest| rest.strip_suffix(')'))
+        }) {
+            let Some(arrow) = top_level_arrow(inner) else {
+                break;
+            };
+            params.push(located(unit, unnamed(&inner[..arrow]), "calldata"));
+            ty = unnamed(&inner[arrow + 2..]);
+        } else {
+            break;
+        }
+    }
+
+    let members = unit
+        .structs
+        .iter()
+        .find(|definition| definition.name == base_type(ty))
+        .map(|definition| {
+            definition
+                .fields
+                .iter()
+                .filter(|field| !field.ty.starts_with("mapping") && !field.ty.ends_with(']'))
+                .map(|field| (field.ty.as_str(), field.name.as_deref()))
+                .collect::<Vec<_>>()
+        });
+    let returns = members.unwrap_or_else(|| vec![(ty, None)]);
+
+    let mut types: Vec<String> = params.iter().map(|param| base_type(param)).collect();
+    types.extend(returns.iter().map(|(ty, _)| base_type(ty)));
+    let returns: Vec<String> = returns
+        .iter()
+        .map(|(ty, name)| {
+            let ty = located(unit, ty, "memory");
+            name.map_or(ty.clone(), |name| format!("{} {}", ty, name))
+        })
+        .collect();
+    let text = format!(
+        "function {}({}) external view returns ({});",
+        variable.name,
+        params.join(", "),
+        returns.join(", ")
+    );
+    FunctionHeader { text, types }
+}
+
+/// The offset of the `=>` of a mapping's `inner` key and value, outside any nested mapping.
+fn top_level_arrow(inner: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (pos, byte) in inner.bytes().enumerate() {
+        match byte {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth = depth.saturating_sub(1),
+            b'=' if depth == 0 && inner.as_bytes().get(pos + 1) == Some(&b'>') => return Some(pos),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns a mapping's key or value type without the name it may be given, e.g.
+/// `address owner` as `address`.
+fn unnamed(ty: &str) -> &str {
+    let ty = ty.trim();
+    match ty.rsplit_once(' ') {
+        Some((rest, name))
+            if name != "payable" && !name.contains(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '$') =>
+        {
+            rest.trim_end()
+        }
+        _ => ty,
+    }
+}
+
+/// Returns `ty` with the data `location` that strings, bytes, arrays and structs need in
+/// an external function, e.g. `string calldata`.
+fn located(unit: &SourceUnit, ty: &str, location: &str) -> String {
+    let is_struct = unit.structs.iter().any(|definition| definition.name == base_type(ty));
+    if matches!(ty, "string" | "bytes") || ty.ends_with(']') || is_struct {
+        format!("{} {}", ty, location)
+    } else {
+        ty.to_string()
+    }
+}
+
+/// Returns a type without array suffixes or qualification, e.g. `Vault.Order[]` as `Order`.
+fn base_type(ty: &str) -> String {
+    let base = ty.split('[').next().unwrap_or(ty).trim();
+    base.rsplit('.').next().unwrap_or(base).to_string()
+}
+
+/// Joins a possibly multi-line list onto one line.
+fn collapse(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+}
+
+/// Returns the text of a declaration, with the indentation of its first line removed from
+/// every line.
+fn verbatim(source: &SourceFile, span: (usize, usize)) -> String {
+    let first_line = source.line_of(span.0);
+    let indent = source.line_text(first_line).len() - source.line_text(first_line).trim_start().len();
+    source.content[span.0..span.1]
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = line.trim_end();
+            if index == 0 {
+                line
+            } else {
+                let leading = line.len() - line.trim_start().len();
+                &line[leading.min(indent)..]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the NatSpec comment lines directly preceding `start`, indented as in a
+/// top-level declaration.
+fn natspec(source: &SourceFile, start: usize) -> Vec<String> {
+    NatSpec::before(source, start).map_or_else(Vec::new, |natspec| natspec.formatted())
+}
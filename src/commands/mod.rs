@@ -1 +1,13 @@
+pub mod baseline;
+pub mod check;
+pub mod deps;
+pub mod dupes;
+pub mod graph;
+pub mod interface;
+pub mod natspec;
+pub mod rename;
+pub mod selectors;
+pub mod shadow;
+pub mod stats;
+pub mod usages;
 pub mod vacuum;
@@ -0,0 +1,340 @@
+use clap::Parser;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use crate::findings::{Finding, FindingKind};
+use crate::ignore::should_ignore_function;
+use crate::natspec::NatSpec;
+use crate::output::{self, Format};
+use crate::solidity::{self, Declaration, Param, SourceUnit, Visibility};
+use crate::source::{canonical, collect_sol_files, expand_path, SourceFile};
+
+/// How many `@inheritdoc` links are followed before giving up, which also stops cycles.
+const MAX_INHERITDOC_DEPTH: usize = 8;
+
+#[derive(Parser, Debug)]
+pub struct NatspecArgs {
+    /// Path to a Solidity file or directory to audit.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    pub(crate) path: PathBuf,
+
+    /// Root directory searched for the contracts named by `@inheritdoc`.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    pub(crate) root: PathBuf,
+
+    /// Patterns for function names to ignore (e.g., '^test' for functions starting with 'test').
+    #[arg(long, default_values = ["^test"])]
+    pub(crate) ignore: Vec<String>,
+
+    /// Also audit events.
+    #[arg(long)]
+    pub(crate) events: bool,
+
+    /// Also audit custom errors.
+    #[arg(long)]
+    pub(crate) errors: bool,
+
+    /// Exit with an error if any declaration is missing documentation.
+    #[arg(long)]
+    pub(crate) check: bool,
+
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub(crate) format: Format,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum DeclarationKind {
+    Function,
+    Event,
+    Error,
+}
+
+/// What is missing from a declaration's documentation.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Issue {
+    /// No NatSpec comment at all.
+    MissingNatspec,
+    /// Named parameters without a `@param` tag.
+    MissingParams { names: Vec<String> },
+    /// Fewer `@return` tags than return values.
+    MissingReturns { documented: usize, expected: usize },
+    /// An `@inheritdoc` naming a contract without a documented function of that signature.
+    UnresolvedInheritdoc { contract: String },
+}
+
+impl Issue {
+    fn describe(&self) -> String {
+        match self {
+            Issue::MissingNatspec => "missing NatSpec".to_string(),
+            Issue::MissingParams { names } => format!("missing @param for {}", names.join(", ")),
+            Issue::MissingReturns { documented, expected } => {
+                format!("missing @return ({} of {} documented)", documented, expected)
+            }
+            Issue::UnresolvedInheritdoc { contract } => {
+                format!("@inheritdoc {} has no documented function to inherit from", contract)
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Undocumented {
+    file: PathBuf,
+    line: usize,
+    contract: Option<String>,
+    name: String,
+    #[serde(skip)]
+    signature: String,
+    #[serde(skip)]
+    visibility: Option<Visibility>,
+    kind: DeclarationKind,
+    issues: Vec<Issue>,
+}
+
+impl Undocumented {
+    fn finding(&self) -> Finding {
+        Finding {
+            file: self.file.clone(),
+            line: self.line,
+            contract: self.contract.clone(),
+            function: self.name.clone(),
+            signature: self.signature.clone(),
+            context: String::new(),
+            visibility: self.visibility,
+            kind: FindingKind::MissingNatspec,
+            suggestion: None,
+            coverage_hits: None,
+            detail: Some(
+                self.issues
+                    .iter()
+                    .map(Issue::describe)
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+        }
+    }
+}
+
+/// A parsed file, kept around to resolve `@inheritdoc` across files.
+struct Parsed {
+    source: SourceFile,
+    unit: SourceUnit,
+}
+
+/// Audits the files under `PATH`, returning the undocumented declarations as findings.
+pub fn find_undocumented(args: &NatspecArgs) -> Result<Vec<Finding>> {
+    Ok(audit_all(args)?.iter().map(Undocumented::finding).collect())
+}
+
+pub fn run(args: NatspecArgs) -> Result<()> {
+    let undocumented = audit_all(&args)?;
+
+    match args.format {
+        Format::Text => {
+            for item in &undocumented {
+                let name = match &item.contract {
+                    Some(contract) => format!("{}.{}", contract, item.name),
+                    None => item.name.clone(),
+                };
+                for issue in &item.issues {
+                    println!("{}:{} {}: {}", item.file.display(), item.line, name, issue.describe());
+                }
+            }
+            println!("\nTotal undocumented declarations: {}", undocumented.len());
+        }
+        Format::Json => println!("{}", output::to_json(&undocumented)?),
+    }
+
+    if args.check && !undocumented.is_empty() {
+        return Err(Error::other(format!(
+            "Found {} undocumented declaration(s).",
+            undocumented.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Audits every file under `PATH`, resolving `@inheritdoc` against the files under `--root`.
+fn audit_all(args: &NatspecArgs) -> Result<Vec<Undocumented>> {
+    let analyzed_files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        collect_sol_files(&args.path)?
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    let parse = |path: PathBuf, content: String| {
+        let source = SourceFile::new(path, content);
+        let unit = solidity::parse(&source);
+        Parsed { source, unit }
+    };
+    let analyzed: Vec<Parsed> = analyzed_files
+        .par_iter()
+        .map(|path| Ok(parse(path.clone(), fs::read_to_string(path)?)))
+        .collect::<Result<_>>()?;
+    let seen: HashSet<PathBuf> = analyzed_files.iter().map(|path| canonical(path)).collect();
+    let others: Vec<Parsed> = collect_sol_files(&args.root)?
+        .into_par_iter()
+        .filter(|path| !seen.contains(&canonical(path)))
+        .map(|path| {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            parse(path, content)
+        })
+        .collect();
+    let index = DocIndex::new(analyzed.iter().chain(&others));
+
+    Ok(analyzed
+        .iter()
+        .flat_map(|parsed| audit(parsed, &index, args))
+        .collect())
+}
+
+fn audit(parsed: &Parsed, index: &DocIndex, args: &NatspecArgs) -> Vec<Undocumented> {
+    let Parsed { source, unit } = parsed;
+    let mut undocumented = Vec::new();
+    let mut report = |offset: usize, contract: Option<String>, name: &str, signature: String, visibility, kind, issues: Vec<Issue>| {
+        if !issues.is_empty() {
+            undocumented.push(Undocumented {
+                file: source.path.clone(),
+                line: source.line_of(offset),
+                contract,
+                name: name.to_string(),
+                signature,
+                visibility,
+                kind,
+                issues,
+            });
+        }
+    };
+
+    for function in &unit.functions {
+        if !function.visibility.is_externally_visible()
+            || should_ignore_function(&function.name, &args.ignore)
+        {
+            continue;
+        }
+        let natspec = NatSpec::before(source, function.start);
+        let issues = match natspec.as_ref().and_then(NatSpec::inheritdoc) {
+            Some(base) if index.is_documented(base, &function.written_signature(), 0) => Vec::new(),
+            Some(base) => vec![Issue::UnresolvedInheritdoc {
+                contract: base.to_string(),
+            }],
+            None => check(natspec.as_ref(), &function.params, function.returns.len()),
+        };
+        report(
+            function.start,
+            function.contract.clone(),
+            &function.name,
+            function.written_signature(),
+            Some(function.visibility),
+            DeclarationKind::Function,
+            issues,
+        );
+    }
+
+    let declarations = args
+        .events
+        .then_some((&unit.events, DeclarationKind::Event))
+        .into_iter()
+        .chain(args.errors.then_some((&unit.errors, DeclarationKind::Error)));
+    for (declarations, kind) in declarations {
+        for declaration in declarations {
+            let natspec = NatSpec::before(source, declaration.span.0);
+            let issues = check(natspec.as_ref(), &declaration_params(source, declaration), 0);
+            let contract = unit
+                .contracts
+                .iter()
+                .find(|contract| contract.body.0 < declaration.span.0 && declaration.span.1 <= contract.body.1)
+                .map(|contract| contract.name.clone());
+            let signature = declaration.name.clone();
+            report(declaration.span.0, contract, &declaration.name, signature, None, kind, issues);
+        }
+    }
+
+    undocumented
+}
+
+/// Compares a NatSpec comment against the parameters and return values it documents.
+fn check(natspec: Option<&NatSpec>, params: &[Param], returns: usize) -> Vec<Issue> {
+    let Some(natspec) = natspec else {
+        return vec![Issue::MissingNatspec];
+    };
+
+    let mut issues = Vec::new();
+    let documented = natspec.params();
+    let names: Vec<String> = params
+        .iter()
+        .filter_map(|param| param.name.clone())
+        .filter(|name| !documented.contains(&name.as_str()))
+        .collect();
+    if !names.is_empty() {
+        issues.push(Issue::MissingParams { names });
+    }
+    if natspec.returns() < returns {
+        issues.push(Issue::MissingReturns {
+            documented: natspec.returns(),
+            expected: returns,
+        });
+    }
+    issues
+}
+
+/// Parses the parameter list of an event or error declaration.
+fn declaration_params(source: &SourceFile, declaration: &Declaration) -> Vec<Param> {
+    let content = &source.content;
+    content[declaration.span.0..declaration.span.1]
+        .find('(')
+        .map(|open| declaration.span.0 + open)
+        .and_then(|open| Some((open, source.group_end(open)?)))
+        .map(|(open, close)| solidity::parse_params(&source.code_only((open + 1, close - 1))))
+        .unwrap_or_default()
+}
+
+/// The NatSpec of every function, keyed by contract and signature, to resolve
+/// `@inheritdoc`.
+struct DocIndex {
+    functions: HashMap<(String, String), Option<NatSpec>>,
+}
+
+impl DocIndex {
+    fn new<'a>(files: impl Iterator<Item = &'a Parsed>) -> Self {
+        let mut functions = HashMap::new();
+        for Parsed { source, unit } in files {
+            for function in &unit.functions {
+                if let Some(contract) = function.contract.as_deref() {
+                    functions
+                        .entry((contract.to_string(), function.written_signature()))
+                        .or_insert_with(|| NatSpec::before(source, function.start));
+                }
+            }
+        }
+        DocIndex { functions }
+    }
+
+    /// Whether `contract` declares a function with `signature` that has NatSpec of its
+    /// own, or inherits it from one that does.
+    fn is_documented(&self, contract: &str, signature: &str, depth: usize) -> bool {
+        if depth >= MAX_INHERITDOC_DEPTH {
+            return false;
+        }
+        match self.functions.get(&(contract.to_string(), signature.to_string())) {
+            Some(Some(natspec)) => match natspec.inheritdoc() {
+                Some(base) => self.is_documented(base, signature, depth + 1),
+                None => true,
+            },
+            _ => false,
+        }
+    }
+}
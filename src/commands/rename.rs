@@ -0,0 +1,148 @@
+use clap::Parser;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use super::vacuum::{self, AnalysisArgs, RenamedFile};
+use crate::output;
+use crate::source::{self, relative, replace_files};
+
+fn red() -> &'static str {
+    output::ansi("\x1b[31m")
+}
+
+fn green() -> &'static str {
+    output::ansi("\x1b[32m")
+}
+
+fn cyan() -> &'static str {
+    output::ansi("\x1b[36m")
+}
+
+fn reset() -> &'static str {
+    output::ansi("\x1b[0m")
+}
+
+#[derive(Parser, Debug)]
+pub struct RenameArgs {
+    /// Name of the function to rename, e.g. `sweep`.
+    #[arg(value_name = "OLD")]
+    old: String,
+
+    /// The new name of the function.
+    #[arg(value_name = "NEW")]
+    new: String,
+
+    /// Only rename the functions declared with this signature, e.g. `sweep(address)`,
+    /// as written in their declaration, leaving the other overloads alone.
+    #[arg(long, value_name = "SIGNATURE")]
+    signature: Option<String>,
+
+    /// Also rename the function in the signature strings passed to
+    /// `abi.encodeWithSignature` or `keccak256`, e.g. `"sweep(address)"`, which changes the
+    /// selector they compute.
+    #[arg(long)]
+    selectors: bool,
+
+    /// Write the renamed files instead of printing the diff.
+    #[arg(long)]
+    write: bool,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+pub fn run(args: RenameArgs) -> Result<()> {
+    let rename = vacuum::plan_rename(
+        &args.analysis,
+        &args.old,
+        &args.new,
+        args.signature.as_deref(),
+        args.selectors,
+    )?;
+
+    if args.write {
+        // A reference in an unreadable directory would keep the old name.
+        let skipped = source::skipped_dirs();
+        if !skipped.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Skipped {} unreadable director(ies), starting with {:?}, whose references to `{}` would keep that name; nothing was renamed.",
+                    skipped.len(),
+                    skipped[0].path,
+                    args.old
+                ),
+            ));
+        }
+        // Every file is staged before any is replaced, so that a failed write doesn't
+        // leave references renamed in some files and not in others.
+        let files: Vec<_> = rename.files.iter().map(|file| (file.path.as_path(), file.renamed.as_str())).collect();
+        replace_files(&files)?;
+        for file in &rename.files {
+            println!("Updated {:?}.", file.path);
+        }
+    } else {
+        output::page();
+        for file in &rename.files {
+            print_diff(file, &args.analysis.root);
+        }
+    }
+
+    println!(
+        "Renamed {} declaration(s) and {} reference(s) of `{}` to `{}` in {} file(s){}",
+        rename.declarations.len(),
+        rename.references,
+        args.old,
+        args.new,
+        rename.files.len(),
+        if args.write { "." } else { "; pass --write to apply." }
+    );
+    for declaration in &rename.declarations {
+        println!("  {}", declaration);
+    }
+    output::end_pager();
+
+    if !rename.signature_strings.is_empty() {
+        eprintln!(
+            "Warning: left {} signature string(s) naming `{}` as they are, at {}. Pass --selectors to rename them too, which changes the selectors they compute.",
+            rename.signature_strings.len(),
+            args.old,
+            rename.signature_strings.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Prints the changed lines of `file` as a unified diff without context, with paths
+/// relative to `root`, which `git apply --unidiff-zero` applies there.
+fn print_diff(file: &RenamedFile, root: &Path) {
+    let path = relative(file.path.strip_prefix(root).unwrap_or(&file.path));
+    println!("--- a/{}", path.display());
+    println!("+++ b/{}", path.display());
+
+    let original: Vec<&str> = file.original.lines().collect();
+    let renamed: Vec<&str> = file.renamed.lines().collect();
+    let changed: Vec<usize> = (0..original.len()).filter(|&line| original[line] != renamed[line]).collect();
+    // Runs of consecutive changed lines, as hunks.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for line in changed {
+        match hunks.last_mut() {
+            Some((start, count)) if *start + *count == line => *count += 1,
+            _ => hunks.push((line, 1)),
+        }
+    }
+    for (start, count) in hunks {
+        let range = if count == 1 {
+            format!("{}", start + 1)
+        } else {
+            format!("{},{}", start + 1, count)
+        };
+        println!("{}@@ -{} +{} @@{}", cyan(), range, range, reset());
+        for line in &original[start..start + count] {
+            println!("{}-{}{}", red(), line, reset());
+        }
+        for line in &renamed[start..start + count] {
+            println!("{}+{}{}", green(), line, reset());
+        }
+    }
+}
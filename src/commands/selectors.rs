@@ -0,0 +1,502 @@
+use clap::Parser;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::abi::{self, TypeTable};
+use crate::output::{self, Format};
+use crate::signatures::SignatureDatabase;
+use crate::solidity;
+use crate::source::{collect_sol_files, expand_path, Context, SourceFile};
+
+#[derive(Parser, Debug)]
+pub struct SelectorsArgs {
+    /// Path to a Solidity file or directory to list selectors for.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    path: PathBuf,
+
+    /// Comma-separated contracts that share one dispatch table, e.g. the facets of a diamond.
+    #[arg(long, value_name = "CONTRACTS")]
+    facets: Vec<String>,
+
+    /// File of selectors to flag, one `0x12345678 [label]` per line.
+    #[arg(long, value_name = "FILE", value_parser = expand_path)]
+    denylist: Option<PathBuf>,
+
+    /// Look up selectors in the openchain and 4byte signature databases: name the raw
+    /// `0x12345678` literals that match no listed function, and flag listed selectors that
+    /// are known for other signatures too. Lookups are cached on disk.
+    #[arg(long)]
+    resolve_selectors: bool,
+
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    contract: String,
+    signature: String,
+    selector: String,
+    file: PathBuf,
+    line: usize,
+}
+
+#[derive(Serialize)]
+struct Collision {
+    selector: String,
+    /// The contract, or the facet set, in which the selectors clash.
+    scope: String,
+    functions: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Denylisted {
+    selector: String,
+    function: String,
+    label: String,
+}
+
+/// A raw selector literal in the sources that matches no listed function.
+#[derive(Serialize)]
+struct UnknownSelector {
+    selector: String,
+    file: PathBuf,
+    line: usize,
+    /// Signatures the databases know for the selector.
+    candidates: Vec<String>,
+}
+
+/// A listed function whose selector the databases also know for other signatures.
+#[derive(Serialize)]
+struct ForeignCollision {
+    selector: String,
+    function: String,
+    known: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    functions: &'a [Entry],
+    collisions: &'a [Collision],
+    denylisted: &'a [Denylisted],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    unknown_selectors: &'a [UnknownSelector],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    foreign_collisions: &'a [ForeignCollision],
+}
+
+fn selector_literal_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b0x[0-9a-fA-F]{8}\b").unwrap())
+}
+
+pub fn run(args: SelectorsArgs) -> Result<()> {
+    let sol_files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        collect_sol_files(&args.path)?
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    let mut units = Vec::new();
+    let mut types = TypeTable::default();
+    let mut bases: HashMap<String, Vec<String>> = HashMap::new();
+    let mut literals = Vec::new();
+    for path in &sol_files {
+        let source = SourceFile::read(path)?;
+        let unit = solidity::parse(&source);
+        types.add(&unit);
+        for contract in &unit.contracts {
+            bases.entry(contract.name.clone()).or_insert_with(|| contract.bases.clone());
+        }
+        units.push((path, unit));
+        if args.resolve_selectors {
+            literals.extend(selector_literals(&source));
+        }
+    }
+
+    let entries: Vec<Entry> = units
+        .iter()
+        .flat_map(|(path, unit)| {
+            unit.functions
+                .iter()
+                .filter(|function| function.visibility.is_externally_visible())
+                .filter_map(|function| {
+                    let signature = types.signature(function);
+                    Some(Entry {
+                        contract: function.contract.clone()?,
+                        selector: abi::format_selector(abi::selector(&signature)),
+                        signature,
+                        file: path.to_path_buf(),
+                        line: function.line,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let collisions = find_collisions(&entries, &bases, &args.facets);
+    let denylisted = match &args.denylist {
+        Some(path) => check_denylist(&entries, &read_denylist(path)?),
+        None => Vec::new(),
+    };
+    let (unknown, foreign) = if args.resolve_selectors {
+        resolve(&entries, literals)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let report = Report {
+        functions: &entries,
+        collisions: &collisions,
+        denylisted: &denylisted,
+        unknown_selectors: &unknown,
+        foreign_collisions: &foreign,
+    };
+    match args.format {
+        Format::Text => print_text_report(&report),
+        Format::Json => println!("{}", output::to_json(&report)?),
+    }
+
+    Ok(())
+}
+
+/// Finds the `0x12345678` literals in the code of `source` that are used as selectors,
+/// e.g. compared with `msg.sig` or in assembly. Other 4-byte constants, such as masks
+/// and magic values, are left out.
+fn selector_literals(source: &SourceFile) -> Vec<UnknownSelector> {
+    let code = source.code_only((0, source.content.len()));
+    selector_literal_pattern()
+        .find_iter(&code)
+        .filter(|found| source.context_at(found.start()) == Context::Code)
+        .filter(|found| is_selector_use(&code[..found.start()], &code[found.end()..]))
+        .map(|found| UnknownSelector {
+            selector: found.as_str().to_lowercase(),
+            file: source.path.clone(),
+            line: source.line_of(found.start()),
+            candidates: Vec::new(),
+        })
+        .collect()
+}
+
+/// Whether a literal between `before` and `after` is used as a selector: converted with
+/// `bytes4(...)`, compared with `==`, `!=`, assembly's `eq` or a `case` of a `switch`, or
+/// passed first to an `abi.encode*` call, e.g. `abi.encodeWithSelector`.
+fn is_selector_use(before: &str, after: &str) -> bool {
+    let before = before.trim_end();
+    let after = after.trim_start();
+    if before.ends_with("==") || before.ends_with("!=") || after.starts_with("==") || after.starts_with("!=") {
+        return true;
+    }
+    if before.strip_suffix("case").is_some_and(|rest| !rest.ends_with(is_identifier)) {
+        return true;
+    }
+    match enclosing_call(before) {
+        Some(("bytes4" | "eq", _)) => true,
+        Some((callee, argument)) => callee.starts_with("abi.encode") && argument == 0,
+        None => false,
+    }
+}
+
+/// The name of the call whose arguments end `before`, and the index of the argument
+/// being written, e.g. `("eq", 1)` for `eq(shr(224, x), `. A statement or a block ends
+/// the search.
+fn enclosing_call(before: &str) -> Option<(&str, usize)> {
+    let (mut depth, mut argument) = (0usize, 0);
+    for (offset, c) in before.char_indices().rev() {
+        match c {
+            ')' | ']' => depth += 1,
+            '(' | '[' if depth > 0 => depth -= 1,
+            '(' => {
+                let callee = before[..offset].trim_end();
+                let start = callee.rfind(|c: char| !is_identifier(c) && c != '.').map_or(0, |i| i + 1);
+                return Some((&callee[start..], argument));
+            }
+            '[' => return None,
+            ',' if depth == 0 => argument += 1,
+            ';' | '{' | '}' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Looks up the literals that match no listed function, and the listed selectors, in the
+/// signature databases.
+fn resolve(entries: &[Entry], literals: Vec<UnknownSelector>) -> (Vec<UnknownSelector>, Vec<ForeignCollision>) {
+    let listed: HashSet<&str> = entries.iter().map(|entry| entry.selector.as_str()).collect();
+    let mut unknown: Vec<UnknownSelector> = literals
+        .into_iter()
+        .filter(|literal| !listed.contains(literal.selector.as_str()))
+        .collect();
+
+    let mut database = SignatureDatabase::open();
+    database.resolve(
+        unknown
+            .iter()
+            .map(|literal| &literal.selector)
+            .chain(entries.iter().map(|entry| &entry.selector)),
+    );
+
+    for literal in &mut unknown {
+        literal.candidates = database.signatures(&literal.selector).to_vec();
+    }
+    let foreign = entries
+        .iter()
+        .filter_map(|entry| {
+            let known: Vec<String> = database
+                .signatures(&entry.selector)
+                .iter()
+                .filter(|signature| **signature != entry.signature)
+                .cloned()
+                .collect();
+            (!known.is_empty()).then(|| ForeignCollision {
+                selector: entry.selector.clone(),
+                function: format!("{}.{}", entry.contract, entry.signature),
+                known,
+            })
+        })
+        .collect();
+    (unknown, foreign)
+}
+
+/// Finds distinct signatures sharing a selector within a contract, including the
+/// functions it inherits from the contracts and interfaces under the path, or within a
+/// facet set.
+fn find_collisions(entries: &[Entry], bases: &HashMap<String, Vec<String>>, facets: &[String]) -> Vec<Collision> {
+    let mut by_contract: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        by_contract.entry(&entry.contract).or_default().push(entry);
+    }
+
+    let mut scopes: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+    for contract in by_contract.keys() {
+        // An inherited function is listed under the nearest contract declaring its
+        // signature, which overrides the others.
+        let mut signatures: HashSet<&str> = HashSet::new();
+        for declaring in lineage(contract, bases) {
+            for entry in by_contract.get(declaring).into_iter().flatten() {
+                if signatures.insert(&entry.signature) {
+                    scopes
+                        .entry((contract.to_string(), entry.selector.clone()))
+                        .or_default()
+                        .insert(format!("{}.{}", entry.contract, entry.signature));
+                }
+            }
+        }
+    }
+
+    for entry in entries {
+        let function = format!("{}.{}", entry.contract, entry.signature);
+        for facet_set in facets_containing(facets, &entry.contract) {
+            scopes
+                .entry((facet_set, entry.selector.clone()))
+                .or_default()
+                .insert(function.clone());
+        }
+    }
+
+    scopes
+        .into_iter()
+        .filter(|(_, functions)| functions.len() > 1)
+        .map(|((scope, selector), functions)| Collision {
+            selector,
+            scope,
+            functions: functions.into_iter().collect(),
+        })
+        .collect()
+}
+
+/// `contract` followed by the contracts it inherits from, nearest first, each once.
+fn lineage<'a>(contract: &'a str, bases: &'a HashMap<String, Vec<String>>) -> Vec<&'a str> {
+    let mut lineage = vec![contract];
+    let mut index = 0;
+    while let Some(&current) = lineage.get(index) {
+        for base in bases.get(current).into_iter().flatten() {
+            if !lineage.contains(&base.as_str()) {
+                lineage.push(base);
+            }
+        }
+        index += 1;
+    }
+    lineage
+}
+
+fn facets_containing(facets: &[String], contract: &str) -> Vec<String> {
+    facets
+        .iter()
+        .filter(|set| set.split(',').any(|member| member.trim() == contract))
+        .map(|set| format!("facets {}", set))
+        .collect()
+}
+
+fn read_denylist(path: &PathBuf) -> Result<HashMap<String, String>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (selector, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            (selector.to_lowercase(), label.trim().to_string())
+        })
+        .collect())
+}
+
+fn check_denylist(entries: &[Entry], denylist: &HashMap<String, String>) -> Vec<Denylisted> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            Some(Denylisted {
+                label: denylist.get(&entry.selector)?.clone(),
+                selector: entry.selector.clone(),
+                function: format!("{}.{}", entry.contract, entry.signature),
+            })
+        })
+        .collect()
+}
+
+fn print_text_report(report: &Report) {
+    let Report {
+        functions: entries,
+        collisions,
+        denylisted,
+        unknown_selectors,
+        foreign_collisions,
+    } = *report;
+    let contract_width = entries.iter().map(|e| e.contract.len()).max().unwrap_or(0).max(8);
+    let signature_width = entries.iter().map(|e| e.signature.len()).max().unwrap_or(0).max(9);
+
+    println!(
+        "{:<cw$}  {:<sw$}  Selector",
+        "Contract",
+        "Signature",
+        cw = contract_width,
+        sw = signature_width
+    );
+    for entry in entries {
+        println!(
+            "{:<cw$}  {:<sw$}  {}",
+            entry.contract,
+            entry.signature,
+            entry.selector,
+            cw = contract_width,
+            sw = signature_width
+        );
+    }
+
+    if !collisions.is_empty() {
+        println!("\nSelector collisions:");
+        for collision in collisions {
+            println!(
+                "- {} in {}: {}",
+                collision.selector,
+                collision.scope,
+                collision.functions.join(", ")
+            );
+        }
+    }
+
+    if !denylisted.is_empty() {
+        println!("\nDenylisted selectors:");
+        for entry in denylisted {
+            if entry.label.is_empty() {
+                println!("- {} {}", entry.selector, entry.function);
+            } else {
+                println!("- {} {} ({})", entry.selector, entry.function, entry.label);
+            }
+        }
+    }
+
+    if !unknown_selectors.is_empty() {
+        println!("\nSelector literals without a listed function:");
+        for literal in unknown_selectors {
+            let candidates = if literal.candidates.is_empty() {
+                "unknown".to_string()
+            } else {
+                literal.candidates.join(", ")
+            };
+            println!(
+                "- {} at {}:{} ({})",
+                literal.selector,
+                literal.file.display(),
+                literal.line,
+                candidates
+            );
+        }
+    }
+
+    if !foreign_collisions.is_empty() {
+        println!("\nSelectors also known for other signatures:");
+        for collision in foreign_collisions {
+            println!(
+                "- {} {} is also {}",
+                collision.selector,
+                collision.function,
+                collision.known.join(", ")
+            );
+        }
+    }
+
+    println!(
+        "\nSelectors listed: {} ({} collisions, {} denylisted)",
+        entries.len(),
+        collisions.len(),
+        denylisted.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literals(code: &str) -> Vec<String> {
+        let source = SourceFile::new(PathBuf::from("A.sol"), format!("contract A {{\n{}\n}}\n", code));
+        selector_literals(&source).into_iter().map(|literal| literal.selector).collect()
+    }
+
+    #[test]
+    fn selector_uses_are_found() {
+        let code = "function f(bytes calldata data) external {
+    if (msg.sig == 0x01681a62) {}
+    require(0x0000dead != bytes4(data), \"no\");
+    bytes4 s = bytes4(0xA9059CBB);
+    bytes memory call = abi.encodeWithSelector(0x23b872dd, msg.sender, 0x00000001);
+    assembly {
+        if eq(shr(224, calldataload(0)), 0x095ea7b3) {}
+        switch selector case 0x70a08231 {}
+    }
+}";
+        assert_eq!(
+            literals(code),
+            ["0x01681a62", "0x0000dead", "0xa9059cbb", "0x23b872dd", "0x095ea7b3", "0x70a08231"]
+        );
+    }
+
+    #[test]
+    fn other_constants_are_not_selectors() {
+        let code = "uint256 constant MASK = 0xffffffff;
+uint32 constant MAGIC = 0xcafebabe;
+function f(uint256 x) external pure returns (uint256) {
+    return (x & 0x0000ffff) + uint32(0x12345678) + abi.encode(1, 0x87654321).length;
+    // if (msg.sig == 0x01681a62) {}
+}";
+        assert!(literals(code).is_empty(), "{:?}", literals(code));
+    }
+}
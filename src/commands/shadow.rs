@@ -0,0 +1,439 @@
+use clap::Parser;
+use regex::Regex;
+use serde::Serialize;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::findings::{self, Finding, FindingKind, Severity, SeverityPolicy, Suppressions};
+use crate::ignore::should_ignore_function;
+use crate::inheritance::{self, Hierarchy, Parsed};
+use crate::output::{self, FindingsFormat};
+use crate::solidity::{self, Contract, ContractKind, Function, SourceUnit, StateVariable, Visibility};
+use crate::source::{collect_sol_files, expand_path, Context, SourceFile};
+
+#[derive(Parser, Debug)]
+pub struct ShadowArgs {
+    /// Path to a Solidity file or directory to check.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    path: PathBuf,
+
+    /// Root directory searched for the base contracts of the analyzed contracts.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    root: PathBuf,
+
+    /// Patterns for function names to ignore (e.g., '^test' for functions starting with 'test').
+    #[arg(long, default_values = ["^test"])]
+    ignore: Vec<String>,
+
+    /// Exit with an error if any declaration is shadowed.
+    #[arg(long)]
+    check: bool,
+
+    /// Output format of the report. The CI formats grade the findings by the
+    /// `[severity]` table of `wand.toml` in --root, like `vacuum`.
+    #[arg(long, value_enum, default_value_t = FindingsFormat::Text)]
+    format: FindingsFormat,
+
+    /// File of finding fingerprints, one per line, that SARIF output marks as suppressed,
+    /// e.g. those dismissed in GitHub code scanning.
+    #[arg(long, value_name = "FILE", value_parser = expand_path)]
+    suppressions: Option<PathBuf>,
+
+    /// Directory that the file URIs of SARIF results are relative to, instead of the root
+    /// of the git repository containing --root.
+    #[arg(long, value_name = "DIR", value_parser = expand_path)]
+    relative_to: Option<PathBuf>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ShadowKind {
+    /// A local variable with the name of a state variable.
+    LocalShadowsState,
+    /// A parameter with the name of a state variable of the contract itself, or of a
+    /// non-public one it inherits.
+    ParamShadowsState,
+    /// A parameter with the name of an inherited public state variable, i.e. its getter.
+    ParamShadowsInheritedGetter,
+    /// A function redeclaring an inherited one without `override`.
+    MissingOverride,
+}
+
+impl ShadowKind {
+    /// The kind of finding, which the severity policy grades: a hidden getter is less
+    /// likely to be a bug than a variable read instead of the state it shadows.
+    fn finding_kind(self) -> FindingKind {
+        match self {
+            ShadowKind::ParamShadowsInheritedGetter => FindingKind::ShadowedGetter,
+            _ => FindingKind::Shadowing,
+        }
+    }
+}
+
+/// Where a declaration is.
+#[derive(Serialize)]
+struct Location {
+    contract: String,
+    name: String,
+    file: PathBuf,
+    line: usize,
+}
+
+#[derive(Serialize)]
+struct Shadowing {
+    kind: ShadowKind,
+    /// Graded by the `[severity]` table of `wand.toml`, `error` or `warning` by default.
+    severity: Severity,
+    /// The function, constructor or modifier the shadowing declaration belongs to.
+    scope: String,
+    shadowing: Location,
+    shadowed: Location,
+}
+
+impl Shadowing {
+    fn new(kind: ShadowKind, scope: String, shadowing: Location, shadowed: Location, policy: &SeverityPolicy) -> Self {
+        let mut shadowing = Shadowing {
+            kind,
+            severity: Severity::Error,
+            scope,
+            shadowing,
+            shadowed,
+        };
+        shadowing.severity = policy.severity(&shadowing.finding());
+        shadowing
+    }
+
+    fn describe(&self) -> String {
+        let shadowed = format!(
+            "{}.{} ({}:{})",
+            self.shadowed.contract,
+            self.shadowed.name,
+            self.shadowed.file.display(),
+            self.shadowed.line
+        );
+        match self.kind {
+            ShadowKind::LocalShadowsState => format!(
+                "Local `{}` in {} shadows state variable {}",
+                self.shadowing.name, self.scope, shadowed
+            ),
+            ShadowKind::ParamShadowsState => format!(
+                "Parameter `{}` of {} shadows state variable {}",
+                self.shadowing.name, self.scope, shadowed
+            ),
+            ShadowKind::ParamShadowsInheritedGetter => format!(
+                "Parameter `{}` of {} shadows the getter of inherited state variable {}",
+                self.shadowing.name, self.scope, shadowed
+            ),
+            ShadowKind::MissingOverride => format!(
+                "Function {} redeclares {} without `override`",
+                self.scope, shadowed
+            ),
+        }
+    }
+
+    /// The finding of the CI formats, at the shadowing declaration. Its signature names
+    /// the scope too, so that two functions' locals of the same name are told apart.
+    fn finding(&self) -> Finding {
+        Finding {
+            file: self.shadowing.file.clone(),
+            line: self.shadowing.line,
+            contract: Some(self.shadowing.contract.clone()),
+            function: self.shadowing.name.clone(),
+            signature: match self.kind {
+                ShadowKind::MissingOverride => self.shadowing.name.clone(),
+                _ => format!("{} in {}", self.shadowing.name, self.scope),
+            },
+            context: String::new(),
+            visibility: None,
+            kind: self.kind.finding_kind(),
+            detail: Some(self.describe()),
+            suggestion: None,
+            coverage_hits: None,
+        }
+    }
+}
+
+/// A variable declared in a function, constructor or modifier.
+struct Variable {
+    name: String,
+    offset: usize,
+}
+
+/// A function, constructor or modifier whose parameters and locals may shadow state.
+struct Scope {
+    name: String,
+    params: Vec<Variable>,
+    locals: Vec<Variable>,
+}
+
+fn constructor_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(?:constructor|modifier\s+([a-zA-Z0-9_$]+))\s*\(").unwrap())
+}
+
+/// A local variable declaration at the start of a statement, a `for` initializer, a
+/// tuple assignment or a `catch` clause.
+fn local_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"[;{}(,]\s*(?:u?int\d*|bytes\d*|address(?:\s+payable)?|bool|string|[A-Z][a-zA-Z0-9_$]*(?:\.[a-zA-Z_$][a-zA-Z0-9_$]*)*)(?:\s*\[[^\]]*\])*\s+(?:(?:memory|storage|calldata)\s+)?([a-zA-Z_$][a-zA-Z0-9_$]*)\s*[=;),]",
+        )
+        .unwrap()
+    })
+}
+
+fn override_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\boverride\b").unwrap())
+}
+
+pub fn run(args: ShadowArgs) -> Result<()> {
+    let analyzed_files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        collect_sol_files(&args.path)?
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    let policy = Config::load(&args.root)?.severity;
+    let files = inheritance::load(&analyzed_files, &args.root)?;
+    let hierarchy = Hierarchy::new(&files);
+
+    let mut shadowings = Vec::new();
+    for (index, file) in files[..analyzed_files.len()].iter().enumerate() {
+        for contract in &file.unit.contracts {
+            let bases = hierarchy.ancestors(contract);
+            shadowings.extend(check_contract(&files, index, contract, &bases, &args, &policy));
+        }
+    }
+    shadowings.sort_by(|a, b| {
+        a.severity
+            .cmp(&b.severity)
+            .then(a.shadowing.file.cmp(&b.shadowing.file))
+            .then(a.shadowing.line.cmp(&b.shadowing.line))
+    });
+
+    match args.format {
+        FindingsFormat::Text => {
+            for shadowing in &shadowings {
+                println!(
+                    "[{}] {}:{} {}",
+                    shadowing.severity.as_str(),
+                    shadowing.shadowing.file.display(),
+                    shadowing.shadowing.line,
+                    shadowing.describe()
+                );
+            }
+            println!("\nTotal shadowed declarations: {}", shadowings.len());
+        }
+        FindingsFormat::Json => println!("{}", output::to_json(&shadowings)?),
+        format => {
+            let findings: Vec<Finding> = shadowings.iter().map(Shadowing::finding).collect();
+            let suppressions = Suppressions::load(args.suppressions.as_deref())?;
+            let uri_base = findings::uri_base(&args.root, args.relative_to.as_deref());
+            if let Some(rendered) = findings::render(format, &findings, &policy, &suppressions, &uri_base)? {
+                println!("{}", rendered);
+            }
+        }
+    }
+
+    if args.check && !shadowings.is_empty() {
+        return Err(Error::other(format!(
+            "Found {} shadowed declaration(s).",
+            shadowings.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_contract(
+    files: &[Parsed],
+    index: usize,
+    contract: &Contract,
+    bases: &[(usize, &Contract)],
+    args: &ShadowArgs,
+    policy: &SeverityPolicy,
+) -> Vec<Shadowing> {
+    let file = &files[index];
+    let location = |file: usize, contract: &str, name: &str, offset: usize| Location {
+        contract: contract.to_string(),
+        name: name.to_string(),
+        file: files[file].source.path.clone(),
+        line: files[file].source.line_of(offset),
+    };
+
+    // State variables visible in the contract: its own, then the non-private inherited ones.
+    let mut state: Vec<(usize, &StateVariable, bool)> = file
+        .unit
+        .state_variables
+        .iter()
+        .filter(|variable| variable.contract == contract.name)
+        .map(|variable| (index, variable, false))
+        .collect();
+    for &(base_file, base) in bases {
+        state.extend(
+            files[base_file]
+                .unit
+                .state_variables
+                .iter()
+                .filter(|variable| variable.contract == base.name && variable.visibility != Visibility::Private)
+                .map(|variable| (base_file, variable, true)),
+        );
+    }
+
+    let mut shadowings = Vec::new();
+    for scope in scopes(&file.source, &file.unit, contract) {
+        if should_ignore_function(&scope.name, &args.ignore) {
+            continue;
+        }
+        let scope_name = format!("{}.{}", contract.name, scope.name);
+        let declared = scope
+            .params
+            .iter()
+            .map(|variable| (variable, true))
+            .chain(scope.locals.iter().map(|variable| (variable, false)));
+        for (variable, is_param) in declared {
+            let Some(&(state_file, shadowed, inherited)) = state.iter().find(|(_, state, _)| state.name == variable.name) else {
+                continue;
+            };
+            let kind = match (is_param, inherited && shadowed.visibility == Visibility::Public) {
+                (false, _) => ShadowKind::LocalShadowsState,
+                (true, true) => ShadowKind::ParamShadowsInheritedGetter,
+                (true, false) => ShadowKind::ParamShadowsState,
+            };
+            let shadowed = Location {
+                contract: shadowed.contract.clone(),
+                name: shadowed.name.clone(),
+                file: files[state_file].source.path.clone(),
+                line: shadowed.line,
+            };
+            let shadowing = location(index, &contract.name, &variable.name, variable.offset);
+            shadowings.push(Shadowing::new(kind, scope_name.clone(), shadowing, shadowed, policy));
+        }
+    }
+
+    for function in own_functions(&file.unit, contract) {
+        if should_ignore_function(&function.name, &args.ignore) {
+            continue;
+        }
+        let header = file.source.code_only((function.params_span.1, function.header_end));
+        if override_pattern().is_match(&header) {
+            continue;
+        }
+        let signature = function.written_signature();
+        // Implementing an interface function doesn't need `override` since Solidity 0.8.8.
+        let inherited = bases
+            .iter()
+            .filter(|(_, base)| base.kind != ContractKind::Interface)
+            .find_map(|&(base_file, base)| {
+                own_functions(&files[base_file].unit, base)
+                    .find(|candidate| candidate.visibility != Visibility::Private && candidate.written_signature() == signature)
+                    .map(|candidate| (base_file, base, candidate))
+            });
+        if let Some((base_file, base, shadowed)) = inherited {
+            shadowings.push(Shadowing::new(
+                ShadowKind::MissingOverride,
+                format!("{}.{}", contract.name, signature),
+                location(index, &contract.name, &signature, function.name_offset),
+                location(base_file, &base.name, &shadowed.written_signature(), shadowed.name_offset),
+                policy,
+            ));
+        }
+    }
+
+    shadowings
+}
+
+fn own_functions<'a>(unit: &'a SourceUnit, contract: &'a Contract) -> impl Iterator<Item = &'a Function> + 'a {
+    unit.functions
+        .iter()
+        .filter(move |function| function.contract.as_deref() == Some(contract.name.as_str()))
+}
+
+/// Returns the functions, constructor and modifiers of `contract` with their parameters,
+/// named return variables and local variables.
+fn scopes(source: &SourceFile, unit: &SourceUnit, contract: &Contract) -> Vec<Scope> {
+    let mut scopes: Vec<Scope> = own_functions(unit, contract)
+        .map(|function| {
+            let returns_start = function.params_span.1.min(function.header_end);
+            let mut params = variables_named(source, function.params_span, function.params.iter().filter_map(|param| param.name.as_deref()));
+            params.extend(variables_named(
+                source,
+                (returns_start, function.header_end),
+                function.returns.iter().filter_map(|param| param.name.as_deref()),
+            ));
+            Scope {
+                name: function.name.clone(),
+                params,
+                locals: function.body.map(|body| locals(source, body)).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let content = &source.content;
+    for cap in constructor_pattern().captures_iter(&content[contract.body.0..contract.body.1]) {
+        let start = contract.body.0 + cap.get(0).unwrap().start();
+        let open = contract.body.0 + cap.get(0).unwrap().end() - 1;
+        if source.context_at(start) != Context::Code {
+            continue;
+        }
+        let Some(close) = source.group_end(open) else {
+            continue;
+        };
+        let param_names: Vec<String> = solidity::parse_params(&source.code_only((open + 1, close - 1)))
+            .into_iter()
+            .filter_map(|param| param.name)
+            .collect();
+        let body = (close..contract.body.1)
+            .find(|&pos| matches!(content.as_bytes()[pos], b'{' | b';') && source.context_at(pos) == Context::Code)
+            .filter(|&pos| content.as_bytes()[pos] == b'{')
+            .and_then(|open| Some((open, source.block_end(open)?)));
+        scopes.push(Scope {
+            name: cap.get(1).map_or("constructor".to_string(), |name| name.as_str().to_string()),
+            params: variables_named(source, (open, close), param_names.iter().map(String::as_str)),
+            locals: body.map(|body| locals(source, body)).unwrap_or_default(),
+        });
+    }
+
+    scopes
+}
+
+/// Finds the declarations of `names` within `span`, e.g. a parameter list.
+fn variables_named<'a>(source: &SourceFile, span: (usize, usize), names: impl Iterator<Item = &'a str>) -> Vec<Variable> {
+    let code = source.code_only(span);
+    names
+        .filter_map(|name| {
+            let pattern = Regex::new(&format!(r"\b{}\b", regex::escape(name))).ok()?;
+            let found = pattern.find_iter(&code).last()?;
+            Some(Variable {
+                name: name.to_string(),
+                offset: span.0 + found.start(),
+            })
+        })
+        .collect()
+}
+
+fn locals(source: &SourceFile, body: (usize, usize)) -> Vec<Variable> {
+    let code = source.code_only(body);
+    let mut variables = Vec::new();
+    let mut pos = 0;
+    // Declarations can be adjacent, e.g. `(uint a, uint b)`, so each search starts at the
+    // delimiter that ended the previous match.
+    while let Some(cap) = local_pattern().captures_at(&code, pos) {
+        let name = cap.get(1).unwrap();
+        variables.push(Variable {
+            name: name.as_str().to_string(),
+            offset: body.0 + name.start(),
+        });
+        pos = cap.get(0).unwrap().end() - 1;
+    }
+    variables
+}
@@ -0,0 +1,335 @@
+use clap::Parser;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+
+use crate::output::{self, Format};
+use crate::solidity::{self, ContractKind, Visibility};
+use crate::source::{collect_sol_files, expand_path, Context, SourceFile};
+
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    /// Path to a Solidity file or directory to measure.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    path: PathBuf,
+
+    /// Also break the metrics down by directory.
+    #[arg(long)]
+    by_dir: bool,
+
+    /// Number of functions and contracts to list as the largest.
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+/// Counts that add up across files and directories.
+#[derive(Serialize, Default, Clone)]
+struct Metrics {
+    files: usize,
+    contracts: usize,
+    abstract_contracts: usize,
+    interfaces: usize,
+    libraries: usize,
+    functions: VisibilityCounts,
+    events: usize,
+    errors: usize,
+    lines: usize,
+    code_lines: usize,
+    comment_lines: usize,
+    blank_lines: usize,
+    /// Total lines of the functions that have a body.
+    function_lines: usize,
+    implemented_functions: usize,
+}
+
+#[derive(Serialize, Default, Clone)]
+struct VisibilityCounts {
+    external: usize,
+    public: usize,
+    internal: usize,
+    private: usize,
+}
+
+impl VisibilityCounts {
+    fn total(&self) -> usize {
+        self.external + self.public + self.internal + self.private
+    }
+}
+
+impl AddAssign<&Metrics> for Metrics {
+    fn add_assign(&mut self, other: &Metrics) {
+        self.files += other.files;
+        self.contracts += other.contracts;
+        self.abstract_contracts += other.abstract_contracts;
+        self.interfaces += other.interfaces;
+        self.libraries += other.libraries;
+        self.functions.external += other.functions.external;
+        self.functions.public += other.functions.public;
+        self.functions.internal += other.functions.internal;
+        self.functions.private += other.functions.private;
+        self.events += other.events;
+        self.errors += other.errors;
+        self.lines += other.lines;
+        self.code_lines += other.code_lines;
+        self.comment_lines += other.comment_lines;
+        self.blank_lines += other.blank_lines;
+        self.function_lines += other.function_lines;
+        self.implemented_functions += other.implemented_functions;
+    }
+}
+
+impl Metrics {
+    fn average_function_lines(&self) -> f64 {
+        if self.implemented_functions == 0 {
+            0.0
+        } else {
+            self.function_lines as f64 / self.implemented_functions as f64
+        }
+    }
+}
+
+/// A function or contract ranked by its length.
+#[derive(Serialize, Clone)]
+struct Ranked {
+    name: String,
+    file: PathBuf,
+    line: usize,
+    lines: usize,
+}
+
+struct FileStats {
+    file: PathBuf,
+    metrics: Metrics,
+    functions: Vec<Ranked>,
+    contracts: Vec<Ranked>,
+}
+
+#[derive(Serialize)]
+struct MetricsReport<'a> {
+    #[serde(flatten)]
+    metrics: &'a Metrics,
+    average_function_lines: f64,
+}
+
+#[derive(Serialize)]
+struct DirectoryReport<'a> {
+    directory: &'a Path,
+    #[serde(flatten)]
+    metrics: MetricsReport<'a>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    total: MetricsReport<'a>,
+    largest_functions: &'a [Ranked],
+    largest_contracts: &'a [Ranked],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    directories: Option<Vec<DirectoryReport<'a>>>,
+}
+
+pub fn run(args: StatsArgs) -> Result<()> {
+    let sol_files = if args.path.is_file() {
+        vec![args.path.clone()]
+    } else if args.path.is_dir() {
+        collect_sol_files(&args.path)?
+    } else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Path {:?} does not exist.", args.path),
+        ));
+    };
+
+    let files: Vec<FileStats> = sol_files
+        .par_iter()
+        .map(|path| Ok(measure(&SourceFile::read(path)?)))
+        .collect::<Result<_>>()?;
+
+    let mut total = Metrics::default();
+    let mut directories: BTreeMap<PathBuf, Metrics> = BTreeMap::new();
+    for file in &files {
+        total += &file.metrics;
+        let directory = file.file.parent().unwrap_or(Path::new("")).to_path_buf();
+        *directories.entry(directory).or_default() += &file.metrics;
+    }
+
+    let largest = |items: Vec<Ranked>| {
+        let mut items = items;
+        items.sort_by(|a, b| b.lines.cmp(&a.lines).then(a.file.cmp(&b.file)).then(a.line.cmp(&b.line)));
+        items.truncate(args.top);
+        items
+    };
+    let largest_functions = largest(files.iter().flat_map(|file| file.functions.clone()).collect());
+    let largest_contracts = largest(files.iter().flat_map(|file| file.contracts.clone()).collect());
+
+    match args.format {
+        Format::Text => {
+            print_metrics(&args.path.display().to_string(), &total);
+            print_largest("Largest functions", &largest_functions);
+            print_largest("Largest contracts", &largest_contracts);
+            if args.by_dir {
+                for (directory, metrics) in &directories {
+                    print_metrics(&format!("directory {}", directory.display()), metrics);
+                }
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            output::to_json(&Report {
+                total: MetricsReport {
+                    metrics: &total,
+                    average_function_lines: total.average_function_lines(),
+                },
+                largest_functions: &largest_functions,
+                largest_contracts: &largest_contracts,
+                directories: args.by_dir.then(|| {
+                    directories
+                        .iter()
+                        .map(|(directory, metrics)| DirectoryReport {
+                            directory,
+                            metrics: MetricsReport {
+                                metrics,
+                                average_function_lines: metrics.average_function_lines(),
+                            },
+                        })
+                        .collect()
+                }),
+            })?
+        ),
+    }
+
+    Ok(())
+}
+
+fn measure(source: &SourceFile) -> FileStats {
+    let unit = solidity::parse(source);
+    let mut metrics = Metrics {
+        files: 1,
+        events: unit.events.len(),
+        errors: unit.errors.len(),
+        ..Metrics::default()
+    };
+
+    for contract in &unit.contracts {
+        match contract.kind {
+            ContractKind::Contract => metrics.contracts += 1,
+            ContractKind::Abstract => metrics.abstract_contracts += 1,
+            ContractKind::Interface => metrics.interfaces += 1,
+            ContractKind::Library => metrics.libraries += 1,
+        }
+    }
+
+    let mut functions = Vec::new();
+    for function in &unit.functions {
+        match function.visibility {
+            Visibility::External => metrics.functions.external += 1,
+            Visibility::Public => metrics.functions.public += 1,
+            Visibility::Internal => metrics.functions.internal += 1,
+            Visibility::Private => metrics.functions.private += 1,
+        }
+        if let Some((_, end)) = function.body {
+            let lines = source.line_of(end) - source.line_of(function.start) + 1;
+            metrics.function_lines += lines;
+            metrics.implemented_functions += 1;
+            functions.push(Ranked {
+                name: function.qualified_name(),
+                file: source.path.clone(),
+                line: function.line,
+                lines,
+            });
+        }
+    }
+
+    let contracts = unit
+        .contracts
+        .iter()
+        .map(|contract| Ranked {
+            name: contract.name.clone(),
+            file: source.path.clone(),
+            line: contract.line,
+            lines: source.line_of(contract.body.1) - contract.line + 1,
+        })
+        .collect();
+
+    let mut offset = 0;
+    for line in source.content.split('\n') {
+        metrics.lines += 1;
+        let mut has_code = false;
+        let mut has_comment = false;
+        for (pos, byte) in line.bytes().enumerate() {
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            match source.context_at(offset + pos) {
+                Context::Comment => has_comment = true,
+                Context::Code | Context::String => has_code = true,
+            }
+        }
+        match (has_code, has_comment) {
+            (true, _) => metrics.code_lines += 1,
+            (false, true) => metrics.comment_lines += 1,
+            (false, false) => metrics.blank_lines += 1,
+        }
+        offset += line.len() + 1;
+    }
+    // A trailing newline doesn't start another line.
+    if source.content.ends_with('\n') {
+        metrics.lines -= 1;
+        metrics.blank_lines -= 1;
+    }
+
+    FileStats {
+        file: source.path.clone(),
+        metrics,
+        functions,
+        contracts,
+    }
+}
+
+fn print_metrics(title: &str, metrics: &Metrics) {
+    println!("\nStatistics for {}:", title);
+    let rows = [
+        ("Files", metrics.files.to_string()),
+        ("Contracts", metrics.contracts.to_string()),
+        ("Abstract contracts", metrics.abstract_contracts.to_string()),
+        ("Interfaces", metrics.interfaces.to_string()),
+        ("Libraries", metrics.libraries.to_string()),
+        ("Functions", metrics.functions.total().to_string()),
+        ("  external", metrics.functions.external.to_string()),
+        ("  public", metrics.functions.public.to_string()),
+        ("  internal", metrics.functions.internal.to_string()),
+        ("  private", metrics.functions.private.to_string()),
+        ("Events", metrics.events.to_string()),
+        ("Errors", metrics.errors.to_string()),
+        ("Lines", metrics.lines.to_string()),
+        ("  code", metrics.code_lines.to_string()),
+        ("  comment", metrics.comment_lines.to_string()),
+        ("  blank", metrics.blank_lines.to_string()),
+        ("Average function length", format!("{:.1} lines", metrics.average_function_lines())),
+    ];
+    for (label, value) in rows {
+        println!("  {:<24} {:>10}", label, value);
+    }
+}
+
+fn print_largest(title: &str, items: &[Ranked]) {
+    if items.is_empty() {
+        return;
+    }
+    println!("\n{}:", title);
+    for item in items {
+        println!(
+            "  {:>5} lines  {} ({}:{})",
+            item.lines,
+            item.name,
+            item.file.display(),
+            item.line
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use clap::Parser;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Error, Result};
+use std::path::Path;
+
+use super::vacuum::{self, AnalysisArgs, DeclarationSite, FunctionReference};
+use crate::output::{self, Format};
+
+#[derive(Parser, Debug)]
+pub struct UsagesArgs {
+    /// Name of the function to list the references of, e.g. `sweep`.
+    #[arg(value_name = "NAME")]
+    name: String,
+
+    /// Only look up the functions declared with this signature, e.g. `sweep(address)`,
+    /// as written in their declaration. Overloads still share the references to the name.
+    #[arg(long, value_name = "SIGNATURE")]
+    signature: Option<String>,
+
+    /// Output format of the listing.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+#[derive(Serialize)]
+struct UsagesReport<'a> {
+    name: &'a str,
+    declarations: &'a [DeclarationSite],
+    references: &'a [FunctionReference],
+    /// The references that keep the function used, none of which means vacuum reports it.
+    counted: usize,
+}
+
+pub fn run(args: UsagesArgs) -> Result<()> {
+    let found = vacuum::find_references(&args.analysis, &args.name, args.signature.as_deref())?;
+    let counted = found.references.iter().filter(|reference| reference.counts).count();
+
+    match args.format {
+        Format::Json => println!(
+            "{}",
+            output::to_json(&UsagesReport {
+                name: &args.name,
+                declarations: &found.declarations,
+                references: &found.references,
+                counted,
+            })?
+        ),
+        Format::Text => print_text(&args.name, &found.declarations, &found.references, counted),
+    }
+
+    // Like vacuum, which would report the function as unused.
+    if counted == 0 {
+        return Err(Error::other(format!("No usages of `{}` found.", args.name)));
+    }
+    Ok(())
+}
+
+fn print_text(name: &str, declarations: &[DeclarationSite], references: &[FunctionReference], counted: usize) {
+    println!("Declarations of `{}`:", name);
+    for declaration in declarations {
+        println!(
+            "  {}:{} {}",
+            declaration.file.display(),
+            declaration.line,
+            qualified(declaration.contract.as_deref(), &declaration.signature)
+        );
+    }
+
+    let mut by_file: BTreeMap<&Path, Vec<&FunctionReference>> = BTreeMap::new();
+    for reference in references {
+        by_file.entry(&reference.file).or_default().push(reference);
+    }
+    for (file, references) in &by_file {
+        println!("\n{}:", file.display());
+        for reference in references {
+            let tag = if reference.counts {
+                reference.kind.as_str().to_string()
+            } else {
+                format!("{}, not counted", reference.kind.as_str())
+            };
+            println!("  {} [{}] {}", reference.line, tag, reference.snippet);
+        }
+    }
+
+    println!(
+        "\n{} reference(s) in {} file(s), {} counted as usages.",
+        references.len(),
+        by_file.len(),
+        counted
+    );
+}
+
+/// e.g. `Vault.sweep(address)`.
+fn qualified(contract: Option<&str>, signature: &str) -> String {
+    match contract {
+        Some(contract) => format!("{}.{}", contract, signature),
+        None => signature.to_string(),
+    }
+}
@@ -1,281 +1,4731 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, Parser, ValueEnum};
+use glob::{MatchOptions, Pattern};
 use rayon::prelude::*;
 use regex::Regex;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{Error, ErrorKind, Result};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
-const RED: &str = "\x1b[31m";
-const YELLOW: &str = "\x1b[33m";
-const GREEN: &str = "\x1b[32m";
-const RESET: &str = "\x1b[0m";
+use crate::abi::{self, AbiExports, AbiFunction, TypeTable};
+use crate::accessors;
+use crate::archive::{self, Extracted};
+use crate::baseline::{Baseline, STALE_AFTER_DAYS};
+use crate::blame::{self, Blame};
+use crate::broadcast::Broadcasts;
+use crate::config::Config;
+use crate::coverage::Coverage;
+use crate::etherscan;
+use crate::exclusions::{GeneratedMarkers, PathExclusions, ScriptFiles, GENERATED_MARKERS, SCRIPT_DIRS};
+use crate::findings::{self, Collapsed, Finding, FindingKind, Record, Suppressions};
+use crate::ignore::{self, matching_ignore_pattern, should_ignore_function};
+use crate::imports::{self, Resolver};
+use crate::natspec::NatSpec;
+use crate::offchain::{OffchainReference, OffchainSources, UsageLang};
+use crate::plaintext::{TextReference, TextSources};
+use crate::output::{self, FindingsFormat};
+use crate::progress;
+use crate::project::{Layout, Project};
+use crate::proxy::ProxyHeuristic;
+use crate::references::{self, CountOptions, NameMatcher, Occurrence};
+use crate::signatures::SignatureDatabase;
+use crate::similar::{IdentifierIndex, Suggestion};
+use crate::solidity::{self, ContractKind, Function, SourceUnit, Visibility};
+use crate::source::{
+    self, canonical, dedup_files, expand, expand_path, has_extension, portable_path, relative, replace_file, Context,
+    SkippedDir, SkippedFile, SourceFile, SOL_EXTENSIONS,
+};
+use crate::timing::{self, Counter, Phase, Timing};
+use crate::unreachable::{self, UnreachableCode};
+use crate::vyper::{VyperReference, VyperSources};
+use crate::workspace;
+use inherited::InheritedUnused;
+
+mod inherited;
+mod plan;
+mod rename;
+
+pub use rename::{plan_rename, RenamedFile};
+
+fn red() -> &'static str {
+    output::ansi("\x1b[31m")
+}
+
+fn yellow() -> &'static str {
+    output::ansi("\x1b[33m")
+}
+
+fn green() -> &'static str {
+    output::ansi("\x1b[32m")
+}
+
+fn reset() -> &'static str {
+    output::ansi("\x1b[0m")
+}
 
 #[derive(Parser, Debug)]
 pub struct VacuumArgs {
-    /// Path to a Solidity file or directory to analyze.
-    #[arg(value_name = "PATH")]
-    path: PathBuf,
-
-    /// Root directory to search for function occurrences.
-    #[arg(long, default_value = ".")]
-    root: PathBuf,
+    #[command(flatten)]
+    analysis: AnalysisArgs,
 
     /// Remove unused functions from the Solidity file(s).
     #[arg(long)]
     delete: bool,
 
+    /// Delete even when directories or files were skipped, e.g. because they couldn't be
+    /// read, although the functions used only by their files look unused.
+    #[arg(long, requires = "delete")]
+    force: bool,
+
+    /// Print a detailed trace of why the named function was or wasn't flagged, then exit.
+    #[arg(long, value_name = "NAME")]
+    explain_unused: Option<String>,
+
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t = FindingsFormat::Text)]
+    format: FindingsFormat,
+
+    /// Print one summary line per file instead of the per-function report.
+    #[arg(long, conflicts_with = "transitive")]
+    compact: bool,
+
+    /// When some projects of a monorepo fail to be analyzed, still analyze and report the
+    /// others, list the errors, and only then exit with an error. The files that fail are
+    /// always reported this way.
+    #[arg(long, conflicts_with_all = ["transitive", "explain_unused"])]
+    write_report_on_error: bool,
+
+    /// Print which .wandignore patterns excluded which directories and files. With
+    /// --compact, also list the unused functions of each file that has any. Also print
+    /// the reports of files without findings, which are otherwise only counted in the
+    /// summary.
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Also remove functions only called by dead code, re-analyzing after each round of
+    /// removals and printing the resulting deletion plan.
+    #[arg(long, conflicts_with = "pattern")]
+    transitive: bool,
+
+    /// Only report unused functions that aren't recorded in this baseline file, and exit
+    /// with an error if there are any.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["transitive", "baseline_write"], value_parser = expand_path)]
+    baseline: Option<PathBuf>,
+
+    /// File of finding fingerprints, one per line, that SARIF output marks as suppressed,
+    /// e.g. those dismissed in GitHub code scanning.
+    #[arg(long, value_name = "FILE", value_parser = expand_path)]
+    suppressions: Option<PathBuf>,
+
+    /// Directory that the file URIs of SARIF results are relative to, instead of the root
+    /// of the git repository containing --root.
+    #[arg(long, value_name = "DIR", value_parser = expand_path)]
+    relative_to: Option<PathBuf>,
+
+    /// Record every unused function in this baseline file, for use with --baseline.
+    #[arg(long, value_name = "FILE", conflicts_with = "transitive", value_parser = expand_path)]
+    baseline_write: Option<PathBuf>,
+
+    /// File listing functions to treat as entry points with --transitive, one `name` or
+    /// `Contract.name` per line, e.g. those a router calls by selector.
+    #[arg(long, value_name = "FILE", requires = "transitive", value_parser = expand_path)]
+    assume_entrypoints: Option<PathBuf>,
+
+    /// Maximum number of analysis passes to run with --transitive.
+    #[arg(long, default_value_t = 10, requires = "transitive")]
+    max_passes: usize,
+
+    /// Occurrence counts up to which the text report colors a function red and yellow,
+    /// as `LOW,HIGH`. Higher counts are green.
+    #[arg(long, value_name = "LOW,HIGH", default_value = "1,2")]
+    color_thresholds: ColorThresholds,
+
+    /// Don't print the legend of the colors at the start of the text report.
+    #[arg(long)]
+    no_legend: bool,
+
+    /// Print the kinds of symbols the report covers, with a description of each, and exit.
+    #[arg(long)]
+    list_targets: bool,
+
+    /// Also report these opt-in targets, as printed by --list-targets, e.g.
+    /// `accessor-pairs`, or `all` for every one of them.
+    #[arg(long, value_name = "TARGETS", value_delimiter = ',', value_parser = parse_target)]
+    targets: Vec<&'static str>,
+
+    /// List the selectors called by broadcast transactions that match no source function,
+    /// with their signatures looked up in the openchain and 4byte databases.
+    #[arg(long)]
+    resolve_selectors: bool,
+
+    /// Collapse the findings in one contract whose functions share a base name, such as
+    /// overloads or `_mint` and `mint`, into one entry with their count.
+    #[arg(long, conflicts_with = "transitive")]
+    dedupe_findings: bool,
+
+    /// Show who last changed each unused function and when, from `git blame`.
+    #[arg(long, conflicts_with = "transitive")]
+    blame: bool,
+
+    /// With --blame, list the unused functions that were last changed longest ago first.
+    #[arg(long, requires = "blame")]
+    oldest_first: bool,
+
+    /// Order of the functions in each file's usage report.
+    #[arg(long, value_enum, default_value_t = UsageOrder::Line)]
+    sort: UsageOrder,
+
+    /// List the used functions referenced from fewer than this many files other than
+    /// their declaration, e.g. 2 for those only one file calls.
+    #[arg(long, value_name = "N", conflicts_with = "transitive")]
+    min_reference_files: Option<usize>,
+
+    /// In a monorepo, only analyze the projects with this name or path.
+    #[arg(long, value_name = "NAME", conflicts_with = "address")]
+    project: Vec<String>,
+
+    /// List the functions left out of the report by --ignore patterns, grouped by the
+    /// pattern that matched each.
+    #[arg(long, conflicts_with = "transitive")]
+    show_ignored: bool,
+
+    /// Fail when an --ignore pattern matches no function, instead of only warning.
+    #[arg(long)]
+    strict_ignores: bool,
+
+    /// Fail on the first directory that can't be read, instead of skipping it with a
+    /// warning.
+    #[arg(long)]
+    strict_fs: bool,
+
+    /// Print how long each phase of the run took, with counts of the files and bytes
+    /// read, on stderr at the end, and add it to JSON reports under `timing`.
+    #[arg(long)]
+    timing: bool,
+
+    /// Never draw the progress bar, which text reports otherwise show on stderr while
+    /// files are analyzed, when stderr is a terminal and the report isn't paged.
+    #[arg(long)]
+    no_progress: bool,
+}
+
+/// How the functions of a usage report are ordered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum UsageOrder {
+    /// By declaration.
+    Line,
+    /// Fewest occurrences first.
+    Occurrences,
+    /// Fewest referencing files first, then fewest occurrences.
+    Files,
+}
+
+/// A kind of symbol whose declarations are extracted and counted.
+#[derive(Serialize)]
+struct Target {
+    name: &'static str,
+    description: &'static str,
+    /// Whether it's only reported when enabled, with its own flag or --targets.
+    opt_in: bool,
+    #[serde(skip)]
+    enable: Option<fn(&mut AnalysisArgs)>,
+}
+
+/// The kinds of symbols the report covers, printed by `--list-targets`.
+const TARGETS: [Target; 8] = [
+    Target {
+        name: "functions",
+        description: "Functions of contracts, interfaces and libraries, and free functions; deleted with --delete",
+        opt_in: false,
+        enable: None,
+    },
+    Target {
+        name: "getters",
+        description: "Getters of public state variables, listed with their usages but never reported or deleted",
+        opt_in: false,
+        enable: None,
+    },
+    Target {
+        name: "patterns",
+        description: "Symbols declared as matched by --pattern, e.g. modifiers or events; never deleted",
+        opt_in: false,
+        enable: None,
+    },
+    Target {
+        name: "accessor-pairs",
+        description: "State variables with a getter and a setter, one of them unused, with --accessor-pairs; never deleted",
+        opt_in: true,
+        enable: Some(|args| args.accessor_pairs = true),
+    },
+    Target {
+        name: "unreachable-code",
+        description: "Code in function bodies that can never run, with --unreachable-code; never deleted",
+        opt_in: true,
+        enable: Some(|args| args.unreachable_code = true),
+    },
+    Target {
+        name: "inherited",
+        description: "Externally visible functions inherited from lib/ or node_modules/ that the project never mentions, with --audit-inherited; never deleted",
+        opt_in: true,
+        enable: Some(|args| args.audit_inherited = true),
+    },
+    Target {
+        name: "never-overridden",
+        description: "Virtual functions with a body that nothing overrides, with --never-overridden; never deleted",
+        opt_in: true,
+        enable: Some(|args| args.never_overridden = true),
+    },
+    Target {
+        name: "script-only",
+        description: "Functions only used by deployment scripts, outside tests, with --flag-script-only; never deleted",
+        opt_in: true,
+        enable: Some(|args| args.flag_script_only = true),
+    },
+];
+
+/// Parses a --targets entry: the name of a target, or `all`.
+fn parse_target(name: &str) -> std::result::Result<&'static str, String> {
+    if name == "all" {
+        return Ok("all");
+    }
+    TARGETS
+        .iter()
+        .find(|target| target.name == name)
+        .map(|target| target.name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = TARGETS.iter().map(|target| target.name).collect();
+            format!("expected `all` or one of {}", names.join(", "))
+        })
+}
+
+/// Enables the opt-in targets named by --targets, or all of them for `all`.
+fn enable_targets(targets: &[&str], args: &mut AnalysisArgs) {
+    let all = targets.contains(&"all");
+    for target in &TARGETS {
+        if let Some(enable) = target.enable.filter(|_| all || targets.contains(&target.name)) {
+            enable(args);
+        }
+    }
+}
+
+/// The boundaries between the red, yellow and green occurrence counts of the text report.
+/// By default only unused functions, with at most one occurrence, are red.
+#[derive(Clone, Copy, Debug)]
+struct ColorThresholds {
+    low: usize,
+    high: usize,
+}
+
+impl ColorThresholds {
+    fn color(self, occurrences: usize) -> &'static str {
+        if occurrences <= self.low {
+            red()
+        } else if occurrences <= self.high {
+            yellow()
+        } else {
+            green()
+        }
+    }
+
+    /// Explains the colors, e.g. `red: at most 1 (only the declaration: unused), yellow: 2
+    /// (used once besides the declaration), green: 3 or more`.
+    fn legend(self) -> String {
+        let mut colors = vec![format!(
+            "{}red{}: at most {}{}",
+            red(),
+            reset(),
+            self.low,
+            if self.low == 1 { " (only the declaration: unused)" } else { "" }
+        )];
+        if self.high > self.low {
+            let range = if self.high == 2 && self.low == 1 {
+                "2 (used once besides the declaration)".to_string()
+            } else if self.high == self.low + 1 {
+                self.high.to_string()
+            } else {
+                format!("{} to {}", self.low + 1, self.high)
+            };
+            colors.push(format!("{}yellow{}: {}", yellow(), reset(), range));
+        }
+        colors.push(format!("{}green{}: {} or more", green(), reset(), self.high + 1));
+        format!(
+            "Occurrences are colored {}; see --color-thresholds.",
+            colors.join(", ")
+        )
+    }
+}
+
+impl FromStr for ColorThresholds {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let (low, high) = value
+            .split_once(',')
+            .ok_or_else(|| format!("expected LOW,HIGH, got {:?}", value))?;
+        let parse = |bound: &str| {
+            bound
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("invalid count {:?}: {}", bound, e))
+        };
+        let thresholds = ColorThresholds {
+            low: parse(low)?,
+            high: parse(high)?,
+        };
+        if thresholds.low > thresholds.high {
+            return Err(format!(
+                "LOW ({}) must not be greater than HIGH ({})",
+                thresholds.low, thresholds.high
+            ));
+        }
+        Ok(thresholds)
+    }
+}
+
+/// The options that decide which functions are unused, shared with `wand baseline`.
+#[derive(Args, Clone, Debug)]
+pub struct AnalysisArgs {
+    /// Path to a Solidity file or directory to analyze. Defaults to the sources directory
+    /// of the project at --root: `src` for Foundry, `contracts` for Hardhat.
+    #[arg(value_name = "PATH", value_parser = expand_path)]
+    pub(crate) path: Option<PathBuf>,
+
+    /// Root directory to search for function occurrences. In a Hardhat project, only its
+    /// `contracts`, `test` and `scripts` directories are searched.
+    #[arg(long, default_value = ".", value_parser = expand_path)]
+    pub(crate) root: PathBuf,
+
+    /// Project layout to use, instead of detecting it from `foundry.toml` or
+    /// `hardhat.config.js`/`.ts` in --root.
+    #[arg(long, value_enum)]
+    pub(crate) layout: Option<Layout>,
+
     /// Patterns for function names to ignore (e.g., '^test' for functions starting with 'test').
     #[arg(long, default_values = ["^test"])]
-    ignore: Vec<String>,
+    pub(crate) ignore: Vec<String>,
+
+    /// Whether `ignore` is the default pattern, rather than patterns that were passed,
+    /// even `--ignore ^test`.
+    #[arg(skip)]
+    pub(crate) default_ignore: bool,
 
     /// Skip searching for function occurrences in the specified directories.
+    #[arg(long, value_parser = expand_path)]
+    pub(crate) no_match_path: Vec<PathBuf>,
+
+    /// File listing more paths or globs to skip like --no-match-path, one per line with
+    /// `#` starting a comment, e.g. a curated exclusion list kept in source control.
+    #[arg(long, value_name = "FILE", value_parser = expand_path)]
+    pub(crate) ignore_paths_from: Option<PathBuf>,
+
+    /// Count occurrences inside string literals as usages.
     #[arg(long)]
-    no_match_path: Vec<PathBuf>,
-}
+    pub(crate) count_strings: bool,
+
+    /// Logging helpers whose message strings are never usages, like those of
+    /// `console.log`, `require` and `revert`, e.g. `log,debugLog`.
+    #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+    pub(crate) log_functions: Vec<String>,
+
+    /// Read commented-out code as code: report functions declared in comments, and count
+    /// occurrences in comments as usages.
+    #[arg(long)]
+    pub(crate) include_commented: bool,
+
+    /// Also report code inside function bodies that can never run: branches behind
+    /// conditions that are always false, and statements after a `return` or `revert`.
+    /// It is only reported, never deleted.
+    #[arg(long)]
+    pub(crate) unreachable_code: bool,
+
+    /// Also report state variables with a getter and a setter of which only one is ever
+    /// called: a variable never set may be immutable or constant, and one never read
+    /// through its getter may be purely internal.
+    #[arg(long)]
+    pub(crate) accessor_pairs: bool,
+
+    /// Also list the external and public functions that each contract inherits from its
+    /// dependencies in `lib/` or `node_modules/` and that the project never mentions, to
+    /// review whether a slimmer base would do. They are never deleted.
+    #[arg(long)]
+    pub(crate) audit_inherited: bool,
+
+    /// Also report the functions with a body declared `virtual` that nothing under the
+    /// root overrides, whose `virtual` may be dropped. Interface members are left out.
+    #[arg(long)]
+    pub(crate) never_overridden: bool,
 
-pub fn run(args: VacuumArgs) -> Result<()> {
-    let mut total_unused = 0;
+    /// Globs of the files of bases published for others to inherit, e.g.
+    /// 'src/base/**', whose virtual functions --never-overridden leaves alone.
+    #[arg(long, value_name = "GLOB")]
+    pub(crate) published_bases: Vec<String>,
 
-    if args.path.is_file() {
-        if !args.path.extension().map_or(false, |ext| ext == "sol") {
-            println!("Warning: {:?} does not have a .sol extension.", args.path);
+    /// Directories and globs of the deployment scripts under the root, whose usages keep
+    /// a function used but only to deploy it. Globs without a `/` match file names.
+    #[arg(long, value_name = "GLOB", default_values = SCRIPT_DIRS)]
+    pub(crate) script_dirs: Vec<String>,
+
+    /// Also report the functions used only by --script-dirs files, besides tests, which
+    /// may be removable once deployed. They are never deleted.
+    #[arg(long)]
+    pub(crate) flag_script_only: bool,
+
+    /// Also note the unused functions that comments still mention, e.g. a `@notice` or
+    /// `@dev` describing a call that was removed, which is likely stale documentation.
+    #[arg(long, conflicts_with = "include_commented")]
+    pub(crate) documented_unused: bool,
+
+    /// Also estimate how much removing each function would shrink its contract, from the
+    /// length of its source without comments and whitespace. It's approximate: the
+    /// compiler's output isn't measured.
+    #[arg(long)]
+    pub(crate) estimate_size: bool,
+
+    /// LCOV file of the tests' coverage, e.g. from `forge coverage --report lcov`, to note
+    /// how often the tests run each function. Unused functions no test runs are the
+    /// safest to delete, and used ones no test runs are gaps in the tests.
+    #[arg(long, value_name = "LCOV", value_parser = expand_path)]
+    pub(crate) coverage: Option<PathBuf>,
+
+    /// Prefix of the source paths in the --coverage file to remove, leaving them relative
+    /// to --root, when it was written elsewhere, e.g. `/home/runner/work/protocol` in CI,
+    /// or `packages/core` when forge ran from the root of a monorepo.
+    #[arg(long, value_name = "PREFIX", requires = "coverage", value_parser = expand_path)]
+    pub(crate) coverage_prefix: Option<PathBuf>,
+
+    /// Additional regex for extracting declarations, with a named capture group `name`
+    /// for the declared symbol's name.
+    #[arg(long, value_name = "REGEX")]
+    pub(crate) pattern: Vec<String>,
+
+    /// Report external and public functions of proxy implementations too, instead of
+    /// treating them as reachable through the proxy's fallback.
+    #[arg(long)]
+    pub(crate) no_proxy_heuristic: bool,
+
+    /// Also search JavaScript and TypeScript sources for usages, e.g. `js,ts`. Functions
+    /// referenced there are kept even without on-chain callers.
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "LANGS")]
+    pub(crate) usage_langs: Vec<UsageLang>,
+
+    /// Directory searched for the --usage-langs sources, instead of --root.
+    #[arg(long, value_name = "DIR", requires = "usage_langs", value_parser = expand_path)]
+    pub(crate) js_root: Option<PathBuf>,
+
+    /// Also count whole-word mentions in the files with this extension under the root as
+    /// usages, e.g. `yaml` for keeper configs or `md` for runbooks.
+    #[arg(long, value_delimiter = ',', value_name = "EXT")]
+    pub(crate) search_ext: Vec<String>,
+
+    /// ABI JSON files of published contracts, e.g. 'deployments/**/*.json'. Functions they
+    /// expose are kept as used by other teams, and entries that match no source function
+    /// are reported as drift.
+    #[arg(long, value_name = "GLOB", value_parser = expand)]
+    pub(crate) abi: Vec<String>,
+
+    /// Directory of Foundry broadcasts, instead of `broadcast/` under the root. Functions
+    /// called by their transactions are kept as deployed usages.
+    #[arg(long, value_name = "DIR", value_parser = expand_path)]
+    pub(crate) broadcasts: Option<PathBuf>,
+
+    /// Analyze the verified sources of the contract at this address, fetched from
+    /// Etherscan with the API key in ETHERSCAN_API_KEY, instead of PATH.
+    #[arg(long, value_name = "ADDRESS", conflicts_with = "path")]
+    pub(crate) address: Option<String>,
+
+    /// Chain of --address, by name (e.g. 'mainnet', 'base') or id.
+    #[arg(long, default_value = "mainnet", requires = "address")]
+    pub(crate) chain: String,
+
+    /// Directory to write the sources fetched for --address to, which is kept afterwards.
+    #[arg(long, value_name = "DIR", requires = "address", value_parser = expand_path)]
+    pub(crate) keep_sources: Option<PathBuf>,
+
+    /// When Etherscan flags --address as a proxy, analyze its implementation instead.
+    #[arg(long, requires = "address")]
+    pub(crate) follow_proxy: bool,
+
+    /// Skip Solidity files larger than this many bytes, e.g. flattened builds, both when
+    /// analyzing and when searching for usages.
+    #[arg(long, value_name = "BYTES")]
+    pub(crate) max_file_size: Option<u64>,
+
+    /// Regexes marking generated files when they match one of their first
+    /// --generated-lines lines. Generated files are searched for usages, but neither
+    /// analyzed nor deleted from.
+    #[arg(long, value_name = "REGEX", default_values = GENERATED_MARKERS)]
+    pub(crate) generated_marker: Vec<String>,
+
+    /// How many lines at the start of each file are searched for --generated-marker.
+    #[arg(long, value_name = "LINES", default_value_t = 10)]
+    pub(crate) generated_lines: usize,
+
+    /// Analyze generated files like any other, and delete from them with --delete, e.g.
+    /// to vacuum the templates of a code generator.
+    #[arg(long)]
+    pub(crate) include_generated: bool,
+
+    /// Other directories searched for usages, from the project's `usage_roots` in
+    /// `wand.toml`.
+    #[arg(skip)]
+    pub(crate) usage_roots: Vec<PathBuf>,
+}
+
+impl AnalysisArgs {
+    fn count_options(&self) -> CountOptions {
+        CountOptions {
+            count_strings: self.count_strings,
+            include_commented: self.include_commented,
+            log_functions: self.log_functions.clone(),
         }
-        total_unused += process_single_file(&args.path, &args.root, args.delete, &args.ignore, &args.no_match_path)?;
-    } else if args.path.is_dir() {
-        let sol_files = collect_sol_files(&args.path)?;
-        total_unused += sol_files
-            .par_iter()
-            .map(|path| process_single_file(path, &args.root, args.delete, &args.ignore, &args.no_match_path))
-            .collect::<Result<Vec<usize>>>()?
+    }
+
+    /// Compiles the `--published-bases` globs.
+    fn published_bases(&self) -> Result<Vec<Pattern>> {
+        self.published_bases
             .iter()
-            .sum::<usize>();
-    } else {
-        return Err(Error::new(
-            ErrorKind::NotFound,
-            format!("Path {:?} does not exist.", args.path),
-        ));
+            .map(|glob| {
+                let glob = glob.strip_prefix("./").unwrap_or(glob);
+                Pattern::new(glob).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid --published-bases glob {:?}: {}", glob, e),
+                    )
+                })
+            })
+            .collect()
     }
 
-    println!("\nTotal unused functions found: {}", total_unused);
-    Ok(())
+    /// Compiles the `--pattern` regexes, checking that each captures a `name`.
+    fn patterns(&self) -> Result<Vec<Regex>> {
+        self.pattern
+            .iter()
+            .map(|pattern| {
+                let regex = Regex::new(pattern).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid --pattern {:?}: {}", pattern, e),
+                    )
+                })?;
+                if !regex.capture_names().any(|name| name == Some("name")) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "--pattern {:?} has no named capture group `name`, e.g. (?P<name>[a-zA-Z0-9_]+).",
+                            pattern
+                        ),
+                    ));
+                }
+                Ok(regex)
+            })
+            .collect()
+    }
+}
+
+/// Settings prepared once before analyzing any file.
+struct Analysis {
+    /// The analyzed path, `PATH` or the project's sources directory.
+    path: PathBuf,
+    project: Project,
+    patterns: Vec<Regex>,
+    /// The files skipped with `--no-match-path` and `--ignore-paths-from`.
+    excluded: PathExclusions,
+    /// The markers of generated files, left out of the analysis unless
+    /// `include_generated` is set.
+    generated: GeneratedMarkers,
+    include_generated: bool,
+    /// The deployment scripts, from --script-dirs.
+    scripts: ScriptFiles,
+    /// The files of bases published for others to inherit, from --published-bases.
+    published_bases: Vec<Pattern>,
+    baseline: Option<Baseline>,
+    proxy: ProxyHeuristic,
+    offchain: OffchainSources,
+    exports: AbiExports,
+    broadcasts: Broadcasts,
+    vyper: VyperSources,
+    text: TextSources,
+    /// How often the tests run each function, from --coverage.
+    coverage: Coverage,
+    /// Types of every file under the root and `PATH`, to match functions against the
+    /// canonical signatures of `exports` and the selectors of `broadcasts`.
+    types: TypeTable,
+    /// Canonical signatures of the externally visible functions under the root and `PATH`.
+    source_signatures: HashSet<String>,
+    /// The contracts under the root and `PATH`, parsed on first use.
+    contracts: OnceLock<ContractIndex>,
+    /// The identifiers referenced under the root and `PATH`, counted on first use.
+    identifiers: OnceLock<IdentifierIndex>,
+    /// The identifiers in the project's own files, for --audit-inherited, read on first use.
+    mentions: OnceLock<inherited::ProjectMentions>,
+    /// The sources extracted from the archive given as `PATH`, or fetched for
+    /// `--address`, removed once the analysis is dropped unless kept.
+    archive: Option<Extracted>,
 }
 
-fn collect_sol_files(dir: &PathBuf) -> Result<Vec<PathBuf>> {
-    let mut sol_files = Vec::new();
-    let mut dirs_to_visit = vec![dir.clone()];
+impl Analysis {
+    fn new(args: &AnalysisArgs, baseline: Option<Baseline>) -> Result<Self> {
+        let archive = match &args.address {
+            Some(address) => Some(etherscan::download(
+                address,
+                &args.chain,
+                args.follow_proxy,
+                args.keep_sources.as_deref(),
+            )?),
+            None => args
+                .path
+                .as_deref()
+                .filter(|path| archive::is_archive(path))
+                .map(archive::extract)
+                .transpose()?,
+        };
+        if let Some(archive) = archive {
+            let root = if args.root == Path::new(".") { &archive.root } else { &args.root };
+            let mut project = Project::detect(root, None, args.layout);
+            project.max_file_size = args.max_file_size;
+            let path = project
+                .sources()
+                .filter(|sources| sources.is_dir())
+                .unwrap_or_else(|| archive.root.clone());
+            return Analysis::prepare(args, baseline, project, path, Some(archive));
+        }
 
-    while let Some(current_dir) = dirs_to_visit.pop() {
-        for entry in fs::read_dir(current_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        let mut project = Project::detect(&args.root, args.path.as_deref(), args.layout);
+        project.usage_roots = args.usage_roots.clone();
+        project.max_file_size = args.max_file_size;
+        let path = args.path.clone().or_else(|| project.sources()).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "No PATH given, and {:?} has no foundry.toml or hardhat.config.js/.ts to take the sources directory from.",
+                    args.root
+                ),
+            )
+        })?;
+        Analysis::prepare(args, baseline, project, path, None)
+    }
 
-            if path.is_dir() {
-                dirs_to_visit.push(path);
-            } else if path.extension().map_or(false, |ext| ext == "sol") {
-                sol_files.push(path);
+    fn prepare(
+        args: &AnalysisArgs,
+        baseline: Option<Baseline>,
+        project: Project,
+        path: PathBuf,
+        archive: Option<Extracted>,
+    ) -> Result<Self> {
+        for (file, size) in project.oversized(&path)? {
+            let limit = args.max_file_size.unwrap_or_default();
+            eprintln!(
+                "Warning: skipping {:?} ({} bytes), which is larger than --max-file-size {}.",
+                file, size, limit
+            );
+            // Listed once however many projects share the file.
+            let _ = source::skip_file(&file, format!("{} bytes, larger than --max-file-size {}", size, limit));
+        }
+        let searched = |root: &Path| canonical(&path).starts_with(canonical(root));
+        if path.exists() && !searched(&project.root) && !project.usage_roots.iter().any(|root| searched(root)) {
+            eprintln!(
+                "Warning: {:?} is not under --root {:?}. Its own files are searched for usages too, so each declaration still counts once, but usages elsewhere, e.g. in the project it was taken from, are not seen. Pass that project as --root.",
+                path, project.root
+            );
+        }
+        let proxy = detect_proxies(args, &project, &path)?;
+        let mut roots = vec![project.root.clone()];
+        roots.extend(project.usage_roots.iter().cloned());
+        let excluded = PathExclusions::load(&args.no_match_path, args.ignore_paths_from.as_deref(), &roots)?;
+        let offchain = OffchainSources::load(
+            args.js_root.as_ref().unwrap_or(&project.root),
+            &args.usage_langs,
+            &excluded,
+        )?;
+        let exports = AbiExports::load(&args.abi)?;
+        let broadcasts = match &args.broadcasts {
+            Some(dir) if !dir.is_dir() => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("Broadcast directory {:?} does not exist.", dir),
+                ));
+            }
+            Some(dir) => Broadcasts::load(dir)?,
+            None => {
+                let dir = project.root.join("broadcast");
+                if dir.is_dir() {
+                    Broadcasts::load(&dir)?
+                } else {
+                    Broadcasts::default()
+                }
+            }
+        };
+        let vyper = VyperSources::load(&project.root, &excluded)?;
+        let text = TextSources::load(&project.root, &args.search_ext, &excluded)?;
+        let coverage = match &args.coverage {
+            Some(lcov) => Coverage::load(lcov, args.coverage_prefix.as_deref(), &project.root)?,
+            None => Coverage::default(),
+        };
+        let scripts = ScriptFiles::new(&project.root, &args.script_dirs)?;
+        let mut analysis = Analysis {
+            path,
+            project,
+            patterns: args.patterns()?,
+            excluded,
+            generated: GeneratedMarkers::new(&args.generated_marker, args.generated_lines)?,
+            include_generated: args.include_generated,
+            scripts,
+            published_bases: args.published_bases()?,
+            baseline,
+            proxy,
+            offchain,
+            exports,
+            broadcasts,
+            vyper,
+            text,
+            coverage,
+            types: TypeTable::default(),
+            source_signatures: HashSet::new(),
+            contracts: OnceLock::new(),
+            identifiers: OnceLock::new(),
+            mentions: OnceLock::new(),
+            archive,
+        };
+        if !analysis.exports.is_empty() || !analysis.broadcasts.is_empty() || !analysis.vyper.is_empty() {
+            analysis.index_signatures()?;
+        }
+        if let Some(lcov) = &args.coverage {
+            let analyzed = analysis.analyzed_files().unwrap_or_default();
+            if !analyzed.iter().any(|file| analysis.coverage.covers(file)) {
+                eprintln!(
+                    "Warning: none of the files covered by {:?} is under {:?}, so no function is noted as run by the tests or not. Pass --coverage-prefix if its paths aren't relative to --root {:?}.",
+                    lcov, analysis.path, analysis.project.root
+                );
             }
         }
+        Ok(analysis)
     }
 
-    Ok(sol_files)
-}
+    /// Parses every file under the root and `PATH` to collect their types and the
+    /// signatures of their externally visible functions.
+    fn index_signatures(&mut self) -> Result<()> {
+        let paths = self.search_files()?;
 
-fn extract_functions(sol_file: &PathBuf) -> Result<Vec<String>> {
-    let content = fs::read_to_string(sol_file)?;
-    let function_pattern = Regex::new(r"\bfunction\s+([a-zA-Z0-9_]+)\s*\(")
-        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let units: Vec<SourceUnit> = paths
+            .par_iter()
+            .map(|path| {
+                let content = fs::read_to_string(path).unwrap_or_default();
+                solidity::parse(&SourceFile::new(path.clone(), content))
+            })
+            .collect();
+        for unit in &units {
+            self.types.add(unit);
+        }
+        self.source_signatures = units
+            .iter()
+            .flat_map(|unit| &unit.functions)
+            .filter(|function| function.visibility.is_externally_visible())
+            .map(|function| self.types.signature(function))
+            .collect();
+        Ok(())
+    }
 
-    Ok(function_pattern
-        .captures_iter(&content)
-        .map(|cap| cap[1].to_string())
-        .collect())
-}
+    /// Whether `file` matches a --published-bases glob.
+    fn is_published(&self, file: &Path) -> bool {
+        let file = relative(&self.display_path(file));
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.published_bases.iter().any(|glob| glob.matches_path_with(&file, options))
+    }
 
-fn count_function_occurrences(
-    root_dir: &PathBuf,
-    function_names: &[String],
-    no_match_path: &[PathBuf],
-) -> Result<HashMap<String, usize>> {
-    let mut function_counts: HashMap<String, usize> =
-        function_names.iter().map(|f| (f.clone(), 0)).collect();
-
-    let sol_files = collect_sol_files(root_dir)?;
-    let sol_files: Vec<_> = sol_files
-        .into_iter()
-        .filter(|path| {
-            !no_match_path.iter().any(|skip_path| {
-                path.starts_with(skip_path)
-            })
+    /// Whether a function is exposed by one of the `--abi` files.
+    fn abi_exported(&self, function: &Function) -> bool {
+        !self.exports.is_empty()
+            && function.visibility.is_externally_visible()
+            && self.exports.exports(&self.types.signature(function))
+    }
+
+    /// The broadcast whose transactions call a function, if any.
+    fn deployed_usage(&self, function: &Function) -> Option<PathBuf> {
+        if self.broadcasts.is_empty() || !function.visibility.is_externally_visible() {
+            return None;
+        }
+        self.broadcasts
+            .caller(&self.types.signature(function))
+            .map(Path::to_path_buf)
+    }
+
+    /// The references to a function from the Vyper contracts, which can only call
+    /// externally visible functions.
+    fn vyper_references(&self, function: &Function) -> Vec<VyperReference> {
+        if self.vyper.is_empty() || !function.visibility.is_externally_visible() {
+            return Vec::new();
+        }
+        self.vyper.references(&function.name, &self.types.signature(function))
+    }
+
+    /// Whether the code at `offset` in the canonical `file` is in a contract inheriting
+    /// from `base`, and so may call its internal functions.
+    fn inherits_at(&self, file: &Path, offset: usize, base: &str) -> bool {
+        let index = self.contracts();
+        index.files.get(file).is_some_and(|contracts| {
+            contracts
+                .iter()
+                .any(|contract| (contract.body.0..contract.body.1).contains(&offset) && index.inherits(&contract.name, base))
         })
-        .collect();
+    }
 
-    let counts: Vec<HashMap<String, usize>> = sol_files
-        .par_iter()
-        .map(|path| {
-            let content = fs::read_to_string(path).unwrap_or_default();
-            let mut local_counts = HashMap::new();
-            for func in function_names {
-                local_counts.insert(func.clone(), content.matches(func).count());
-            }
-            local_counts
+    /// Whether the code at `offset` in the canonical `file` is in a contract that `derived`
+    /// inherits from, whose calls of virtual functions reach the overrides in `derived`.
+    fn base_at(&self, file: &Path, offset: usize, derived: &str) -> bool {
+        let index = self.contracts();
+        index.files.get(file).is_some_and(|contracts| {
+            contracts
+                .iter()
+                .any(|contract| (contract.body.0..contract.body.1).contains(&offset) && index.inherits(derived, &contract.name))
         })
-        .collect();
+    }
+
+    /// The public state variable named `name` whose getter implements the member of
+    /// `interface`, e.g. `Vault.rewardToken`, preferring contracts inheriting from it.
+    fn auto_getter(&self, interface: &str, name: &str) -> Option<String> {
+        let index = self.contracts();
+        let contracts = index.getters.get(name)?;
+        contracts
+            .iter()
+            .find(|contract| index.inherits(contract, interface))
+            .or(contracts.first())
+            .map(|contract| format!("{}.{}", contract, name))
+    }
 
-    for count in counts {
-        for (func, count) in count {
-            *function_counts.entry(func).or_default() += count;
+    /// Finds the member of `base` that `@inheritdoc <base>` above the function `name`
+    /// with `params` parameters refers to, declared by `base` or one of its bases.
+    /// Returns `None` when no file declares `base`, and `Some(None)` when it has no such
+    /// member, i.e. the tag names the wrong contract.
+    fn inheritdoc_base(&self, base: &str, name: &str, params: usize) -> Option<Option<String>> {
+        let index = self.contracts();
+        if !index.bases.contains_key(base) {
+            return None;
         }
+        let mut seen = HashSet::new();
+        let mut to_visit = vec![base];
+        while let Some(contract) = to_visit.pop() {
+            if !seen.insert(contract) {
+                continue;
+            }
+            let declares = index
+                .functions
+                .get(contract)
+                .is_some_and(|functions| functions.iter().any(|function| function == &(name.to_string(), params)));
+            if declares {
+                return Some(Some(format!("{}.{}", contract, name)));
+            }
+            to_visit.extend(index.bases.get(contract).into_iter().flatten().map(String::as_str));
+        }
+        Some(None)
     }
 
-    Ok(function_counts)
-}
+    /// The declarations overriding `decl` in the contracts inheriting from its own, with
+    /// the inheritance line to each, e.g. `Base <- Vault`.
+    fn overrides(&self, decl: &FunctionDecl) -> Vec<(String, &MemberDecl)> {
+        let Some(base) = decl.contract.as_deref() else {
+            return Vec::new();
+        };
+        let index = self.contracts();
+        let mut overrides: Vec<(String, &MemberDecl)> = index
+            .members
+            .get(&decl.name)
+            .into_iter()
+            .flatten()
+            .filter(|member| member.contract != base && member.params == decl.params)
+            .filter_map(|member| Some((index.lineage(&member.contract, base)?, member)))
+            .collect();
+        overrides.sort_by(|a, b| a.0.cmp(&b.0));
+        overrides
+    }
 
-fn should_ignore_function(func_name: &str, ignore_patterns: &[String]) -> bool {
-    ignore_patterns
-        .iter()
-        .filter_map(|pattern| Regex::new(pattern).ok())
-        .any(|regex| regex.is_match(func_name))
-}
-
-fn remove_unused_functions(sol_file: &PathBuf, unused_functions: &[String]) -> Result<()> {
-    let mut content = fs::read_to_string(sol_file)?;
-
-    for func_name in unused_functions {
-        let escaped_name = regex::escape(func_name);
-        let pattern = format!(r"\bfunction\s+{}\s*\(", escaped_name);
-        let function_pattern =
-            Regex::new(&pattern).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
-
-        if let Some(mat) = function_pattern.find(&content) {
-            let start_pos = mat.start();
-
-            // Find the opening bracket after the function declaration
-            let mut pos = mat.end();
-            let mut found_semicolon = false;
-            while pos < content.len() {
-                match content.chars().nth(pos) {
-                    Some('{') => break,
-                    Some(';') => {
-                        found_semicolon = true;
-                        break;
-                    }
-                    _ => {}
+    /// Whether an occurrence is a usage of `decl`. A call qualified by a known contract or
+    /// library, e.g. `FixedPointMath.mulDiv(...)`, only uses the function of that contract
+    /// or one it inherits from, while other qualifiers may be variables or aliases and use
+    /// every function of that name. `--pattern` symbols, e.g. constants, are used by any
+    /// qualified access, e.g. `Constants.MAX_FEE`, under the same rule.
+    fn credits(&self, occurrence: &Occurrence, decl: &FunctionDecl) -> bool {
+        let (qualifier, contract) = match decl.visibility {
+            Some(_) => (occurrence.qualifier.as_deref(), decl.contract.as_deref()),
+            None => (occurrence.member_qualifier.as_deref(), decl.enclosing.as_deref()),
+        };
+        let Some(qualifier) = qualifier else {
+            return true;
+        };
+        if contract == Some(qualifier) {
+            return true;
+        }
+        let index = self.contracts();
+        let qualifier = &index.resolve_alias(&canonical(&occurrence.file), qualifier);
+        if contract == Some(qualifier.as_str()) {
+            return true;
+        }
+        !index.bases.contains_key(qualifier) || contract.is_some_and(|contract| index.inherits(qualifier, contract))
+    }
+
+    /// The occurrences of the names under which files import the free function `name`,
+    /// e.g. the calls of `h` after `import {helper as h} from "./Utils.sol";`.
+    fn alias_occurrences(&self, name: &str) -> Vec<Occurrence> {
+        let index = self.contracts();
+        let mut occurrences = Vec::new();
+        for (file, aliases) in &index.aliases {
+            for alias in aliases.aliases.keys() {
+                if alias == name || index.resolve_alias(file, alias) != name {
+                    continue;
                 }
-                pos += 1;
+                let content = fs::read_to_string(&aliases.path).unwrap_or_default();
+                let source = SourceFile::new(aliases.path.clone(), content);
+                occurrences.extend(
+                    references::find_occurrences(&source, alias)
+                        .into_iter()
+                        .filter(|occurrence| {
+                            !aliases
+                                .imports
+                                .iter()
+                                .any(|&(start, end)| (start..end).contains(&occurrence.offset))
+                        }),
+                );
             }
+        }
+        occurrences.sort_by(|a, b| a.file.cmp(&b.file).then(a.offset.cmp(&b.offset)));
+        occurrences
+    }
 
-            if pos < content.len() {
-                let end_pos = if found_semicolon {
-                    pos + 1
-                } else {
-                    let mut bracket_count = 1;
-                    pos += 1;
-
-                    // Count brackets to find the end of the function
-                    while bracket_count > 0 && pos < content.len() {
-                        match content.chars().nth(pos) {
-                            Some('{') => bracket_count += 1,
-                            Some('}') => bracket_count -= 1,
-                            _ => {}
+    fn contracts(&self) -> &ContractIndex {
+        self.contracts.get_or_init(|| {
+            let mut paths = self.project.search_files().unwrap_or_default();
+            paths.extend(self.collected_files().unwrap_or_default());
+            let resolver = Resolver::new(&self.project.root).ok();
+            let mut index = ContractIndex::default();
+            for path in paths {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                let source = SourceFile::new(path.clone(), content);
+                let unit = solidity::parse(&source);
+                let imports = imports::parse_imports(&source);
+                if imports.iter().any(|import| !import.aliases.is_empty()) {
+                    let mut aliases = HashMap::new();
+                    for import in &imports {
+                        let from = resolver
+                            .as_ref()
+                            .and_then(|resolver| resolver.resolve(&path, &import.path))
+                            .map(|from| canonical(&from));
+                        for alias in &import.aliases {
+                            aliases.insert(alias.alias.clone(), (alias.name.clone(), from.clone()));
                         }
-                        pos += 1;
                     }
-
-                    if bracket_count == 0 {
-                        pos
-                    } else {
-                        continue;
+                    index.aliases.entry(canonical(&path)).or_insert(FileAliases {
+                        path: path.clone(),
+                        imports: imports.iter().map(|import| import.span).collect(),
+                        aliases,
+                    });
+                }
+                index.files.entry(canonical(&path)).or_insert_with(|| {
+                    unit.contracts
+                        .iter()
+                        .map(|contract| ContractSpan {
+                            name: contract.name.clone(),
+                            body: contract.body,
+                        })
+                        .collect()
+                });
+                for function in &unit.functions {
+                    if let Some(contract) = &function.contract {
+                        index
+                            .functions
+                            .entry(contract.clone())
+                            .or_default()
+                            .push((function.name.clone(), function.params.len()));
+                        index.members.entry(function.name.clone()).or_default().push(MemberDecl {
+                            contract: contract.clone(),
+                            params: function.params.len(),
+                            file: canonical(&path),
+                            offset: function.name_offset,
+                        });
                     }
-                };
-
-                // Look for NatSpec comments before the function
-                let mut natspec_start = start_pos;
-                if let Some(possible_natspec_start) = content[..start_pos].rfind("/**") {
-                    let between_text = content[possible_natspec_start..start_pos].trim();
-                    if between_text.starts_with("/**") && between_text.ends_with("*/") {
-                        natspec_start = possible_natspec_start;
+                }
+                for variable in &unit.state_variables {
+                    if variable.visibility == Visibility::Public {
+                        index
+                            .getters
+                            .entry(variable.name.clone())
+                            .or_default()
+                            .push(variable.contract.clone());
                     }
                 }
-
-                // Find the start of the line containing the natspec or function
-                let line_start = content[..natspec_start]
-                    .rfind('\n')
-                    .map_or(0, |pos| pos + 1);
-
-                // Find the end of the line after the function
-                let next_line_start = content[end_pos..]
-                    .find('\n')
-                    .map(|pos| end_pos + pos + 1)
-                    .unwrap_or(content.len());
-
-                // Remove the function and its natspec completely
-                let mut new_content = String::new();
-                new_content.push_str(&content[..line_start]);
-                if next_line_start < content.len() {
-                    new_content.push_str(&content[next_line_start..]);
+                for contract in unit.contracts {
+                    index.bases.entry(contract.name).or_insert(contract.bases);
                 }
-                content = new_content;
-                println!("Removed function: {}", func_name);
             }
+            index
+        })
+    }
+
+    /// A referenced identifier with a name similar to `name`, for a function nothing
+    /// references, which may be misspelled.
+    fn suggestion(&self, name: &str) -> Option<Suggestion> {
+        self.identifiers
+            .get_or_init(|| {
+                IdentifierIndex::load(&self.search_files().unwrap_or_default())
+            })
+            .suggest(name)
+    }
+
+    /// The selectors called by broadcast transactions that match no externally visible
+    /// source function, with the first broadcast calling each.
+    fn unknown_broadcast_selectors(&self) -> Vec<UnknownSelector> {
+        let known: HashSet<String> = self
+            .source_signatures
+            .iter()
+            .map(|signature| abi::format_selector(abi::selector(signature)))
+            .collect();
+        let mut unknown: Vec<UnknownSelector> = self
+            .broadcasts
+            .calls()
+            .filter(|(selector, _)| !known.contains(selector))
+            .map(|(selector, broadcast)| UnknownSelector {
+                selector,
+                broadcast: broadcast.to_path_buf(),
+                candidates: Vec::new(),
+            })
+            .collect();
+        unknown.sort_by(|a, b| a.broadcast.cmp(&b.broadcast).then(a.selector.cmp(&b.selector)));
+        unknown
+    }
+
+    /// The category of the code referencing a function, with the scripts among the
+    /// referencing files when those are all the production code does. `elsewhere` is
+    /// whether anything outside Solidity references it, which counts as production.
+    fn usage_category(&self, references: &[Reference], elsewhere: bool) -> (Option<UsageCategory>, Vec<PathBuf>) {
+        let mut scripts: Vec<PathBuf> = Vec::new();
+        let mut category = elsewhere.then_some(UsageCategory::Production);
+        for reference in references {
+            let found = if is_test_file(&reference.file) {
+                UsageCategory::Test
+            } else if self.scripts.contains(&reference.file) {
+                scripts.push(relative(&reference.file));
+                UsageCategory::Script
+            } else {
+                UsageCategory::Production
+            };
+            category = Some(category.map_or(found, |current| current.min(found)));
+        }
+        if category != Some(UsageCategory::Script) {
+            scripts.clear();
         }
+        scripts.sort();
+        scripts.dedup();
+        (category, scripts)
     }
 
-    fs::write(sol_file, content)?;
-    println!("Updated {:?} with unused functions removed.", sol_file);
+    /// The `--abi` entries that match no externally visible source function.
+    fn abi_drift(&self) -> Vec<&AbiFunction> {
+        self.exports
+            .functions
+            .iter()
+            .filter(|function| !self.source_signatures.contains(&function.signature))
+            .collect()
+    }
 
-    Ok(())
-}
+    /// The path of an analyzed file as it is reported: inside the archive or address, if
+    /// it came from one.
+    fn display_path(&self, path: &Path) -> PathBuf {
+        self.archive
+            .as_ref()
+            .map_or_else(|| path.to_path_buf(), |archive| archive.display_path(path))
+    }
 
-fn process_single_file(
-    sol_file: &PathBuf,
-    root_dir: &PathBuf,
-    delete: bool,
-    ignore_patterns: &[String],
-    no_match_path: &[PathBuf],
-) -> Result<usize> {
-    // Skip processing if the file is in a no_match_path directory
-    if no_match_path.iter().any(|skip_path| sol_file.starts_with(skip_path)) {
-        return Ok(0);
+    /// The Solidity files searched for usages: those of the project, and the analyzed
+    /// files in case `PATH` is outside of it, so that each declaration is found there.
+    /// An analyzed file inside the project is searched once, however its path is spelled,
+    /// or its declarations would count twice and no function in it would be unused.
+    /// Generated files are searched too, and so are the --script-dirs directories, which
+    /// a Hardhat project wouldn't otherwise search unless they are its `scripts`.
+    fn search_files(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = self.project.search_files()?;
+        paths.extend(self.collected_files()?);
+        for dir in self.scripts.dirs() {
+            paths.extend(self.project.collect(&dir)?);
+        }
+        dedup_files(&mut paths);
+        Ok(paths)
     }
 
-    let functions = extract_functions(sol_file)?;
-    let function_counts = count_function_occurrences(root_dir, &functions, no_match_path)?;
+    /// The Solidity files to analyze, without the generated ones unless
+    /// `--include-generated` is passed.
+    fn analyzed_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = self.collected_files()?;
+        if !self.include_generated {
+            files.retain(|file| !self.generated.marks(file));
+        }
+        Ok(files)
+    }
 
-    println!("\nFunction Usage Report for {:?}:", sol_file);
-    let unused_functions: Vec<_> = functions
-        .iter()
-        .filter(|func| {
-            let count = function_counts.get(*func).unwrap_or(&0);
-            *count <= 1 && !should_ignore_function(func, ignore_patterns)
-        })
-        .cloned()
-        .collect();
+    /// The generated files under `PATH`, analyzed only with `--include-generated`.
+    fn generated_files(&self) -> Vec<PathBuf> {
+        let mut files = self.collected_files().unwrap_or_default();
+        files.retain(|file| self.generated.marks(file));
+        files
+    }
+
+    /// The Solidity files under `PATH`, generated or not.
+    fn collected_files(&self) -> Result<Vec<PathBuf>> {
+        if self.path.is_file() {
+            let within = self.project.within_size_limit(&self.path);
+            Ok(within.then(|| self.path.clone()).into_iter().collect())
+        } else if self.path.is_dir() {
+            self.project.collect(&self.path)
+        } else {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Path {:?} does not exist.", self.path),
+            ))
+        }
+    }
+}
+
+/// An `@inheritdoc` tag naming a contract that doesn't declare the function, usually
+/// left behind when the function or the interface was renamed or moved.
+#[derive(Serialize)]
+struct WrongInheritdoc {
+    function: String,
+    line: usize,
+    target: String,
+}
+
+/// How many renaming imports are followed to the original name, which also stops cycles.
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// The contracts of every file, to tell which files can call an internal function.
+#[derive(Default)]
+struct ContractIndex {
+    /// The bases listed by each contract, by name.
+    bases: HashMap<String, Vec<String>>,
+    /// The contracts declared in each file, by canonical path.
+    files: HashMap<PathBuf, Vec<ContractSpan>>,
+    /// The names and parameter counts of the functions of each contract.
+    functions: HashMap<String, Vec<(String, usize)>>,
+    /// The symbols each file imports under another name, by canonical path.
+    aliases: HashMap<PathBuf, FileAliases>,
+    /// The contracts declaring a public state variable, by the variable's name.
+    getters: HashMap<String, Vec<String>>,
+    /// The functions declared in contracts, by name, to find overrides.
+    members: HashMap<String, Vec<MemberDecl>>,
+}
+
+/// A function declared in a contract.
+struct MemberDecl {
+    contract: String,
+    params: usize,
+    /// The canonical path of the declaring file.
+    file: PathBuf,
+    /// Offset of the name in the declaration, which isn't a call.
+    offset: usize,
+}
+
+/// The renaming imports of a file.
+struct FileAliases {
+    path: PathBuf,
+    /// Spans of the import directives, where aliases are declared rather than used.
+    imports: Vec<(usize, usize)>,
+    /// The imported name and the canonical path of the file it's imported from, by alias.
+    aliases: HashMap<String, (String, Option<PathBuf>)>,
+}
+
+struct ContractSpan {
+    name: String,
+    body: (usize, usize),
+}
 
-    for func in &functions {
-        if !should_ignore_function(func, ignore_patterns) {
-            let count = function_counts.get(func).unwrap_or(&0);
-            let color = match count {
-                1 => RED,
-                2 => YELLOW,
-                _ => GREEN,
+impl ContractIndex {
+    /// Follows the aliases of `name` in the canonical `file`, through files that import
+    /// a symbol under one name and re-export it to files renaming it again.
+    fn resolve_alias(&self, file: &Path, name: &str) -> String {
+        let mut file = file.to_path_buf();
+        let mut name = name.to_string();
+        for _ in 0..MAX_ALIAS_DEPTH {
+            let Some((original, from)) = self
+                .aliases
+                .get(&file)
+                .and_then(|aliases| aliases.aliases.get(&name))
+            else {
+                break;
             };
-            println!("{}{}{}: {}", color, func, RESET, count);
+            name = original.clone();
+            match from {
+                Some(from) => file = from.clone(),
+                None => break,
+            }
         }
+        name
     }
 
-    if !unused_functions.is_empty() {
-        println!("\nFunctions marked for removal in {:?}:", sol_file);
-        for func in &unused_functions {
-            println!("- {}", func);
+    /// The contracts `contract` inherits through from `base`, as `Base <- Mid <- Contract`,
+    /// or `None` if it doesn't inherit from `base`.
+    fn lineage(&self, contract: &str, base: &str) -> Option<String> {
+        let mut seen = HashSet::new();
+        let mut to_visit = vec![vec![contract]];
+        while let Some(path) = to_visit.pop() {
+            let name = path[path.len() - 1];
+            if name == base {
+                let path: Vec<&str> = path.into_iter().rev().collect();
+                return Some(path.join(" <- "));
+            }
+            if seen.insert(name) {
+                for parent in self.bases.get(name).into_iter().flatten() {
+                    let mut next = path.clone();
+                    next.push(parent.as_str());
+                    to_visit.push(next);
+                }
+            }
         }
+        None
+    }
 
-        if delete {
-            remove_unused_functions(sol_file, &unused_functions)?;
+    /// The inheritance lines from `base` to every contract inheriting from it, e.g.
+    /// `Base <- Mid <- Vault`, in order of the contract names.
+    fn derived(&self, base: &str) -> Vec<String> {
+        let mut contracts: Vec<&String> = self.bases.keys().filter(|contract| *contract != base).collect();
+        contracts.sort();
+        contracts
+            .into_iter()
+            .filter_map(|contract| self.lineage(contract, base))
+            .collect()
+    }
+
+    /// Whether `contract` is `base` or inherits from it, directly or not.
+    fn inherits(&self, contract: &str, base: &str) -> bool {
+        let mut seen = HashSet::new();
+        let mut to_visit = vec![contract];
+        while let Some(name) = to_visit.pop() {
+            if name == base {
+                return true;
+            }
+            if seen.insert(name) {
+                to_visit.extend(self.bases.get(name).into_iter().flatten().map(String::as_str));
+            }
         }
-    } else {
-        println!("\nNo unused functions found in {:?}.", sol_file);
+        false
     }
+}
+
+/// A function declaration extracted from a Solidity file.
+struct FunctionDecl {
+    name: String,
+    /// Offset of the name in the declaration, which isn't a reference.
+    offset: usize,
+    contract: Option<String>,
+    /// The name and parameter types as written, e.g. `sweep(address)`.
+    signature: String,
+    line: usize,
+    /// `None` for symbols matched by a `--pattern`.
+    visibility: Option<Visibility>,
+    abi_exported: bool,
+    /// The broadcast calling the function, if any.
+    deployed_usage: Option<PathBuf>,
+    vyper: Vec<VyperReference>,
+    /// The comments above the declaration mention a TODO or FIXME.
+    todo: bool,
+    /// The spans of the comments above the declaration, whose mentions of the function
+    /// describe it rather than a use of it.
+    doc_comments: Vec<(usize, usize)>,
+    /// The bytes of its code, leaving out comments and whitespace, or `None` for getters
+    /// and `--pattern` symbols, which have no body of their own.
+    size: Option<usize>,
+    /// Its code, see `Function::code`, which its findings are fingerprinted with: the
+    /// line it's on for `--pattern` symbols, and empty for getters.
+    context: String,
+    /// Declared in a library, whose internal functions any file may call.
+    library: bool,
+    /// The contract named by an `@inheritdoc` tag, and the number of parameters to find
+    /// the member it refers to.
+    inheritdoc: Option<(String, usize)>,
+    /// For an interface function, the public state variable whose getter implements it.
+    implemented_by: Option<String>,
+    /// The getter of a public state variable rather than a function.
+    auto_getter: bool,
+    /// Declared `virtual` without a body in a contract, for others to implement.
+    virtual_stub: bool,
+    /// For a function with a body declared `virtual` in a contract, the line of the
+    /// keyword.
+    virtual_line: Option<usize>,
+    params: usize,
+    /// The contract a `--pattern` symbol is declared in, which qualified references to it
+    /// must name, e.g. `Constants` for `Constants.MAX_FEE`.
+    enclosing: Option<String>,
+    /// For a Yul function, the `assembly { }` block it's defined in, the only place it can
+    /// be called from.
+    assembly: Option<(usize, usize)>,
+}
 
-    Ok(unused_functions.len())
+/// The analysis results for a single Solidity file.
+#[derive(Serialize)]
+struct FileReport {
+    file: PathBuf,
+    functions: Vec<FunctionUsage>,
+    unused: Vec<String>,
+    removed: Vec<String>,
+    /// Unused functions left out of `unused` because they are in the baseline.
+    baselined: Vec<String>,
+    /// Unused functions left out of `unused` because they are reachable through a proxy.
+    proxy_reachable: Vec<String>,
+    /// Unused functions left out of `unused` because off-chain sources refer to them.
+    offchain_used: Vec<String>,
+    /// Unused functions left out of `unused` because an `--abi` file exposes them.
+    abi_exported: Vec<String>,
+    /// Unused functions left out of `unused` because broadcast transactions call them.
+    deployed_usage: Vec<String>,
+    /// Functions whose `@inheritdoc` tag names a contract without such a member.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    wrong_inheritdoc: Vec<WrongInheritdoc>,
+    /// Used functions referenced from fewer files than `--min-reference-files`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    few_reference_files: Vec<String>,
+    /// Used functions that the tests never run, according to --coverage.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    uncovered: Vec<String>,
+    /// `virtual` functions without a body that are overridden but never called.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    uncalled_virtual: Vec<UncalledVirtual>,
+    /// Functions with a body declared `virtual` that nothing overrides, with
+    /// `--never-overridden`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    never_overridden: Vec<NeverOverridden>,
+    /// Functions only used by deployment scripts, with `--flag-script-only`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    script_only: Vec<ScriptOnly>,
+    /// Code that can never run, with `--unreachable-code`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unreachable_code: Vec<UnreachableBlock>,
+    /// State variables whose getter or setter is never called, with `--accessor-pairs`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    accessor_pairs: Vec<AccessorPair>,
+    /// Functions inherited from dependencies that the project never mentions, with
+    /// `--audit-inherited`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    inherited_unused: Vec<InheritedUnused>,
+    /// Functions left out of `functions` by an `--ignore` pattern, listed with
+    /// `--show-ignored`.
+    #[serde(skip)]
+    ignored: Vec<IgnoredFunction>,
+    /// The `--ignore` patterns matching any function of the file, including those that an
+    /// earlier pattern matched too.
+    #[serde(skip)]
+    matched_ignores: HashSet<String>,
+    #[serde(skip)]
+    findings: Vec<Finding>,
+}
+
+impl FileReport {
+    /// Removes the unused functions from `sol_file`, the file the report is for, noting
+    /// the names actually removed.
+    fn delete_unused(&mut self, sol_file: &Path) -> Result<()> {
+        if self.findings.is_empty() {
+            return Ok(());
+        }
+        let _deletion = timing::time(Phase::Deletion);
+        // --pattern symbols are left in place, since where their declaration ends is
+        // only known for functions.
+        let deleted: Vec<(String, usize)> = self
+            .findings
+            .iter()
+            .filter(|finding| finding.visibility.is_some())
+            .map(|finding| (finding.function.clone(), finding.line))
+            .collect();
+        self.removed = remove_unused_functions(sol_file, &deleted)?;
+        Ok(())
+    }
+
+    /// The findings of every kind, in the order the formats other than text list them.
+    fn all_findings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .chain(self.uncalled_virtual.iter().map(|uncalled| &uncalled.finding))
+            .chain(self.never_overridden.iter().map(|never| &never.finding))
+            .chain(self.script_only.iter().map(|script_only| &script_only.finding))
+            .chain(self.unreachable_code.iter().map(|block| &block.finding))
+    }
+
+    /// Whether the report has anything to act on: unused or unreachable code, or a
+    /// warning about the file's functions.
+    fn has_findings(&self) -> bool {
+        !self.unused.is_empty()
+            || !self.removed.is_empty()
+            || !self.uncalled_virtual.is_empty()
+            || !self.never_overridden.is_empty()
+            || !self.script_only.is_empty()
+            || !self.unreachable_code.is_empty()
+            || !self.accessor_pairs.is_empty()
+            || !self.inherited_unused.is_empty()
+            || !self.wrong_inheritdoc.is_empty()
+            || !self.few_reference_files.is_empty()
+            || !self.uncovered.is_empty()
+            || self.functions.iter().any(|usage| !usage.impossible_references.is_empty())
+    }
+
+    /// Whether a TODO or FIXME is written above the function `name`.
+    fn has_todo(&self, name: &str) -> bool {
+        self.functions.iter().any(|usage| usage.name == name && usage.todo)
+    }
+
+    /// The --estimate-size of the function of `finding`.
+    fn estimated_size(&self, finding: &Finding) -> Option<usize> {
+        self.functions
+            .iter()
+            .find(|usage| usage.name == finding.function && usage.line == finding.line)
+            .and_then(|usage| usage.estimated_size)
+    }
+
+    /// The --coverage hits of the function of `finding`.
+    fn coverage_hits(&self, finding: &Finding) -> Option<u64> {
+        self.functions
+            .iter()
+            .find(|usage| usage.name == finding.function && usage.line == finding.line)
+            .and_then(|usage| usage.coverage_hits)
+    }
+
+    /// The notes on an unused function in the text report: a TODO above it, and who last
+    /// changed it with `--blame`.
+    fn notes(&self, finding: &Finding) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.has_todo(&finding.function) {
+            notes.push("TODO".to_string());
+        }
+        let only_in_messages = self
+            .functions
+            .iter()
+            .any(|usage| usage.name == finding.function && usage.line == finding.line && usage.only_in_messages());
+        if only_in_messages {
+            notes.push("only in log/revert messages".to_string());
+        }
+        let only_documented = self
+            .functions
+            .iter()
+            .any(|usage| usage.name == finding.function && usage.line == finding.line && usage.only_documented());
+        if only_documented {
+            notes.push("documented but unused in code".to_string());
+        }
+        if let Some(size) = self.estimated_size(finding) {
+            notes.push(format!("~{} bytes of source", size));
+        }
+        match self.coverage_hits(finding) {
+            Some(0) => notes.push("never run by the tests".to_string()),
+            Some(hits) => notes.push(format!("run {} time(s) by the tests", hits)),
+            None => {}
+        }
+        if finding.kind == FindingKind::UnimplementedVirtual {
+            let inherited_by = self
+                .functions
+                .iter()
+                .find(|usage| usage.name == finding.function && usage.line == finding.line)
+                .and_then(|usage| usage.virtual_stub.as_ref())
+                .map_or(&[][..], |stub| stub.inherited_by.as_slice());
+            if inherited_by.is_empty() {
+                notes.push("virtual without a body, not inherited".to_string());
+            } else {
+                notes.push(format!("virtual without a body, inherited by {}", inherited_by.join(", ")));
+            }
+        }
+        if let Some(suggestion) = &finding.suggestion {
+            notes.push(suggestion.describe());
+        }
+        match self.blame(finding) {
+            Some(blame) if blame.is_uncommitted() => notes.push("uncommitted".to_string()),
+            Some(blame) => notes.push(format!("last changed {} by {}", blame.date, blame.author)),
+            None => {}
+        }
+        notes
+    }
+
+    fn blame(&self, finding: &Finding) -> Option<&Blame> {
+        self.functions
+            .iter()
+            .find(|usage| usage.name == finding.function && usage.line == finding.line)
+            .and_then(|usage| usage.blame.as_ref())
+    }
+
+    /// Blames the declarations of the unused functions, returning whether git could.
+    fn attach_blame(&mut self) -> bool {
+        let lines: Vec<usize> = self.findings.iter().map(|finding| finding.line).collect();
+        let Some(blamed) = blame::blame_lines(&self.file, &lines) else {
+            return false;
+        };
+        for usage in &mut self.functions {
+            if self.findings.iter().any(|finding| finding.function == usage.name && finding.line == usage.line) {
+                usage.blame = blamed.get(&usage.line).cloned();
+            }
+        }
+        true
+    }
+
+    /// The author time of the oldest unused function, for `--oldest-first`.
+    fn oldest(&self) -> Option<i64> {
+        self.findings.iter().filter_map(|finding| self.blame(finding)).map(|blame| blame.time).min()
+    }
+
+    /// Orders the unused functions by when they were last changed, oldest first, with the
+    /// ones without blame last.
+    fn sort_oldest_first(&mut self) {
+        let mut findings = std::mem::take(&mut self.findings);
+        findings.sort_by_key(|finding| self.blame(finding).map_or(i64::MAX, |blame| blame.time));
+        self.unused = findings.iter().map(|finding| finding.function.clone()).collect();
+        self.findings = findings;
+    }
+
+    fn sort_functions(&mut self, order: UsageOrder) {
+        match order {
+            UsageOrder::Line => {}
+            UsageOrder::Occurrences => self.functions.sort_by_key(|usage| usage.occurrences),
+            UsageOrder::Files => self
+                .functions
+                .sort_by_key(|usage| (usage.reference_files, usage.occurrences)),
+        }
+    }
+
+    /// Lists the used functions referenced from fewer than `min` files.
+    fn flag_few_reference_files(&mut self, min: usize) {
+        self.few_reference_files = self
+            .functions
+            .iter()
+            .filter(|usage| usage.occurrences > 1 && usage.reference_files < min)
+            .map(|usage| usage.name.clone())
+            .collect();
+    }
+}
+
+/// How often a function (that isn't ignored) occurs within the search root.
+#[derive(Serialize)]
+struct FunctionUsage {
+    name: String,
+    contract: Option<String>,
+    signature: String,
+    line: usize,
+    visibility: Option<Visibility>,
+    occurrences: usize,
+    /// How many files reference the function, other than with its declaration.
+    reference_files: usize,
+    /// References from the --usage-langs sources, which aren't part of `occurrences`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    offchain: Vec<OffchainReference>,
+    /// Calls and selectors of the function in Vyper contracts, which are part of
+    /// `occurrences`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    vyper: Vec<VyperReference>,
+    /// Mentions in the --search-ext files, which are part of `occurrences`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    text_references: Vec<TextReference>,
+    /// The getter of a public state variable, which is never unused.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    auto_getter: bool,
+    /// For an interface function, the public state variable whose getter implements it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    implemented_by: Option<String>,
+    /// Mentions in log and revert messages, e.g. `console.log("entering rebalance")`,
+    /// which never count as occurrences.
+    message_references: usize,
+    /// With --documented-unused, mentions in comments and NatSpec, e.g. `@dev calls
+    /// rebalance`, which don't count as occurrences either.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment_references: Option<usize>,
+    /// With --estimate-size, the bytes of its source without comments and whitespace, a
+    /// rough proxy for the bytecode removing it would save.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_size: Option<usize>,
+    /// With --coverage, how often the tests ran the function, or `None` if the coverage
+    /// has no record of it, e.g. for an interface function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage_hits: Option<u64>,
+    /// Declared `virtual` without a body, for the contracts inheriting it to implement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    virtual_stub: Option<VirtualStub>,
+    /// With --never-overridden, the line of the `virtual` keyword of a function with a
+    /// body that nothing overrides.
+    #[serde(skip)]
+    never_overridden: Option<usize>,
+    /// The code keeping the function used, `None` when nothing does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<UsageCategory>,
+    /// The deployment scripts using a function of the `script` category.
+    #[serde(skip)]
+    scripts: Vec<PathBuf>,
+    /// A Yul function, whose occurrences are only those in its `assembly { }` block.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    yul: bool,
+    /// For a function with no references besides its declaration, a referenced identifier
+    /// with a similar name, which it may be a misspelling of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<Suggestion>,
+    /// The base declaration named by the function's `@inheritdoc` tag, e.g. `IVault.sweep`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inheritdoc: Option<String>,
+    /// Its code, which its findings are fingerprinted with.
+    #[serde(skip)]
+    context: String,
+    /// The files referencing an internal or private function, when its visibility keeps
+    /// all of them from calling it. The matches are likely a same-named unrelated function.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    impossible_references: Vec<PathBuf>,
+    /// Exposed by an `--abi` file, so consumed by other teams.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    abi_exported: bool,
+    /// The broadcast whose transactions call the function, so deployment or operations
+    /// scripts rely on it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deployed_usage: Option<PathBuf>,
+    /// Who last changed the declaration of an unused function, with `--blame`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blame: Option<Blame>,
+    /// The NatSpec or comments above the declaration mention a TODO or FIXME, which
+    /// often ties dead code to abandoned work.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    todo: bool,
+}
+
+/// How a `virtual` function without a body is implemented and called.
+#[derive(Serialize)]
+struct VirtualStub {
+    /// The inheritance lines to the contracts overriding it, e.g. `Base <- Mid <- Vault`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    overridden_by: Vec<String>,
+    /// When nothing overrides it, the inheritance lines to the contracts inheriting it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    inherited_by: Vec<String>,
+    /// References other than the declarations of its overrides.
+    calls: usize,
+}
+
+/// Statements of a function that can never run, reported for review but never deleted.
+#[derive(Serialize)]
+struct UnreachableBlock {
+    #[serde(flatten)]
+    code: UnreachableCode,
+    #[serde(skip)]
+    finding: Finding,
+}
+
+/// A state variable with a getter and a setter, one of which is never called.
+#[derive(Serialize)]
+struct AccessorPair {
+    variable: String,
+    contract: String,
+    line: usize,
+    getter: String,
+    setter: String,
+    unused: AccessorSide,
+}
+
+/// The accessor of an `AccessorPair` that is never called.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum AccessorSide {
+    Getter,
+    Setter,
+}
+
+impl AccessorPair {
+    /// e.g. `setter setFee never called, getter fee used: fee is effectively constant,
+    /// consider immutable or constant`.
+    fn describe(&self) -> String {
+        match self.unused {
+            AccessorSide::Setter => format!(
+                "setter {} never called, getter {} used: {} is effectively constant, consider immutable or constant",
+                self.setter, self.getter, self.variable
+            ),
+            AccessorSide::Getter => format!(
+                "getter {} never called, setter {} used: {} may be purely internal",
+                self.getter, self.setter, self.variable
+            ),
+        }
+    }
+}
+
+/// A `virtual` function without a body that is overridden but never called, so neither
+/// it nor its overrides run.
+#[derive(Serialize)]
+struct UncalledVirtual {
+    function: String,
+    line: usize,
+    overridden_by: Vec<String>,
+    #[serde(skip)]
+    finding: Finding,
+}
+
+/// What kind of code keeps a function used, by the files of its references, from the
+/// kind that keeps it used the most.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+enum UsageCategory {
+    /// The project's own code, or anything outside Solidity.
+    Production,
+    /// Deployment scripts, and maybe tests, but no production code.
+    Script,
+    /// Tests only.
+    Test,
+}
+
+/// A used function that only --script-dirs files use besides tests, reported as a
+/// candidate for removal once deployed but never deleted.
+#[derive(Serialize)]
+struct ScriptOnly {
+    function: String,
+    signature: String,
+    line: usize,
+    /// The scripts using it.
+    scripts: Vec<PathBuf>,
+    category: UsageCategory,
+    #[serde(skip)]
+    finding: Finding,
+}
+
+/// A function with a body declared `virtual` that nothing overrides, reported for review
+/// but never changed.
+#[derive(Serialize)]
+struct NeverOverridden {
+    function: String,
+    signature: String,
+    /// Line of the `virtual` keyword.
+    line: usize,
+    kind: FindingKind,
+    #[serde(skip)]
+    finding: Finding,
+}
+
+impl FunctionUsage {
+    /// Whether the function is `virtual` without a body, overridden, and never called.
+    fn uncalled_virtual(&self) -> bool {
+        self.virtual_stub
+            .as_ref()
+            .is_some_and(|stub| !stub.overridden_by.is_empty() && stub.calls == 0)
+            && self.offchain.is_empty()
+            && self.vyper.is_empty()
+            && self.text_references.is_empty()
+    }
+
+    /// Whether the function is unused, although log or revert messages mention it.
+    fn only_in_messages(&self) -> bool {
+        self.occurrences <= 1 && self.message_references > 0
+    }
+
+    /// Whether the function is unused in code, although comments mention it.
+    fn only_documented(&self) -> bool {
+        self.occurrences <= 1 && self.comment_references.is_some_and(|comments| comments > 0)
+    }
+
+    /// Whether the function would be unused without its mentions in --search-ext files.
+    fn used_only_as_text(&self) -> bool {
+        !self.text_references.is_empty() && self.occurrences - self.text_references.len() <= 1
+    }
+
+    /// Counts the --search-ext mentions by file type, e.g. `2 yaml, 1 md`.
+    fn text_reference_counts(&self) -> String {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for reference in &self.text_references {
+            match counts.iter_mut().find(|(file_type, _)| *file_type == reference.file_type) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((&reference.file_type, 1)),
+            }
+        }
+        counts
+            .iter()
+            .map(|(file_type, count)| format!("{} {}", count, file_type))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A function left out of the report by an `--ignore` pattern.
+#[derive(Serialize)]
+struct IgnoredFunction {
+    file: PathBuf,
+    function: String,
+    contract: Option<String>,
+    line: usize,
+    #[serde(skip)]
+    pattern: String,
+}
+
+/// The functions an `--ignore` pattern left out of the report, with `--show-ignored`.
+#[derive(Serialize)]
+struct IgnoredPattern<'a> {
+    pattern: String,
+    count: usize,
+    functions: Vec<&'a IgnoredFunction>,
+}
+
+/// Groups the ignored functions of `reports` by the pattern that matched them, in the
+/// order of `patterns`. Patterns that matched nothing are listed with a count of 0, since
+/// they may no longer be needed.
+fn ignored_by_pattern<'a>(reports: &'a [FileReport], patterns: &[String]) -> Vec<IgnoredPattern<'a>> {
+    let mut seen = HashSet::new();
+    patterns
+        .iter()
+        .filter(|pattern| seen.insert(pattern.as_str()))
+        .map(|pattern| {
+            let functions: Vec<&IgnoredFunction> = reports
+                .iter()
+                .flat_map(|report| &report.ignored)
+                .filter(|ignored| ignored.pattern == *pattern)
+                .collect();
+            IgnoredPattern {
+                pattern: pattern.clone(),
+                count: functions.len(),
+                functions,
+            }
+        })
+        .collect()
+}
+
+/// A selector called by a broadcast transaction that matches no source function.
+#[derive(Serialize)]
+struct UnknownSelector {
+    selector: String,
+    broadcast: PathBuf,
+    /// Signatures the databases know for the selector.
+    candidates: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    files: &'a [FileReport],
+    total_unused: usize,
+    /// The summed `estimated_size` of the unused functions, with `--estimate-size`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_unused_size: Option<usize>,
+    /// The unused functions that the tests never run, with `--coverage`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unused_uncovered: Option<usize>,
+    /// The used functions that the tests never run, with `--coverage`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    used_uncovered: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    abi_drift: Vec<&'a AbiFunction>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    unknown_broadcast_selectors: &'a [UnknownSelector],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deduped_findings: Vec<Collapsed>,
+    /// The files that failed or were skipped, always present.
+    errors: Vec<AnalysisError>,
+    /// The functions left out by each `--ignore` pattern, with `--show-ignored`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ignored: Vec<IgnoredPattern<'a>>,
+    /// The time of each phase so far, with `--timing`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timing: Option<Timing>,
+    /// The directories that couldn't be read, whose files the report misses.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_dirs: Vec<SkippedDir>,
+    /// The generated files under `PATH`, analyzed only with `--include-generated`.
+    generated_files: usize,
+}
+
+#[derive(Serialize)]
+struct WorkspaceReport<'a> {
+    projects: Vec<ProjectReport<'a>>,
+    total_unused: usize,
+    /// The projects that failed with `--write-report-on-error`, always present.
+    errors: &'a [AnalysisError],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timing: Option<Timing>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_dirs: Vec<SkippedDir>,
+}
+
+/// A file that failed to be analyzed, a project left out of a partial report by
+/// `--write-report-on-error`, or a file skipped without failing the run.
+#[derive(Serialize, Clone)]
+struct AnalysisError {
+    file: PathBuf,
+    reason: String,
+}
+
+impl From<SkippedFile> for AnalysisError {
+    fn from(skipped: SkippedFile) -> Self {
+        AnalysisError {
+            file: skipped.file,
+            reason: skipped.reason,
+        }
+    }
+}
+
+impl AnalysisError {
+    /// Lists the errors of the `what`, e.g. `file(s)`, left out of the report.
+    fn print_all(errors: &[AnalysisError], what: &str) {
+        if errors.is_empty() {
+            return;
+        }
+        println!("\nFailed {}: {} could not be analyzed, so this report is partial:", what, errors.len());
+        for error in errors {
+            println!("- {}: {}", error.file.display(), error.reason);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ProjectReport<'a> {
+    name: &'a str,
+    root: &'a Path,
+    #[serde(flatten)]
+    report: Report<'a>,
+}
+
+/// The analysis of one project, kept until the findings of every project are combined.
+struct ProjectRun {
+    analysis: Analysis,
+    reports: Vec<FileReport>,
+    unknown_selectors: Vec<UnknownSelector>,
+    /// The files that failed to be analyzed.
+    errors: Vec<AnalysisError>,
+}
+
+impl ProjectRun {
+    fn total_unused(&self) -> usize {
+        self.reports.iter().map(|report| report.unused.len()).sum()
+    }
+
+    /// The summed --estimate-size of the unused functions.
+    fn unused_size(&self) -> usize {
+        self.reports
+            .iter()
+            .flat_map(|report| {
+                report
+                    .findings
+                    .iter()
+                    .filter(|finding| finding.kind == FindingKind::Unused)
+                    .filter_map(|finding| report.estimated_size(finding))
+            })
+            .sum()
+    }
+
+    /// The unused functions that the tests never run, according to --coverage.
+    fn unused_uncovered(&self) -> usize {
+        self.reports
+            .iter()
+            .map(|report| {
+                report
+                    .findings
+                    .iter()
+                    .filter(|finding| report.coverage_hits(finding) == Some(0))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// The used functions that the tests never run, according to --coverage.
+    fn used_uncovered(&self) -> usize {
+        self.reports.iter().map(|report| report.uncovered.len()).sum()
+    }
+
+    fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.reports.iter().flat_map(FileReport::all_findings)
+    }
+
+    fn report(&self, args: &VacuumArgs) -> Report<'_> {
+        let deduped_findings = if args.dedupe_findings {
+            self.reports.iter().flat_map(|report| findings::dedupe(&report.findings)).collect()
+        } else {
+            Vec::new()
+        };
+        Report {
+            files: &self.reports,
+            total_unused: self.total_unused(),
+            estimated_unused_size: args.analysis.estimate_size.then(|| self.unused_size()),
+            unused_uncovered: args.analysis.coverage.is_some().then(|| self.unused_uncovered()),
+            used_uncovered: args.analysis.coverage.is_some().then(|| self.used_uncovered()),
+            abi_drift: self.analysis.abi_drift(),
+            unknown_broadcast_selectors: &self.unknown_selectors,
+            deduped_findings,
+            errors: self
+                .errors
+                .iter()
+                .cloned()
+                .chain(source::skipped_files().into_iter().map(AnalysisError::from))
+                .collect(),
+            ignored: if args.show_ignored {
+                ignored_by_pattern(&self.reports, &args.analysis.ignore)
+            } else {
+                Vec::new()
+            },
+            timing: None,
+            skipped_dirs: source::skipped_dirs(),
+            generated_files: self.analysis.generated_files().len(),
+        }
+    }
+
+    fn print_text(&self, args: &VacuumArgs) {
+        let reports = &self.reports;
+        // Clean files are only counted, unless --verbose asks for their reports too.
+        for report in reports {
+            if !args.verbose && !report.has_findings() {
+                continue;
+            }
+            if args.compact {
+                print_compact_report(report, args.verbose, args.dedupe_findings);
+            } else {
+                print_text_report(report, args.delete, args.dedupe_findings, args.color_thresholds);
+            }
+        }
+        println!("\nTotal unused functions found: {}", self.total_unused());
+        let generated = self.analysis.generated_files().len();
+        if generated > 0 && self.analysis.include_generated {
+            println!("Generated files analyzed (--include-generated): {}", generated);
+        } else if generated > 0 {
+            println!("Generated files skipped (pass --include-generated to analyze them): {}", generated);
+        }
+        let clean = reports.iter().filter(|report| !report.has_findings()).count();
+        if !args.verbose && clean > 0 {
+            println!("Files without findings, not shown (see --verbose): {}", clean);
+        }
+        if args.dedupe_findings {
+            let distinct = reports
+                .iter()
+                .map(|report| findings::dedupe(&report.findings).len())
+                .sum::<usize>();
+            println!("Distinct findings after deduplication: {}", distinct);
+        }
+        let todo = reports
+            .iter()
+            .map(|report| report.unused.iter().filter(|name| report.has_todo(name)).count())
+            .sum::<usize>();
+        if todo > 0 {
+            println!("Unused functions with a TODO or FIXME above them: {}", todo);
+        }
+        let baselined = reports.iter().map(|report| report.baselined.len()).sum::<usize>();
+        if baselined > 0 {
+            println!("Unused functions suppressed by the baseline: {}", baselined);
+        }
+        if let Some(baseline) = &self.analysis.baseline {
+            let stale = baseline.entries.iter().filter(|entry| entry.is_stale()).count();
+            if stale > 0 {
+                println!(
+                    "Baseline entries older than {} days: {} (see wand baseline show)",
+                    STALE_AFTER_DAYS, stale
+                );
+            }
+        }
+        let proxy_reachable = reports.iter().map(|report| report.proxy_reachable.len()).sum::<usize>();
+        if proxy_reachable > 0 {
+            println!(
+                "Unused functions kept as reachable through a proxy: {} (see --no-proxy-heuristic)",
+                proxy_reachable
+            );
+        }
+        let offchain_used = reports.iter().map(|report| report.offchain_used.len()).sum::<usize>();
+        if offchain_used > 0 {
+            println!("Unused functions kept as referenced off-chain: {}", offchain_used);
+        }
+        let abi_exported = reports.iter().map(|report| report.abi_exported.len()).sum::<usize>();
+        if abi_exported > 0 {
+            println!("Unused functions kept as exported by an ABI: {}", abi_exported);
+        }
+        let text_only = reports
+            .iter()
+            .flat_map(|report| &report.functions)
+            .filter(|usage| usage.used_only_as_text())
+            .count();
+        if text_only > 0 {
+            println!("Functions kept only by mentions in --search-ext files: {}", text_only);
+        }
+        let deployed_usage = reports.iter().map(|report| report.deployed_usage.len()).sum::<usize>();
+        if deployed_usage > 0 {
+            println!("Unused functions kept as called by broadcast transactions: {}", deployed_usage);
+        }
+        let only_in_messages = reports
+            .iter()
+            .flat_map(|report| &report.functions)
+            .filter(|usage| usage.only_in_messages())
+            .count();
+        if only_in_messages > 0 {
+            println!("Unused functions only mentioned in log/revert messages: {}", only_in_messages);
+        }
+        let only_documented = reports
+            .iter()
+            .flat_map(|report| &report.functions)
+            .filter(|usage| usage.only_documented())
+            .count();
+        if only_documented > 0 {
+            println!("Documented but unused in code: {}", only_documented);
+        }
+        if args.analysis.estimate_size {
+            println!(
+                "Estimated size of the unused functions: ~{} bytes of source (approximate, not compiled)",
+                self.unused_size()
+            );
+        }
+        let suspicious = reports
+            .iter()
+            .flat_map(|report| &report.functions)
+            .filter(|usage| !usage.impossible_references.is_empty())
+            .count();
+        if suspicious > 0 {
+            println!(
+                "Functions only referenced where their visibility forbids (likely false matches): {}",
+                suspicious
+            );
+        }
+        let unimplemented = reports
+            .iter()
+            .flat_map(|report| &report.findings)
+            .filter(|finding| finding.kind == FindingKind::UnimplementedVirtual)
+            .count();
+        if unimplemented > 0 {
+            println!("Unused virtual functions without a body, never overridden: {}", unimplemented);
+        }
+        let unreachable = reports.iter().flat_map(|report| &report.unreachable_code);
+        let (blocks, lines) = unreachable.fold((0, 0), |(blocks, lines), block| (blocks + 1, lines + block.code.lines));
+        if blocks > 0 {
+            println!("Unreachable code: {} line(s) in {} place(s)", lines, blocks);
+        }
+        let accessor_pairs = reports.iter().flat_map(|report| &report.accessor_pairs);
+        let (never_set, never_read) = accessor_pairs.fold((0, 0), |(set, read), pair| match pair.unused {
+            AccessorSide::Setter => (set + 1, read),
+            AccessorSide::Getter => (set, read + 1),
+        });
+        if never_set + never_read > 0 {
+            println!(
+                "State variables with an unused setter: {}, with an unused getter: {}",
+                never_set, never_read
+            );
+        }
+        let inherited_unused = reports.iter().map(|report| report.inherited_unused.len()).sum::<usize>();
+        if inherited_unused > 0 {
+            println!("Inherited functions unused by the project: {}", inherited_unused);
+        }
+        let uncalled_virtual = reports.iter().map(|report| report.uncalled_virtual.len()).sum::<usize>();
+        if uncalled_virtual > 0 {
+            println!("Virtual functions overridden but never called: {}", uncalled_virtual);
+        }
+        let never_overridden = reports.iter().map(|report| report.never_overridden.len()).sum::<usize>();
+        if never_overridden > 0 {
+            println!("Virtual functions never overridden: {}", never_overridden);
+        }
+        let script_only = reports.iter().map(|report| report.script_only.len()).sum::<usize>();
+        if script_only > 0 {
+            println!("Functions only used by deployment scripts: {}", script_only);
+        }
+        let wrong_inheritdoc = reports.iter().map(|report| report.wrong_inheritdoc.len()).sum::<usize>();
+        if wrong_inheritdoc > 0 {
+            println!("Wrong @inheritdoc targets: {}", wrong_inheritdoc);
+        }
+        let few_reference_files = reports.iter().map(|report| report.few_reference_files.len()).sum::<usize>();
+        if few_reference_files > 0 {
+            println!("Used functions referenced from few files: {}", few_reference_files);
+        }
+        if args.analysis.coverage.is_some() {
+            println!(
+                "Unused functions never run by the tests (the safest to delete): {}",
+                self.unused_uncovered()
+            );
+            println!("Used functions never run by the tests (gaps in the tests): {}", self.used_uncovered());
+        }
+        let drift = self.analysis.abi_drift();
+        if !drift.is_empty() {
+            println!("\nABI entries without a matching source function (drift): {}", drift.len());
+            for function in drift {
+                println!("- {}: {}", function.file.display(), function.signature);
+            }
+        }
+        if !self.unknown_selectors.is_empty() {
+            println!(
+                "\nBroadcast selectors without a source function: {}",
+                self.unknown_selectors.len()
+            );
+            for unknown in &self.unknown_selectors {
+                let candidates = if unknown.candidates.is_empty() {
+                    "unknown".to_string()
+                } else {
+                    unknown.candidates.join(", ")
+                };
+                println!("- {} in {} ({})", unknown.selector, unknown.broadcast.display(), candidates);
+            }
+        }
+        if args.show_ignored {
+            println!("\nFunctions left out by --ignore patterns:");
+            for group in ignored_by_pattern(reports, &args.analysis.ignore) {
+                println!("- {:?}: {}", group.pattern, group.count);
+                for ignored in group.functions {
+                    let name = match &ignored.contract {
+                        Some(contract) => format!("{}.{}", contract, ignored.function),
+                        None => ignored.function.clone(),
+                    };
+                    println!("  - {}:{} {}", ignored.file.display(), ignored.line, name);
+                }
+            }
+        }
+        AnalysisError::print_all(&self.errors, "file(s)");
+    }
+}
+
+impl VacuumArgs {
+    /// Notes which of the options parsed into `matches` were left at their defaults.
+    pub fn note_defaults(&mut self, matches: &ArgMatches) {
+        self.analysis.default_ignore = matches.value_source("ignore") == Some(ValueSource::DefaultValue);
+    }
+}
+
+pub fn run(mut args: VacuumArgs) -> Result<()> {
+    enable_targets(&args.targets, &mut args.analysis);
+    if args.timing {
+        timing::enable();
+    }
+    source::set_strict_fs(args.strict_fs);
+    if args.format == FindingsFormat::Text {
+        output::page();
+        if !args.no_progress {
+            progress::enable();
+        }
+    }
+    let result = run_projects(&args);
+    output::end_pager();
+    if args.timing {
+        eprintln!("{}", timing::snapshot().table());
+    }
+    result
+}
+
+fn run_projects(args: &VacuumArgs) -> Result<()> {
+    if args.list_targets {
+        match args.format {
+            FindingsFormat::Json => println!("{}", output::to_json(&TARGETS)?),
+            _ => TARGETS
+                .iter()
+                .for_each(|target| println!("{}: {}", target.name, target.description)),
+        }
+        return Ok(());
+    }
+
+    let members = if args.analysis.address.is_some() {
+        Vec::new()
+    } else if args.analysis.path.is_some() && args.project.is_empty() {
+        // An explicit PATH is analyzed as given, unless --project asks for its projects.
+        Vec::new()
+    } else {
+        let config = Config::load(&args.analysis.root)?;
+        let configured = if args.analysis.path.is_some() { &[][..] } else { &config.projects };
+        let dir = args.analysis.path.as_deref().unwrap_or(&args.analysis.root);
+        let excluded = PathExclusions::load(
+            &args.analysis.no_match_path,
+            args.analysis.ignore_paths_from.as_deref(),
+            &[dir.to_path_buf()],
+        )?;
+        workspace::members(&args.analysis.root, dir, configured, &args.project, &excluded)?
+    };
+
+    let reports_functions = args.explain_unused.is_none() && !args.transitive && !args.compact;
+    if args.format == FindingsFormat::Text && reports_functions && !args.no_legend && output::colored() {
+        println!("{}", args.color_thresholds.legend());
+    }
+    if args.format == FindingsFormat::Jsonl && args.explain_unused.is_none() && !args.transitive {
+        println!("{}", Record::meta().to_line()?);
+    }
+
+    if members.is_empty() {
+        let Some(project) = run_project(args, &args.analysis)? else {
+            return Ok(());
+        };
+        let reporting = timing::time(Phase::Reporting);
+        match args.format {
+            FindingsFormat::Text => {
+                project.print_text(args);
+                print_delete_hint(args, &[&project]);
+            }
+            FindingsFormat::Json => {
+                let mut report = project.report(args);
+                report.timing = args.timing.then(timing::snapshot);
+                println!("{}", output::to_json(&report)?);
+            }
+            _ => {}
+        }
+        drop(reporting);
+        let findings: Vec<Finding> = project.findings().cloned().collect();
+        let unmatched = unmatched_ignores(args, &[&project], project.errors.len());
+        return finish(args, &findings, project.reports.len(), project.errors.len(), &unmatched);
+    }
+
+    let mut projects = Vec::new();
+    let mut errors = Vec::new();
+    for member in &members {
+        if args.format == FindingsFormat::Text {
+            println!("\n=== Project {} ({}) ===", member.name, member.root.display());
+        }
+        let mut member_args = args.analysis.clone();
+        member_args.root = member.root.clone();
+        member_args.path = None;
+        member_args.usage_roots = member.usage_roots.clone();
+        match run_project(args, &member_args) {
+            Ok(Some(project)) => {
+                if args.format == FindingsFormat::Text {
+                    let _reporting = timing::time(Phase::Reporting);
+                    project.print_text(args);
+                }
+                projects.push((member, project));
+            }
+            Ok(None) => {}
+            Err(error) if args.write_report_on_error => {
+                if args.format == FindingsFormat::Text {
+                    println!("Error: {}", error);
+                }
+                errors.push(AnalysisError {
+                    file: member.root.clone(),
+                    reason: error.to_string(),
+                });
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    if projects.is_empty() && errors.is_empty() {
+        return Ok(());
+    }
+
+    let total_unused = projects.iter().map(|(_, project)| project.total_unused()).sum::<usize>();
+    let reporting = timing::time(Phase::Reporting);
+    match args.format {
+        FindingsFormat::Text => {
+            println!("\nProjects analyzed: {}", projects.len());
+            for (member, project) in &projects {
+                println!("- {}: {} unused function(s)", member.name, project.total_unused());
+            }
+            println!("Total unused functions across projects: {}", total_unused);
+            AnalysisError::print_all(&errors, "project(s)");
+            let analyzed: Vec<&ProjectRun> = projects.iter().map(|(_, project)| project).collect();
+            print_delete_hint(args, &analyzed);
+        }
+        FindingsFormat::Json => println!(
+            "{}",
+            output::to_json(&WorkspaceReport {
+                projects: projects
+                    .iter()
+                    .map(|(member, project)| ProjectReport {
+                        name: &member.name,
+                        root: &member.root,
+                        report: project.report(args),
+                    })
+                    .collect(),
+                total_unused,
+                errors: &errors,
+                timing: args.timing.then(timing::snapshot),
+                skipped_dirs: source::skipped_dirs(),
+            })?
+        ),
+        _ => {}
+    }
+    drop(reporting);
+    let findings: Vec<Finding> = projects
+        .iter()
+        .flat_map(|(_, project)| project.findings().cloned())
+        .collect();
+    let failed = errors.len() + projects.iter().map(|(_, project)| project.errors.len()).sum::<usize>();
+    let analyzed: Vec<&ProjectRun> = projects.iter().map(|(_, project)| project).collect();
+    let unmatched = unmatched_ignores(args, &analyzed, failed);
+    let files = analyzed.iter().map(|project| project.reports.len()).sum();
+    finish(args, &findings, files, failed, &unmatched)
+}
+
+/// Warns about the `--ignore` patterns that matched no function in any of `projects`,
+/// returning them. The default pattern isn't checked unless it was passed, nor are partial
+/// reports, where the files that failed may hold the matches.
+fn unmatched_ignores<'a>(args: &'a VacuumArgs, projects: &[&ProjectRun], failed: usize) -> Vec<&'a str> {
+    let patterns = &args.analysis.ignore;
+    if failed > 0 || args.analysis.default_ignore {
+        return Vec::new();
+    }
+    let matched: HashSet<&str> = projects
+        .iter()
+        .flat_map(|project| &project.reports)
+        .flat_map(|report| report.matched_ignores.iter().map(String::as_str))
+        .collect();
+    let unmatched: Vec<&str> = patterns
+        .iter()
+        .map(String::as_str)
+        .filter(|pattern| !matched.contains(pattern))
+        .collect();
+    for pattern in &unmatched {
+        eprintln!("Warning: --ignore pattern {:?} from the command line matched no function.", pattern);
+    }
+    unmatched
+}
+
+/// Ends a text report listing functions that `--delete` would remove with how to remove
+/// them, unless it just did. The temporary sources of an archive or `--address` can't be
+/// deleted from, so their functions don't count.
+fn print_delete_hint(args: &VacuumArgs, projects: &[&ProjectRun]) {
+    if args.delete || args.format != FindingsFormat::Text {
+        return;
+    }
+    let deletable: usize = projects
+        .iter()
+        .filter(|project| project.analysis.archive.as_ref().is_none_or(|archive| archive.kept))
+        .flat_map(|project| &project.reports)
+        .map(|report| report.findings.len())
+        .sum();
+    if deletable > 0 {
+        println!(
+            "\nRun again with --delete to remove the {} unused function(s) listed above; without it, nothing is changed.",
+            deletable
+        );
+    }
+}
+
+/// Prints the directories the `.wandignore` files exclude, and the files they exclude
+/// outside of those, each with the pattern excluding it, to debug patterns that exclude
+/// too much.
+fn print_wandignore_exclusions(analysis: &Analysis) -> Result<()> {
+    let wandignore = &analysis.excluded.wandignore;
+    let dirs: Vec<(&Path, &str)> = wandignore.excluded_dirs().collect();
+    for (dir, origin) in &dirs {
+        eprintln!("Note: {}/ is excluded by {}", dir.display(), origin);
+    }
+    for file in analysis.search_files()? {
+        let file = relative(&file);
+        if dirs.iter().any(|(dir, _)| file.starts_with(dir)) {
+            continue;
+        }
+        if let Some(origin) = wandignore.excluded_by(&file) {
+            eprintln!("Note: {} is excluded by {}", file.display(), origin);
+        }
+    }
+    Ok(())
+}
+
+/// Analyzes one project, or prints the explanation or deletion plan and returns `None`.
+fn run_project(args: &VacuumArgs, analysis_args: &AnalysisArgs) -> Result<Option<ProjectRun>> {
+    let baseline = args
+        .baseline
+        .as_deref()
+        .map(|path| Baseline::read(path, &args.analysis.root))
+        .transpose()?;
+    let collection = timing::time(Phase::Collection);
+    let analysis = Analysis::new(analysis_args, baseline)?;
+    drop(collection);
+    if args.verbose {
+        print_wandignore_exclusions(&analysis)?;
+    }
+    if args.delete {
+        if let Some(archive) = analysis.archive.as_ref().filter(|archive| !archive.kept) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "--delete can't remove functions from the temporary sources of {:?}; extract them first, or pass --keep-sources with --address.",
+                    archive.origin
+                ),
+            ));
+        }
+    }
+
+    if let Some(name) = &args.explain_unused {
+        explain_unused(analysis_args, &analysis, name)?;
+        return Ok(None);
+    }
+
+    if args.transitive {
+        let analyzed_files = analysis.analyzed_files()?;
+        plan::run(args, &analyzed_files, &analysis)?;
+        return Ok(None);
+    }
+
+    let mut reports = Vec::new();
+    let mut errors = Vec::new();
+    let policy = match args.format {
+        FindingsFormat::Jsonl => Some(Config::load(&args.analysis.root)?.severity),
+        _ => None,
+    };
+    let stream = |path: &Path, result: &Result<Option<FileReport>>| {
+        let Some(policy) = &policy else {
+            return;
+        };
+        let records: Vec<Record> = match result {
+            Ok(report) => report
+                .iter()
+                .flat_map(FileReport::all_findings)
+                .map(|finding| Record::finding(finding, policy))
+                .collect(),
+            Err(error) => vec![Record::Error {
+                file: path,
+                error: error.to_string(),
+            }],
+        };
+        // One line per record, so that records of files finishing at once don't interleave.
+        for record in records {
+            if let Ok(line) = record.to_line() {
+                println!("{}", line);
+            }
+        }
+    };
+    for (path, result) in analyze_each(analysis_args, &analysis, args.delete, args.force, stream)? {
+        match result {
+            Ok(report) => reports.extend(report),
+            Err(error) => errors.push(AnalysisError {
+                file: analysis.display_path(&path),
+                reason: error.to_string(),
+            }),
+        }
+    }
+    if args.delete {
+        let kept: Vec<&Finding> = reports
+            .iter()
+            .flat_map(|report| &report.findings)
+            .filter(|finding| finding.visibility.is_none())
+            .collect();
+        if let Some(first) = kept.first() {
+            eprintln!(
+                "Warning: --delete only removes functions, so {} unused --pattern symbol(s) were left in place, starting with `{}` at {}:{}; remove them by hand.",
+                kept.len(),
+                first.function,
+                first.file.display(),
+                first.line
+            );
+        }
+    }
+    if args.blame {
+        let unblamed: Vec<&PathBuf> = reports
+            .par_iter_mut()
+            .filter(|report| !report.findings.is_empty())
+            .filter_map(|report| (!report.attach_blame()).then_some(&report.file))
+            .collect();
+        if let Some(file) = unblamed.first() {
+            eprintln!(
+                "Warning: git blame failed for {} file(s), e.g. {:?}, which may be untracked or outside a git repository.",
+                unblamed.len(),
+                file
+            );
+        }
+        if args.oldest_first {
+            reports.iter_mut().for_each(FileReport::sort_oldest_first);
+            reports.sort_by_key(|report| report.oldest().unwrap_or(i64::MAX));
+        }
+    }
+    for report in &mut reports {
+        report.sort_functions(args.sort);
+        if let Some(min) = args.min_reference_files {
+            report.flag_few_reference_files(min);
+        }
+    }
+
+    let mut unknown_selectors = Vec::new();
+    if args.resolve_selectors {
+        unknown_selectors = analysis.unknown_broadcast_selectors();
+        let mut database = SignatureDatabase::open();
+        database.resolve(unknown_selectors.iter().map(|unknown| &unknown.selector));
+        for unknown in &mut unknown_selectors {
+            unknown.candidates = database.signatures(&unknown.selector).to_vec();
+        }
+    }
+
+    Ok(Some(ProjectRun {
+        analysis,
+        reports,
+        unknown_selectors,
+        errors,
+    }))
+}
+
+/// Records the findings of every project in the baseline and renders them in the
+/// formats other than text and JSON, or ends the JSON Lines stream with a summary of the
+/// `files` analyzed, then fails if any isn't in the baseline, `failed`
+/// files or projects couldn't be analyzed, or `--strict-ignores` is passed and some
+/// `--ignore` patterns matched nothing.
+fn finish(
+    args: &VacuumArgs,
+    findings: &[Finding],
+    files: usize,
+    failed: usize,
+    unmatched_ignores: &[&str],
+) -> Result<()> {
+    if let Some(path) = args.baseline_write.as_ref().filter(|_| failed > 0) {
+        // A partial baseline would drop the findings of the files that failed.
+        eprintln!("Warning: not writing baseline {:?} from a partial report.", path);
+    } else if let Some(path) = &args.baseline_write {
+        Baseline::record(path, findings, &args.analysis.root)?;
+        if args.format == FindingsFormat::Text {
+            println!("Wrote {} finding(s) to baseline {:?}.", findings.len(), path);
+        }
+    }
+
+    if args.format == FindingsFormat::Jsonl {
+        // The findings were written as each file was analyzed.
+        let summary = Record::Summary {
+            files: Some(files),
+            findings: findings.len(),
+            errors: failed,
+            skipped_dirs: source::skipped_dirs().len(),
+        };
+        println!("{}", summary.to_line()?);
+    } else if !matches!(args.format, FindingsFormat::Text | FindingsFormat::Json) {
+        let _reporting = timing::time(Phase::Reporting);
+        let config = Config::load(&args.analysis.root)?;
+        let deduped: Vec<Finding>;
+        let rendered_findings = if args.dedupe_findings {
+            deduped = findings::dedupe(findings).into_iter().map(|group| group.finding).collect();
+            &deduped
+        } else {
+            findings
+        };
+        let suppressions = Suppressions::load(args.suppressions.as_deref())?;
+        let uri_base = findings::uri_base(&args.analysis.root, args.relative_to.as_deref());
+        let rendered = findings::render(
+            args.format,
+            rendered_findings,
+            &config.severity,
+            &suppressions,
+            &uri_base,
+        )?;
+        if let Some(rendered) = rendered {
+            println!("{}", rendered);
+        }
+    }
+
+    let skipped_dirs = source::take_skipped_dirs();
+    if args.format == FindingsFormat::Text {
+        print_skipped_dirs(&skipped_dirs);
+    } else {
+        source::warn_skipped_dirs(&skipped_dirs);
+    }
+
+    if failed > 0 {
+        return Err(Error::other(format!(
+            "Failed to analyze {} file(s) or project(s); the report only covers the others.",
+            failed
+        )));
+    }
+    if args.baseline.is_some() && !findings.is_empty() {
+        return Err(Error::other(format!(
+            "Found {} unused function(s) not in the baseline.",
+            findings.len()
+        )));
+    }
+    if args.strict_ignores && !unmatched_ignores.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} --ignore pattern(s) matched no function: {}.",
+                unmatched_ignores.len(),
+                unmatched_ignores.join(", ")
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Finds every unused function, without consulting a baseline or deleting anything.
+pub fn find_unused(args: &AnalysisArgs) -> Result<Vec<Finding>> {
+    let analysis = Analysis::new(args, None)?;
+    Ok(analyze(args, &analysis, false)?
+        .into_iter()
+        .flat_map(|report| report.findings)
+        .collect())
+}
+
+/// How a reference listed by `wand usages` refers to the function.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// A call or other use in code, outside of test files.
+    Call,
+    /// A signature string or `abi.encodeCall`, which call the function by selector.
+    Selector,
+    /// A call or other use in the code of a test file, e.g. `test/Vault.t.sol`.
+    Test,
+    /// A call or other use in the code of a --script-dirs file, e.g. `script/Deploy.s.sol`.
+    Script,
+    /// A mention in a string literal, e.g. a log or revert message.
+    String,
+    /// A mention in a comment.
+    Comment,
+    /// A call or selector of the function in a Vyper contract.
+    Vyper,
+    /// A mention in a --search-ext file.
+    Text,
+    /// A reference from the --usage-langs sources.
+    Offchain,
+}
+
+impl ReferenceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReferenceKind::Call => "call",
+            ReferenceKind::Selector => "selector",
+            ReferenceKind::Test => "test",
+            ReferenceKind::Script => "script",
+            ReferenceKind::String => "string",
+            ReferenceKind::Comment => "comment",
+            ReferenceKind::Vyper => "vyper",
+            ReferenceKind::Text => "text",
+            ReferenceKind::Offchain => "offchain",
+        }
+    }
+}
+
+/// A reference to a function, and whether `vacuum` counts it as a usage.
+#[derive(Serialize)]
+pub struct FunctionReference {
+    pub file: PathBuf,
+    pub line: usize,
+    pub kind: ReferenceKind,
+    /// False for the mentions that don't keep the function used, e.g. those in strings
+    /// without --count-strings.
+    pub counts: bool,
+    pub snippet: String,
+}
+
+/// A declaration of the function looked up by `find_references`.
+#[derive(Serialize)]
+pub struct DeclarationSite {
+    pub file: PathBuf,
+    pub line: usize,
+    pub contract: Option<String>,
+    pub signature: String,
+}
+
+pub struct FunctionReferences {
+    pub declarations: Vec<DeclarationSite>,
+    pub references: Vec<FunctionReference>,
+}
+
+/// Finds the references to the functions named `name` under `PATH`, or only to those
+/// declared with the written `signature`, e.g. `sweep(address)`, by the rules that decide
+/// which functions are unused, for `wand usages`. Overloads share their references, which
+/// are matched by name, as in the analysis.
+pub fn find_references(args: &AnalysisArgs, name: &str, signature: Option<&str>) -> Result<FunctionReferences> {
+    let analysis = Analysis::new(args, None)?;
+    let options = args.count_options();
+    let signature = signature.map(|signature| signature.split_whitespace().collect::<String>());
+
+    // Every declaration of the name is left out of the references, the selected or not.
+    // The selected are kept with the file they're in, as displayed and canonical.
+    let mut decls = Vec::new();
+    let mut files = Vec::new();
+    let mut declaration_offsets = HashSet::new();
+    for sol_file in analysis.analyzed_files()? {
+        if analysis.excluded.excludes(&sol_file) {
+            continue;
+        }
+        let file = canonical(&sol_file);
+        for decl in extract_functions(&sol_file, &analysis, &options)? {
+            if decl.name != name {
+                continue;
+            }
+            declaration_offsets.insert((file.clone(), decl.offset));
+            if signature.as_ref().is_none_or(|signature| *signature == decl.signature) {
+                files.push((analysis.display_path(&sol_file), file.clone()));
+                decls.push(decl);
+            }
+        }
+    }
+    if decls.is_empty() {
+        let function = signature.unwrap_or_else(|| format!("`{}`", name));
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("No function {} is declared in {:?}.", function, analysis.path),
+        ));
+    }
+
+    let mut references = Vec::new();
+    for occurrence in name_occurrences(&analysis, &decls, name)? {
+        let file = canonical(&occurrence.file);
+        if declaration_offsets.contains(&(file.clone(), occurrence.offset)) {
+            continue;
+        }
+        // Yul functions are only called from their own assembly block.
+        let in_scope = |decl: &FunctionDecl, declaring_file: &Path| match decl.assembly {
+            Some((open, close)) => (open..close).contains(&occurrence.offset) && file == declaring_file,
+            None => true,
+        };
+        let credited = decls
+            .iter()
+            .zip(&files)
+            .any(|(decl, (_, declaring_file))| in_scope(decl, declaring_file) && analysis.credits(&occurrence, decl));
+        if !credited {
+            continue;
+        }
+        let kind = if occurrence.in_signature || occurrence.in_encode_call {
+            ReferenceKind::Selector
+        } else {
+            match occurrence.context {
+                Context::Code if is_test_file(&occurrence.file) => ReferenceKind::Test,
+                Context::Code if analysis.scripts.contains(&occurrence.file) => ReferenceKind::Script,
+                Context::Code => ReferenceKind::Call,
+                Context::String => ReferenceKind::String,
+                Context::Comment => ReferenceKind::Comment,
+            }
+        };
+        references.push(FunctionReference {
+            counts: occurrence.counts(&options),
+            file: relative(&occurrence.file),
+            line: occurrence.line,
+            kind,
+            snippet: occurrence.snippet,
+        });
+    }
+
+    let vyper = decls.iter().map(|decl| &decl.vyper).find(|references| !references.is_empty());
+    references.extend(vyper.into_iter().flatten().map(|reference| FunctionReference {
+        file: relative(&reference.file),
+        line: reference.line,
+        kind: ReferenceKind::Vyper,
+        counts: true,
+        snippet: reference.snippet.clone(),
+    }));
+    // Nothing outside the assembly block can call a Yul function.
+    if decls.iter().any(|decl| decl.assembly.is_none()) {
+        references.extend(analysis.text.references(name).into_iter().map(|reference| FunctionReference {
+            file: relative(&reference.file),
+            line: reference.line,
+            kind: ReferenceKind::Text,
+            counts: true,
+            snippet: reference.snippet,
+        }));
+        references.extend(analysis.offchain.references(name).into_iter().map(|reference| FunctionReference {
+            file: relative(&reference.file),
+            line: reference.line,
+            kind: ReferenceKind::Offchain,
+            counts: true,
+            snippet: reference.snippet,
+        }));
+    }
+    references.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+
+    Ok(FunctionReferences {
+        declarations: decls
+            .into_iter()
+            .zip(files)
+            .map(|(decl, (path, _))| DeclarationSite {
+                file: path,
+                line: decl.line,
+                contract: decl.contract,
+                signature: decl.signature,
+            })
+            .collect(),
+        references,
+    })
+}
+
+/// Whether `path` holds tests, i.e. is a `.t.sol` file or under a `test` or `tests`
+/// directory.
+fn is_test_file(path: &Path) -> bool {
+    let test_dir = |component: Component| matches!(component.as_os_str().to_str(), Some("test" | "tests"));
+    path.to_string_lossy().ends_with(".t.sol") || path.parent().is_some_and(|dir| dir.components().any(test_dir))
+}
+
+/// The occurrences of `name` under the root and `PATH`, with those of the names that
+/// files import it under when one of `declarations` is a free function, by file and line.
+fn name_occurrences(analysis: &Analysis, declarations: &[FunctionDecl], name: &str) -> Result<Vec<Occurrence>> {
+    let mut occurrences =
+        count_function_occurrences(&analysis.search_files()?, &[name], &analysis.excluded)?.take(name);
+    if declarations.iter().any(|decl| decl.contract.is_none() && decl.visibility.is_some()) {
+        occurrences.extend(analysis.alias_occurrences(name));
+    }
+    occurrences.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    Ok(occurrences)
+}
+
+/// Analyzes every file under `PATH`, removing the unused functions if `delete` is set.
+fn analyze(args: &AnalysisArgs, analysis: &Analysis, delete: bool) -> Result<Vec<FileReport>> {
+    Ok(analyze_each(args, analysis, delete, false, |_, _| {})?
+        .into_iter()
+        .map(|(path, result)| result.map_err(|error| file_error(&analysis.display_path(&path), error)))
+        .collect::<Result<Vec<Option<FileReport>>>>()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Lists the directories that couldn't be read at the end of the text report.
+fn print_skipped_dirs(skipped_dirs: &[SkippedDir]) {
+    if skipped_dirs.is_empty() {
+        return;
+    }
+    println!(
+        "\nWarning: skipped {} unreadable director(ies), whose files were neither analyzed nor searched for usages:",
+        skipped_dirs.len()
+    );
+    for skipped in skipped_dirs {
+        println!("- {}: {}", skipped.path.display(), skipped.error);
+    }
+}
+
+/// Fails `--delete` without `--force` once directories or files were skipped, e.g. an
+/// unreadable directory or a searched file that isn't valid UTF-8, since a function used
+/// only by their files would be deleted as unused.
+fn refuse_partial_delete() -> Result<()> {
+    let skipped = source::skipped_dirs();
+    if !skipped.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Skipped {} unreadable director(ies), starting with {:?}, so functions used only there would look unused; nothing was deleted. Pass --force to delete anyway, or --strict-fs to fail on them.",
+                skipped.len(),
+                skipped[0].path
+            ),
+        ));
+    }
+    let skipped = source::skipped_files();
+    if !skipped.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Skipped {} file(s), starting with {:?} ({}), so functions used only there would look unused; nothing was deleted. Pass --force to delete anyway.",
+                skipped.len(),
+                skipped[0].file,
+                skipped[0].reason
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Analyzes every file under `PATH` like `analyze`, with the result of each file, so that
+/// one failing file doesn't lose the reports of the others. `on_file` is called with
+/// each result as soon as it's known, e.g. to stream the findings, and again with the
+/// error of a file whose deletion failed.
+///
+/// Functions are only deleted once every file has been analyzed, so that the counts of
+/// each file are taken from the same content as the others', whichever finishes first,
+/// and not at all when any file failed, since a function used only by that file would
+/// be deleted as unused.
+fn analyze_each(
+    args: &AnalysisArgs,
+    analysis: &Analysis,
+    delete: bool,
+    force: bool,
+    on_file: impl Fn(&Path, &Result<Option<FileReport>>) + Sync,
+) -> Result<Vec<(PathBuf, Result<Option<FileReport>>)>> {
+    if analysis.path.is_file() && !has_extension(&analysis.path, SOL_EXTENSIONS) {
+        eprintln!("Warning: {:?} does not have a .sol extension.", analysis.path);
+    }
+    let collection = timing::time(Phase::Collection);
+    let search_files = analysis.search_files()?;
+    let analyzed_files = analysis.analyzed_files()?;
+    drop(collection);
+    if delete && !force {
+        refuse_partial_delete()?;
+    }
+    if delete && !analysis.include_generated && analysis.path.is_file() && analysis.generated.marks(&analysis.path) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{:?} is generated, so nothing is deleted from it; pass --include-generated to vacuum it anyway.",
+                analysis.path
+            ),
+        ));
+    }
+    let bar = progress::files(analyzed_files.len());
+    let extracted: Vec<(PathBuf, Result<Option<Vec<FunctionDecl>>>)> = analyzed_files
+        .into_par_iter()
+        .map(|path| {
+            let decls = isolate(|| extract_analyzed(&path, args, analysis));
+            (path, decls)
+        })
+        .collect();
+    // The functions of every file are counted in a single scan of the search files,
+    // rather than one scan per analyzed file.
+    let names: Vec<&str> = extracted
+        .iter()
+        .filter_map(|(_, decls)| decls.as_ref().ok()?.as_ref())
+        .flatten()
+        .map(|decl| decl.name.as_str())
+        .collect();
+    let occurrences = count_function_occurrences(&search_files, &names, &analysis.excluded)?;
+    let mut results: Vec<(PathBuf, Result<Option<FileReport>>)> = extracted
+        .into_par_iter()
+        .map(|(path, decls)| {
+            let result = match decls {
+                Ok(Some(decls)) => isolate(|| process_single_file(&path, decls, args, analysis, &occurrences)),
+                Ok(None) => Ok(None),
+                Err(error) => Err(error),
+            };
+            bar.inc(1);
+            on_file(&path, &result);
+            (path, result)
+        })
+        .collect();
+    bar.finish_and_clear();
+
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    if delete && failed > 0 {
+        eprintln!(
+            "Warning: {} file(s) failed to be analyzed, so nothing was deleted; fix them and run again.",
+            failed
+        );
+    } else if delete {
+        // The files searched for usages are only read while analyzing.
+        if !force {
+            refuse_partial_delete()?;
+        }
+        results.par_iter_mut().for_each(|(path, result)| {
+            let Ok(Some(report)) = result else {
+                return;
+            };
+            if let Err(error) = isolate(|| report.delete_unused(path)) {
+                *result = Err(error);
+                on_file(path, result);
+            }
+        });
+    }
+    Ok(results)
+}
+
+/// Runs the work on one file, turning a panic into the file's error, so that a bug hit by
+/// one pathological file fails that file instead of the whole run.
+fn isolate<T>(work: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(work)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown cause".to_string());
+        Err(Error::other(format!(
+            "wand panicked while analyzing it ({}); this is a bug, please report it with the file",
+            message
+        )))
+    })
+}
+
+/// The error of a file that failed to be analyzed, naming it.
+fn file_error(path: &Path, error: Error) -> Error {
+    Error::new(error.kind(), format!("Failed to analyze {:?}: {}", path, error))
+}
+
+/// Extracts the declared functions, followed by any symbols matched by `patterns`.
+///
+/// Declarations in comments are skipped unless `options` includes commented-out code.
+fn extract_functions(sol_file: &Path, analysis: &Analysis, options: &CountOptions) -> Result<Vec<FunctionDecl>> {
+    let source = SourceFile::read(sol_file)?;
+
+    let unit = solidity::parse(&source);
+    let kind_of = |kind: fn(&ContractKind) -> bool| -> HashSet<String> {
+        unit.contracts
+            .iter()
+            .filter(|contract| kind(&contract.kind))
+            .map(|contract| contract.name.clone())
+            .collect()
+    };
+    let libraries = kind_of(|kind| matches!(kind, ContractKind::Library));
+    let interfaces = kind_of(|kind| matches!(kind, ContractKind::Interface));
+    let abstracts = kind_of(|kind| matches!(kind, ContractKind::Abstract | ContractKind::Contract));
+    let getters = unit.state_variables;
+    let mut functions = unit.functions;
+    if options.include_commented {
+        // The parser only reads code, so commented-out declarations are parsed from a
+        // copy with the comment delimiters removed.
+        functions.extend(
+            solidity::parse(&source.uncommented())
+                .functions
+                .into_iter()
+                .filter(|function| source.context_at(function.name_offset) == Context::Comment),
+        );
+        functions.sort_by_key(|function| function.start);
+    }
+
+    let mut decls: Vec<FunctionDecl> = functions
+        .into_iter()
+        .filter(|function| !function.is_special())
+        .map(|function| {
+            let context = function.code(&source);
+            FunctionDecl {
+                signature: function.written_signature(),
+                abi_exported: analysis.abi_exported(&function),
+                deployed_usage: analysis.deployed_usage(&function),
+                vyper: analysis.vyper_references(&function),
+                todo: has_todo(&source, function.start),
+                doc_comments: source.preceding_comments(function.start),
+                size: Some(context.len()),
+                context,
+                library: function.contract.as_deref().is_some_and(|contract| libraries.contains(contract)),
+                inheritdoc: NatSpec::before(&source, function.start)
+                    .and_then(|natspec| natspec.inheritdoc().map(str::to_string))
+                    .map(|base| (base, function.params.len())),
+                implemented_by: function
+                    .contract
+                    .as_deref()
+                    .filter(|contract| interfaces.contains(*contract))
+                    .and_then(|interface| analysis.auto_getter(interface, &function.name)),
+                auto_getter: false,
+                virtual_stub: function.body.is_none()
+                    && function
+                        .contract
+                        .as_deref()
+                        .is_some_and(|contract| abstracts.contains(contract))
+                    && virtual_pattern().is_match(&source.code_only((function.params_span.1, function.header_end))),
+                virtual_line: virtual_line(&source, &function, &abstracts),
+                params: function.params.len(),
+                enclosing: None,
+                assembly: function.assembly,
+                offset: function.name_offset,
+                name: function.name,
+                contract: function.contract,
+                line: function.line,
+                visibility: Some(function.visibility),
+            }
+        })
+        .collect();
+
+    // Public state variables declare external getters, which are listed like functions
+    // but never reported or deleted.
+    decls.extend(
+        getters
+            .into_iter()
+            .filter(|variable| variable.visibility == Visibility::Public)
+            .filter(|variable| options.reads(source.context_at(variable.name_offset)))
+            .map(|variable| FunctionDecl {
+                signature: format!("{}()", variable.name),
+                offset: variable.name_offset,
+                contract: Some(variable.contract),
+                line: variable.line,
+                visibility: Some(Visibility::Public),
+                abi_exported: false,
+                deployed_usage: None,
+                vyper: Vec::new(),
+                todo: false,
+                doc_comments: Vec::new(),
+                size: None,
+                context: String::new(),
+                library: false,
+                inheritdoc: None,
+                implemented_by: None,
+                auto_getter: true,
+                virtual_stub: false,
+                virtual_line: None,
+                params: 0,
+                enclosing: None,
+                assembly: None,
+                name: variable.name,
+            }),
+    );
+    decls.sort_by_key(|decl| decl.offset);
+
+    for pattern in &analysis.patterns {
+        for captures in pattern.captures_iter(&source.content) {
+            let Some(name) = captures.name("name") else {
+                continue;
+            };
+            let context = source.context_at(name.start());
+            if context == Context::String || !options.reads(context) {
+                continue;
+            }
+            let enclosing = unit
+                .contracts
+                .iter()
+                .find(|contract| (contract.body.0..contract.body.1).contains(&name.start()))
+                .map(|contract| contract.name.clone());
+            // A pattern broad enough to match any call matches constructors too, of either
+            // spelling.
+            if name.as_str() == "constructor" || enclosing.as_deref() == Some(name.as_str()) {
+                continue;
+            }
+            let line = source.line_of(name.start());
+            if !decls.iter().any(|decl| decl.name == name.as_str() && decl.line == line) {
+                decls.push(FunctionDecl {
+                    name: name.as_str().to_string(),
+                    offset: name.start(),
+                    contract: None,
+                    signature: name.as_str().to_string(),
+                    line,
+                    visibility: None,
+                    abi_exported: false,
+                    deployed_usage: None,
+                    vyper: Vec::new(),
+                    todo: false,
+                    doc_comments: Vec::new(),
+                    size: None,
+                    context: source.line_text(line).split_whitespace().collect(),
+                    library: false,
+                    inheritdoc: None,
+                    implemented_by: None,
+                    auto_getter: false,
+                    virtual_stub: false,
+                    virtual_line: None,
+                    params: 0,
+                    enclosing,
+                    assembly: None,
+                });
+            }
+        }
+    }
+
+    Ok(decls)
+}
+
+/// A counted occurrence of a function other than its declaration.
+struct Reference {
+    canonical: PathBuf,
+    file: PathBuf,
+    offset: usize,
+}
+
+/// The files referencing a function when none of the references can call it: those in
+/// other files for a private function, or for an internal one those outside contracts
+/// inheriting from its contract or inherited by it, which calls a virtual function that
+/// it may override.
+fn impossible_references(
+    decl: &FunctionDecl,
+    references: &[Reference],
+    declaring_file: &Path,
+    analysis: &Analysis,
+) -> Vec<PathBuf> {
+    if references.is_empty() || references.iter().any(|reference| reference.canonical == declaring_file) {
+        return Vec::new();
+    }
+    let possible = match (decl.visibility, &decl.contract) {
+        (Some(Visibility::Private), _) => false,
+        (Some(Visibility::Internal), Some(contract)) if !decl.library => references
+            .iter()
+            .any(|reference| {
+                analysis.inherits_at(&reference.canonical, reference.offset, contract)
+                    || analysis.base_at(&reference.canonical, reference.offset, contract)
+            }),
+        _ => true,
+    };
+    if possible {
+        return Vec::new();
+    }
+    let mut files: Vec<PathBuf> = references
+        .iter()
+        .map(|reference| analysis.display_path(&reference.file))
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// The line of the `virtual` keyword of `function`, when it has a body and is declared in
+/// one of `contracts`, as opposed to an interface or a library.
+fn virtual_line(source: &SourceFile, function: &Function, contracts: &HashSet<String>) -> Option<usize> {
+    if function.body.is_none() || function.assembly.is_some() || !contracts.contains(function.contract.as_deref()?) {
+        return None;
+    }
+    let header = (function.params_span.1, function.header_end);
+    let offset = virtual_pattern().find(&source.code_only(header))?.start();
+    Some(source.line_of(header.0 + offset))
+}
+
+fn virtual_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bvirtual\b").unwrap())
+}
+
+fn todo_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(?:TODO|FIXME)\b").unwrap())
+}
+
+/// Whether the comments directly above the declaration at `offset`, NatSpec or not,
+/// mention a TODO or FIXME.
+fn has_todo(source: &SourceFile, offset: usize) -> bool {
+    source
+        .preceding_comments(offset)
+        .into_iter()
+        .any(|(start, end)| todo_pattern().is_match(&source.content[start..end]))
+}
+
+/// The occurrences of a set of functions, indexed by the id of their name in the matcher
+/// that found them.
+struct FunctionOccurrences {
+    matcher: NameMatcher,
+    by_id: Vec<Vec<Occurrence>>,
+}
+
+impl FunctionOccurrences {
+    fn get(&self, name: &str) -> &[Occurrence] {
+        self.matcher.id(name).map_or(&[], |id| &self.by_id[id])
+    }
+
+    fn take(&mut self, name: &str) -> Vec<Occurrence> {
+        self.matcher
+            .id(name)
+            .map(|id| std::mem::take(&mut self.by_id[id]))
+            .unwrap_or_default()
+    }
+}
+
+fn count_function_occurrences(
+    search_files: &[PathBuf],
+    function_names: &[&str],
+    excluded: &PathExclusions,
+) -> Result<FunctionOccurrences> {
+    let sol_files: Vec<_> = search_files
+        .iter()
+        .filter(|path| !excluded.excludes(path))
+        .collect();
+
+    let matcher = NameMatcher::new(function_names.iter().copied())?;
+    let occurrences: Vec<Vec<(usize, Occurrence)>> = sol_files
+        .par_iter()
+        .map(|path| {
+            let _counting = timing::time(Phase::Counting);
+            // An unreadable file is searched as empty, and listed with the errors.
+            let content = fs::read_to_string(path).unwrap_or_else(|e| {
+                if source::skip_file(path, format!("not searched for usages: {}", e)) {
+                    eprintln!("Warning: not searching {:?} for usages: {}.", path, e);
+                }
+                String::new()
+            });
+            timing::count(Counter::FilesRead, 1);
+            timing::count(Counter::BytesScanned, content.len());
+            let source = SourceFile::new(path.to_path_buf(), content);
+            let occurrences = matcher.find_occurrences(&source);
+            timing::count(Counter::MatchesFound, occurrences.len());
+            occurrences
+        })
+        .collect();
+
+    let mut by_id: Vec<Vec<Occurrence>> = std::iter::repeat_with(Vec::new).take(matcher.names().len()).collect();
+    for (id, occurrence) in occurrences.into_iter().flatten() {
+        by_id[id].push(occurrence);
+    }
+
+    Ok(FunctionOccurrences { matcher, by_id })
+}
+
+/// Removes the given functions from the file, returning the names actually removed.
+pub fn remove_unused_functions(sol_file: &Path, unused_functions: &[(String, usize)]) -> Result<Vec<String>> {
+    let content = fs::read_to_string(sol_file)?;
+    let (content, removed) = remove_functions(&content, unused_functions)
+        .map_err(|e| Error::new(e.kind(), format!("Failed to delete from {:?}: {}", sol_file, e)))?;
+    replace_file(sol_file, &content)?;
+
+    Ok(removed)
+}
+
+/// Removes the given functions (and their NatSpec) from `content`, each named with the
+/// line of its declaration, returning the new content and the names actually removed.
+/// Fails when a name isn't declared on its line, e.g. because the file changed since it
+/// was analyzed, rather than remove another overload or homonym.
+fn remove_functions(original: &str, unused_functions: &[(String, usize)]) -> Result<(String, Vec<String>)> {
+    let mut content = original.to_string();
+    let mut removed = Vec::new();
+
+    // The last functions are removed first, which leaves the lines above them in place.
+    let mut unused_functions = unused_functions.to_vec();
+    unused_functions.sort_by_key(|(_, line)| std::cmp::Reverse(*line));
+    for (func_name, line) in &unused_functions {
+        let source = SourceFile::new(PathBuf::new(), content.clone());
+        // Declarations, braces and semicolons inside comments and strings are skipped, so
+        // blocks such as `unchecked { }` and `assembly { }` are matched like any other.
+        let Some(function) = solidity::parse(&source)
+            .functions
+            .into_iter()
+            .find(|function| function.name == *func_name && function.line == *line)
+        else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "`{}` is no longer declared on line {}, so it may have changed since it was analyzed; the file was left unchanged.",
+                    func_name, line
+                ),
+            ));
+        };
+        let end_pos = match function.body {
+            Some((_, close)) => close,
+            None if content.as_bytes().get(function.header_end) == Some(&b';') => function.header_end + 1,
+            // An unterminated declaration or unbalanced body.
+            None => continue,
+        };
+
+        let natspec_start = NatSpec::before(&source, function.start).map_or(function.start, |natspec| natspec.start);
+
+        // Find the start of the line containing the natspec or function
+        let line_start = content[..natspec_start]
+            .rfind('\n')
+            .map_or(0, |pos| pos + 1);
+
+        // Find the end of the line after the function
+        let next_line_start = content[end_pos..]
+            .find('\n')
+            .map(|pos| end_pos + pos + 1)
+            .unwrap_or(content.len());
+
+        // Whole lines are removed when nothing but comments shares them, and otherwise
+        // only the declaration, which keeps e.g. the braces of `contract A { function f() {} }`
+        // or of the assembly block around a Yul function.
+        let alone = source.code_only((line_start, natspec_start)).trim().is_empty()
+            && source.code_only((end_pos, next_line_start)).trim().is_empty();
+        let (start, end) = if alone {
+            (line_start, next_line_start)
+        } else {
+            let trailing = content[end_pos..next_line_start]
+                .find(|c: char| c != ' ' && c != '\t')
+                .unwrap_or(next_line_start - end_pos);
+            (natspec_start, end_pos + trailing)
+        };
+
+        // Remove the function and its natspec completely
+        let mut new_content = String::new();
+        new_content.push_str(&content[..start]);
+        new_content.push_str(&content[end..]);
+        content = new_content;
+        removed.push(func_name.clone());
+    }
+
+    check_removal(original, &content, &removed)?;
+    Ok((content, removed))
+}
+
+/// Checks that removing `removed` from `original` left every other declaration whole, so
+/// that a second `--delete` finds nothing more to remove: braces are still balanced if
+/// they were, and each removed name is declared at least as many times less, or more
+/// when a removed function held Yul functions of the same name.
+fn check_removal(original: &str, content: &str, removed: &[String]) -> Result<()> {
+    let before = SourceFile::new(PathBuf::new(), original.to_string());
+    let after = SourceFile::new(PathBuf::new(), content.to_string());
+    let declared = |source: &SourceFile, name: &str| {
+        solidity::parse(source)
+            .functions
+            .iter()
+            .filter(|function| function.name == name)
+            .count()
+    };
+    let mismatched = removed.iter().find(|&name| {
+        let count = removed.iter().filter(|other| *other == name).count();
+        declared(&after, name) + count > declared(&before, name)
+    });
+    let problem = if braces_balanced(&before) && !braces_balanced(&after) {
+        Some("unbalanced braces".to_string())
+    } else {
+        mismatched.map(|name| format!("a partial declaration of `{}`", name))
+    };
+    match problem {
+        Some(problem) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Removing the unused functions would leave {}; the file was left unchanged.", problem),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether every `{` of the code has a matching `}`, outside comments and strings.
+fn braces_balanced(source: &SourceFile) -> bool {
+    let mut depth = 0usize;
+    for byte in source.code_only((0, source.content.len())).bytes() {
+        match byte {
+            b'{' => depth += 1,
+            b'}' => match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+/// Extracts the functions of `sol_file` to be analyzed, or `None` if the file is
+/// excluded, e.g. in a `--no-match-path` directory.
+fn extract_analyzed(sol_file: &Path, args: &AnalysisArgs, analysis: &Analysis) -> Result<Option<Vec<FunctionDecl>>> {
+    if analysis.excluded.excludes(sol_file) {
+        return Ok(None);
+    }
+    let _extraction = timing::time(Phase::Extraction);
+    extract_functions(sol_file, analysis, &args.count_options()).map(Some)
+}
+
+/// Reports the functions `decls` of `sol_file`, counted in `occurrences`, the
+/// occurrences of the functions of every analyzed file.
+fn process_single_file(
+    sol_file: &Path,
+    decls: Vec<FunctionDecl>,
+    args: &AnalysisArgs,
+    analysis: &Analysis,
+    occurrences: &FunctionOccurrences,
+) -> Result<Option<FileReport>> {
+    let options = args.count_options();
+    let declaring_file = canonical(sol_file);
+    let mut declarations: HashMap<&str, HashSet<usize>> = HashMap::new();
+    for decl in &decls {
+        declarations.entry(decl.name.as_str()).or_default().insert(decl.offset);
+    }
+    // The definition of a Yul function never refers to a Solidity function of its name.
+    let yul_declarations: HashSet<usize> =
+        decls.iter().filter(|decl| decl.assembly.is_some()).map(|decl| decl.offset).collect();
+    // The occurrence count of each function, its references other than the declaration,
+    // how many log or revert messages mention it, and with --documented-unused, how many
+    // more occurrences would count if comments were read as code.
+    let with_comments = CountOptions {
+        include_commented: true,
+        ..options.clone()
+    };
+    let function_counts: Vec<(usize, Vec<Reference>, usize, Option<usize>)> = decls
+        .iter()
+        .map(|decl| {
+            let occurrences = occurrences.get(&decl.name);
+            // Free functions can be imported under another name, unlike contract members.
+            let aliased = if decl.contract.is_none() && decl.visibility.is_some() && decl.assembly.is_none() {
+                analysis.alias_occurrences(&decl.name)
+            } else {
+                Vec::new()
+            };
+            let in_scope = |occurrence: &&Occurrence| match decl.assembly {
+                Some((open, close)) => {
+                    (open..close).contains(&occurrence.offset) && canonical(&occurrence.file) == declaring_file
+                }
+                None => {
+                    !yul_declarations.contains(&occurrence.offset) || canonical(&occurrence.file) != declaring_file
+                }
+            };
+            let credited: Vec<&Occurrence> = occurrences
+                .iter()
+                .filter(in_scope)
+                .filter(|occurrence| occurrence.counts(&options) && analysis.credits(occurrence, decl))
+                .chain(aliased.iter().filter(|occurrence| occurrence.counts(&options)))
+                .collect();
+            let references = credited
+                .iter()
+                .filter_map(|occurrence| {
+                    let file = canonical(&occurrence.file);
+                    let declaration = file == declaring_file
+                        && declarations
+                            .get(decl.name.as_str())
+                            .is_some_and(|offsets| offsets.contains(&occurrence.offset));
+                    (!declaration).then(|| Reference {
+                        canonical: file,
+                        file: occurrence.file.clone(),
+                        offset: occurrence.offset,
+                    })
+                })
+                .collect();
+            let messages = occurrences.iter().filter(|occurrence| occurrence.in_message(&options)).count();
+            let comments = args.documented_unused.then(|| {
+                occurrences
+                    .iter()
+                    .filter(in_scope)
+                    .filter(|occurrence| !occurrence.counts(&options) && occurrence.counts(&with_comments))
+                    .filter(|occurrence| analysis.credits(occurrence, decl))
+                    .filter(|occurrence| {
+                        let own_doc = |&(start, end): &(usize, usize)| (start..end).contains(&occurrence.offset);
+                        !(decl.doc_comments.iter().any(own_doc) && canonical(&occurrence.file) == declaring_file)
+                    })
+                    .count()
+            });
+            (credited.len(), references, messages, comments)
+        })
+        .collect();
+
+    // Getters are never reported, so ignoring them hides nothing.
+    let ignored: Vec<IgnoredFunction> = decls
+        .iter()
+        .filter(|decl| !decl.auto_getter)
+        .filter_map(|decl| {
+            Some(IgnoredFunction {
+                pattern: matching_ignore_pattern(&decl.name, &args.ignore)?.to_string(),
+                file: analysis.display_path(sol_file),
+                function: decl.name.clone(),
+                contract: decl.contract.clone(),
+                line: decl.line,
+            })
+        })
+        .collect();
+    let names: Vec<&str> = decls
+        .iter()
+        .filter(|decl| !decl.auto_getter)
+        .map(|decl| decl.name.as_str())
+        .collect();
+    let matched_ignores = ignore::matched_patterns(&args.ignore, &names)
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    let mut wrong_inheritdoc = Vec::new();
+    let usages: Vec<FunctionUsage> = decls
+        .into_iter()
+        .zip(function_counts)
+        .filter(|(decl, _)| !should_ignore_function(&decl.name, &args.ignore))
+        .map(|(decl, (count, references, messages, comments))| {
+            let yul = decl.assembly.is_some();
+            // Nothing outside the assembly block can call a Yul function.
+            let text_references = if yul {
+                Vec::new()
+            } else {
+                analysis.text.references(&decl.name)
+            };
+            let mut files: HashSet<PathBuf> =
+                references.iter().map(|reference| reference.canonical.clone()).collect();
+            files.extend(decl.vyper.iter().map(|reference| canonical(&reference.file)));
+            files.extend(text_references.iter().map(|reference| canonical(&reference.file)));
+            let inheritdoc = decl
+                .inheritdoc
+                .as_ref()
+                .and_then(|(base, params)| analysis.inheritdoc_base(base, &decl.name, *params));
+            if let Some(None) = inheritdoc {
+                let (target, _) = decl.inheritdoc.clone().unwrap_or_default();
+                wrong_inheritdoc.push(WrongInheritdoc {
+                    function: decl.name.clone(),
+                    line: decl.line,
+                    target,
+                });
+            }
+            let impossible_references = if decl.vyper.is_empty() && text_references.is_empty() {
+                impossible_references(&decl, &references, &declaring_file, analysis)
+            } else {
+                Vec::new()
+            };
+            let virtual_stub = decl.virtual_stub.then(|| {
+                let overrides = analysis.overrides(&decl);
+                let calls = references
+                    .iter()
+                    .filter(|reference| {
+                        !overrides
+                            .iter()
+                            .any(|(_, member)| member.file == reference.canonical && member.offset == reference.offset)
+                    })
+                    .count();
+                let mut overridden_by: Vec<String> = overrides.into_iter().map(|(lineage, _)| lineage).collect();
+                overridden_by.dedup();
+                let inherited_by = match (&decl.contract, overridden_by.is_empty()) {
+                    (Some(contract), true) => analysis.contracts().derived(contract),
+                    _ => Vec::new(),
+                };
+                VirtualStub {
+                    overridden_by,
+                    inherited_by,
+                    calls,
+                }
+            });
+            let never_overridden = decl
+                .virtual_line
+                .filter(|_| args.never_overridden && !analysis.is_published(sol_file))
+                .filter(|_| analysis.overrides(&decl).is_empty());
+            // Only unreferenced functions are looked up, and the suggestion never changes
+            // whether one is unused.
+            let suggestion = (count <= 1 && files.is_empty() && !decl.auto_getter)
+                .then(|| analysis.suggestion(&decl.name))
+                .flatten();
+            let offchain = if yul {
+                Vec::new()
+            } else {
+                analysis.offchain.references(&decl.name)
+            };
+            let elsewhere = !offchain.is_empty() || !decl.vyper.is_empty() || !text_references.is_empty();
+            let (category, scripts) = analysis.usage_category(&references, elsewhere);
+            FunctionUsage {
+                suggestion,
+                yul,
+                virtual_stub,
+                never_overridden,
+                category,
+                scripts,
+                auto_getter: decl.auto_getter,
+                implemented_by: decl.implemented_by,
+                message_references: messages,
+                comment_references: comments,
+                estimated_size: decl.size.filter(|_| args.estimate_size),
+                coverage_hits: decl
+                    .visibility
+                    .and_then(|_| analysis.coverage.hits(sol_file, decl.contract.as_deref(), &decl.name, decl.line)),
+                inheritdoc: inheritdoc.flatten(),
+                impossible_references,
+                occurrences: count + decl.vyper.len() + text_references.len(),
+                reference_files: files.len(),
+                offchain,
+                vyper: decl.vyper,
+                text_references,
+                abi_exported: decl.abi_exported,
+                deployed_usage: decl.deployed_usage,
+                blame: None,
+                todo: decl.todo,
+                context: decl.context,
+                name: decl.name,
+                contract: decl.contract,
+                signature: decl.signature,
+                line: decl.line,
+                visibility: decl.visibility,
+            }
+        })
+        .collect();
+
+    let (proxy_reachable, unused): (Vec<&FunctionUsage>, Vec<&FunctionUsage>) = usages
+        .iter()
+        .filter(|usage| usage.occurrences <= 1 && !usage.auto_getter)
+        .partition(|usage| analysis.proxy.reaches(usage.contract.as_deref(), usage.visibility));
+    let proxy_reachable: Vec<String> = proxy_reachable.iter().map(|usage| usage.name.clone()).collect();
+    let (offchain_used, unused): (Vec<&FunctionUsage>, Vec<&FunctionUsage>) =
+        unused.into_iter().partition(|usage| !usage.offchain.is_empty());
+    let offchain_used: Vec<String> = offchain_used.iter().map(|usage| usage.name.clone()).collect();
+    let (abi_exported, unused): (Vec<&FunctionUsage>, Vec<&FunctionUsage>) =
+        unused.into_iter().partition(|usage| usage.abi_exported);
+    let abi_exported: Vec<String> = abi_exported.iter().map(|usage| usage.name.clone()).collect();
+    let (deployed_usage, unused): (Vec<&FunctionUsage>, Vec<&FunctionUsage>) =
+        unused.into_iter().partition(|usage| usage.deployed_usage.is_some());
+    let deployed_usage: Vec<String> = deployed_usage.iter().map(|usage| usage.name.clone()).collect();
+    let (findings, baselined): (Vec<Finding>, Vec<Finding>) = unused
+        .into_iter()
+        .map(|usage| Finding {
+            file: analysis.display_path(sol_file),
+            line: usage.line,
+            contract: usage.contract.clone(),
+            function: usage.name.clone(),
+            signature: usage.signature.clone(),
+            context: usage.context.clone(),
+            visibility: usage.visibility,
+            kind: if usage.virtual_stub.is_some() {
+                FindingKind::UnimplementedVirtual
+            } else {
+                FindingKind::Unused
+            },
+            suggestion: usage.suggestion.clone(),
+            coverage_hits: usage.coverage_hits,
+            detail: None,
+        })
+        .partition(|finding| {
+            analysis
+                .baseline
+                .as_ref()
+                .is_none_or(|baseline| !baseline.contains(finding))
+        });
+    // Deleting an overridden function would break its overrides, so these are only reported.
+    let uncalled_virtual: Vec<UncalledVirtual> = usages
+        .iter()
+        .filter(|usage| usage.uncalled_virtual())
+        .map(|usage| {
+            let overridden_by = usage
+                .virtual_stub
+                .as_ref()
+                .map_or_else(Vec::new, |stub| stub.overridden_by.clone());
+            UncalledVirtual {
+                finding: Finding {
+                    file: analysis.display_path(sol_file),
+                    line: usage.line,
+                    contract: usage.contract.clone(),
+                    function: usage.name.clone(),
+                    signature: usage.signature.clone(),
+                    context: usage.context.clone(),
+                    visibility: usage.visibility,
+                    kind: FindingKind::UncalledVirtual,
+                    suggestion: None,
+                    coverage_hits: None,
+                    detail: Some(overridden_by.join(", ")),
+                },
+                function: usage.name.clone(),
+                line: usage.line,
+                overridden_by,
+            }
+        })
+        .filter(|uncalled| {
+            analysis
+                .baseline
+                .as_ref()
+                .is_none_or(|baseline| !baseline.contains(&uncalled.finding))
+        })
+        .collect();
+    let unused_functions: Vec<String> = findings.iter().map(|finding| finding.function.clone()).collect();
+    // Kept functions are used, e.g. through a proxy, and the baselined known to be unused.
+    let uncovered: Vec<String> = usages
+        .iter()
+        .filter(|usage| usage.coverage_hits == Some(0))
+        .filter(|usage| {
+            !findings
+                .iter()
+                .chain(&baselined)
+                .any(|finding| finding.function == usage.name && finding.line == usage.line)
+        })
+        .map(|usage| usage.name.clone())
+        .collect();
+    // An unused function is deleted rather than kept without `virtual`.
+    let never_overridden: Vec<NeverOverridden> = usages
+        .iter()
+        .filter(|usage| {
+            !findings
+                .iter()
+                .chain(&baselined)
+                .any(|finding| finding.function == usage.name && finding.line == usage.line)
+        })
+        .filter_map(|usage| {
+            let line = usage.never_overridden?;
+            Some(NeverOverridden {
+                finding: Finding {
+                    file: analysis.display_path(sol_file),
+                    line,
+                    contract: usage.contract.clone(),
+                    function: usage.name.clone(),
+                    signature: usage.signature.clone(),
+                    context: usage.context.clone(),
+                    visibility: usage.visibility,
+                    kind: FindingKind::NeverOverridden,
+                    suggestion: None,
+                    coverage_hits: None,
+                    detail: None,
+                },
+                function: usage.name.clone(),
+                signature: usage.signature.clone(),
+                line,
+                kind: FindingKind::NeverOverridden,
+            })
+        })
+        .filter(|never| {
+            analysis
+                .baseline
+                .as_ref()
+                .is_none_or(|baseline| !baseline.contains(&never.finding))
+        })
+        .collect();
+    // Functions kept for others, e.g. by an ABI, aren't the scripts' alone.
+    let script_only: Vec<ScriptOnly> = usages
+        .iter()
+        .filter(|usage| args.flag_script_only && usage.category == Some(UsageCategory::Script))
+        .filter(|usage| !usage.auto_getter && !usage.abi_exported)
+        .map(|usage| ScriptOnly {
+            finding: Finding {
+                file: analysis.display_path(sol_file),
+                line: usage.line,
+                contract: usage.contract.clone(),
+                function: usage.name.clone(),
+                signature: usage.signature.clone(),
+                context: usage.context.clone(),
+                visibility: usage.visibility,
+                kind: FindingKind::ScriptOnly,
+                suggestion: None,
+                coverage_hits: None,
+                detail: Some(
+                    usage.scripts.iter().map(|script| portable_path(script)).collect::<Vec<_>>().join(", "),
+                ),
+            },
+            function: usage.name.clone(),
+            signature: usage.signature.clone(),
+            line: usage.line,
+            scripts: usage.scripts.clone(),
+            category: UsageCategory::Script,
+        })
+        .filter(|script_only| {
+            analysis
+                .baseline
+                .as_ref()
+                .is_none_or(|baseline| !baseline.contains(&script_only.finding))
+        })
+        .collect();
+    let unreachable_code = if args.unreachable_code {
+        unreachable_blocks(sol_file, args, analysis)?
+    } else {
+        Vec::new()
+    };
+    let accessor_pairs = if args.accessor_pairs {
+        accessor_pairs(sol_file, &usages)?
+    } else {
+        Vec::new()
+    };
+    let inherited_unused = if args.audit_inherited {
+        inherited::audit(sol_file, args, analysis)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(Some(FileReport {
+        file: analysis.display_path(sol_file),
+        functions: usages,
+        unused: unused_functions,
+        removed: Vec::new(),
+        baselined: baselined.into_iter().map(|finding| finding.function).collect(),
+        proxy_reachable,
+        offchain_used,
+        abi_exported,
+        deployed_usage,
+        wrong_inheritdoc,
+        few_reference_files: Vec::new(),
+        uncovered,
+        uncalled_virtual,
+        never_overridden,
+        script_only,
+        unreachable_code,
+        accessor_pairs,
+        inherited_unused,
+        ignored,
+        matched_ignores,
+        findings,
+    }))
+}
+
+/// Finds the state variables of `sol_file` with a getter and a setter of which only one
+/// is used, according to `usages`. When both are unused, both are reported as unused
+/// functions already, and ignored accessors aren't looked at.
+fn accessor_pairs(sol_file: &Path, usages: &[FunctionUsage]) -> Result<Vec<AccessorPair>> {
+    let source = SourceFile::read(sol_file)?;
+    let unit = solidity::parse(&source);
+    let unused = |name: &str, contract: &str| {
+        usages
+            .iter()
+            .find(|usage| usage.name == name && usage.contract.as_deref() == Some(contract) && !usage.auto_getter)
+            .map(|usage| usage.occurrences <= 1)
+    };
+    Ok(accessors::find(&source, &unit)
+        .into_iter()
+        .filter_map(|pair| {
+            let side = match (unused(&pair.getter, &pair.contract)?, unused(&pair.setter, &pair.contract)?) {
+                (false, true) => AccessorSide::Setter,
+                (true, false) => AccessorSide::Getter,
+                _ => return None,
+            };
+            Some(AccessorPair {
+                variable: pair.variable,
+                contract: pair.contract,
+                line: pair.line,
+                getter: pair.getter,
+                setter: pair.setter,
+                unused: side,
+            })
+        })
+        .collect())
+}
+
+/// Finds the code of `sol_file` that can never run, leaving out ignored functions and
+/// those in the baseline.
+fn unreachable_blocks(sol_file: &Path, args: &AnalysisArgs, analysis: &Analysis) -> Result<Vec<UnreachableBlock>> {
+    let source = SourceFile::read(sol_file)?;
+    let unit = solidity::parse(&source);
+    Ok(unreachable::scan(&source, &unit)
+        .into_iter()
+        .filter(|code| !should_ignore_function(&code.function, &args.ignore))
+        .map(|code| UnreachableBlock {
+            finding: Finding {
+                file: analysis.display_path(sol_file),
+                line: code.line,
+                contract: code.contract.clone(),
+                function: code.function.clone(),
+                signature: code.signature.clone(),
+                context: code.context.clone(),
+                visibility: Some(code.visibility),
+                kind: FindingKind::UnreachableCode,
+                suggestion: None,
+                coverage_hits: None,
+                detail: Some(code.describe()),
+            },
+            code,
+        })
+        .filter(|block| {
+            analysis
+                .baseline
+                .as_ref()
+                .is_none_or(|baseline| !baseline.contains(&block.finding))
+        })
+        .collect())
+}
+
+fn print_text_report(report: &FileReport, delete: bool, dedupe: bool, thresholds: ColorThresholds) {
+    println!("\nFunction Usage Report for {:?}:", report.file);
+    for func in &report.functions {
+        let color = thresholds.color(func.occurrences);
+        let mut tags = Vec::new();
+        if !func.offchain.is_empty() {
+            tags.push(format!("+{} offchain", func.offchain.len()));
+        }
+        if !func.vyper.is_empty() {
+            tags.push(format!("{} vyper", func.vyper.len()));
+        }
+        if !func.text_references.is_empty() {
+            tags.push(func.text_reference_counts());
+            if func.used_only_as_text() {
+                tags.push("only outside Solidity".to_string());
+            }
+        }
+        if func.auto_getter {
+            tags.push("auto-getter (public variable)".to_string());
+        }
+        if func.yul {
+            tags.push("Yul, in assembly".to_string());
+        }
+        if let Some(getter) = &func.implemented_by {
+            tags.push(format!("implemented by auto-getter {}", getter));
+        }
+        if func.only_in_messages() {
+            tags.push(format!("{} in log/revert messages only", func.message_references));
+        }
+        if func.only_documented() {
+            let comments = func.comment_references.unwrap_or_default();
+            tags.push(format!("{} in comments only", comments));
+        }
+        if let Some(base) = &func.inheritdoc {
+            tags.push(format!("inheritdoc {}", base));
+        }
+        if let Some(stub) = &func.virtual_stub {
+            match stub.overridden_by.len() {
+                0 => tags.push("virtual, never overridden".to_string()),
+                1 => tags.push("virtual, 1 override".to_string()),
+                overrides => tags.push(format!("virtual, {} overrides", overrides)),
+            }
+        }
+        if func.abi_exported {
+            tags.push("abi-exported".to_string());
+        }
+        if func.deployed_usage.is_some() {
+            tags.push("deployed-usage".to_string());
+        }
+        match func.category {
+            Some(UsageCategory::Script) => tags.push("scripts only".to_string()),
+            Some(UsageCategory::Test) => tags.push("tests only".to_string()),
+            _ => {}
+        }
+        let count = match func.reference_files {
+            0 => func.occurrences.to_string(),
+            1 => format!("{} in 1 file", func.occurrences),
+            files => format!("{} in {} files", func.occurrences, files),
+        };
+        if tags.is_empty() {
+            println!("{}{}{}: {}", color, func.name, reset(), count);
+        } else {
+            println!("{}{}{}: {} ({})", color, func.name, reset(), count, tags.join(", "));
+        }
+    }
+
+    let suspicious: Vec<&FunctionUsage> = report
+        .functions
+        .iter()
+        .filter(|usage| !usage.impossible_references.is_empty())
+        .collect();
+    if !suspicious.is_empty() {
+        println!("\nWarning: suspicious usages in {:?}:", report.file);
+        for usage in suspicious {
+            let files: Vec<String> = usage
+                .impossible_references
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            println!(
+                "- {} is {} but only referenced from {}, likely by a same-named unrelated function",
+                usage.name,
+                usage.visibility.map_or("internal", Visibility::as_str),
+                files.join(", ")
+            );
+        }
+    }
+
+    if !report.wrong_inheritdoc.is_empty() {
+        println!("\nWarning: wrong @inheritdoc targets in {:?}:", report.file);
+        for wrong in &report.wrong_inheritdoc {
+            println!(
+                "- {} (line {}): {} declares no {} with the same number of parameters",
+                wrong.function, wrong.line, wrong.target, wrong.function
+            );
+        }
+    }
+
+    if !report.uncalled_virtual.is_empty() {
+        println!("\nVirtual functions overridden but never called in {:?}:", report.file);
+        for uncalled in &report.uncalled_virtual {
+            println!(
+                "- {} (line {}): overridden in {}",
+                uncalled.function,
+                uncalled.line,
+                uncalled.overridden_by.join(", ")
+            );
+        }
+    }
+
+    if !report.never_overridden.is_empty() {
+        println!("\nVirtual functions never overridden in {:?}:", report.file);
+        for never in &report.never_overridden {
+            println!(
+                "- {} at {}:{}: nothing overrides it, consider dropping `virtual`",
+                never.signature,
+                report.file.display(),
+                never.line
+            );
+        }
+    }
+
+    if !report.script_only.is_empty() {
+        println!("\nFunctions only used by scripts in {:?}:", report.file);
+        for script_only in &report.script_only {
+            println!(
+                "- {} (line {}): used by {}, may be removable once deployed",
+                script_only.signature,
+                script_only.line,
+                script_only.finding.detail.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
+    if !report.unreachable_code.is_empty() {
+        println!("\nUnreachable code in {:?}:", report.file);
+        for block in &report.unreachable_code {
+            println!("- {} (line {}): {}", block.code.function, block.code.line, block.code.describe());
+        }
+    }
+
+    if !report.accessor_pairs.is_empty() {
+        println!("\nState variables with an unused accessor in {:?}:", report.file);
+        for pair in &report.accessor_pairs {
+            println!("- {} (line {}): {}", pair.variable, pair.line, pair.describe());
+        }
+    }
+
+    if !report.inherited_unused.is_empty() {
+        println!("\nInherited functions unused by the project in {:?} (review whether a slimmer base would do):", report.file);
+        for (contract, bases) in inherited::grouped(&report.inherited_unused) {
+            println!("- {}:", contract);
+            for (base, functions) in bases {
+                println!("  - from {} ({}):", base, functions[0].base_file.display());
+                for function in functions {
+                    println!("    - {} (line {}, {})", function.signature, function.line, function.visibility.as_str());
+                }
+            }
+        }
+    }
+
+    if !report.few_reference_files.is_empty() {
+        println!("\nFunctions referenced from few files in {:?}:", report.file);
+        for name in &report.few_reference_files {
+            println!("- {}", name);
+        }
+    }
+
+    if !report.uncovered.is_empty() {
+        println!("\nUsed functions never run by the tests in {:?}:", report.file);
+        for name in &report.uncovered {
+            println!("- {}", name);
+        }
+    }
+
+    if !report.unused.is_empty() {
+        println!("\nFunctions marked for removal in {:?}:", report.file);
+        if dedupe {
+            for group in findings::dedupe(&report.findings) {
+                println!("- {}", describe_collapsed(report, &group));
+            }
+        } else {
+            for finding in &report.findings {
+                let notes = report.notes(finding);
+                if notes.is_empty() {
+                    println!("- {}", finding.function);
+                } else {
+                    println!("- {} ({})", finding.function, notes.join(", "));
+                }
+            }
+        }
+
+        if delete && !report.removed.is_empty() {
+            for func in &report.removed {
+                println!("Removed function: {}", func);
+            }
+            println!("Updated {:?} with unused functions removed.", report.file);
+        }
+    } else {
+        println!("\nNo unused functions found in {:?}.", report.file);
+    }
+}
+
+/// Describes collapsed findings by the name of the first, followed by how many there are
+/// and their signatures, e.g. `mint (3 findings: mint(), mint(address), _mint())`.
+fn describe_collapsed(report: &FileReport, group: &Collapsed) -> String {
+    let mut notes = report.notes(&group.finding);
+    if group.signatures.len() > 1 {
+        notes.push(format!(
+            "{} findings: {}",
+            group.signatures.len(),
+            group.signatures.join(", ")
+        ));
+    }
+    if notes.is_empty() {
+        group.finding.function.clone()
+    } else {
+        format!("{} ({})", group.finding.function, notes.join("; "))
+    }
+}
+
+/// Prints a file's report as a single line, e.g. `src/Vault.sol: 3 unused (of 12)`,
+/// followed by its unused functions when `verbose` is set.
+fn print_compact_report(report: &FileReport, verbose: bool, dedupe: bool) {
+    let color = if report.unused.is_empty() { green() } else { red() };
+    let mut line = format!(
+        "{}: {}{} unused{} (of {})",
+        report.file.display(),
+        color,
+        report.unused.len(),
+        reset(),
+        report.functions.len()
+    );
+    if !report.removed.is_empty() {
+        line.push_str(&format!(", {} removed", report.removed.len()));
+    }
+    if !report.unreachable_code.is_empty() {
+        let lines = report.unreachable_code.iter().map(|block| block.code.lines).sum::<usize>();
+        line.push_str(&format!(", {} unreachable lines", lines));
+    }
+    println!("{}", line);
+
+    if verbose && dedupe {
+        for group in findings::dedupe(&report.findings) {
+            println!("  - {} (line {})", describe_collapsed(report, &group), group.line);
+        }
+    } else if verbose {
+        for finding in &report.findings {
+            let mut notes = vec![format!("line {}", finding.line)];
+            notes.extend(report.notes(finding));
+            println!("  - {} ({})", finding.function, notes.join(", "));
+        }
+    }
+    if verbose {
+        for block in &report.unreachable_code {
+            println!(
+                "  - {} (line {}, unreachable: {})",
+                block.code.function,
+                block.code.line,
+                block.code.describe()
+            );
+        }
+        for uncalled in &report.uncalled_virtual {
+            println!(
+                "  - {} (line {}, overridden but never called, overridden in {})",
+                uncalled.function,
+                uncalled.line,
+                uncalled.overridden_by.join(", ")
+            );
+        }
+        for never in &report.never_overridden {
+            println!("  - {} (line {}, virtual but never overridden)", never.function, never.line);
+        }
+        for script_only in &report.script_only {
+            println!("  - {} (line {}, only used by scripts)", script_only.function, script_only.line);
+        }
+        for pair in &report.accessor_pairs {
+            println!("  - {} (line {}, {})", pair.variable, pair.line, pair.describe());
+        }
+        for function in &report.inherited_unused {
+            println!(
+                "  - {}.{} (inherited from {}, unused by project)",
+                function.contract, function.signature, function.base
+            );
+        }
+    }
+}
+
+/// Reads the files under `--root` and the analyzed path to find proxies, unless
+/// `--no-proxy-heuristic` is set. Directories skipped with `--no-match-path` are still
+/// read, since that is usually where library proxies live.
+fn detect_proxies(args: &AnalysisArgs, project: &Project, path: &Path) -> Result<ProxyHeuristic> {
+    if args.no_proxy_heuristic {
+        return Ok(ProxyHeuristic::default());
+    }
+
+    let mut paths = project.search_files()?;
+    if path.is_file() {
+        paths.push(path.to_path_buf());
+    } else if path.is_dir() {
+        paths.extend(project.collect(path)?);
+    }
+    dedup_files(&mut paths);
+
+    let sources: Vec<SourceFile> = paths
+        .into_par_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            SourceFile::new(path, content)
+        })
+        .collect();
+    Ok(ProxyHeuristic::detect(&sources, |path| inherited::is_dependency(path, &project.root)))
+}
+
+/// Prints a forensic report explaining the verdict for a single function.
+fn explain_unused(args: &AnalysisArgs, analysis: &Analysis, name: &str) -> Result<()> {
+    let analyzed_files = analysis.analyzed_files()?;
+
+    println!("Explaining verdict for function `{}`", name);
+
+    println!("\nDeclarations in {:?}:", analysis.path);
+    let mut declarations = Vec::new();
+    for sol_file in &analyzed_files {
+        if analysis.excluded.excludes(sol_file) {
+            continue;
+        }
+        for decl in extract_functions(sol_file, analysis, &args.count_options())? {
+            if decl.name == name {
+                println!("  {}:{}", analysis.display_path(sol_file).display(), decl.line);
+                declarations.push(decl);
+            }
+        }
+    }
+    if declarations.is_empty() {
+        println!("  (none)");
+    }
+
+    let occurrences = name_occurrences(analysis, &declarations, name)?;
+
+    println!(
+        "\nOccurrences under {:?} ({} match(es)):",
+        args.root,
+        occurrences.len()
+    );
+    let options = args.count_options();
+    for occurrence in &occurrences {
+        let credited = declarations.iter().any(|decl| analysis.credits(occurrence, decl));
+        let tag = match (occurrence.in_signature, occurrence.counts(&options)) {
+            (true, _) => "signature string".to_string(),
+            (false, _) if occurrence.in_encode_call => "encodeCall".to_string(),
+            (false, true) if !credited => format!(
+                "{} {}.{}, excluded",
+                if occurrence.qualifier.is_some() { "call of" } else { "access of" },
+                occurrence
+                    .qualifier
+                    .as_deref()
+                    .or(occurrence.member_qualifier.as_deref())
+                    .unwrap_or_default(),
+                name
+            ),
+            (false, true) if occurrence.qualifier.is_some() => format!(
+                "{}, {}.{}",
+                occurrence.context.label(),
+                occurrence.qualifier.as_deref().unwrap_or_default(),
+                name
+            ),
+            (false, true) => occurrence.context.label().to_string(),
+            (false, false) if occurrence.in_message(&options) => "log/revert message, excluded".to_string(),
+            (false, false) => format!("{}, excluded", occurrence.context.label()),
+        };
+        println!(
+            "  {}:{} [{}] {}",
+            occurrence.file.display(),
+            occurrence.line,
+            tag,
+            occurrence.snippet
+        );
+    }
+    if occurrences.is_empty() {
+        println!("  (none)");
+    }
+
+    let in_context = |context: Context| {
+        occurrences
+            .iter()
+            .filter(move |occurrence| occurrence.context == context)
+    };
+    let excluded = in_context(Context::String)
+        .filter(|occurrence| !occurrence.counts(&options))
+        .count();
+    println!(
+        "\nComment/string stripping: {} match(es) inside strings were excluded{}; {} match(es) inside comments were {}.",
+        excluded,
+        if args.count_strings { " (--count-strings)" } else { "" },
+        in_context(Context::Comment).count(),
+        if args.include_commented { "counted (--include-commented)" } else { "excluded" }
+    );
+
+    let offchain = analysis.offchain.references(name);
+    if !args.usage_langs.is_empty() {
+        println!(
+            "\nOff-chain references under {:?} ({} match(es)):",
+            args.js_root.as_ref().unwrap_or(&args.root),
+            offchain.len()
+        );
+        for reference in &offchain {
+            println!(
+                "  {}:{} [offchain, {}] {}",
+                reference.file.display(),
+                reference.line,
+                reference.kind.label(),
+                reference.snippet
+            );
+        }
+        if offchain.is_empty() {
+            println!("  (none)");
+        }
+    }
+
+    let vyper = declarations
+        .iter()
+        .map(|decl| &decl.vyper)
+        .find(|references| !references.is_empty())
+        .cloned()
+        .unwrap_or_default();
+    if !analysis.vyper.is_empty() {
+        println!(
+            "\nVyper references under {:?} ({} match(es)):",
+            args.root,
+            vyper.len()
+        );
+        for reference in &vyper {
+            println!(
+                "  {}:{} [vyper, {}] {}",
+                reference.file.display(),
+                reference.line,
+                reference.kind.label(),
+                reference.snippet
+            );
+        }
+        if vyper.is_empty() {
+            println!("  (none)");
+        }
+    }
+
+    let text = analysis.text.references(name);
+    if !args.search_ext.is_empty() {
+        println!(
+            "\nMentions in --search-ext files under {:?} ({} match(es)):",
+            args.root,
+            text.len()
+        );
+        for reference in &text {
+            println!(
+                "  {}:{} [{}] {}",
+                reference.file.display(),
+                reference.line,
+                reference.file_type,
+                reference.snippet
+            );
+        }
+        if text.is_empty() {
+            println!("  (none)");
+        }
+    }
+
+    // Vyper references and --search-ext mentions count as occurrences, like in the report.
+    let credited = occurrences
+        .iter()
+        .filter(|occurrence| occurrence.counts(&options))
+        .filter(|occurrence| declarations.iter().any(|decl| analysis.credits(occurrence, decl)))
+        .count();
+    let solidity_count = credited + vyper.len();
+    let count = solidity_count + text.len();
+    print!("\nVerdict: ");
+    if declarations.is_empty() {
+        println!("not analyzed (no declaration of `{}` found in {:?}).", name, analysis.path);
+    } else if let Some(pattern) = matching_ignore_pattern(name, &args.ignore) {
+        println!("{}ignored{} (matches ignore pattern {:?}).", yellow(), reset(), pattern);
+    } else if let Some(decl) = declarations
+        .iter()
+        .find(|decl| count <= 1 && analysis.proxy.reaches(decl.contract.as_deref(), decl.visibility))
+    {
+        println!(
+            "{}kept{} ({} counted occurrence(s), but `{}` is an external or public function of `{}`, which is reached through the proxy in {}; see --no-proxy-heuristic).",
+            yellow(),
+            reset(),
+            count,
+            name,
+            decl.contract.as_deref().unwrap_or_default(),
+            analysis
+                .proxy
+                .proxies
+                .iter()
+                .map(|proxy| format!("{:?}", proxy))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    } else if count <= 1 && declarations.iter().any(|decl| decl.abi_exported) {
+        println!(
+            "{}kept{} ({} counted occurrence(s), but an --abi file exports `{}`, so other teams may call it).",
+            yellow(), reset(), count, name
+        );
+    } else if let Some(broadcast) = declarations
+        .iter()
+        .find_map(|decl| decl.deployed_usage.as_ref().filter(|_| count <= 1))
+    {
+        println!(
+            "{}kept{} ({} counted occurrence(s), but a transaction in {:?} calls `{}`, so deployment or operations scripts rely on it).",
+            yellow(), reset(), count, broadcast, name
+        );
+    } else if count <= 1 && !offchain.is_empty() {
+        println!(
+            "{}kept{} ({} counted occurrence(s), but {} off-chain reference(s) call `{}`).",
+            yellow(),
+            reset(),
+            count,
+            offchain.len(),
+            name
+        );
+    } else if count <= 1 {
+        println!(
+            "{}unused{} ({} counted occurrence(s); functions with at most 1, i.e. only their declaration, are unused).",
+            red(), reset(), count
+        );
+    } else if solidity_count <= 1 {
+        println!(
+            "{}used{} ({} counted occurrence(s), but only because of {} mention(s) in --search-ext files).",
+            yellow(),
+            reset(),
+            count,
+            text.len()
+        );
+    } else {
+        println!(
+            "{}used{} ({} counted occurrence(s); functions with at most 1, i.e. only their declaration, are unused).",
+            green(), reset(), count
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `wand vacuum --delete` over the Foundry project at `root`, returning the names
+    /// removed and the content left in `src/A.sol`.
+    fn delete(root: &Path) -> (Vec<String>, String) {
+        let args = VacuumArgs::parse_from(["vacuum", "--delete", "--root", root.to_str().unwrap()]);
+        let analysis = Analysis::new(&args.analysis, None).unwrap();
+        let removed = analyze(&args.analysis, &analysis, true)
+            .unwrap()
+            .into_iter()
+            .flat_map(|report| report.removed)
+            .collect();
+        (removed, fs::read_to_string(root.join("src/A.sol")).unwrap())
+    }
+
+    /// Deletes the unused functions of `source` twice, checking that the first run removes
+    /// `expected`, that the second removes nothing and that both leave balanced braces.
+    fn delete_twice(source: &str, expected: &[&str]) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("foundry.toml"), "[profile.default]\n").unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/A.sol"), source).unwrap();
+
+        let (mut removed, first) = delete(dir.path());
+        removed.sort();
+        assert_eq!(removed, expected);
+        assert!(braces_balanced(&SourceFile::new(PathBuf::new(), first.clone())), "{}", first);
+
+        let (removed, second) = delete(dir.path());
+        assert!(removed.is_empty(), "the second run removed {:?} from:\n{}", removed, first);
+        assert_eq!(first, second);
+        second
+    }
+
+    #[test]
+    fn unchecked_blocks() {
+        let content = delete_twice(
+            "contract A {\n    function testRun() external { kept(); }\n\n    function kept() internal {\n        unchecked { uint256 i; i++; }\n    }\n\n    function gone() internal {\n        unchecked {\n            uint256 j;\n            { j++; }\n        }\n    }\n}\n",
+            &["gone"],
+        );
+        assert!(content.contains("unchecked { uint256 i; i++; }"));
+        assert!(!content.contains("j++"));
+    }
+
+    #[test]
+    fn assembly_blocks() {
+        let content = delete_twice(
+            "contract A {\n    function testRun() external { kept(); }\n\n    function kept() internal {\n        assembly { let x := 1 }\n    }\n\n    function gone() internal {\n        assembly {\n            let y := 1\n            if y { y := 2 }\n            switch y case 0 { y := 3 } default { y := 4 }\n        }\n    }\n}\n",
+            &["gone"],
+        );
+        assert!(content.contains("let x := 1"));
+        assert!(!content.contains("switch"));
+    }
+
+    #[test]
+    fn braces_in_strings_and_comments() {
+        let content = delete_twice(
+            "contract A {\n    function testRun() external { kept(); }\n\n    /// @notice Returns a \"}\" as well.\n    function kept() internal pure returns (string memory) {\n        return \"} function fake() {\";\n    }\n\n    /// @dev Has a { in its comment.\n    function gone() internal pure returns (string memory) {\n        // }\n        return \"{ { {\";\n    }\n}\n",
+            &["gone"],
+        );
+        assert!(content.contains("return \"} function fake() {\";"));
+        assert!(!content.contains("Has a {"));
+    }
+
+    #[test]
+    fn nested_braces() {
+        let content = delete_twice(
+            "contract A {\n    struct S { uint256 a; }\n\n    function testRun() external { kept(); }\n\n    function kept() internal {\n        if (true) { { } } else { for (;;) { break; } }\n    }\n\n    function gone() internal {\n        if (true) {\n            { }\n        } else {\n            while (true) { if (false) { } else { break; } }\n        }\n    }\n\n    function alsoGone() internal {}\n}\n",
+            &["alsoGone", "gone"],
+        );
+        assert!(content.contains("struct S { uint256 a; }"));
+        assert!(content.contains("if (true) { { } } else { for (;;) { break; } }"));
+        assert!(!content.contains("while"));
+    }
+
+    #[test]
+    fn only_the_overload_on_the_line_is_removed() {
+        let source = "contract A {\n    function f(uint256) internal {}\n    function f(address) internal {}\n}\n";
+        let (content, removed) = remove_functions(source, &[("f".to_string(), 3)]).unwrap();
+        assert_eq!(removed, ["f"]);
+        assert_eq!(content, "contract A {\n    function f(uint256) internal {}\n}\n");
+    }
+
+    #[test]
+    fn a_function_moved_off_its_line_fails_the_file() {
+        let source = "contract A {\n    function f() internal {}\n    function g() internal {}\n}\n";
+        let error = remove_functions(source, &[("g".to_string(), 3), ("f".to_string(), 4)]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains("`f` is no longer declared on line 4"), "{}", error);
+    }
+
+    #[test]
+    fn functions_sharing_a_line_with_their_contract() {
+        let content = delete_twice("contract A { function gone() internal {} }\n", &["gone"]);
+        assert_eq!(content, "contract A { }\n");
+    }
+
+    #[test]
+    fn occurrences_are_counted_by_name_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("A.sol");
+        let b = dir.path().join("B.sol");
+        fs::write(&a, "contract A {\n    function fee() external {}\n    function feeRecipient() external {}\n}\n").unwrap();
+        fs::write(&b, "contract B {\n    function f(A a) external {\n        a.fee();\n        a.fee();\n    }\n}\n").unwrap();
+
+        let names = ["feeRecipient", "fee", "unknown"];
+        let mut occurrences =
+            count_function_occurrences(&[a.clone(), b.clone()], &names, &PathExclusions::default()).unwrap();
+        for name in names {
+            let expected: usize = [&a, &b]
+                .iter()
+                .map(|path| references::find_occurrences(&SourceFile::read(path).unwrap(), name).len())
+                .sum();
+            assert_eq!(occurrences.get(name).len(), expected, "occurrences of {}", name);
+        }
+        assert_eq!(occurrences.get("fee").len(), 3);
+        assert_eq!(occurrences.take("fee").len(), 3);
+        assert!(occurrences.get("fee").is_empty());
+        assert!(occurrences.get("undeclared").is_empty());
+    }
+
+    /// Writes `files` files declaring `functions` functions between them, named `fn0`,
+    /// `fn1`, ..., each calling the `calls` next ones, and returns the files and names.
+    fn synthetic_project(dir: &Path, files: usize, functions: usize, calls: usize) -> (Vec<PathBuf>, Vec<String>) {
+        let names: Vec<String> = (0..functions).map(|index| format!("fn{}", index)).collect();
+        let paths: Vec<PathBuf> = (0..files).map(|index| dir.join(format!("C{}.sol", index))).collect();
+        for (file, path) in paths.iter().enumerate() {
+            let mut content = format!("contract C{} {{\n", file);
+            for index in (file..functions).step_by(files) {
+                let name = &names[index];
+                content.push_str(&format!("    function {}(uint256 x) internal pure returns (uint256) {{\n", name));
+                for call in 1..=calls {
+                    content.push_str(&format!("        x = {}(x);\n", names[(index + call) % functions]));
+                }
+                content.push_str("        return x;\n    }\n");
+            }
+            content.push_str("}\n");
+            fs::write(path, content).unwrap();
+        }
+        (paths, names)
+    }
+
+    /// Times counting the functions of every file in one scan of the project against one
+    /// scan per file, as each file's functions used to be counted:
+    /// `cargo test --release counting_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn counting_benchmark() {
+        let dir = tempfile::tempdir().unwrap();
+        let (files, names) = synthetic_project(dir.path(), 400, 3_000, 40);
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let per_file: Vec<&[&str]> = names.chunks(names.len() / files.len()).collect();
+        let excluded = PathExclusions::default();
+        let time = |count: &dyn Fn() -> usize| {
+            let mut runs = Vec::new();
+            let mut found = 0;
+            for _ in 0..3 {
+                let start = std::time::Instant::now();
+                found = count();
+                runs.push(start.elapsed());
+            }
+            runs.sort();
+            (found, runs[runs.len() / 2])
+        };
+        let count = |names: &[&str]| {
+            let occurrences = count_function_occurrences(&files, names, &excluded).unwrap();
+            names.iter().map(|name| occurrences.get(name).len()).sum::<usize>()
+        };
+        let (once, single_scan) = time(&|| count(&names));
+        let (each, per_file_scans) = time(&|| per_file.iter().map(|names| count(names)).sum());
+        assert_eq!(once, each);
+        println!(
+            "{} occurrences of {} functions in {} files: one scan median {:?}, one scan per file median {:?} on {} thread(s)",
+            once,
+            names.len(),
+            files.len(),
+            single_scan,
+            per_file_scans,
+            rayon::current_num_threads()
+        );
+    }
 }
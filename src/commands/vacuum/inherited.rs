@@ -0,0 +1,166 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{Analysis, AnalysisArgs};
+use crate::ignore::should_ignore_function;
+use crate::imports::{self, Resolver};
+use crate::inheritance::{Hierarchy, Parsed};
+use crate::solidity::{self, ContractKind, Function, Visibility};
+use crate::source::{canonical, SourceFile};
+
+/// Directories under the root holding dependencies, e.g. OpenZeppelin, whose functions
+/// are only audited when inherited.
+const DEPENDENCY_DIRS: [&str; 2] = ["lib", "node_modules"];
+
+/// An externally visible function that a contract of the project inherits from a
+/// dependency, whose name the project's code never mentions.
+#[derive(Serialize)]
+pub(super) struct InheritedUnused {
+    /// The project's contract inheriting the function.
+    pub contract: String,
+    /// The base declaring it, nearest to `contract` when several do.
+    pub base: String,
+    pub base_file: PathBuf,
+    pub function: String,
+    pub signature: String,
+    pub visibility: Visibility,
+    /// Line of the declaration in `base_file`.
+    pub line: usize,
+}
+
+/// The identifiers in the code of the project's own files, outside its dependencies.
+pub(super) struct ProjectMentions {
+    names: HashSet<String>,
+}
+
+impl ProjectMentions {
+    fn load(analysis: &Analysis) -> Self {
+        let mut names = HashSet::new();
+        for path in analysis.search_files().unwrap_or_default() {
+            if is_dependency(&path, &analysis.project.root) || analysis.excluded.excludes(&path) {
+                continue;
+            }
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let source = SourceFile::new(path, content);
+            let code = source.code_only((0, source.content.len()));
+            names.extend(identifier_pattern().find_iter(&code).map(|found| found.as_str().to_string()));
+        }
+        ProjectMentions { names }
+    }
+}
+
+fn identifier_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"[A-Za-z_$][\w$]*").unwrap())
+}
+
+/// Whether `path` is under one of the `DEPENDENCY_DIRS` of `root`.
+pub(super) fn is_dependency(path: &Path, root: &Path) -> bool {
+    let path = canonical(path);
+    DEPENDENCY_DIRS.iter().any(|dir| path.starts_with(canonical(&root.join(dir))))
+}
+
+/// Finds the externally visible functions that the contracts of `sol_file` inherit from
+/// dependencies and that the project never mentions, grouped by contract and then by
+/// base, nearest base first. The bases are found by following the imports of `sol_file`,
+/// through the remappings of the root. Functions that the contract or one of its bases
+/// in the project overrides are the project's own, and are analyzed like any other.
+pub(super) fn audit(sol_file: &Path, args: &AnalysisArgs, analysis: &Analysis) -> Result<Vec<InheritedUnused>> {
+    let source = SourceFile::read(sol_file)?;
+    let unit = solidity::parse(&source);
+    if unit.contracts.iter().all(|contract| contract.bases.is_empty()) {
+        return Ok(Vec::new());
+    }
+    let mut files = vec![Parsed { source, unit }];
+    let resolver = Resolver::new(&analysis.project.root)?;
+    let mut seen: HashSet<PathBuf> = HashSet::from([canonical(sol_file)]);
+    let mut queue: VecDeque<usize> = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        let importer = files[index].source.path.clone();
+        for import in imports::parse_imports(&files[index].source) {
+            let Some(path) = resolver.resolve(&importer, &import.path) else {
+                continue;
+            };
+            if !seen.insert(canonical(&path)) {
+                continue;
+            }
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let source = SourceFile::new(path, content);
+            let unit = solidity::parse(&source);
+            files.push(Parsed { source, unit });
+            queue.push_back(files.len() - 1);
+        }
+    }
+
+    let mentions = analysis.mentions.get_or_init(|| ProjectMentions::load(analysis));
+    let hierarchy = Hierarchy::new(&files);
+    let dependency: Vec<bool> = files
+        .iter()
+        .map(|file| is_dependency(&file.source.path, &analysis.project.root))
+        .collect();
+    let mut inherited = Vec::new();
+    for contract in &files[0].unit.contracts {
+        if !matches!(contract.kind, ContractKind::Contract | ContractKind::Abstract) {
+            continue;
+        }
+        // The functions already declared nearer to the contract, by name and arity.
+        let mut overridden: HashSet<(&str, usize)> = declared(&files[0], &contract.name)
+            .map(|function| (function.name.as_str(), function.params.len()))
+            .collect();
+        for (file, base) in hierarchy.ancestors(contract) {
+            for function in declared(&files[file], &base.name) {
+                if !overridden.insert((function.name.as_str(), function.params.len())) || !dependency[file] {
+                    continue;
+                }
+                if !function.visibility.is_externally_visible()
+                    || function.is_special()
+                    || mentions.names.contains(&function.name)
+                    || should_ignore_function(&function.name, &args.ignore)
+                {
+                    continue;
+                }
+                inherited.push(InheritedUnused {
+                    contract: contract.name.clone(),
+                    base: base.name.clone(),
+                    base_file: analysis.display_path(&files[file].source.path),
+                    function: function.name.clone(),
+                    signature: function.written_signature(),
+                    visibility: function.visibility,
+                    line: function.line,
+                });
+            }
+        }
+    }
+    Ok(inherited)
+}
+
+/// The functions of the contract `name` declared in `file`.
+fn declared<'a>(file: &'a Parsed, name: &'a str) -> impl Iterator<Item = &'a Function> {
+    file.unit
+        .functions
+        .iter()
+        .filter(move |function| function.contract.as_deref() == Some(name))
+}
+
+/// A base and the functions inherited from it.
+type BaseGroup<'a> = (&'a str, Vec<&'a InheritedUnused>);
+
+/// Groups `inherited` by contract and then by base, in order.
+pub(super) fn grouped(inherited: &[InheritedUnused]) -> Vec<(&str, Vec<BaseGroup<'_>>)> {
+    let mut groups: Vec<(&str, Vec<BaseGroup>)> = Vec::new();
+    for function in inherited {
+        match groups.last_mut() {
+            Some((contract, bases)) if *contract == function.contract => match bases.last_mut() {
+                Some((base, functions)) if *base == function.base => functions.push(function),
+                _ => bases.push((&function.base, vec![function])),
+            },
+            _ => groups.push((&function.contract, vec![(&function.base, vec![function])])),
+        }
+    }
+    groups
+}
@@ -0,0 +1,369 @@
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+
+use super::{refuse_partial_delete, remove_functions, Analysis, VacuumArgs};
+use crate::callgraph::CallGraph;
+use crate::config::Config;
+use crate::findings::{self, Finding, FindingKind, Suppressions};
+use crate::ignore::should_ignore_function;
+use crate::output::{self, FindingsFormat};
+use crate::references::{CountOptions, NameMatcher};
+use crate::solidity::{self, Function, Visibility};
+use crate::source::{self, canonical, replace_file, SourceFile};
+
+/// A dead function found during one analysis pass.
+#[derive(Serialize)]
+struct PlannedRemoval {
+    file: PathBuf,
+    function: String,
+    #[serde(skip)]
+    contract: Option<String>,
+    #[serde(skip)]
+    signature: String,
+    /// Its code, which its finding is fingerprinted with.
+    #[serde(skip)]
+    context: String,
+    /// Line of the declaration in the file as it was before any removal.
+    line: usize,
+    visibility: Visibility,
+    reason: FindingKind,
+    #[serde(skip)]
+    file_index: usize,
+    #[serde(skip)]
+    node: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Plan<'a> {
+    passes: &'a [Vec<PlannedRemoval>],
+    passes_run: usize,
+    converged: bool,
+    total_unused: usize,
+}
+
+/// Repeatedly finds dead functions and removes them from an in-memory copy of the
+/// analyzed files, leaf functions first, until nothing dead remains or `--max-passes` is
+/// reached. The files are only written when `--delete` is set.
+pub(super) fn run(args: &VacuumArgs, analyzed_files: &[PathBuf], analysis: &Analysis) -> Result<()> {
+    let options = args.analysis.count_options();
+    let skipped = |path: &Path| analysis.excluded.excludes(path);
+
+    let mut analyzed = analyzed_files
+        .iter()
+        .filter(|path| !skipped(path))
+        .map(|path| SourceFile::read(path))
+        .collect::<Result<Vec<_>>>()?;
+    let original: Vec<String> = analyzed.iter().map(|source| source.content.clone()).collect();
+    let original_lines: Vec<HashMap<String, usize>> = analyzed
+        .iter()
+        .map(|source| {
+            let mut lines = HashMap::new();
+            for function in solidity::parse(source).functions {
+                lines.entry(function.name).or_insert(function.line);
+            }
+            lines
+        })
+        .collect();
+
+    let seen: HashSet<PathBuf> = analyzed.iter().map(|source| canonical(&source.path)).collect();
+    let others: Vec<SourceFile> = analysis
+        .project
+        .search_files()?
+        .into_par_iter()
+        .filter(|path| !skipped(path) && !seen.contains(&canonical(path)))
+        .map(|path| {
+            // An unreadable file is searched as empty, and refuses --delete like a skipped
+            // directory.
+            let content = fs::read_to_string(&path).unwrap_or_else(|e| {
+                if source::skip_file(&path, format!("not searched for usages: {}", e)) {
+                    eprintln!("Warning: not searching {:?} for usages: {}.", path, e);
+                }
+                String::new()
+            });
+            SourceFile::new(path, content)
+        })
+        .collect();
+    if args.delete && !args.force {
+        refuse_partial_delete()?;
+    }
+
+    let entry_points = match &args.assume_entrypoints {
+        Some(path) => read_entry_points(path)?,
+        None => Vec::new(),
+    };
+
+    let mut passes: Vec<Vec<PlannedRemoval>> = Vec::new();
+    let mut passes_run = 0;
+    let mut converged = false;
+    while passes_run < args.max_passes {
+        passes_run += 1;
+        let dead = find_dead_functions(&analyzed, &others, &entry_points, passes_run == 1, args, &options, analysis)?;
+
+        let mut removed_this_pass = Vec::new();
+        for (index, source) in analyzed.iter_mut().enumerate() {
+            let names: Vec<(String, usize)> = dead
+                .iter()
+                .filter(|planned| planned.file_index == index)
+                .map(|planned| (planned.function.clone(), planned.line))
+                .collect();
+            if names.is_empty() {
+                continue;
+            }
+            let (content, removed) = remove_functions(&source.content, &names)?;
+            *source = SourceFile::new(source.path.clone(), content);
+            removed_this_pass.extend(removed.into_iter().map(|name| (index, name)));
+        }
+
+        let planned: Vec<PlannedRemoval> = dead
+            .into_iter()
+            .filter(|planned| removed_this_pass.contains(&(planned.file_index, planned.function.clone())))
+            .map(|mut planned| {
+                if let Some(&line) = original_lines[planned.file_index].get(&planned.function) {
+                    planned.line = line;
+                }
+                planned
+            })
+            .collect();
+        if planned.is_empty() {
+            converged = true;
+            break;
+        }
+        passes.push(planned);
+    }
+
+    if args.delete {
+        for (source, original) in analyzed.iter().zip(&original) {
+            if source.content != *original {
+                replace_file(&source.path, &source.content)?;
+            }
+        }
+    }
+
+    let total_unused = passes.iter().map(Vec::len).sum();
+    match args.format {
+        FindingsFormat::Text => print_text_plan(&passes, passes_run, converged, args),
+        FindingsFormat::Json => println!(
+            "{}",
+            output::to_json(&Plan {
+                passes: &passes,
+                passes_run,
+                converged,
+                total_unused,
+            })?
+        ),
+        format => {
+            let config = Config::load(&args.analysis.root)?;
+            let findings: Vec<Finding> = passes
+                .iter()
+                .flatten()
+                .map(|planned| Finding {
+                    file: planned.file.clone(),
+                    line: planned.line,
+                    contract: planned.contract.clone(),
+                    function: planned.function.clone(),
+                    signature: planned.signature.clone(),
+                    context: planned.context.clone(),
+                    visibility: Some(planned.visibility),
+                    kind: planned.reason,
+                    suggestion: None,
+                    coverage_hits: None,
+                    detail: None,
+                })
+                .collect();
+            let suppressions = Suppressions::load(args.suppressions.as_deref())?;
+            let uri_base = findings::uri_base(&args.analysis.root, args.relative_to.as_deref());
+            if let Some(rendered) = findings::render(format, &findings, &config.severity, &suppressions, &uri_base)? {
+                println!("{}", rendered);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads an `--assume-entrypoints` file: one function per line, as `name` or
+/// `Contract.name`, with `#` starting a comment.
+fn read_entry_points(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("Failed to read --assume-entrypoints file {:?}: {}", path, e),
+        )
+    })?;
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Finds every dead function in `analyzed`, ordered so that functions which don't call
+/// any other dead function come first. The `entry_points` and everything they reach are
+/// live; those that match no function are reported when `warn` is set.
+fn find_dead_functions(
+    analyzed: &[SourceFile],
+    others: &[SourceFile],
+    entry_points: &[String],
+    warn: bool,
+    args: &VacuumArgs,
+    options: &CountOptions,
+    analysis: &Analysis,
+) -> Result<Vec<PlannedRemoval>> {
+    let mut graph = CallGraph::from_sources(analyzed, others, options)?;
+    if !entry_points.is_empty() {
+        for entry in graph.assume_entry_points(entry_points) {
+            if warn {
+                eprintln!("Warning: --assume-entrypoints entry {:?} matches no analyzed function.", entry);
+            }
+        }
+    }
+    let node_ids: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.id.as_str(), index))
+        .collect();
+
+    let declared: Vec<Vec<Function>> = analyzed
+        .iter()
+        .map(|source| {
+            let mut seen = HashSet::new();
+            solidity::parse(source)
+                .functions
+                .into_iter()
+                .filter(|function| !function.is_special())
+                .filter(|function| !should_ignore_function(&function.name, &args.analysis.ignore))
+                .filter(|function| !analysis.proxy.reaches(function.contract.as_deref(), Some(function.visibility)))
+                .filter(|function| analysis.offchain.references(&function.name).is_empty())
+                .filter(|function| !analysis.abi_exported(function))
+                .filter(|function| analysis.deployed_usage(function).is_none())
+                .filter(|function| seen.insert(function.name.clone()))
+                .collect()
+        })
+        .collect();
+
+    let matcher = NameMatcher::new(declared.iter().flatten().map(|function| function.name.as_str()))?;
+    // The usage count of each name, indexed by its id in the matcher.
+    let names = matcher.names().len();
+    let counts: Vec<usize> = analyzed
+        .par_iter()
+        .chain(others.par_iter())
+        .map(|source| {
+            let mut counts = vec![0; names];
+            for (id, occurrence) in matcher.find_occurrences(source) {
+                counts[id] += usize::from(occurrence.counts(options));
+            }
+            counts
+        })
+        .reduce(
+            || vec![0; names],
+            |mut total, counts| {
+                total.iter_mut().zip(counts).for_each(|(total, count)| *total += count);
+                total
+            },
+        );
+
+    let mut dead = Vec::new();
+    for (file_index, functions) in declared.iter().enumerate() {
+        for function in functions {
+            let node = node_ids.get(function.qualified_name().as_str()).copied();
+            if node.is_some_and(|node| graph.nodes[node].assumed_entry_point) {
+                continue;
+            }
+            let reason = if matcher.id(&function.name).map_or(0, |id| counts[id]) <= 1 {
+                FindingKind::Unused
+            } else if node.is_some_and(|node| !graph.nodes[node].reachable) {
+                FindingKind::Unreachable
+            } else {
+                continue;
+            };
+            dead.push(PlannedRemoval {
+                file: analysis.display_path(&analyzed[file_index].path),
+                function: function.name.clone(),
+                contract: function.contract.clone(),
+                signature: function.written_signature(),
+                context: function.code(&analyzed[file_index]),
+                line: function.line,
+                visibility: function.visibility,
+                reason,
+                file_index,
+                node,
+            });
+        }
+    }
+
+    Ok(order_leaves_first(dead, &graph))
+}
+
+/// Orders dead functions so that each one comes before the dead functions calling it.
+/// Members of a dead call cycle keep their relative order.
+fn order_leaves_first(mut remaining: Vec<PlannedRemoval>, graph: &CallGraph) -> Vec<PlannedRemoval> {
+    let mut ordered = Vec::with_capacity(remaining.len());
+
+    while !remaining.is_empty() {
+        let dead_nodes: HashSet<usize> = remaining.iter().filter_map(|planned| planned.node).collect();
+        let (leaves, rest): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|planned| {
+            planned.node.is_none_or(|node| {
+                !graph
+                    .edges
+                    .iter()
+                    .any(|&(caller, callee)| caller == node && callee != node && dead_nodes.contains(&callee))
+            })
+        });
+
+        if leaves.is_empty() {
+            ordered.extend(rest);
+            break;
+        }
+        ordered.extend(leaves);
+        remaining = rest;
+    }
+
+    ordered
+}
+
+fn print_text_plan(passes: &[Vec<PlannedRemoval>], passes_run: usize, converged: bool, args: &VacuumArgs) {
+    println!("\nDeletion plan:");
+    let mut step = 0;
+    for (index, pass) in passes.iter().enumerate() {
+        println!("  Pass {}:", index + 1);
+        for planned in pass {
+            step += 1;
+            println!(
+                "    {}. {}: {} ({})",
+                step,
+                planned.file.display(),
+                planned.function,
+                planned.reason.as_str()
+            );
+        }
+    }
+    if passes.is_empty() {
+        println!("  (nothing to remove)");
+    }
+
+    if converged {
+        println!("\nPasses run: {} (no dead functions remain)", passes_run);
+    } else {
+        println!(
+            "\nPasses run: {} (stopped at --max-passes; dead functions may remain)",
+            passes_run
+        );
+    }
+
+    if args.delete && !passes.is_empty() {
+        let mut files: Vec<&PathBuf> = passes.iter().flatten().map(|planned| &planned.file).collect();
+        files.sort();
+        files.dedup();
+        for file in files {
+            println!("Updated {:?} with unused functions removed.", file);
+        }
+    }
+
+    println!("\nTotal unused functions found: {}", step);
+}
@@ -0,0 +1,486 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use super::{count_function_occurrences, extract_functions, Analysis, AnalysisArgs, FunctionDecl};
+use crate::abi::TypeTable;
+use crate::references::Occurrence;
+use crate::similar::RESERVED;
+use crate::solidity::{self, Function, SourceUnit};
+use crate::source::{canonical, relative, Context, SourceFile};
+
+/// How many of the references that can't be told apart are listed when refusing.
+const MAX_LISTED: usize = 10;
+
+/// A file changed by a rename, with its content before and after.
+pub struct RenamedFile {
+    /// The path as searched, to write the file.
+    pub path: PathBuf,
+    pub original: String,
+    pub renamed: String,
+}
+
+/// The edits of `wand rename`, not yet written.
+pub struct Rename {
+    pub files: Vec<RenamedFile>,
+    /// The declarations renamed, e.g. `src/Vault.sol:12 Vault.sweep(address)`.
+    pub declarations: Vec<String>,
+    /// The references renamed, besides the declarations.
+    pub references: usize,
+    /// The signature strings of the renamed functions left as they are without
+    /// `--selectors`, e.g. `src/Router.sol:40`.
+    pub signature_strings: Vec<String>,
+}
+
+/// A declaration of the renamed name in a searched file.
+struct Declared {
+    path: PathBuf,
+    /// The canonical path of the declaring file.
+    file: PathBuf,
+    decl: FunctionDecl,
+    /// The canonical parameter types, e.g. `address,uint256`, the same for every function
+    /// an override or implementation of another.
+    params: String,
+}
+
+impl Declared {
+    fn describe(&self) -> String {
+        let signature = match &self.decl.contract {
+            Some(contract) => format!("{}.{}", contract, self.decl.signature),
+            None => self.decl.signature.clone(),
+        };
+        format!("{}:{} {}", relative(&self.path).display(), self.decl.line, signature)
+    }
+}
+
+/// Plans renaming the functions named `old` under `PATH`, or only those declared with the
+/// written `signature`, to `new`, for `wand rename`. The declarations they override or
+/// implement, and those overriding or implementing them, across the inheritance chain, are
+/// renamed with them, and so is every reference resolving to any of them. Signature
+/// strings passed to `abi.encodeWithSignature` or `keccak256` are only renamed with
+/// `selectors`, since the selector they compute changes with the name.
+///
+/// Refuses when `new` is already used in a file that would change or in a contract whose
+/// members the renamed functions are, and when a reference may as well be to another
+/// function named `old`, rather than rename what it can't tell apart.
+pub fn plan_rename(
+    args: &AnalysisArgs,
+    old: &str,
+    new: &str,
+    signature: Option<&str>,
+    selectors: bool,
+) -> Result<Rename> {
+    check_name(old, new)?;
+    let analysis = Analysis::new(args, None)?;
+    if analysis.archive.is_some() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "Renaming in an archive or in sources fetched for --address is not supported.",
+        ));
+    }
+    let options = args.count_options();
+    let signature = signature.map(|signature| signature.split_whitespace().collect::<String>());
+
+    let search_files: Vec<PathBuf> = analysis
+        .search_files()?
+        .into_iter()
+        .filter(|path| !analysis.excluded.excludes(path))
+        .collect();
+    let units: Vec<SourceUnit> = search_files
+        .iter()
+        .map(|path| {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            solidity::parse(&SourceFile::new(path.clone(), content))
+        })
+        .collect();
+    let mut types = TypeTable::default();
+    for unit in &units {
+        types.add(unit);
+    }
+
+    let mut decls = Vec::new();
+    for (path, unit) in search_files.iter().zip(&units) {
+        let declares = unit.functions.iter().any(|function| function.name == old)
+            || unit.state_variables.iter().any(|variable| variable.name == old);
+        if !declares {
+            continue;
+        }
+        for decl in extract_functions(path, &analysis, &options)? {
+            if decl.name != old {
+                continue;
+            }
+            let params = unit
+                .functions
+                .iter()
+                .find(|function| function.name_offset == decl.offset)
+                .map(|function| param_types(&types, function))
+                .unwrap_or_default();
+            decls.push(Declared {
+                path: path.clone(),
+                file: canonical(path),
+                decl,
+                params,
+            });
+        }
+    }
+
+    let analyzed: HashSet<PathBuf> = analysis.analyzed_files()?.iter().map(|path| canonical(path)).collect();
+    let mut family: Vec<bool> = decls
+        .iter()
+        .map(|declared| {
+            analyzed.contains(&declared.file)
+                && declared.decl.visibility.is_some()
+                && !declared.decl.auto_getter
+                && signature.as_ref().is_none_or(|signature| *signature == declared.decl.signature)
+        })
+        .collect();
+    if !family.contains(&true) {
+        let function = signature.unwrap_or_else(|| format!("`{}`", old));
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("No function {} is declared in {:?}.", function, analysis.path),
+        ));
+    }
+    let yul = decls.iter().zip(&family).find(|(declared, &renamed)| renamed && declared.decl.assembly.is_some());
+    if let Some((yul, _)) = yul {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("Renaming the Yul function at {} is not supported.", yul.describe()),
+        ));
+    }
+
+    // The declarations of the same parameter types in the contracts inheriting from a
+    // renamed one, or inherited by it, are overrides or implementations, renamed with it.
+    let index = analysis.contracts();
+    let related = |a: &Declared, b: &Declared| match (&a.decl.contract, &b.decl.contract) {
+        (Some(a_contract), Some(b_contract)) => {
+            a.params == b.params && (index.inherits(a_contract, b_contract) || index.inherits(b_contract, a_contract))
+        }
+        _ => false,
+    };
+    loop {
+        let joining: Vec<usize> = (0..decls.len())
+            .filter(|&i| !family[i] && decls[i].decl.visibility.is_some() && decls[i].decl.assembly.is_none())
+            .filter(|&i| (0..decls.len()).any(|j| family[j] && related(&decls[i], &decls[j])))
+            .collect();
+        if joining.is_empty() {
+            break;
+        }
+        for i in joining {
+            if decls[i].decl.auto_getter {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "`{}` is implemented by the public state variable at {}, which isn't renamed.",
+                        old,
+                        decls[i].describe()
+                    ),
+                ));
+            }
+            family[i] = true;
+        }
+    }
+
+    let mut sources: HashMap<PathBuf, SourceFile> = HashMap::new();
+    let mut edits: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+    let mut ambiguous = Vec::new();
+    let mut signature_strings = Vec::new();
+    let mut references = 0;
+    for occurrence in count_function_occurrences(&search_files, &[old], &analysis.excluded)?.take(old) {
+        let file = canonical(&occurrence.file);
+        let source = sources.entry(file.clone()).or_insert_with(|| {
+            let content = fs::read_to_string(&occurrence.file).unwrap_or_default();
+            SourceFile::new(occurrence.file.clone(), content)
+        });
+        let declaration = decls
+            .iter()
+            .position(|declared| declared.file == file && declared.decl.offset == occurrence.offset);
+        if let Some(i) = declaration {
+            if family[i] {
+                edits.entry(occurrence.file.clone()).or_default().push(occurrence.offset);
+            }
+            continue;
+        }
+
+        let (renamed, other) = match occurrence.context {
+            Context::Comment => continue,
+            Context::String if !occurrence.in_signature => continue,
+            Context::String => {
+                let Some(types) = signature_types(source, occurrence.offset + old.len()) else {
+                    continue;
+                };
+                let matches = |declared: &Declared| declared.decl.visibility.is_some() && declared.params == types;
+                let renamed = decls.iter().zip(&family).any(|(declared, &renamed)| renamed && matches(declared));
+                let other = decls.iter().zip(&family).any(|(declared, &renamed)| !renamed && matches(declared));
+                if renamed && !other && !selectors {
+                    signature_strings.push(format!("{}:{}", relative(&occurrence.file).display(), occurrence.line));
+                    continue;
+                }
+                (renamed, other)
+            }
+            Context::Code => {
+                // Yul functions of the same name are only called from their assembly block.
+                let in_yul = decls.iter().any(|declared| {
+                    let in_block = |(open, close): (usize, usize)| (open..close).contains(&occurrence.offset);
+                    declared.file == file && declared.decl.assembly.is_some_and(in_block)
+                });
+                if in_yul {
+                    continue;
+                }
+                let arity = call_arity(source, occurrence.offset + old.len());
+                let enclosing = index.files.get(&file).and_then(|contracts| {
+                    contracts
+                        .iter()
+                        .find(|contract| (contract.body.0..contract.body.1).contains(&occurrence.offset))
+                        .map(|contract| contract.name.as_str())
+                });
+                let dotted = source.content[..occurrence.offset].trim_end().ends_with('.');
+                let matches = |declared: &Declared| {
+                    declared.decl.assembly.is_none()
+                        && (declared.decl.auto_getter || arity.is_none_or(|arity| arity == declared.decl.params))
+                        && refers_to(&analysis, &occurrence, enclosing, dotted, &declared.decl)
+                };
+                let renamed = decls.iter().zip(&family).any(|(declared, &renamed)| renamed && matches(declared));
+                let other = decls.iter().zip(&family).any(|(declared, &renamed)| !renamed && matches(declared));
+                (renamed, other)
+            }
+        };
+        if renamed && other {
+            ambiguous.push(format!(
+                "{}:{} {}",
+                relative(&occurrence.file).display(),
+                occurrence.line,
+                occurrence.snippet
+            ));
+        } else if renamed {
+            edits.entry(occurrence.file.clone()).or_default().push(occurrence.offset);
+            references += 1;
+        }
+    }
+
+    check_collisions(&analysis, &search_files, &units, &decls, &family, &edits, new)?;
+    if !ambiguous.is_empty() {
+        eprintln!("References that may be to another `{}`:", old);
+        for reference in ambiguous.iter().take(MAX_LISTED) {
+            eprintln!("  {}", reference);
+        }
+        if ambiguous.len() > MAX_LISTED {
+            eprintln!("  ... and {} more", ambiguous.len() - MAX_LISTED);
+        }
+        eprintln!("The other declarations of `{}`:", old);
+        for (declared, _) in decls.iter().zip(&family).filter(|(_, &renamed)| !renamed) {
+            eprintln!("  {}", declared.describe());
+        }
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Can't tell whether {} reference(s) are to the renamed `{}` or to another, so nothing was renamed.",
+                ambiguous.len(),
+                old
+            ),
+        ));
+    }
+
+    let mut files = Vec::new();
+    for (path, mut offsets) in edits {
+        let original = fs::read_to_string(&path)?;
+        let mut renamed = original.clone();
+        offsets.sort_unstable();
+        offsets.dedup();
+        for &offset in offsets.iter().rev() {
+            renamed.replace_range(offset..offset + old.len(), new);
+        }
+        files.push(RenamedFile { path, original, renamed });
+    }
+
+    Ok(Rename {
+        files,
+        declarations: decls
+            .iter()
+            .zip(&family)
+            .filter(|(_, &renamed)| renamed)
+            .map(|(declared, _)| declared.describe())
+            .collect(),
+        references,
+        signature_strings,
+    })
+}
+
+/// Refuses a `new` name that isn't an identifier, or that is reserved.
+fn check_name(old: &str, new: &str) -> Result<()> {
+    let starts = new.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$');
+    let identifier = starts && new.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if !identifier || RESERVED.contains(&new) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("`{}` is not a valid function name.", new),
+        ));
+    }
+    if new == old {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("`{}` is already named `{}`.", old, new),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuses the rename when `new` is already used in code in a file that changes, or in a
+/// contract of the inheritance chains of the renamed functions, where the new name would
+/// clash with a member, a local or an event of that name. A renamed free function also
+/// clashes with any free function of the new name.
+fn check_collisions(
+    analysis: &Analysis,
+    search_files: &[PathBuf],
+    units: &[SourceUnit],
+    decls: &[Declared],
+    family: &[bool],
+    edits: &BTreeMap<PathBuf, Vec<usize>>,
+    new: &str,
+) -> Result<()> {
+    let renamed = || decls.iter().zip(family).filter(|(_, &renamed)| renamed).map(|(declared, _)| declared);
+    let collision = |place: String| {
+        Error::new(
+            ErrorKind::AlreadyExists,
+            format!("`{}` is already used at {}; pick another name.", new, place),
+        )
+    };
+
+    if renamed().any(|declared| declared.decl.contract.is_none()) {
+        for (path, unit) in search_files.iter().zip(units) {
+            if let Some(function) = unit
+                .functions
+                .iter()
+                .find(|function| function.contract.is_none() && function.name == new)
+            {
+                return Err(collision(format!("{}:{}", relative(path).display(), function.line)));
+            }
+        }
+    }
+
+    let index = analysis.contracts();
+    let contracts: HashSet<&str> = renamed().filter_map(|declared| declared.decl.contract.as_deref()).collect();
+    let affected = |contract: &str| {
+        contracts
+            .iter()
+            .any(|renamed| index.inherits(contract, renamed) || index.inherits(renamed, contract))
+    };
+    let edited: HashSet<PathBuf> = edits.keys().map(|path| canonical(path)).collect();
+    let occurrences = count_function_occurrences(search_files, &[new], &analysis.excluded)?.take(new);
+    for occurrence in occurrences.iter().filter(|occurrence| occurrence.context == Context::Code) {
+        let file = canonical(&occurrence.file);
+        let in_affected = || {
+            index.files.get(&file).is_some_and(|spans| {
+                spans
+                    .iter()
+                    .any(|span| (span.body.0..span.body.1).contains(&occurrence.offset) && affected(&span.name))
+            })
+        };
+        if edited.contains(&file) || in_affected() {
+            return Err(collision(format!(
+                "{}:{} (`{}`)",
+                relative(&occurrence.file).display(),
+                occurrence.line,
+                occurrence.snippet
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a code occurrence can refer to `decl`. An unqualified name, or one accessed on
+/// `this` or `super`, in a contract refers to a member of the contract or of those it
+/// inherits, or to a free function, and outside of any contract, e.g. in an import, to a
+/// free function. A name qualified by a known contract refers to a member of that contract
+/// or of those it inherits, and one qualified otherwise, e.g. by a variable, to any.
+fn refers_to(
+    analysis: &Analysis,
+    occurrence: &Occurrence,
+    enclosing: Option<&str>,
+    dotted: bool,
+    decl: &FunctionDecl,
+) -> bool {
+    let index = analysis.contracts();
+    let in_lineage = |contract: &str| {
+        decl.contract
+            .as_deref()
+            .is_some_and(|declaring| index.inherits(contract, declaring))
+    };
+    let qualifier = occurrence.member_qualifier.as_deref();
+    if !dotted || matches!(qualifier, Some("this" | "super")) {
+        return decl.contract.is_none() || enclosing.is_some_and(in_lineage);
+    }
+    let Some(qualifier) = qualifier else {
+        return true;
+    };
+    if decl.contract.as_deref() == Some(qualifier) {
+        return true;
+    }
+    let qualifier = index.resolve_alias(&canonical(&occurrence.file), qualifier);
+    !index.bases.contains_key(&qualifier) || in_lineage(&qualifier)
+}
+
+/// The canonical parameter types of `function`, e.g. `address,uint256`.
+fn param_types(types: &TypeTable, function: &Function) -> String {
+    let signature = types.signature(function);
+    signature[function.name.len() + 1..signature.len() - 1].to_string()
+}
+
+/// The parameter types of the signature string whose name ends at `end`, e.g.
+/// `address,uint256` for `"sweep(address, uint256)"`, without whitespace.
+fn signature_types(source: &SourceFile, end: usize) -> Option<String> {
+    let rest = source.content[end..].strip_prefix('(')?;
+    let mut depth = 1usize;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '"' | '\'' | '\n' => return None,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(rest[..index].split_whitespace().collect());
+        }
+    }
+    None
+}
+
+/// The number of arguments the name ending at `end` is called with, e.g. 2 for
+/// `sweep(to, amount)` and for `sweep{value: fee}(to, amount)`, or `None` if it isn't
+/// called there, e.g. for `this.sweep.selector`.
+fn call_arity(source: &SourceFile, end: usize) -> Option<usize> {
+    let content = &source.content;
+    let skip_whitespace = |from: usize| from + (content[from..].len() - content[from..].trim_start().len());
+    let mut open = skip_whitespace(end);
+    if content[open..].starts_with('{') {
+        open = skip_whitespace(source.block_end(open)?);
+    }
+    if !content[open..].starts_with('(') {
+        return None;
+    }
+    let close = source.group_end(open)? - 1;
+
+    let mut depth = 0usize;
+    let mut commas = 0;
+    let mut empty = true;
+    for (pos, &byte) in content.as_bytes().iter().enumerate().take(close).skip(open + 1) {
+        match source.context_at(pos) {
+            Context::Comment => continue,
+            Context::String => {
+                empty = false;
+                continue;
+            }
+            Context::Code => {}
+        }
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            b',' if depth == 0 => commas += 1,
+            _ => {}
+        }
+        empty &= byte.is_ascii_whitespace();
+    }
+    Some(if empty { 0 } else { commas + 1 })
+}
+
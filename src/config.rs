@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::findings::{Severity, SeverityPolicy};
+use crate::output::FindingsFormat;
+use crate::source::expand_path;
+
+/// Project settings read from `wand.toml`.
+#[derive(Deserialize, Serialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub severity: SeverityPolicy,
+    pub check: CheckConfig,
+    /// The projects of a monorepo, from `[[projects]]` tables, instead of detecting them.
+    pub projects: Vec<ProjectConfig>,
+}
+
+/// Settings of `wand check`, from the `[check]` table.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckConfig {
+    /// Files or directories to check, relative to the root. Defaults to the sources
+    /// directory of the project, `contracts` for Hardhat and `src` otherwise.
+    pub paths: Option<Vec<PathBuf>>,
+    /// Rule ids to run, e.g. `unused-function`.
+    pub rules: Vec<String>,
+    /// Patterns for function names to ignore, as for `vacuum --ignore`.
+    pub ignore: Vec<String>,
+    /// Baseline of known findings that don't fail the check, relative to the root.
+    pub baseline: Option<PathBuf>,
+    pub format: FindingsFormat,
+    /// Least severe finding that fails the check.
+    pub fail_on: Severity,
+    /// Number of failing findings tolerated before the check fails.
+    pub max_findings: usize,
+}
+
+/// A project of a monorepo, analyzed on its own by `wand vacuum`.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Name for `--project` and the report, instead of the directory name.
+    pub name: Option<String>,
+    /// The project's root, relative to the root.
+    pub path: PathBuf,
+    /// Other directories searched for usages of the project's functions, e.g. the
+    /// packages that import it, relative to the root.
+    #[serde(default)]
+    pub usage_roots: Vec<PathBuf>,
+}
+
+impl Default for CheckConfig {
+    fn default() -> Self {
+        CheckConfig {
+            paths: None,
+            rules: ["unused-function", "import-cycle", "missing-natspec"]
+                .map(String::from)
+                .to_vec(),
+            ignore: vec!["^test".to_string()],
+            baseline: None,
+            format: FindingsFormat::Text,
+            fail_on: Severity::Warning,
+            max_findings: 0,
+        }
+    }
+}
+
+impl Config {
+    /// Loads `wand.toml` from `root`, falling back to the defaults when there is none.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join("wand.toml");
+        if !path.is_file() {
+            return Ok(Config::default());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let invalid = |e: String| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid config {:?}: {}", path, e),
+            )
+        };
+        let mut config: Config = toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+
+        // Paths are expanded like the path arguments, since no shell sees them.
+        let check = &mut config.check;
+        for path in check.paths.iter_mut().flatten().chain(check.baseline.as_mut()) {
+            *path = expand_path(&path.to_string_lossy()).map_err(invalid)?;
+        }
+        for project in &mut config.projects {
+            for path in std::iter::once(&mut project.path).chain(&mut project.usage_roots) {
+                *path = expand_path(&path.to_string_lossy()).map_err(invalid)?;
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// The line of `wand.toml` under `root` where `value` is written as a string, quoted
+/// either way, to point at the setting holding it.
+pub fn locate_value(root: &Path, value: &str) -> Option<usize> {
+    let content = fs::read_to_string(root.join("wand.toml")).ok()?;
+    let basic = format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    let literal = format!("'{}'", value);
+    content
+        .lines()
+        .position(|line| line.contains(&basic) || line.contains(&literal))
+        .map(|index| index + 1)
+}
+
+/// The line of `wand.toml` under `root` setting `key` in the table `section`, or the
+/// line of the table's header when `key` is empty, to point at where a setting comes from.
+pub fn locate_key(root: &Path, section: &str, key: &str) -> Option<usize> {
+    let content = fs::read_to_string(root.join("wand.toml")).ok()?;
+    let mut table = "";
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            table = line.trim_matches(|c| c == '[' || c == ']').trim();
+            if table == section && key.is_empty() {
+                return Some(index + 1);
+            }
+        } else if table == section
+            && !key.is_empty()
+            && line.strip_prefix(key).is_some_and(|rest| rest.trim_start().starts_with('='))
+        {
+            return Some(index + 1);
+        }
+    }
+    None
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+
+use crate::source::{canonical, relative};
+
+/// A function of an LCOV record, from its `FN` and `FNDA` lines.
+struct CoveredFunction {
+    /// The name as recorded, e.g. `Vault.sweep`, which forge qualifies with the contract.
+    name: String,
+    line: usize,
+    hits: u64,
+}
+
+impl CoveredFunction {
+    /// Whether the record is of the function `name`, of `contract` if the record is
+    /// qualified.
+    fn is(&self, contract: Option<&str>, name: &str) -> bool {
+        match self.name.rsplit_once('.') {
+            Some((qualifier, unqualified)) => unqualified == name && contract == Some(qualifier),
+            None => self.name == name,
+        }
+    }
+}
+
+/// How often the tests ran each function, read from an LCOV file such as the one
+/// `forge coverage --report lcov` writes.
+#[derive(Default)]
+pub struct Coverage {
+    /// The functions of each source file, by canonical path.
+    files: HashMap<PathBuf, Vec<CoveredFunction>>,
+}
+
+impl Coverage {
+    /// Reads `lcov`, whose source paths are relative to `root` once `prefix` is removed
+    /// from them, if they start with it.
+    ///
+    /// Only the `SF`, `FN`, `FNDA` and `end_of_record` lines are read, and what they can't
+    /// be made sense of is skipped rather than failing: `FN` lines with an end line, as
+    /// recent forge versions write, records without `end_of_record` at the end of the
+    /// file, and overloads recorded under one name. The hits of a function recorded for
+    /// several runs of the tests are summed.
+    pub fn load(lcov: &Path, prefix: Option<&Path>, root: &Path) -> Result<Self> {
+        let content = fs::read_to_string(lcov)
+            .map_err(|e| Error::new(e.kind(), format!("Failed to read coverage file {:?}: {}", lcov, e)))?;
+        let prefix = prefix.map(relative);
+
+        let mut coverage = Coverage::default();
+        let mut record = Record::default();
+        for line in content.lines() {
+            let line = line.trim();
+            let (tag, value) = line.split_once(':').unwrap_or((line, ""));
+            match tag {
+                "SF" => {
+                    coverage.add(std::mem::take(&mut record));
+                    let path = relative(Path::new(value.trim()));
+                    let path = prefix
+                        .as_ref()
+                        .and_then(|prefix| path.strip_prefix(prefix).ok())
+                        .unwrap_or(&path);
+                    record.file = Some(canonical(&root.join(path)));
+                }
+                "FN" => record.functions.extend(parse_function(value)),
+                "FNDA" => {
+                    let hits = value.split_once(',').and_then(|(hits, name)| {
+                        // Some tools write hits as decimals, e.g. `1.0`.
+                        let hits = hits.trim();
+                        let hits = hits.parse::<u64>().ok().or_else(|| Some(hits.parse::<f64>().ok()? as u64))?;
+                        Some((name.trim().to_string(), hits))
+                    });
+                    record.hits.extend(hits);
+                }
+                "end_of_record" => coverage.add(std::mem::take(&mut record)),
+                _ => {}
+            }
+        }
+        coverage.add(record);
+        Ok(coverage)
+    }
+
+    /// Whether any record is of `file`.
+    pub fn covers(&self, file: &Path) -> bool {
+        self.files.contains_key(&canonical(file))
+    }
+
+    /// How often the tests ran the function `name` of `contract` declared on `line` of
+    /// `file`, or `None` if no record is of it, e.g. for an interface function. Overloads
+    /// share the name, so the record nearest to the line is taken.
+    pub fn hits(&self, file: &Path, contract: Option<&str>, name: &str, line: usize) -> Option<u64> {
+        self.files
+            .get(&canonical(file))?
+            .iter()
+            .filter(|function| function.is(contract, name))
+            .min_by_key(|function| function.line.abs_diff(line))
+            .map(|function| function.hits)
+    }
+
+    /// Adds the functions of a record, pairing each `FN` line with the `FNDA` line of its
+    /// name in order, so that overloads recorded under one name each get their own.
+    fn add(&mut self, record: Record) {
+        let Some(file) = record.file else {
+            return;
+        };
+        let mut hits_by_name: HashMap<&str, Vec<u64>> = HashMap::new();
+        for (name, hits) in &record.hits {
+            hits_by_name.entry(name).or_default().push(*hits);
+        }
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        let functions = self.files.entry(file).or_default();
+        for (line, name) in &record.functions {
+            let index = seen.entry(name).or_default();
+            // A name recorded once for several functions gives its hits to each, so that
+            // none of them is taken for uncovered.
+            let hits = hits_by_name
+                .get(name.as_str())
+                .and_then(|hits| hits.get(*index).or(hits.last()))
+                .copied()
+                .unwrap_or_default();
+            *index += 1;
+            match functions.iter_mut().find(|function| function.name == *name && function.line == *line) {
+                Some(function) => function.hits += hits,
+                None => functions.push(CoveredFunction {
+                    name: name.clone(),
+                    line: *line,
+                    hits,
+                }),
+            }
+        }
+    }
+}
+
+/// The lines read so far of the record of one source file.
+#[derive(Default)]
+struct Record {
+    file: Option<PathBuf>,
+    /// The line and name of each `FN` line.
+    functions: Vec<(usize, String)>,
+    /// The name and hits of each `FNDA` line.
+    hits: Vec<(String, u64)>,
+}
+
+/// Parses the value of an `FN` line, `<line>,<name>` or `<line>,<end line>,<name>`.
+fn parse_function(value: &str) -> Option<(usize, String)> {
+    let (line, rest) = value.split_once(',')?;
+    let line = line.trim().parse().ok()?;
+    let name = match rest.split_once(',') {
+        Some((end, name)) if end.trim().parse::<usize>().is_ok() => name,
+        _ => rest,
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| (line, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads `lcov` for a project with `src/Vault.sol`, with `prefix` removed from its paths.
+    fn load(lcov: &str, prefix: Option<&str>) -> (tempfile::TempDir, Coverage) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/Vault.sol"), "").unwrap();
+        fs::write(dir.path().join("lcov.info"), lcov).unwrap();
+        let coverage = Coverage::load(&dir.path().join("lcov.info"), prefix.map(Path::new), dir.path()).unwrap();
+        (dir, coverage)
+    }
+
+    fn hits(dir: &tempfile::TempDir, coverage: &Coverage, name: &str, line: usize) -> Option<u64> {
+        coverage.hits(&dir.path().join("src/Vault.sol"), Some("Vault"), name, line)
+    }
+
+    #[test]
+    fn fn_and_fnda_lines_give_the_hits() {
+        let lcov = "TN:\nSF:src/Vault.sol\nFN:3,Vault.sweep\nFN:7,Vault.pause\nFNDA:4,Vault.sweep\nFNDA:0,Vault.pause\n\
+                    FNF:2\nFNH:1\nDA:3,4\nend_of_record\n";
+        let (dir, coverage) = load(lcov, None);
+        assert!(coverage.covers(&dir.path().join("src/Vault.sol")));
+        assert_eq!(hits(&dir, &coverage, "sweep", 3), Some(4));
+        assert_eq!(hits(&dir, &coverage, "pause", 7), Some(0));
+        assert_eq!(hits(&dir, &coverage, "rescue", 9), None);
+        assert_eq!(coverage.hits(&dir.path().join("src/Vault.sol"), Some("Other"), "sweep", 3), None);
+    }
+
+    #[test]
+    fn the_prefix_is_removed_from_source_paths() {
+        let lcov = "SF:/home/runner/work/protocol/src/Vault.sol\nFN:3,Vault.sweep\nFNDA:2,Vault.sweep\nend_of_record\n";
+        let (dir, coverage) = load(lcov, Some("/home/runner/work/protocol"));
+        assert_eq!(hits(&dir, &coverage, "sweep", 3), Some(2));
+        let (dir, coverage) = load(lcov, None);
+        assert!(!coverage.covers(&dir.path().join("src/Vault.sol")));
+    }
+
+    #[test]
+    fn forge_records_are_tolerated() {
+        // End lines in `FN`, `FNDA` before `FN`, decimal hits, a malformed line, and no
+        // `end_of_record` at the end of the file.
+        let lcov = "SF:src/Vault.sol\nFNDA:5,Vault.sweep\nFNDA:1.0,Vault.pause\nFN:3,5,Vault.sweep\nFN:garbage\n\
+                    FN:7,9,Vault.pause\n";
+        let (dir, coverage) = load(lcov, None);
+        assert_eq!(hits(&dir, &coverage, "sweep", 3), Some(5));
+        assert_eq!(hits(&dir, &coverage, "pause", 7), Some(1));
+    }
+
+    #[test]
+    fn overloads_and_repeated_runs() {
+        let lcov = "SF:src/Vault.sol\nFN:3,Vault.sweep\nFN:8,Vault.sweep\nFNDA:2,Vault.sweep\nFNDA:0,Vault.sweep\n\
+                    end_of_record\nSF:src/Vault.sol\nFN:3,Vault.sweep\nFN:8,Vault.sweep\nFNDA:1,Vault.sweep\n\
+                    FNDA:0,Vault.sweep\nend_of_record\n";
+        let (dir, coverage) = load(lcov, None);
+        assert_eq!(hits(&dir, &coverage, "sweep", 3), Some(3));
+        assert_eq!(hits(&dir, &coverage, "sweep", 8), Some(0));
+    }
+
+    #[test]
+    fn a_missing_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = Coverage::load(&dir.path().join("lcov.info"), None, dir.path()).err().unwrap();
+        assert!(error.to_string().contains("Failed to read coverage file"), "{}", error);
+    }
+}
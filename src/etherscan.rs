@@ -0,0 +1,275 @@
+use serde_json::Value;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::archive::Extracted;
+
+/// Environment variable holding the Etherscan API key.
+pub const API_KEY_VAR: &str = "ETHERSCAN_API_KEY";
+
+/// Environment variable overriding the API endpoint, e.g. for an Etherscan-compatible
+/// explorer.
+pub const API_URL_VAR: &str = "ETHERSCAN_API_URL";
+
+pub const API_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// How often a rate-limited request is sent before giving up.
+const MAX_ATTEMPTS: u64 = 4;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Chain names accepted by `--chain`, with their ids.
+const CHAINS: [(&str, u64); 14] = [
+    ("mainnet", 1),
+    ("ethereum", 1),
+    ("sepolia", 11_155_111),
+    ("holesky", 17_000),
+    ("optimism", 10),
+    ("arbitrum", 42_161),
+    ("base", 8_453),
+    ("polygon", 137),
+    ("bsc", 56),
+    ("avalanche", 43_114),
+    ("gnosis", 100),
+    ("linea", 59_144),
+    ("scroll", 534_352),
+    ("zksync", 324),
+];
+
+/// The verified source of a contract.
+struct Verified {
+    name: String,
+    /// Source files by path.
+    sources: Vec<(PathBuf, String)>,
+    /// The implementation address, if Etherscan flags the contract as a proxy.
+    implementation: Option<String>,
+}
+
+/// Downloads the verified sources of the contract at `address` into a temporary
+/// directory, or into `keep` if given. With `follow_proxy`, the implementation of a proxy
+/// is downloaded instead.
+pub fn download(address: &str, chain: &str, follow_proxy: bool, keep: Option<&Path>) -> Result<Extracted> {
+    if !is_address(address) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("--address {:?} isn't a 0x-prefixed, 20-byte hex address.", address),
+        ));
+    }
+    let chain_id = chain_id(chain)?;
+    let key = std::env::var(API_KEY_VAR).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Set {} to fetch verified sources from Etherscan.", API_KEY_VAR),
+        )
+    })?;
+
+    let mut address = address.to_string();
+    let mut verified = fetch(&address, chain_id, &key)?;
+    if let Some(implementation) = verified.implementation.clone() {
+        if follow_proxy {
+            eprintln!(
+                "Note: {} is a proxy; analyzing its implementation {}.",
+                address, implementation
+            );
+            verified = fetch(&implementation, chain_id, &key)?;
+            address = implementation;
+        } else {
+            eprintln!(
+                "Note: Etherscan flags {} as a proxy of {}; pass --follow-proxy to analyze the implementation instead.",
+                address, implementation
+            );
+        }
+    }
+
+    let origin = PathBuf::from(&address);
+    let extracted = match keep {
+        Some(dir) => Extracted::kept(&origin, dir)?,
+        None => Extracted::temporary(&origin)?,
+    };
+    for (path, content) in &verified.sources {
+        let path = extracted.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+    }
+    eprintln!(
+        "Fetched {} source file(s) of {} ({}).",
+        verified.sources.len(),
+        verified.name,
+        address
+    );
+    Ok(extracted)
+}
+
+fn is_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Returns the id of a chain given by name or id.
+fn chain_id(chain: &str) -> Result<u64> {
+    if let Ok(id) = chain.parse() {
+        return Ok(id);
+    }
+    CHAINS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(chain))
+        .map(|&(_, id)| id)
+        .ok_or_else(|| {
+            let names: Vec<&str> = CHAINS.iter().map(|(name, _)| *name).collect();
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unknown --chain {:?}; expected a chain id or one of {}.",
+                    chain,
+                    names.join(", ")
+                ),
+            )
+        })
+}
+
+/// Fetches the verified source of `address`, retrying with a growing delay while
+/// Etherscan rate-limits the requests.
+fn fetch(address: &str, chain_id: u64, key: &str) -> Result<Verified> {
+    let url = std::env::var(API_URL_VAR).unwrap_or_else(|_| API_URL.to_string());
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    let chain_id = chain_id.to_string();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let request = agent
+            .get(&url)
+            .query("chainid", &chain_id)
+            .query("module", "contract")
+            .query("action", "getsourcecode")
+            .query("address", address)
+            .query("apikey", key);
+        let body = match request.call() {
+            Ok(response) => response.into_string()?,
+            Err(ureq::Error::Status(429, _)) if attempt < MAX_ATTEMPTS => {
+                thread::sleep(Duration::from_secs(attempt));
+                continue;
+            }
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Failed to fetch the source of {} from Etherscan: {}",
+                    address, e
+                )));
+            }
+        };
+        let value: Value = serde_json::from_str(&body).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Etherscan returned an invalid response for {}: {}", address, e),
+            )
+        })?;
+
+        if value.get("status").and_then(Value::as_str) != Some("1") {
+            let result = value.get("result").and_then(Value::as_str).unwrap_or_default();
+            if result.to_lowercase().contains("rate limit") && attempt < MAX_ATTEMPTS {
+                thread::sleep(Duration::from_secs(attempt));
+                continue;
+            }
+            return Err(Error::other(format!(
+                "Etherscan rejected the request for {}: {} {}",
+                address,
+                value.get("message").and_then(Value::as_str).unwrap_or_default(),
+                result
+            )));
+        }
+        return parse_verified(address, chain_id.as_str(), &value);
+    }
+
+    Err(Error::other(format!(
+        "Etherscan kept rate-limiting the request for {} after {} attempts.",
+        address, MAX_ATTEMPTS
+    )))
+}
+
+fn parse_verified(address: &str, chain_id: &str, value: &Value) -> Result<Verified> {
+    let entry = value
+        .get("result")
+        .and_then(Value::as_array)
+        .and_then(|result| result.first())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Etherscan returned no result for {}.", address),
+            )
+        })?;
+    let field = |name: &str| entry.get(name).and_then(Value::as_str).unwrap_or_default();
+
+    let code = field("SourceCode");
+    if code.trim().is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "{} on chain {} has no verified source on Etherscan.",
+                address, chain_id
+            ),
+        ));
+    }
+    let name = match field("ContractName") {
+        "" => "Contract".to_string(),
+        name => name.to_string(),
+    };
+    let implementation = Some(field("Implementation"))
+        .filter(|implementation| field("Proxy") == "1" && is_address(implementation))
+        .map(str::to_string);
+
+    Ok(Verified {
+        sources: parse_sources(&name, code)?,
+        name,
+        implementation,
+    })
+}
+
+/// Reads the files of a verified source: a single flattened file, a JSON object of files,
+/// or standard JSON input, which Etherscan wraps in an extra pair of braces.
+fn parse_sources(name: &str, code: &str) -> Result<Vec<(PathBuf, String)>> {
+    let trimmed = code.trim();
+    let json = if trimmed.starts_with("{{") && trimmed.ends_with("}}") {
+        &trimmed[1..trimmed.len() - 1]
+    } else if trimmed.starts_with('{') {
+        trimmed
+    } else {
+        return Ok(vec![(PathBuf::from(format!("{}.sol", name)), code.to_string())]);
+    };
+
+    let invalid = |detail: String| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Failed to read the verified sources of {}: {}", name, detail),
+        )
+    };
+    let value: Value = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    let files = value
+        .get("sources")
+        .unwrap_or(&value)
+        .as_object()
+        .ok_or_else(|| invalid("no `sources` object".to_string()))?;
+
+    files
+        .iter()
+        .map(|(path, file)| {
+            let content = file
+                .get("content")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid(format!("{:?} has no `content`", path)))?;
+            Ok((relative_path(path), content.to_string()))
+        })
+        .collect()
+}
+
+/// Keeps the normal components of a source path, so that absolute paths and `..` can't
+/// write outside the download directory.
+fn relative_path(path: &str) -> PathBuf {
+    Path::new(path)
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
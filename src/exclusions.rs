@@ -0,0 +1,187 @@
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::source::{canonical, relative};
+use crate::wandignore::WandIgnore;
+
+/// The files left out of the analysis and of the search for usages: those under the
+/// `--no-match-path` directories, those the `--ignore-paths-from` list names exactly or
+/// matches with a glob, and those the `.wandignore` files under the roots exclude.
+#[derive(Default, Debug)]
+pub struct PathExclusions {
+    paths: Vec<PathBuf>,
+    globs: Vec<Pattern>,
+    pub wandignore: WandIgnore,
+}
+
+impl PathExclusions {
+    /// Merges `no_match_path` with the entries of the `list` file, if one is given, and
+    /// the `.wandignore` files under `roots`.
+    pub fn load(no_match_path: &[PathBuf], list: Option<&Path>, roots: &[PathBuf]) -> Result<Self> {
+        let mut exclusions = PathExclusions {
+            paths: no_match_path.iter().map(|path| relative(path)).collect(),
+            globs: Vec::new(),
+            wandignore: WandIgnore::load(roots)?,
+        };
+        if let Some(list) = list {
+            exclusions.read(list)?;
+        }
+        Ok(exclusions)
+    }
+
+    /// Reads an exclusion list: one path or glob per line, with `#` starting a comment.
+    /// Entries with any of `*?[` are globs, e.g. `src/**/mocks/*.sol`, where `*` doesn't
+    /// cross directories; others exclude the file or everything under the directory.
+    fn read(&mut self, list: &Path) -> Result<()> {
+        let content = fs::read_to_string(list).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("Failed to read path exclusion list {:?}: {}", list, e),
+            )
+        })?;
+        for (index, line) in content.lines().enumerate() {
+            let entry = line.split('#').next().unwrap_or_default().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            if entry.contains(['*', '?', '[']) {
+                let entry = entry.strip_prefix("./").unwrap_or(entry);
+                let glob = Pattern::new(entry).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid glob {:?} at {}:{}: {}", entry, list.display(), index + 1, e),
+                    )
+                })?;
+                self.globs.push(glob);
+            } else {
+                self.paths.push(relative(Path::new(entry)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `path` is excluded, comparing paths as given, so that `src/mocks` and
+    /// `./src/mocks` are the same.
+    pub fn excludes(&self, path: &Path) -> bool {
+        let path = relative(path);
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.paths.iter().any(|excluded| path.starts_with(excluded))
+            || self.globs.iter().any(|glob| glob.matches_path_with(&path, options))
+            || self.wandignore.excluded_by(&path).is_some()
+    }
+}
+
+/// The markers of generated files that `--generated-marker` defaults to.
+pub const GENERATED_MARKERS: [&str; 2] = ["@generated", "DO NOT EDIT"];
+
+/// The markers telling generated files, such as bindings emitted by a code generator,
+/// apart from the project's own: a file is generated when one of its first `lines`
+/// lines matches one of the patterns.
+#[derive(Debug)]
+pub struct GeneratedMarkers {
+    patterns: Vec<Regex>,
+    lines: usize,
+}
+
+impl GeneratedMarkers {
+    pub fn new(markers: &[String], lines: usize) -> Result<Self> {
+        let patterns = markers
+            .iter()
+            .map(|marker| {
+                Regex::new(marker).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid --generated-marker {:?}: {}", marker, e),
+                    )
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(GeneratedMarkers { patterns, lines })
+    }
+
+    /// Whether `path` is generated. A file that can't be read isn't.
+    pub fn marks(&self, path: &Path) -> bool {
+        let Ok(file) = File::open(path) else {
+            return false;
+        };
+        BufReader::new(file)
+            .lines()
+            .take(self.lines)
+            .map_while(|line| line.ok())
+            .any(|line| self.patterns.iter().any(|pattern| pattern.is_match(&line)))
+    }
+}
+
+/// The entries that `--script-dirs` defaults to: Foundry's `script` directory, and its
+/// `.s.sol` naming convention anywhere.
+pub const SCRIPT_DIRS: [&str; 2] = ["script", "*.s.sol"];
+
+/// The deployment and operations scripts of a project, e.g. Foundry's `script/*.s.sol`,
+/// whose usages keep a function used but only to deploy or operate it. Entries are
+/// relative to the root: those with any of `*?[` are globs, matched against the file
+/// name when they have no `/` and otherwise against the whole path, and others are
+/// directories.
+#[derive(Debug)]
+pub struct ScriptFiles {
+    root: PathBuf,
+    dirs: Vec<PathBuf>,
+    globs: Vec<Pattern>,
+}
+
+impl ScriptFiles {
+    pub fn new(root: &Path, entries: &[String]) -> Result<Self> {
+        let mut scripts = ScriptFiles {
+            root: canonical(root),
+            dirs: Vec::new(),
+            globs: Vec::new(),
+        };
+        for entry in entries {
+            let entry = entry.strip_prefix("./").unwrap_or(entry);
+            if entry.contains(['*', '?', '[']) {
+                let glob = Pattern::new(entry).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid --script-dirs glob {:?}: {}", entry, e),
+                    )
+                })?;
+                scripts.globs.push(glob);
+            } else {
+                scripts.dirs.push(relative(Path::new(entry.trim_end_matches('/'))));
+            }
+        }
+        Ok(scripts)
+    }
+
+    /// The existing directories among the entries, under the root.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        self.dirs
+            .iter()
+            .map(|dir| self.root.join(dir))
+            .filter(|dir| dir.is_dir())
+            .collect()
+    }
+
+    /// Whether `path` is a script.
+    pub fn contains(&self, path: &Path) -> bool {
+        let path = canonical(path);
+        let Ok(path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let name = path.file_name().map(Path::new).unwrap_or(path);
+        self.dirs.iter().any(|dir| path.starts_with(dir))
+            || self.globs.iter().any(|glob| {
+                let target = if glob.as_str().contains('/') { path } else { name };
+                glob.matches_path_with(target, options)
+            })
+    }
+}
@@ -0,0 +1,442 @@
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command, ValueEnum};
+use glob::Pattern;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::commands::check::RULES;
+use crate::config::{self, Config};
+use crate::etherscan;
+use crate::findings::FindingKind;
+use crate::output;
+use crate::project::{Layout, Project};
+use crate::signatures;
+use crate::source::expand_path;
+use crate::wandignore::WandIgnore;
+
+/// The path arguments naming what a command writes, which needn't exist yet.
+const WRITTEN_PATHS: [&str; 3] = ["output", "baseline_write", "keep_sources"];
+
+/// The commands that read `wand.toml`.
+const CONFIGURED_COMMANDS: [&str; 2] = ["vacuum", "check"];
+
+/// An effective setting, and where its value comes from.
+#[derive(Serialize)]
+struct Setting {
+    /// `options`, `wand.toml`, `project` or `environment`.
+    section: &'static str,
+    name: String,
+    /// `null` for an option that isn't set.
+    value: Value,
+    /// e.g. `default`, `command line`, `./wand.toml:3 [severity] external` or
+    /// `env PAGER`.
+    source: String,
+}
+
+#[derive(Serialize)]
+struct Explanation {
+    command: String,
+    settings: Vec<Setting>,
+    problems: Vec<String>,
+}
+
+/// Whether `--explain-config` is among `args`, before any `--`.
+pub fn requested(args: &[OsString]) -> bool {
+    args.iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--explain-config")
+}
+
+/// Prints the effective value of every setting of the command `args` run, with where
+/// it comes from, and every problem of the configuration, instead of running it. Fails
+/// if there are problems, so that a configuration can be checked in CI.
+pub fn run(mut command: Command, args: &[OsString]) -> Result<()> {
+    command.build();
+    let mut problems = Vec::new();
+    // Clap stops at the first problem of the arguments; the conflicts are all listed
+    // below, and the rest parsed leniently.
+    if let Err(e) = command.try_get_matches_from_mut(args) {
+        if e.kind() != clap::error::ErrorKind::ArgumentConflict {
+            problems.push(clap_problem(&e));
+        }
+    }
+    let matches = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, clap_problem(&e)))?;
+
+    let mut names = Vec::new();
+    let (mut leaf_command, mut leaf) = (&command, &matches);
+    while let Some((name, sub_matches)) = leaf.subcommand() {
+        let Some(sub_command) = leaf_command.find_subcommand(name) else {
+            break;
+        };
+        names.push(name);
+        (leaf_command, leaf) = (sub_command, sub_matches);
+    }
+
+    let mut settings = Vec::new();
+    explain_options(leaf_command, leaf, &mut settings, &mut problems);
+    let root = raw_values(leaf, leaf_command, "root")
+        .first()
+        .and_then(|root| expand_path(root).ok());
+    if let Some(root) = &root {
+        if names.first().is_some_and(|name| CONFIGURED_COMMANDS.contains(name)) {
+            explain_wand_toml(root, &mut settings, &mut problems);
+        }
+        if has_arg(leaf_command, "layout") || names.first() == Some(&"check") {
+            explain_project(root, leaf_command, leaf, &mut settings);
+        }
+        if root.is_dir() {
+            if let Err(e) = WandIgnore::load(std::slice::from_ref(root)) {
+                problems.push(e.to_string());
+            }
+        }
+    }
+    explain_environment(&mut settings);
+
+    let explanation = Explanation {
+        command: names.join(" "),
+        settings,
+        problems,
+    };
+    output::set_pretty(raw_values(leaf, leaf_command, "pretty").first().is_some_and(|pretty| pretty == "true"));
+    if raw_values(leaf, leaf_command, "format").first().map(String::as_str) == Some("json") {
+        println!("{}", output::to_json(&explanation)?);
+    } else {
+        print_text(&explanation);
+    }
+
+    if explanation.problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Found {} configuration problem(s).", explanation.problems.len()),
+        ))
+    }
+}
+
+/// The message of a clap error, without its usage and help lines.
+fn clap_problem(e: &clap::Error) -> String {
+    let rendered = e.render().to_string();
+    let first = rendered.lines().next().unwrap_or_default();
+    first.strip_prefix("error: ").unwrap_or(first).to_string()
+}
+
+fn has_arg(command: &Command, id: &str) -> bool {
+    command.get_arguments().any(|arg| arg.get_id() == id)
+}
+
+/// The values of the argument `id` as written, or its defaults, if the command has it.
+fn raw_values(matches: &ArgMatches, command: &Command, id: &str) -> Vec<String> {
+    if !has_arg(command, id) {
+        return Vec::new();
+    }
+    matches
+        .get_raw(id)
+        .map(|values| values.map(|value| value.to_string_lossy().into_owned()).collect())
+        .unwrap_or_default()
+}
+
+/// `--name` for an option, `<NAME>` for a positional argument.
+fn display_name(arg: &clap::Arg) -> String {
+    match arg.get_long() {
+        Some(long) => format!("--{}", long),
+        None => {
+            let value_name = arg
+                .get_value_names()
+                .and_then(|names| names.first())
+                .map_or_else(|| arg.get_id().as_str().to_uppercase(), |name| name.to_string());
+            format!("<{}>", value_name)
+        }
+    }
+}
+
+/// Adds the options of the command, and the problems of their values: invalid regexes
+/// and globs, paths that don't exist, and conflicting options.
+fn explain_options(command: &Command, matches: &ArgMatches, settings: &mut Vec<Setting>, problems: &mut Vec<String>) {
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches!(id, "help" | "version" | "explain_config") {
+            continue;
+        }
+        let name = display_name(arg);
+        let raw = raw_values(matches, command, id);
+        let value = match arg.get_action() {
+            ArgAction::SetTrue | ArgAction::SetFalse => Value::Bool(raw.first().is_some_and(|value| value == "true")),
+            ArgAction::Append => raw.iter().cloned().collect(),
+            _ => raw.first().cloned().map_or(Value::Null, Value::String),
+        };
+        let source = match matches.value_source(id) {
+            Some(ValueSource::CommandLine) => "command line",
+            Some(ValueSource::EnvVariable) => "env",
+            _ => "default",
+        };
+
+        let value_name = arg.get_value_names().and_then(|names| names.first()).map(|name| name.as_str());
+        if id == "ignore" || value_name == Some("REGEX") {
+            for pattern in &raw {
+                if let Err(e) = Regex::new(pattern) {
+                    problems.push(format!("Invalid {} {:?}: {}", name, pattern, e));
+                }
+            }
+        }
+        if value_name == Some("GLOB") {
+            for glob in &raw {
+                if let Err(e) = Pattern::new(glob) {
+                    problems.push(format!("Invalid {} {:?}: {}", name, glob, e));
+                }
+            }
+        }
+        let is_path = id == "root" || matches!(value_name, Some("PATH" | "FILE" | "DIR" | "LCOV"));
+        if is_path && !WRITTEN_PATHS.contains(&id) {
+            for path in &raw {
+                match expand_path(path) {
+                    Ok(expanded) if !expanded.exists() => {
+                        problems.push(format!("{} {:?} does not exist.", name, expanded))
+                    }
+                    Ok(_) => {}
+                    Err(e) => problems.push(format!("Invalid {} {:?}: {}", name, path, e)),
+                }
+            }
+        }
+        if source == "command line" && !arg.is_global_set() {
+            for other in command.get_arg_conflicts_with(arg) {
+                // Each pair is reported once, from the option declared first.
+                let declared_later = command
+                    .get_arguments()
+                    .position(|candidate| candidate.get_id() == other.get_id())
+                    > command.get_arguments().position(|candidate| candidate.get_id() == arg.get_id());
+                if declared_later && matches.value_source(other.get_id().as_str()) == Some(ValueSource::CommandLine) {
+                    problems.push(format!("{} conflicts with {}.", name, display_name(other)));
+                }
+            }
+        }
+
+        settings.push(Setting {
+            section: "options",
+            name,
+            value,
+            source: source.to_string(),
+        });
+    }
+}
+
+/// Adds the settings of `wand.toml` under `root`, and the problems of their values:
+/// unknown rules, invalid patterns and paths that don't exist.
+fn explain_wand_toml(root: &Path, settings: &mut Vec<Setting>, problems: &mut Vec<String>) {
+    let path = root.join("wand.toml");
+    let config = match Config::load(root) {
+        Ok(config) => config,
+        Err(e) => {
+            problems.push(e.to_string());
+            return;
+        }
+    };
+    let written: toml::Table = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| content.parse().ok())
+        .unwrap_or_default();
+    let source = |section: &str, key: &str| {
+        let location = match config::locate_key(root, section, key) {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        if key.is_empty() {
+            format!("{} [[{}]]", location, section)
+        } else {
+            format!("{} [{}] {}", location, section, key)
+        }
+    };
+
+    let Ok(Value::Object(effective)) = serde_json::to_value(&config) else {
+        return;
+    };
+    for (section, value) in effective {
+        let written_section = written.get(&section);
+        match value {
+            Value::Object(keys) => {
+                for (key, value) in keys {
+                    let is_written = written_section.and_then(|table| table.get(&key)).is_some();
+                    settings.push(Setting {
+                        section: "wand.toml",
+                        name: format!("{}.{}", section, key),
+                        value,
+                        source: if is_written { source(&section, &key) } else { "default".to_string() },
+                    });
+                }
+            }
+            value => settings.push(Setting {
+                section: "wand.toml",
+                name: section.clone(),
+                value,
+                source: if written_section.is_some() { source(&section, "") } else { "default".to_string() },
+            }),
+        }
+    }
+
+    let check = &config.check;
+    for rule in &check.rules {
+        if !RULES.iter().any(|kind| kind.rule_id() == rule) {
+            problems.push(format!(
+                "Unknown rule {:?} in the [check] table of {:?}, expected one of: {}.",
+                rule,
+                path,
+                RULES.map(FindingKind::rule_id).join(", ")
+            ));
+        }
+    }
+    for pattern in &check.ignore {
+        if let Err(e) = Regex::new(pattern) {
+            problems.push(format!("Invalid ignore pattern {:?} in the [check] table of {:?}: {}", pattern, path, e));
+        }
+    }
+    let configured_paths = check
+        .paths
+        .iter()
+        .flatten()
+        .chain(&check.baseline)
+        .map(|configured| ("[check]", configured))
+        .chain(config.projects.iter().flat_map(|project| {
+            std::iter::once(&project.path)
+                .chain(&project.usage_roots)
+                .map(|configured| ("[[projects]]", configured))
+        }));
+    for (table, configured) in configured_paths {
+        if !root.join(configured).exists() {
+            problems.push(format!(
+                "{:?} in the {} table of {:?} does not exist under {:?}.",
+                configured, table, path, root
+            ));
+        }
+    }
+}
+
+/// Adds the layout of the project at `root`, and the sources it analyzes by default.
+fn explain_project(root: &Path, command: &Command, matches: &ArgMatches, settings: &mut Vec<Setting>) {
+    let layout = raw_values(matches, command, "layout")
+        .first()
+        .and_then(|layout| Layout::from_str(layout, true).ok());
+    let path = raw_values(matches, command, "path")
+        .first()
+        .and_then(|path| expand_path(path).ok());
+    let project = Project::detect(root, path.as_deref(), layout);
+    let configs = Project::config_files(root);
+    let source = if has_arg(command, "layout") && matches.value_source("layout") == Some(ValueSource::CommandLine) {
+        "command line (--layout)".to_string()
+    } else if configs.is_empty() {
+        format!("no foundry.toml or Hardhat config in {:?}", root)
+    } else {
+        format!("detected from {} in {:?}", configs.join(" and "), root)
+    };
+    settings.push(Setting {
+        section: "project",
+        name: "layout".to_string(),
+        value: project
+            .layout
+            .and_then(|layout| layout.to_possible_value())
+            .map_or(Value::Null, |value| Value::String(value.get_name().to_string())),
+        source,
+    });
+    settings.push(Setting {
+        section: "project",
+        name: "sources".to_string(),
+        value: project
+            .sources()
+            .map_or(Value::Null, |sources| Value::String(sources.display().to_string())),
+        source: "layout".to_string(),
+    });
+}
+
+/// Adds the environment variables wand reads.
+fn explain_environment(settings: &mut Vec<Setting>) {
+    let variable = |name: &str, default: Option<&str>, secret: bool| {
+        let (value, source) = match std::env::var(name) {
+            Ok(_) if secret => (Value::String("(set)".to_string()), format!("env {}", name)),
+            Ok(value) => (Value::String(value), format!("env {}", name)),
+            Err(_) => (
+                default.map_or(Value::Null, |default| Value::String(default.to_string())),
+                "default".to_string(),
+            ),
+        };
+        Setting {
+            section: "environment",
+            name: name.to_string(),
+            value,
+            source,
+        }
+    };
+    settings.push(variable("PAGER", Some(output::DEFAULT_PAGER), false));
+    settings.push(variable("LESS", Some("FRX"), false));
+    settings.push(variable(etherscan::API_KEY_VAR, None, true));
+    settings.push(variable(etherscan::API_URL_VAR, Some(etherscan::API_URL), false));
+
+    let cache = signatures::cache_path();
+    let source = ["XDG_CACHE_HOME", "HOME"]
+        .into_iter()
+        .find(|name| std::env::var_os(name).is_some())
+        .map_or_else(|| "default".to_string(), |name| format!("env {}", name));
+    settings.push(Setting {
+        section: "environment",
+        name: "signature cache".to_string(),
+        value: cache.map_or(Value::Null, |cache: PathBuf| Value::String(cache.display().to_string())),
+        source,
+    });
+}
+
+fn print_text(explanation: &Explanation) {
+    if explanation.command.is_empty() {
+        println!("Configuration of wand:");
+    } else {
+        println!("Configuration of `wand {}`:", explanation.command);
+    }
+    let width = explanation
+        .settings
+        .iter()
+        .map(|setting| setting.name.len())
+        .max()
+        .unwrap_or_default();
+    let mut section = "";
+    for setting in &explanation.settings {
+        if setting.section != section {
+            section = setting.section;
+            let title = match section {
+                "options" => "Options",
+                "project" => "Project",
+                "environment" => "Environment",
+                file => file,
+            };
+            println!("\n{}:", title);
+        }
+        let value = match &setting.value {
+            Value::Null => "(unset)".to_string(),
+            Value::String(value) => value.clone(),
+            Value::Array(values) if values.is_empty() => "(none)".to_string(),
+            Value::Array(values) if values.iter().all(Value::is_string) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+            value => value.to_string(),
+        };
+        println!("  {:width$}  {}  ({})", setting.name, value, setting.source, width = width);
+    }
+
+    if explanation.problems.is_empty() {
+        println!("\nNo problems found.");
+    } else {
+        println!("\nProblems:");
+        for problem in &explanation.problems {
+            // Regex errors span several lines, which are kept under their problem.
+            println!("  - {}", problem.replace('\n', "\n    "));
+        }
+    }
+}
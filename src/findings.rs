@@ -0,0 +1,755 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::baseline;
+use crate::output::{self, FindingsFormat};
+use crate::similar::Suggestion;
+use crate::solidity::Visibility;
+use crate::source::{self, canonical, portable_path};
+
+/// How severe a finding is, in the vocabulary shared by SARIF, JUnit and GitHub. Ordered
+/// from most to least severe.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// What is wrong with the declaration or file a finding points at.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// Its name occurs nowhere but its own declaration.
+    Unused,
+    /// It is only called from functions that are themselves dead.
+    Unreachable,
+    /// The file imports itself through a chain of other files.
+    ImportCycle,
+    /// Its NatSpec is missing or doesn't document every parameter and return value.
+    MissingNatspec,
+    /// It is `virtual` without a body, and neither overridden nor called.
+    UnimplementedVirtual,
+    /// It is `virtual` without a body, and overridden but never called.
+    UncalledVirtual,
+    /// It is `virtual` with a body, and never overridden.
+    NeverOverridden,
+    /// Only deployment scripts use it, besides tests.
+    ScriptOnly,
+    /// Its body has statements that can never run.
+    UnreachableCode,
+    /// A local variable or parameter shadows a state variable, or a function redeclares
+    /// an inherited one without `override`.
+    Shadowing,
+    /// A parameter shadows the getter of an inherited public state variable.
+    ShadowedGetter,
+}
+
+impl FindingKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FindingKind::Unused => "unused",
+            FindingKind::Unreachable => "unreachable",
+            FindingKind::ImportCycle => "import_cycle",
+            FindingKind::MissingNatspec => "missing_natspec",
+            FindingKind::UnimplementedVirtual => "unimplemented_virtual",
+            FindingKind::UncalledVirtual => "uncalled_virtual",
+            FindingKind::NeverOverridden => "never_overridden",
+            FindingKind::ScriptOnly => "script_only",
+            FindingKind::UnreachableCode => "unreachable_code",
+            FindingKind::Shadowing => "shadowing",
+            FindingKind::ShadowedGetter => "shadowed_getter",
+        }
+    }
+
+    pub fn rule_id(self) -> &'static str {
+        match self {
+            FindingKind::Unused => "unused-function",
+            FindingKind::Unreachable => "unreachable-function",
+            FindingKind::ImportCycle => "import-cycle",
+            FindingKind::MissingNatspec => "missing-natspec",
+            FindingKind::UnimplementedVirtual => "unimplemented-virtual-function",
+            FindingKind::UncalledVirtual => "uncalled-virtual-function",
+            FindingKind::NeverOverridden => "never-overridden-virtual-function",
+            FindingKind::ScriptOnly => "script-only-function",
+            FindingKind::UnreachableCode => "unreachable-code",
+            FindingKind::Shadowing => "shadowed-declaration",
+            FindingKind::ShadowedGetter => "shadowed-getter",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            FindingKind::Unused => "Unused function",
+            FindingKind::Unreachable => "Unreachable function",
+            FindingKind::ImportCycle => "Import cycle",
+            FindingKind::MissingNatspec => "Missing NatSpec",
+            FindingKind::UnimplementedVirtual => "Unimplemented virtual function",
+            FindingKind::UncalledVirtual => "Uncalled virtual function",
+            FindingKind::NeverOverridden => "Virtual function never overridden",
+            FindingKind::ScriptOnly => "Function only used by scripts",
+            FindingKind::UnreachableCode => "Unreachable code",
+            FindingKind::Shadowing => "Shadowed declaration",
+            FindingKind::ShadowedGetter => "Shadowed getter",
+        }
+    }
+}
+
+/// A problem reported by an analysis, usually with a function.
+#[derive(Clone)]
+pub struct Finding {
+    pub file: PathBuf,
+    pub line: usize,
+    pub contract: Option<String>,
+    pub function: String,
+    /// The function's name and parameter types as written, e.g. `sweep(address)`.
+    pub signature: String,
+    /// `None` for symbols that aren't functions, e.g. those matched by `--pattern`.
+    pub visibility: Option<Visibility>,
+    pub kind: FindingKind,
+    /// What exactly is wrong, for kinds that need more than the symbol to explain, e.g.
+    /// the files of an import cycle.
+    pub detail: Option<String>,
+    /// For a function nothing references, a referenced identifier with a similar name.
+    pub suggestion: Option<Suggestion>,
+    /// How often the tests ran the function, with `--coverage`.
+    pub coverage_hits: Option<u64>,
+    /// The code of the function, see `Function::code`, which tells apart same-named
+    /// functions of same-named contracts, e.g. mocks. Empty for findings of other
+    /// declarations, which their contract and signature identify.
+    pub context: String,
+}
+
+impl Finding {
+    /// The fingerprint that baselines and SARIF identify the finding by. It depends on
+    /// neither the line nor the file, so moving a function, within its file or to
+    /// another, keeps it.
+    pub fn fingerprint(&self) -> String {
+        baseline::fingerprint(self.contract.as_deref(), &self.signature, self.kind, &self.context)
+    }
+
+    pub fn message(&self) -> String {
+        let subject = match self.visibility {
+            Some(visibility) => format!("{} function `{}`", visibility.as_str(), self.function),
+            None => format!("`{}`", self.function),
+        };
+        // The detail of a collapsed unused or unreachable finding lists the others.
+        let others = self
+            .detail
+            .as_ref()
+            .map_or_else(String::new, |signatures| format!(" Same for {}.", signatures));
+        let others = match &self.suggestion {
+            Some(suggestion) => format!(
+                "{} Did you mean `{}`? It is referenced {} time{}.",
+                others,
+                suggestion.name,
+                suggestion.references,
+                if suggestion.references == 1 { "" } else { "s" }
+            ),
+            None => others,
+        };
+        match self.kind {
+            FindingKind::Unused => format!("{} is never used.{}", subject, others),
+            FindingKind::Unreachable => format!("{} is only called by dead code.{}", subject, others),
+            FindingKind::ImportCycle => {
+                format!("Import cycle: {}.", self.detail.as_deref().unwrap_or_default())
+            }
+            FindingKind::MissingNatspec => format!(
+                "{} is not fully documented: {}.",
+                subject,
+                self.detail.as_deref().unwrap_or_default()
+            ),
+            FindingKind::UnimplementedVirtual => {
+                format!("{} is virtual without a body and never overridden.{}", subject, others)
+            }
+            FindingKind::UncalledVirtual => format!(
+                "{} is overridden in {} but never called.",
+                subject,
+                self.detail.as_deref().unwrap_or_default()
+            ),
+            FindingKind::NeverOverridden => {
+                format!("{} is virtual but never overridden; consider dropping `virtual`.", subject)
+            }
+            FindingKind::ScriptOnly => format!(
+                "{} is only used by deployment scripts ({}); it may be removable once deployed.",
+                subject,
+                self.detail.as_deref().unwrap_or_default()
+            ),
+            FindingKind::UnreachableCode => format!(
+                "{} has unreachable code: {}.",
+                subject,
+                self.detail.as_deref().unwrap_or_default()
+            ),
+            // The detail describes both declarations.
+            FindingKind::Shadowing | FindingKind::ShadowedGetter => {
+                format!("{}.", self.detail.as_deref().unwrap_or_default())
+            }
+        }
+    }
+}
+
+/// Findings collapsed by `dedupe`, represented by the first of them.
+#[derive(Serialize)]
+pub struct Collapsed {
+    pub file: PathBuf,
+    pub line: usize,
+    pub contract: Option<String>,
+    pub base_name: String,
+    pub kind: FindingKind,
+    /// The signatures of every collapsed finding, in order.
+    pub signatures: Vec<String>,
+    #[serde(skip)]
+    pub finding: Finding,
+}
+
+/// The name findings are collapsed by: without leading and trailing underscores and a
+/// trailing number, so that `_mint`, `mint_` and `mint2` all have the base name `mint`.
+pub fn base_name(name: &str) -> &str {
+    let name = name.trim_matches('_');
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit()).trim_end_matches('_');
+    if base.is_empty() {
+        name
+    } else {
+        base
+    }
+}
+
+/// Collapses the findings of one kind in the same file and contract whose functions share
+/// a base name, such as overloads, into the first of them, whose `detail` then lists the
+/// signatures of the others.
+pub fn dedupe(findings: &[Finding]) -> Vec<Collapsed> {
+    let mut collapsed: Vec<Collapsed> = Vec::new();
+    for finding in findings {
+        let base = base_name(&finding.function);
+        let group = collapsed.iter_mut().find(|group| {
+            group.file == finding.file
+                && group.contract == finding.contract
+                && group.kind == finding.kind
+                && group.base_name == base
+        });
+        match group {
+            Some(group) => group.signatures.push(finding.signature.clone()),
+            None => collapsed.push(Collapsed {
+                file: finding.file.clone(),
+                line: finding.line,
+                contract: finding.contract.clone(),
+                base_name: base.to_string(),
+                kind: finding.kind,
+                signatures: vec![finding.signature.clone()],
+                finding: finding.clone(),
+            }),
+        }
+    }
+    for group in &mut collapsed {
+        if group.signatures.len() > 1 {
+            let others: Vec<String> = group.signatures[1..]
+                .iter()
+                .map(|signature| format!("`{}`", signature))
+                .collect();
+            group.finding.detail = Some(others.join(", "));
+        }
+    }
+    collapsed
+}
+
+/// Severity of each kind of finding, configurable through the `[severity]` table of
+/// `wand.toml`. Unused functions are graded by visibility, since deleting a function that
+/// may be part of the ABI is riskier than deleting a private helper.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeverityPolicy {
+    pub external: Severity,
+    pub public: Severity,
+    pub internal: Severity,
+    pub private: Severity,
+    pub unreachable: Severity,
+    pub import_cycle: Severity,
+    pub missing_natspec: Severity,
+    pub unimplemented_virtual: Severity,
+    pub uncalled_virtual: Severity,
+    pub never_overridden: Severity,
+    pub script_only: Severity,
+    pub unreachable_code: Severity,
+    pub shadowing: Severity,
+    pub shadowed_getter: Severity,
+}
+
+impl Default for SeverityPolicy {
+    fn default() -> Self {
+        SeverityPolicy {
+            external: Severity::Note,
+            public: Severity::Note,
+            internal: Severity::Warning,
+            private: Severity::Warning,
+            unreachable: Severity::Error,
+            import_cycle: Severity::Error,
+            missing_natspec: Severity::Note,
+            unimplemented_virtual: Severity::Warning,
+            uncalled_virtual: Severity::Note,
+            never_overridden: Severity::Note,
+            script_only: Severity::Note,
+            unreachable_code: Severity::Error,
+            shadowing: Severity::Error,
+            shadowed_getter: Severity::Warning,
+        }
+    }
+}
+
+impl SeverityPolicy {
+    pub fn severity(&self, finding: &Finding) -> Severity {
+        match (finding.kind, finding.visibility) {
+            (FindingKind::Unreachable, _) => self.unreachable,
+            (FindingKind::Unused, Some(Visibility::External)) => self.external,
+            (FindingKind::Unused, Some(Visibility::Public)) => self.public,
+            // Symbols without a visibility are graded like internal functions.
+            (FindingKind::Unused, Some(Visibility::Internal) | None) => self.internal,
+            (FindingKind::Unused, Some(Visibility::Private)) => self.private,
+            (FindingKind::ImportCycle, _) => self.import_cycle,
+            (FindingKind::MissingNatspec, _) => self.missing_natspec,
+            (FindingKind::UnimplementedVirtual, _) => self.unimplemented_virtual,
+            (FindingKind::UncalledVirtual, _) => self.uncalled_virtual,
+            (FindingKind::NeverOverridden, _) => self.never_overridden,
+            (FindingKind::ScriptOnly, _) => self.script_only,
+            (FindingKind::UnreachableCode, _) => self.unreachable_code,
+            (FindingKind::Shadowing, _) => self.shadowing,
+            (FindingKind::ShadowedGetter, _) => self.shadowed_getter,
+        }
+    }
+}
+
+/// Fingerprints of findings suppressed elsewhere, e.g. dismissed in GitHub code
+/// scanning, which SARIF output marks as suppressed.
+#[derive(Default)]
+pub struct Suppressions {
+    path: PathBuf,
+    fingerprints: HashSet<String>,
+}
+
+impl Suppressions {
+    /// Reads a suppressions file: one fingerprint per line, with `#` starting a comment.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("Failed to read suppressions file {:?}: {}", path, e),
+            )
+        })?;
+        let fingerprints = content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        Ok(Suppressions {
+            path: path.to_path_buf(),
+            fingerprints,
+        })
+    }
+
+    /// Reads the suppressions file, if one is given.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        path.map_or_else(|| Ok(Suppressions::default()), Suppressions::read)
+    }
+
+    fn contains(&self, fingerprint: &str) -> bool {
+        self.fingerprints.contains(fingerprint)
+    }
+}
+
+/// The id of the base directory that SARIF file URIs are relative to, which GitHub code
+/// scanning resolves to the repository checkout.
+const SRCROOT: &str = "SRCROOT";
+
+/// The directory that SARIF file URIs are relative to: `relative_to` if given, or else
+/// the root of the git repository containing `root`, or `root` itself outside of one.
+pub fn uri_base(root: &Path, relative_to: Option<&Path>) -> PathBuf {
+    if let Some(dir) = relative_to {
+        return canonical(dir);
+    }
+    Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+        .map_or_else(|| canonical(root), |toplevel| canonical(&toplevel))
+}
+
+/// Renders findings in one of the CI formats, or returns `None` for text and JSON, whose
+/// reports are specific to each command. Only SARIF has a way to mark the suppressed
+/// findings; the other formats report them like any other. SARIF file URIs are relative
+/// to `uri_base`.
+pub fn render(
+    format: FindingsFormat,
+    findings: &[Finding],
+    policy: &SeverityPolicy,
+    suppressions: &Suppressions,
+    uri_base: &Path,
+) -> Result<Option<String>> {
+    Ok(match format {
+        FindingsFormat::Text | FindingsFormat::Json => None,
+        FindingsFormat::Sarif => Some(render_sarif(findings, policy, suppressions, uri_base)?),
+        FindingsFormat::Junit => Some(render_junit(findings, policy)),
+        FindingsFormat::Github => Some(render_github(findings, policy)),
+        FindingsFormat::Jsonl => Some(render_jsonl(findings, policy)?),
+    })
+}
+
+/// Renders findings as a SARIF 2.1.0 log, listing each file with findings once as an
+/// artifact. Files under `uri_base` are located relative to `SRCROOT`, and others by
+/// their absolute path. The run records the version of wand and how it was invoked.
+fn render_sarif(
+    findings: &[Finding],
+    policy: &SeverityPolicy,
+    suppressions: &Suppressions,
+    uri_base: &Path,
+) -> Result<String> {
+    let mut kinds = vec![FindingKind::Unused, FindingKind::Unreachable];
+    for finding in findings {
+        if !kinds.contains(&finding.kind) {
+            kinds.push(finding.kind);
+        }
+    }
+    let rules: Vec<_> = kinds
+        .iter()
+        .map(|kind| json!({ "id": kind.rule_id(), "shortDescription": { "text": kind.title() } }))
+        .collect();
+    let mut artifacts: Vec<serde_json::Value> = Vec::new();
+    let mut artifact_index = |file: &Path| {
+        let location = artifact_location(file, uri_base);
+        match artifacts.iter().position(|artifact| artifact["location"] == location) {
+            Some(index) => index,
+            None => {
+                artifacts.push(json!({ "location": location }));
+                artifacts.len() - 1
+            }
+        }
+    };
+    let results: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            let index = artifact_index(&finding.file);
+            let mut location = artifact_location(&finding.file, uri_base);
+            location["index"] = json!(index);
+            let fingerprint = finding.fingerprint();
+            let mut result = json!({
+                "ruleId": finding.kind.rule_id(),
+                "level": policy.severity(finding).as_str(),
+                "message": { "text": finding.message() },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": location,
+                        "region": { "startLine": finding.line },
+                    },
+                }],
+                "partialFingerprints": { "wand/v1": fingerprint },
+            });
+            if suppressions.contains(&fingerprint) {
+                result["suppressions"] = json!([{
+                    "kind": "external",
+                    "justification": format!("Listed in {}", portable_path(&suppressions.path)),
+                }]);
+            }
+            result
+        })
+        .collect();
+
+    output::to_json(&json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": { "name": "wand", "version": env!("CARGO_PKG_VERSION"), "rules": rules },
+            },
+            "invocations": [invocation()],
+            "originalUriBaseIds": { SRCROOT: { "uri": directory_uri(uri_base) } },
+            "artifacts": artifacts,
+            "results": results,
+        }],
+    }))
+}
+
+/// The SARIF invocation of this run: its command line, as the shell would take it back,
+/// the directory it ran in, and a warning for each directory that couldn't be read. The
+/// run completed, since it got to report.
+fn invocation() -> serde_json::Value {
+    let args: Vec<String> = std::env::args().collect();
+    let command_line: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
+    let mut invocation = json!({
+        "commandLine": command_line.join(" "),
+        "arguments": args.iter().skip(1).collect::<Vec<_>>(),
+        "executionSuccessful": true,
+    });
+    if let Ok(dir) = std::env::current_dir() {
+        invocation["workingDirectory"] = json!({ "uri": directory_uri(&dir) });
+    }
+    let skipped: Vec<_> = source::skipped_dirs()
+        .iter()
+        .map(|skipped| {
+            json!({
+                "level": "warning",
+                "message": { "text": format!("Skipped unreadable directory {}: {}", portable_path(&skipped.path), skipped.error) },
+            })
+        })
+        .collect();
+    if !skipped.is_empty() {
+        invocation["toolExecutionNotifications"] = json!(skipped);
+    }
+    invocation
+}
+
+/// `arg` in single quotes if the shell would split or expand it, e.g. `'src/**/*.sol'`.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// The SARIF location of `file`: its forward-slashed path relative to `uri_base`, or its
+/// absolute `file://` URI when it's elsewhere.
+fn artifact_location(file: &Path, uri_base: &Path) -> serde_json::Value {
+    let file = canonical(file);
+    match file.strip_prefix(uri_base) {
+        Ok(relative) => json!({ "uri": portable_path(relative), "uriBaseId": SRCROOT }),
+        Err(_) => json!({ "uri": file_uri(&file) }),
+    }
+}
+
+/// The `file://` URI of a directory, with the trailing slash SARIF requires of base URIs.
+fn directory_uri(dir: &Path) -> String {
+    let uri = file_uri(dir);
+    if uri.ends_with('/') {
+        uri
+    } else {
+        uri + "/"
+    }
+}
+
+fn file_uri(path: &Path) -> String {
+    let path = portable_path(path);
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        // A Windows path, e.g. `C:/repo`.
+        format!("file:///{}", path)
+    }
+}
+
+/// Renders findings as a JUnit XML report with one failed test case per finding.
+fn render_junit(findings: &[Finding], policy: &SeverityPolicy) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuites>\n  <testsuite name=\"wand\" tests=\"{}\" failures=\"{}\">\n",
+        findings.len(),
+        findings.len()
+    ));
+    for finding in findings {
+        let location = format!("{}:{}", finding.file.display(), finding.line);
+        out.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\">\n      <failure type=\"{}\" message=\"{}\">{}</failure>\n    </testcase>\n",
+            xml_escape(&finding.file.display().to_string()),
+            xml_escape(&finding.function),
+            policy.severity(finding).as_str(),
+            xml_escape(&finding.message()),
+            xml_escape(&location)
+        ));
+    }
+    out.push_str("  </testsuite>\n</testsuites>");
+    out
+}
+
+/// Renders findings as GitHub Actions workflow commands, which annotate the diff.
+fn render_github(findings: &[Finding], policy: &SeverityPolicy) -> String {
+    findings
+        .iter()
+        .map(|finding| {
+            let command = match policy.severity(finding) {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Note => "notice",
+            };
+            format!(
+                "::{} file={},line={},title={}::{}",
+                command,
+                escape_property(&finding.file.display().to_string()),
+                finding.line,
+                escape_property(finding.kind.title()),
+                escape_data(&finding.message())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A line of `--format jsonl`: a `meta` record first, then one record per finding or
+/// file that failed to analyze, and a `summary` last. Each line is a complete JSON
+/// object, so the lines written before a run aborts can still be read.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record<'a> {
+    /// The version of wand and the arguments it was run with.
+    Meta { version: &'static str, args: Vec<String> },
+    Finding {
+        file: &'a Path,
+        line: usize,
+        rule: &'static str,
+        severity: Severity,
+        contract: Option<&'a str>,
+        function: &'a str,
+        signature: &'a str,
+        message: String,
+        fingerprint: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        coverage_hits: Option<u64>,
+    },
+    Error { file: &'a Path, error: String },
+    Summary {
+        /// The files analyzed, when the command counts them.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        files: Option<usize>,
+        findings: usize,
+        errors: usize,
+        /// The directories that couldn't be read, whose files were left out.
+        skipped_dirs: usize,
+    },
+}
+
+impl<'a> Record<'a> {
+    pub fn meta() -> Self {
+        Record::Meta {
+            version: env!("CARGO_PKG_VERSION"),
+            args: std::env::args().skip(1).collect(),
+        }
+    }
+
+    pub fn finding(finding: &'a Finding, policy: &SeverityPolicy) -> Self {
+        Record::Finding {
+            file: &finding.file,
+            line: finding.line,
+            rule: finding.kind.rule_id(),
+            severity: policy.severity(finding),
+            contract: finding.contract.as_deref(),
+            function: &finding.function,
+            signature: &finding.signature,
+            message: finding.message(),
+            fingerprint: finding.fingerprint(),
+            coverage_hits: finding.coverage_hits,
+        }
+    }
+
+    /// The record as a single line of JSON, whatever `--pretty` is set to.
+    pub fn to_line(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Renders findings as JSON Lines all at once, for the commands that don't write the
+/// records as they go.
+fn render_jsonl(findings: &[Finding], policy: &SeverityPolicy) -> Result<String> {
+    let summary = Record::Summary {
+        files: None,
+        findings: findings.len(),
+        errors: 0,
+        skipped_dirs: source::skipped_dirs().len(),
+    };
+    std::iter::once(Record::meta())
+        .chain(findings.iter().map(|finding| Record::finding(finding, policy)))
+        .chain(std::iter::once(summary))
+        .map(|record| record.to_line())
+        .collect::<Result<Vec<_>>>()
+        .map(|lines| lines.join("\n"))
+}
+
+/// Escapes the message of a workflow command the way `@actions/core` does, so that a `%`
+/// or a line break in it doesn't end the command or turn into something else.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command, e.g. its `file`, in which `:` and `,` would
+/// otherwise end the value.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unused(file: &str, function: &str) -> Finding {
+        Finding {
+            file: PathBuf::from(file),
+            line: 3,
+            contract: Some("A".to_string()),
+            function: function.to_string(),
+            signature: format!("{}()", function),
+            visibility: Some(Visibility::Internal),
+            kind: FindingKind::Unused,
+            detail: None,
+            suggestion: None,
+            coverage_hits: None,
+            context: format!("function{}(){{}}", function),
+        }
+    }
+
+    #[test]
+    fn unreachable_code_is_an_error_by_default() {
+        let mut finding = unused("src/A.sol", "f");
+        finding.kind = FindingKind::UnreachableCode;
+        assert_eq!(SeverityPolicy::default().severity(&finding), Severity::Error);
+    }
+
+    #[test]
+    fn github_messages_are_escaped() {
+        let mut finding = unused("src/A.sol", "f");
+        finding.detail = Some("g() at 100%,\r\nh()".to_string());
+        assert_eq!(
+            render_github(&[finding], &SeverityPolicy::default()),
+            "::warning file=src/A.sol,line=3,title=Unused function::internal function `f` is never used. Same for g() at 100%25,%0D%0Ah()."
+        );
+    }
+
+    #[test]
+    fn github_properties_are_escaped() {
+        let rendered = render_github(&[unused("src/a,b:c%.sol", "f")], &SeverityPolicy::default());
+        assert!(rendered.starts_with("::warning file=src/a%2Cb%3Ac%25.sol,line=3,"), "{}", rendered);
+    }
+
+    #[test]
+    fn github_commands_stay_on_one_line_each() {
+        let mut finding = unused("src/A.sol", "f");
+        finding.detail = Some("a\nb".to_string());
+        let rendered = render_github(&[finding, unused("src/B.sol", "g")], &SeverityPolicy::default());
+        assert_eq!(rendered.lines().count(), 2, "{}", rendered);
+        assert!(rendered.lines().all(|line| line.starts_with("::")), "{}", rendered);
+    }
+}
@@ -0,0 +1,26 @@
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Whether `name` matches any of the `--ignore` patterns.
+pub fn should_ignore_function(name: &str, ignore_patterns: &[String]) -> bool {
+    matching_ignore_pattern(name, ignore_patterns).is_some()
+}
+
+/// Returns the first `--ignore` pattern that matches `name`. Invalid patterns match nothing.
+pub fn matching_ignore_pattern<'a>(name: &str, ignore_patterns: &'a [String]) -> Option<&'a str> {
+    ignore_patterns
+        .iter()
+        .find(|pattern| Regex::new(pattern).is_ok_and(|regex| regex.is_match(name)))
+        .map(String::as_str)
+}
+
+/// The patterns in `ignore_patterns` that match at least one of `names`, so that those
+/// matching nothing, e.g. because the code they were written for is gone, can be listed.
+/// Invalid patterns match nothing.
+pub fn matched_patterns<'a>(ignore_patterns: &'a [String], names: &[&str]) -> HashSet<&'a str> {
+    ignore_patterns
+        .iter()
+        .filter(|pattern| Regex::new(pattern).is_ok_and(|regex| names.iter().any(|name| regex.is_match(name))))
+        .map(String::as_str)
+        .collect()
+}
@@ -0,0 +1,355 @@
+use regex::Regex;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::io::Result;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::source::{canonical, Context, SourceFile};
+
+/// An import directive, e.g. `import {A} from "./A.sol";`.
+pub struct Import {
+    /// The imported path as written.
+    pub path: String,
+    pub line: usize,
+    /// Span of the whole directive.
+    pub span: (usize, usize),
+    /// The symbols renamed by the import, e.g. `IVault` as `IVaultV2` for
+    /// `import {IVault as IVaultV2} from "./IVault.sol";`.
+    pub aliases: Vec<Alias>,
+}
+
+/// A symbol imported under another name.
+pub struct Alias {
+    pub name: String,
+    pub alias: String,
+}
+
+fn alias_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\s*([A-Za-z_$][\w$]*)\s+as\s+([A-Za-z_$][\w$]*)\s*$").unwrap())
+}
+
+fn import_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r#"\bimport\s+(?:[^;"']*?\bfrom\s+)?["']([^"']+)["'][^;]*;"#).unwrap()
+    })
+}
+
+/// Finds the import directives of a file, skipping any inside comments or strings.
+pub fn parse_imports(source: &SourceFile) -> Vec<Import> {
+    import_pattern()
+        .captures_iter(&source.content)
+        .filter_map(|captures| {
+            let directive = captures.get(0)?;
+            if source.context_at(directive.start()) != Context::Code {
+                return None;
+            }
+            let symbols = directive
+                .as_str()
+                .split_once('{')
+                .and_then(|(_, rest)| rest.split_once('}'))
+                .map_or("", |(symbols, _)| symbols);
+            Some(Import {
+                path: captures[1].to_string(),
+                line: source.line_of(directive.start()),
+                span: (directive.start(), directive.end()),
+                aliases: symbols
+                    .split(',')
+                    .filter_map(|symbol| alias_pattern().captures(symbol))
+                    .map(|captures| Alias {
+                        name: captures[1].to_string(),
+                        alias: captures[2].to_string(),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// A Foundry-style remapping, `[context:]prefix=target`.
+struct Remapping {
+    context: String,
+    prefix: String,
+    target: String,
+}
+
+/// Resolves import paths to files, relative to the importing file, through the
+/// remappings of the project root, or from its `node_modules`.
+pub struct Resolver {
+    root: PathBuf,
+    remappings: Vec<Remapping>,
+}
+
+impl Resolver {
+    /// Creates a resolver for the project at `root`, reading `remappings.txt` if present.
+    pub fn new(root: &Path) -> Result<Self> {
+        let path = root.join("remappings.txt");
+        let content = if path.is_file() {
+            fs::read_to_string(path)?
+        } else {
+            String::new()
+        };
+
+        let remappings = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (key, target) = line.split_once('=')?;
+                let (context, prefix) = key.split_once(':').unwrap_or(("", key));
+                Some(Remapping {
+                    context: context.to_string(),
+                    prefix: prefix.to_string(),
+                    target: target.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(Resolver {
+            root: root.to_path_buf(),
+            remappings,
+        })
+    }
+
+    /// Returns the file imported by `import` from `importer`, if it exists.
+    pub fn resolve(&self, importer: &Path, import: &str) -> Option<PathBuf> {
+        let path = if import.starts_with("./") || import.starts_with("../") {
+            importer.parent().unwrap_or(Path::new("")).join(import)
+        } else {
+            let importer = importer.strip_prefix(&self.root).unwrap_or(importer);
+            // The longest matching prefix wins, and a matching context beats none.
+            let remapping = self
+                .remappings
+                .iter()
+                .filter(|remapping| {
+                    import.starts_with(&remapping.prefix)
+                        && importer.to_string_lossy().starts_with(&remapping.context)
+                })
+                .max_by_key(|remapping| (remapping.prefix.len(), remapping.context.len()));
+            match remapping {
+                Some(remapping) => self.root.join(format!(
+                    "{}{}",
+                    remapping.target,
+                    &import[remapping.prefix.len()..]
+                )),
+                None => {
+                    // Hardhat resolves package imports such as `@openzeppelin/...`
+                    // through `node_modules`.
+                    let path = self.root.join(import);
+                    let package = self.root.join("node_modules").join(import);
+                    if !path.is_file() && package.is_file() {
+                        package
+                    } else {
+                        path
+                    }
+                }
+            }
+        };
+
+        let path = normalize(&path);
+        path.is_file().then_some(path)
+    }
+}
+
+/// Removes `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// An import whose target could not be found.
+pub struct Unresolved {
+    pub file: PathBuf,
+    pub line: usize,
+    pub import: String,
+}
+
+/// The file-level import graph of a set of files.
+pub struct ImportGraph {
+    /// The analyzed files first, followed by imported files outside of them.
+    pub files: Vec<PathBuf>,
+    /// How many of `files` were analyzed; the rest are only known as import targets.
+    pub analyzed: usize,
+    /// `(importer, imported)` pairs of indices into `files`.
+    pub edges: BTreeSet<(usize, usize)>,
+    pub unresolved: Vec<Unresolved>,
+}
+
+impl ImportGraph {
+    pub fn build(files: &[PathBuf], resolver: &Resolver) -> Result<Self> {
+        let mut graph = ImportGraph {
+            files: files.to_vec(),
+            analyzed: files.len(),
+            edges: BTreeSet::new(),
+            unresolved: Vec::new(),
+        };
+        let mut index_of: HashMap<PathBuf, usize> = files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| (canonical(file), index))
+            .collect();
+
+        for (importer, file) in files.iter().enumerate() {
+            let source = SourceFile::read(file)?;
+            for import in parse_imports(&source) {
+                let Some(target) = resolver.resolve(file, &import.path) else {
+                    graph.unresolved.push(Unresolved {
+                        file: file.clone(),
+                        line: import.line,
+                        import: import.path,
+                    });
+                    continue;
+                };
+                let next = graph.files.len();
+                let imported = *index_of.entry(canonical(&target)).or_insert(next);
+                if imported == next {
+                    graph.files.push(target);
+                }
+                graph.edges.insert((importer, imported));
+            }
+        }
+
+        Ok(graph)
+    }
+
+    pub fn fan_in(&self, file: usize) -> usize {
+        self.edges.iter().filter(|&&(_, imported)| imported == file).count()
+    }
+
+    pub fn fan_out(&self, file: usize) -> usize {
+        self.edges.iter().filter(|&&(importer, _)| importer == file).count()
+    }
+
+    /// Returns one import chain per cycle, as file indices ending where they start.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut successors = vec![Vec::new(); self.files.len()];
+        for &(importer, imported) in &self.edges {
+            successors[importer].push(imported);
+        }
+
+        strongly_connected_components(&successors)
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || successors[component[0]].contains(&component[0])
+            })
+            .filter_map(|component| {
+                let start = *component.iter().min()?;
+                let mut chain = shortest_path_back(&successors, &component, start)?;
+                chain.insert(0, start);
+                Some(chain)
+            })
+            .collect()
+    }
+}
+
+/// Tarjan's algorithm over an adjacency list.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        successors: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        lowlink: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next);
+        state.lowlink[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for &successor in &state.successors[node] {
+            match state.index[successor] {
+                None => {
+                    visit(state, successor);
+                    state.lowlink[node] = state.lowlink[node].min(state.lowlink[successor]);
+                }
+                Some(index) if state.on_stack[successor] => {
+                    state.lowlink[node] = state.lowlink[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(state.lowlink[node]) == state.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let count = successors.len();
+    let mut state = State {
+        successors,
+        index: vec![None; count],
+        lowlink: vec![0; count],
+        on_stack: vec![false; count],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for node in 0..count {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+    state.components
+}
+
+/// Breadth-first search within `component` for the shortest path from `start` back to
+/// itself, excluding `start` at the front.
+fn shortest_path_back(
+    successors: &[Vec<usize>],
+    component: &[usize],
+    start: usize,
+) -> Option<Vec<usize>> {
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        for &successor in &successors[node] {
+            if !component.contains(&successor) {
+                continue;
+            }
+            if successor == start {
+                let mut path = vec![start];
+                let mut current = node;
+                while current != start {
+                    path.push(current);
+                    current = previous[&current];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if let Entry::Vacant(entry) = previous.entry(successor) {
+                entry.insert(node);
+                queue.push_back(successor);
+            }
+        }
+    }
+
+    None
+}
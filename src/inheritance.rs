@@ -0,0 +1,82 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::solidity::{self, Contract, SourceUnit};
+use crate::source::{canonical, collect_sol_files, SourceFile};
+
+/// How many levels of base contracts are searched, which also stops inheritance cycles.
+const MAX_INHERITANCE_DEPTH: usize = 32;
+
+/// A parsed file.
+pub struct Parsed {
+    pub source: SourceFile,
+    pub unit: SourceUnit,
+}
+
+/// Parses the analyzed files, followed by every other file under `root`, which is
+/// searched for their base contracts.
+pub fn load(analyzed_files: &[PathBuf], root: &Path) -> Result<Vec<Parsed>> {
+    let parse = |path: PathBuf, content: String| {
+        let source = SourceFile::new(path, content);
+        let unit = solidity::parse(&source);
+        Parsed { source, unit }
+    };
+    let mut files: Vec<Parsed> = analyzed_files
+        .iter()
+        .map(|path| Ok(parse(path.clone(), fs::read_to_string(path)?)))
+        .collect::<Result<_>>()?;
+    let seen: HashSet<PathBuf> = analyzed_files.iter().map(|path| canonical(path)).collect();
+    for path in collect_sol_files(root)? {
+        if !seen.contains(&canonical(&path)) {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            files.push(parse(path, content));
+        }
+    }
+    Ok(files)
+}
+
+/// The contracts of a set of files by name, for resolving base contracts.
+pub struct Hierarchy<'a> {
+    contracts: HashMap<&'a str, (usize, &'a Contract)>,
+}
+
+impl<'a> Hierarchy<'a> {
+    /// Indexes the contracts of `files`. The first declaration of each contract name wins,
+    /// so files loaded first are preferred.
+    pub fn new(files: &'a [Parsed]) -> Self {
+        let mut contracts: HashMap<&str, (usize, &Contract)> = HashMap::new();
+        for (index, file) in files.iter().enumerate() {
+            for contract in &file.unit.contracts {
+                contracts.entry(contract.name.as_str()).or_insert((index, contract));
+            }
+        }
+        Hierarchy { contracts }
+    }
+
+    /// Returns the base contracts of `contract` with the index of their file, nearest
+    /// first, each once.
+    pub fn ancestors(&self, contract: &Contract) -> Vec<(usize, &'a Contract)> {
+        let mut found: Vec<(usize, &Contract)> = Vec::new();
+        let mut seen: HashSet<&str> = HashSet::from([contract.name.as_str()]);
+        let mut level: Vec<&str> = contract.bases.iter().map(String::as_str).collect();
+        for _ in 0..MAX_INHERITANCE_DEPTH {
+            let mut next = Vec::new();
+            for name in level {
+                if !seen.insert(name) {
+                    continue;
+                }
+                if let Some(&(file, base)) = self.contracts.get(name) {
+                    found.push((file, base));
+                    next.extend(base.bases.iter().map(String::as_str));
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            level = next;
+        }
+        found
+    }
+}
@@ -1,27 +1,222 @@
-use clap::{Parser, Subcommand};
-use std::io::Result;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Error, Result};
 
+mod abi;
+mod accessors;
+mod archive;
+mod baseline;
+mod blame;
+mod body;
+mod broadcast;
+mod callgraph;
 mod commands;
+mod config;
+mod coverage;
+mod etherscan;
+mod exclusions;
+mod explain;
+mod findings;
+mod ignore;
+mod imports;
+mod inheritance;
+mod natspec;
+mod offchain;
+mod output;
+mod plaintext;
+mod progress;
+mod project;
+mod proxy;
+mod references;
+mod signatures;
+mod similar;
+mod solidity;
+mod source;
+mod timing;
+mod unreachable;
+mod vyper;
+mod wandignore;
+mod workspace;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Pretty-print JSON and SARIF reports with indentation instead of compact JSON. Has no effect
+    /// on `text` reports, and `jsonl` records stay one per line.
+    #[arg(long, global = true)]
+    pretty: bool,
+
+    /// When to color text reports: `auto` when stdout is a terminal, `always`, or `never`.
+    #[arg(long, value_name = "WHEN", global = true, default_value = "auto")]
+    color: output::ColorChoice,
+
+    /// Never pipe text reports through `$PAGER`, which they otherwise are when stdout is
+    /// a terminal.
+    #[arg(long, global = true)]
+    no_pager: bool,
+
+    /// Number of threads to analyze files with, one per CPU by default.
+    #[arg(long, value_name = "N", global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Print every setting of the command, its effective value and where it comes from,
+    /// and every problem of the configuration, instead of running the command.
+    #[arg(long, global = true)]
+    explain_config: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Finds dead/unused code within a Solidity project.
     Vacuum(commands::vacuum::VacuumArgs),
+    /// Exports the function-level call graph of a Solidity project.
+    Graph(commands::graph::GraphArgs),
+    /// Finds duplicate and near-duplicate function implementations.
+    Dupes(commands::dupes::DupesArgs),
+    /// Lists function selectors and detects selector collisions.
+    Selectors(commands::selectors::SelectorsArgs),
+    /// Reports the import graph of a Solidity project, including cycles and orphans.
+    Deps(commands::deps::DepsArgs),
+    /// Reports size and composition metrics of a Solidity project.
+    Stats(commands::stats::StatsArgs),
+    /// Generates an interface from a contract's external and public functions.
+    Interface(commands::interface::InterfaceArgs),
+    /// Reports external and public functions with missing or incomplete NatSpec.
+    Natspec(commands::natspec::NatspecArgs),
+    /// Creates, trims and lists baselines of known unused functions.
+    Baseline(commands::baseline::BaselineArgs),
+    /// Runs the analyses configured in wand.toml as a single CI gate.
+    Check(commands::check::CheckArgs),
+    /// Reports state variables shadowed by parameters or locals, and missing overrides.
+    Shadow(commands::shadow::ShadowArgs),
+    /// Lists every reference to a function, counted by the same rules as vacuum.
+    Usages(commands::usages::UsagesArgs),
+    /// Renames a function, its overrides and every reference to them.
+    Rename(commands::rename::RenameArgs),
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let args = expand_argfiles(std::env::args_os())?;
+    let parsed = Cli::command()
+        .try_get_matches_from(&args)
+        .and_then(|matches| Ok((Cli::from_arg_matches(&matches)?, matches)));
+    let (mut cli, matches) = match parsed {
+        Ok((cli, matches)) if !cli.explain_config => (cli, matches),
+        // Problems with the arguments are listed with the others of the configuration.
+        Err(e) if !e.use_stderr() || !explain::requested(&args) => e.exit(),
+        _ => return explain::run(Cli::command(), &args),
+    };
+    if let (Commands::Vacuum(vacuum), Some(("vacuum", matches))) = (&mut cli.command, matches.subcommand()) {
+        vacuum.note_defaults(matches);
+    }
+    output::set_pretty(cli.pretty);
+    output::set_color(cli.color);
+    output::set_paging(!cli.no_pager);
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.into())
+            .build_global()
+            .map_err(Error::other)?;
+    }
+
+    let result = match cli.command {
+        Commands::Vacuum(args) => commands::vacuum::run(args),
+        Commands::Graph(args) => commands::graph::run(args),
+        Commands::Dupes(args) => commands::dupes::run(args),
+        Commands::Selectors(args) => commands::selectors::run(args),
+        Commands::Deps(args) => commands::deps::run(args),
+        Commands::Stats(args) => commands::stats::run(args),
+        Commands::Interface(args) => commands::interface::run(args),
+        Commands::Natspec(args) => commands::natspec::run(args),
+        Commands::Baseline(args) => commands::baseline::run(args),
+        Commands::Check(args) => commands::check::run(args),
+        Commands::Shadow(args) => commands::shadow::run(args),
+        Commands::Usages(args) => commands::usages::run(args),
+        Commands::Rename(args) => commands::rename::run(args),
+    };
+    // Whatever the command didn't report itself, e.g. when it failed.
+    source::warn_skipped_dirs(&source::take_skipped_dirs());
+    result
+}
+
+/// Replaces every `@path` argument with the lines of the named file, one argument per
+/// line. A leading `@@` passes a literal argument starting with `@`.
+fn expand_argfiles(mut args: impl Iterator<Item = OsString>) -> Result<Vec<OsString>> {
+    let mut expanded: Vec<OsString> = args.next().into_iter().collect();
+
+    for arg in args {
+        match arg.to_str() {
+            Some(escaped) if escaped.starts_with("@@") => expanded.push(escaped[1..].into()),
+            Some(argfile) if argfile.len() > 1 && argfile.starts_with('@') => {
+                let path = &argfile[1..];
+                let content = fs::read_to_string(path).map_err(|e| {
+                    Error::new(e.kind(), format!("Failed to read argfile {:?}: {}", path, e))
+                })?;
+                expanded.extend(
+                    content
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(OsString::from),
+                );
+            }
+            _ => expanded.push(arg),
+        }
+    }
 
-    match cli.command {
-        Commands::Vacuum(args) => commands::vacuum::run(args)?,
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(args: &[&str]) -> Result<Vec<String>> {
+        let args = args.iter().map(OsString::from);
+        Ok(expand_argfiles(args)?.into_iter().map(|arg| arg.into_string().unwrap()).collect())
+    }
+
+    #[test]
+    fn argfiles_are_replaced_by_their_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let argfile = dir.path().join("wand.args");
+        fs::write(&argfile, "vacuum\nsrc/My Vault.sol\n\n   \n--ignore\n^_\n").unwrap();
+        let argfile = format!("@{}", argfile.display());
+        assert_eq!(
+            expand(&["wand", &argfile, "--no-pager"]).unwrap(),
+            ["wand", "vacuum", "src/My Vault.sol", "--ignore", "^_", "--no-pager"]
+        );
+    }
+
+    #[test]
+    fn argfile_lines_are_taken_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let argfile = dir.path().join("wand.args");
+        fs::write(&argfile, "# not a comment\n@nested.args\n  --root  \n").unwrap();
+        let argfile = format!("@{}", argfile.display());
+        assert_eq!(expand(&["wand", &argfile]).unwrap(), ["wand", "# not a comment", "@nested.args", "  --root  "]);
+    }
+
+    #[test]
+    fn a_doubled_at_sign_escapes_an_argument() {
+        assert_eq!(expand(&["wand", "@@foo", "@", "a@b"]).unwrap(), ["wand", "@foo", "@", "a@b"]);
     }
 
-    Ok(())
+    #[test]
+    fn the_program_name_is_never_expanded() {
+        assert_eq!(expand(&["@wand"]).unwrap(), ["@wand"]);
+    }
+
+    #[test]
+    fn a_missing_argfile_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.args");
+        let error = expand(&["wand", &format!("@{}", missing.display())]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().contains("Failed to read argfile"), "{}", error);
+        assert!(error.to_string().contains("missing.args"), "{}", error);
+    }
 }
@@ -0,0 +1,88 @@
+use crate::source::SourceFile;
+
+/// The NatSpec comment written directly above a declaration: a run of `///` lines or a
+/// `/** */` block.
+pub struct NatSpec {
+    /// Offset of the first comment.
+    pub start: usize,
+    comments: Vec<String>,
+}
+
+impl NatSpec {
+    /// Returns the NatSpec directly preceding `offset`, if there is any. Plain `//` and
+    /// `/* */` comments end the run.
+    pub fn before(source: &SourceFile, offset: usize) -> Option<NatSpec> {
+        let spans: Vec<(usize, usize)> = source
+            .preceding_comments(offset)
+            .into_iter()
+            .rev()
+            .take_while(|&(start, end)| is_doc_comment(&source.content[start..end]))
+            .collect();
+        let start = spans.last()?.0;
+        Some(NatSpec {
+            start,
+            comments: spans
+                .iter()
+                .rev()
+                .map(|&(start, end)| source.content[start..end].to_string())
+                .collect(),
+        })
+    }
+
+    /// Returns the comment lines as they would be written at the top level: each `///`
+    /// line trimmed, and the interior lines of a block indented by one space.
+    pub fn formatted(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for comment in &self.comments {
+            for (index, line) in comment.lines().enumerate() {
+                if index == 0 {
+                    lines.push(line.trim().to_string());
+                } else {
+                    lines.push(format!(" {}", line.trim()));
+                }
+            }
+        }
+        lines
+    }
+
+    /// Names documented by `@param` tags.
+    pub fn params(&self) -> Vec<&str> {
+        self.tags("@param")
+            .filter_map(|text| text.split_whitespace().next())
+            .collect()
+    }
+
+    /// Number of `@return` tags.
+    pub fn returns(&self) -> usize {
+        self.tags("@return").count()
+    }
+
+    /// The contract named by an `@inheritdoc` tag.
+    pub fn inheritdoc(&self) -> Option<&str> {
+        self.tags("@inheritdoc")
+            .find_map(|text| text.split_whitespace().next())
+    }
+
+    /// Returns the text following each occurrence of `tag` at the start of a line.
+    fn tags<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.comments
+            .iter()
+            .flat_map(|comment| comment.lines())
+            .map(|line| {
+                line.trim()
+                    .trim_start_matches("///")
+                    .trim_start_matches("/**")
+                    .trim_start_matches('*')
+                    .trim_start()
+            })
+            .filter_map(move |line| {
+                let rest = line.strip_prefix(tag)?;
+                (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest.trim())
+            })
+    }
+}
+
+fn is_doc_comment(comment: &str) -> bool {
+    (comment.starts_with("///") && !comment.starts_with("////"))
+        || (comment.starts_with("/**") && comment != "/**/" && !comment.starts_with("/***"))
+}
@@ -0,0 +1,233 @@
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::exclusions::PathExclusions;
+use crate::source::{collect_searched_files, Context, SourceFile};
+
+/// Directories holding dependencies or generated code, whose mentions of a function
+/// aren't calls made by the project. TypeChain bindings mention every function.
+const SKIPPED_DIRS: [&str; 5] = ["node_modules", "artifacts", "cache", "typechain", "typechain-types"];
+
+/// Off-chain languages whose sources can be searched for usages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum UsageLang {
+    /// `.js`, `.jsx`, `.mjs` and `.cjs` files.
+    Js,
+    /// `.ts`, `.tsx`, `.mts` and `.cts` files.
+    Ts,
+}
+
+impl UsageLang {
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            UsageLang::Js => &["js", "jsx", "mjs", "cjs"],
+            UsageLang::Ts => &["ts", "tsx", "mts", "cts"],
+        }
+    }
+}
+
+/// How an off-chain source refers to a function.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OffchainKind {
+    /// A method call on a contract object, e.g. `vault.sweep(to)`.
+    MethodCall,
+    /// A viem `functionName: 'sweep'` property.
+    FunctionName,
+    /// The name or signature in a string, e.g. `encodeFunctionData('sweep', ...)` or a
+    /// human-readable ABI entry `'function sweep(address)'`.
+    AbiString,
+}
+
+impl OffchainKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            OffchainKind::MethodCall => "method call",
+            OffchainKind::FunctionName => "functionName",
+            OffchainKind::AbiString => "ABI string",
+        }
+    }
+}
+
+/// A reference to a function from a JavaScript or TypeScript source.
+#[derive(Serialize)]
+pub struct OffchainReference {
+    pub file: PathBuf,
+    pub line: usize,
+    pub snippet: String,
+    pub kind: OffchainKind,
+}
+
+/// The patterns of each kind of reference, capturing the name, from the most specific to
+/// the least. Identifiers may contain `$`, as in JavaScript and Solidity.
+fn patterns() -> &'static [(OffchainKind, Regex); 3] {
+    static PATTERNS: OnceLock<[(OffchainKind, Regex); 3]> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (
+                OffchainKind::FunctionName,
+                r#"\bfunctionName\s*:\s*['"`]([a-zA-Z_$][a-zA-Z0-9_$]*)['"`]"#,
+            ),
+            (OffchainKind::MethodCall, r"\.\s*([a-zA-Z_$][a-zA-Z0-9_$]*)\s*\("),
+            (
+                OffchainKind::AbiString,
+                r#"['"`](?:function\s+)?([a-zA-Z_$][a-zA-Z0-9_$]*)(?:\s*\(|['"`])"#,
+            ),
+        ]
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).unwrap()))
+    })
+}
+
+/// A match of one of the `patterns`, in the source at `source`.
+struct Match {
+    kind: OffchainKind,
+    source: usize,
+    start: usize,
+}
+
+/// The off-chain sources searched for usages, read once before the analysis, with the
+/// references found in them by name, so that each function is looked up rather than
+/// searched for.
+#[derive(Default)]
+pub struct OffchainSources {
+    sources: Vec<SourceFile>,
+    matches: HashMap<String, Vec<Match>>,
+}
+
+impl OffchainSources {
+    /// Reads the sources of the given languages under `root` that aren't `excluded`,
+    /// skipping dependencies, generated code and declaration files.
+    pub fn load(root: &Path, langs: &[UsageLang], excluded: &PathExclusions) -> Result<Self> {
+        if langs.is_empty() {
+            return Ok(OffchainSources::default());
+        }
+
+        let extensions: Vec<&str> = langs.iter().flat_map(|lang| lang.extensions()).copied().collect();
+        let sources: Vec<SourceFile> = collect_searched_files(root, &extensions, &SKIPPED_DIRS, excluded)?
+            .into_iter()
+            .filter(|path| !path.file_name().unwrap_or_default().to_string_lossy().ends_with(".d.ts"))
+            .map(|path| {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                SourceFile::new(path, content)
+            })
+            .collect();
+
+        let mut matches: HashMap<String, Vec<Match>> = HashMap::new();
+        for (index, source) in sources.iter().enumerate() {
+            // A `functionName: 'sweep'` property also matches as an ABI string, so each
+            // match only counts if it doesn't overlap an earlier, more specific one.
+            let mut matched: Vec<(usize, usize)> = Vec::new();
+            for (kind, pattern) in patterns() {
+                for captures in pattern.captures_iter(&source.content) {
+                    let (found, name) = (captures.get(0).unwrap(), &captures[1]);
+                    if matched.iter().any(|&(start, end)| found.start() < end && start < found.end()) {
+                        continue;
+                    }
+                    // JavaScript comments are written like Solidity's, and a commented-out
+                    // call calls nothing.
+                    if source.context_at(found.start()) == Context::Comment {
+                        continue;
+                    }
+                    matched.push((found.start(), found.end()));
+                    matches.entry(name.to_string()).or_default().push(Match {
+                        kind: *kind,
+                        source: index,
+                        start: found.start(),
+                    });
+                }
+            }
+        }
+
+        Ok(OffchainSources { sources, matches })
+    }
+
+    /// Finds the references to the function `name`. Matches are word-boundary aware but
+    /// otherwise pattern-based, so a method of another object with the same name counts too.
+    pub fn references(&self, name: &str) -> Vec<OffchainReference> {
+        let Some(matches) = self.matches.get(name) else {
+            return Vec::new();
+        };
+        let mut references: Vec<OffchainReference> = matches
+            .iter()
+            .map(|found| {
+                let source = &self.sources[found.source];
+                let line = source.line_of(found.start);
+                OffchainReference {
+                    file: source.path.clone(),
+                    line,
+                    snippet: source.line_text(line).trim().to_string(),
+                    kind: found.kind,
+                }
+            })
+            .collect();
+        references.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        references
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(files: &[(&str, &str)]) -> (tempfile::TempDir, OffchainSources) {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let sources = OffchainSources::load(dir.path(), &[UsageLang::Js, UsageLang::Ts], &PathExclusions::default()).unwrap();
+        (dir, sources)
+    }
+
+    fn kinds(sources: &OffchainSources, name: &str) -> Vec<(usize, OffchainKind)> {
+        sources.references(name).iter().map(|reference| (reference.line, reference.kind)).collect()
+    }
+
+    #[test]
+    fn each_kind_of_reference_is_found() {
+        let (_dir, sources) = load(&[(
+            "scripts/ops.ts",
+            "await vault.sweep(to);\nconst data = encodeFunctionData({ abi, functionName: 'pause' });\nconst abi = ['function rescue(address)'];\n",
+        )]);
+        assert_eq!(kinds(&sources, "sweep"), [(1, OffchainKind::MethodCall)]);
+        assert_eq!(kinds(&sources, "pause"), [(2, OffchainKind::FunctionName)]);
+        assert_eq!(kinds(&sources, "rescue"), [(3, OffchainKind::AbiString)]);
+    }
+
+    #[test]
+    fn names_are_matched_whole() {
+        let (_dir, sources) = load(&[("app.js", "vault.sweepAll();\nvault.$sweep();\nconst name = 'sweeper';\n")]);
+        assert!(sources.references("sweep").is_empty());
+        assert_eq!(kinds(&sources, "$sweep"), [(2, OffchainKind::MethodCall)]);
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let (_dir, sources) = load(&[(
+            "app.js",
+            "// vault.sweep(to);\n/* functionName: 'sweep' */\n/**\n * 'function sweep(address)'\n */\nvault.pause(); // not vault.sweep()\n",
+        )]);
+        assert!(sources.references("sweep").is_empty());
+        assert_eq!(kinds(&sources, "pause"), [(6, OffchainKind::MethodCall)]);
+    }
+
+    #[test]
+    fn dependencies_and_declarations_are_skipped() {
+        let (_dir, sources) = load(&[
+            ("node_modules/lib/index.js", "vault.sweep();\n"),
+            ("typechain-types/Vault.ts", "vault.sweep();\n"),
+            ("types/vault.d.ts", "sweep(to: string): Promise<void>;\nvault.sweep();\n"),
+            ("src/app.tsx", "vault.sweep();\n"),
+        ]);
+        let files: Vec<PathBuf> = sources.references("sweep").into_iter().map(|reference| reference.file).collect();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("src/app.tsx"));
+    }
+}
@@ -0,0 +1,168 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, IsTerminal, Result, Write};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Whether JSON reports are pretty-printed, set from the global `--pretty` flag.
+static PRETTY: AtomicBool = AtomicBool::new(false);
+
+/// Whether text reports are colored, resolved from the global `--color` flag.
+static COLORED: AtomicBool = AtomicBool::new(false);
+
+/// Whether text reports may be paged, cleared by the global `--no-pager` flag.
+static PAGING: AtomicBool = AtomicBool::new(true);
+
+/// The pager stdout is piped through, once `page` started it.
+static PAGER: Mutex<Option<Child>> = Mutex::new(None);
+
+/// The pager run when `$PAGER` isn't set.
+pub const DEFAULT_PAGER: &str = "less -R";
+
+/// When text reports are colored with ANSI escapes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// When stdout is a terminal.
+    Auto,
+    /// Always, e.g. to replay a captured report with `less -R`.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// Report formats shared by the analysis commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable, colored report.
+    Text,
+    /// A single JSON document.
+    Json,
+}
+
+/// Report formats of the commands that produce findings, including those read by CI.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingsFormat {
+    /// Human-readable, colored report.
+    Text,
+    /// A single JSON document.
+    Json,
+    /// A SARIF 2.1.0 log, e.g. for GitHub code scanning.
+    Sarif,
+    /// A JUnit XML report with one failed test case per finding.
+    Junit,
+    /// GitHub Actions workflow commands that annotate the changed files.
+    Github,
+    /// JSON Lines: a `meta` record, one record per finding and a `summary`, each on its
+    /// own line. `vacuum` writes each file's findings as soon as it is analyzed.
+    Jsonl,
+}
+
+pub fn set_pretty(pretty: bool) {
+    PRETTY.store(pretty, Ordering::Relaxed);
+}
+
+pub fn set_color(choice: ColorChoice) {
+    let colored = match choice {
+        ColorChoice::Auto => std::io::stdout().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    COLORED.store(colored, Ordering::Relaxed);
+}
+
+pub fn colored() -> bool {
+    COLORED.load(Ordering::Relaxed)
+}
+
+/// The ANSI escape `code`, or nothing unless text reports are colored.
+pub fn ansi(code: &'static str) -> &'static str {
+    if colored() {
+        code
+    } else {
+        ""
+    }
+}
+
+pub fn set_paging(paging: bool) {
+    PAGING.store(paging, Ordering::Relaxed);
+}
+
+/// Pipes the rest of stdout through `$PAGER`, or `less -R`, when stdout is a terminal,
+/// like git does. Unless `LESS` is set, less runs with `FRX`, so that a report that fits
+/// on the screen is printed as is, with its colors. An empty `PAGER` or `cat` turns
+/// paging off, and so does failing to start the pager.
+pub fn page() {
+    if !PAGING.load(Ordering::Relaxed) || !std::io::stdout().is_terminal() {
+        return;
+    }
+    let command = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    if matches!(command.trim(), "" | "cat") {
+        return;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::fd::AsRawFd;
+        use std::process::{Command, Stdio};
+
+        let mut pager = Command::new("sh");
+        pager.arg("-c").arg(&command).stdin(Stdio::piped());
+        if std::env::var_os("LESS").is_none() {
+            pager.env("LESS", "FRX");
+        }
+        let mut child = match pager.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Warning: failed to start pager {:?}: {}", command, e);
+                return;
+            }
+        };
+        let Some(input) = child.stdin.take() else {
+            return;
+        };
+        // SAFETY: both file descriptors are open, and stdout has nothing buffered yet.
+        // Quitting the pager before the end of the report ends wand like it would git,
+        // instead of failing the next write.
+        unsafe {
+            libc::dup2(input.as_raw_fd(), libc::STDOUT_FILENO);
+            libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        }
+        *PAGER.lock().unwrap_or_else(|e| e.into_inner()) = Some(child);
+    }
+}
+
+/// Whether stdout is piped through the pager started by `page`.
+pub fn paged() -> bool {
+    PAGER.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Closes the pipe to the pager started by `page`, if any, and waits for the user to
+/// quit it, so that whatever is printed next isn't drawn over the report.
+pub fn end_pager() {
+    let Some(mut child) = PAGER.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    let _ = std::io::stdout().flush();
+    #[cfg(unix)]
+    // SAFETY: nothing is printed to stdout once the pager is done with it.
+    unsafe {
+        libc::close(libc::STDOUT_FILENO);
+    }
+    let _ = child.wait();
+}
+
+/// Serializes a report as JSON, compact unless `--pretty` is set.
+pub fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    if PRETTY.load(Ordering::Relaxed) {
+        to_pretty_json(value)
+    } else {
+        serde_json::to_string(value).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Serializes a file that is kept in the repository as indented JSON, so that it diffs
+/// well whatever `--pretty` is set to.
+pub fn to_pretty_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
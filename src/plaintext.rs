@@ -0,0 +1,72 @@
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::exclusions::PathExclusions;
+use crate::source::{collect_searched_files, SourceFile};
+
+/// Directories holding dependencies or build output, whose mentions aren't the project's.
+const SKIPPED_DIRS: [&str; 5] = ["lib", "node_modules", "out", "cache", "artifacts"];
+
+/// A mention of a function in a file searched with `--search-ext`.
+#[derive(Serialize, Clone)]
+pub struct TextReference {
+    pub file: PathBuf,
+    pub line: usize,
+    pub snippet: String,
+    /// The file's extension, e.g. `yaml`.
+    pub file_type: String,
+}
+
+/// Files of other types searched for function names as plain words, e.g. keeper configs
+/// or runbooks.
+#[derive(Default)]
+pub struct TextSources {
+    sources: Vec<(String, SourceFile)>,
+}
+
+impl TextSources {
+    /// Reads the files with the given extensions under `root` that aren't `excluded`,
+    /// skipping dependencies and build output. Extensions may be given with or without the
+    /// leading dot.
+    pub fn load(root: &Path, extensions: &[String], excluded: &PathExclusions) -> Result<Self> {
+        if extensions.is_empty() {
+            return Ok(TextSources::default());
+        }
+
+        let extensions: Vec<&str> = extensions.iter().map(|ext| ext.trim_start_matches('.')).collect();
+        let sources = collect_searched_files(root, &extensions, &SKIPPED_DIRS, excluded)?
+            .into_iter()
+            .map(|path| {
+                let ext = path.extension().unwrap_or_default().to_string_lossy().into_owned();
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                (ext, SourceFile::new(path, content))
+            })
+            .collect();
+        Ok(TextSources { sources })
+    }
+
+    /// Finds every occurrence of `name` as a whole word, wherever it is in the file.
+    pub fn references(&self, name: &str) -> Vec<TextReference> {
+        if self.sources.is_empty() {
+            return Vec::new();
+        }
+
+        let pattern = Regex::new(&format!(r"\b{}\b", regex::escape(name))).unwrap();
+        let mut references = Vec::new();
+        for (ext, source) in &self.sources {
+            for found in pattern.find_iter(&source.content) {
+                let line = source.line_of(found.start());
+                references.push(TextReference {
+                    file: source.path.clone(),
+                    line,
+                    snippet: source.line_text(line).trim().to_string(),
+                    file_type: ext.clone(),
+                });
+            }
+        }
+        references
+    }
+}
@@ -0,0 +1,56 @@
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::output;
+
+/// Whether a progress bar may be drawn, set by `vacuum` for text reports unless
+/// `--no-progress` is passed.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The bar, the files done out of all of them, how many are analyzed per second and the
+/// estimated time left at that rate, which is measured over every thread at once.
+const TEMPLATE: &str = "{bar:40} {pos}/{len} files, {rate}, ETA {eta} ({msg})";
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// A progress bar over `len` files, drawn on stderr and cleared once finished, or a hidden
+/// one unless it's enabled and stderr is a terminal. A paged report hides it too, since
+/// it would be drawn over the pager.
+pub fn files(len: usize) -> ProgressBar {
+    if !shown() {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(len as u64);
+    let style = ProgressStyle::with_template(TEMPLATE).expect("the progress template is valid");
+    let style = style.with_key("rate", |state: &ProgressState, out: &mut dyn fmt::Write| {
+        let _ = write!(out, "{:.1}/s", state.per_sec());
+    });
+    bar.set_style(style.progress_chars("=> "));
+    bar.set_message(format!("{} thread(s)", rayon::current_num_threads()));
+    bar
+}
+
+fn shown() -> bool {
+    ENABLED.load(Ordering::Relaxed) && std::io::stderr().is_terminal() && !output::paged()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_template_is_valid() {
+        assert!(ProgressStyle::with_template(TEMPLATE).is_ok());
+    }
+
+    #[test]
+    fn the_bar_is_hidden_unless_stderr_is_a_terminal() {
+        enable();
+        let bar = files(10);
+        assert_eq!(bar.is_hidden(), !std::io::stderr().is_terminal());
+    }
+}
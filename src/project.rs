@@ -0,0 +1,211 @@
+use clap::ValueEnum;
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::source::{canonical, collect_sol_files, dedup_files};
+
+/// The configuration files a Hardhat project may have.
+const HARDHAT_CONFIGS: [&str; 4] = [
+    "hardhat.config.js",
+    "hardhat.config.ts",
+    "hardhat.config.cjs",
+    "hardhat.config.mjs",
+];
+
+/// Directories of a Hardhat project searched for usages, relative to its root.
+const HARDHAT_SEARCH_DIRS: [&str; 3] = ["contracts", "test", "scripts"];
+
+/// Build output of a Hardhat project, which is never analyzed.
+const HARDHAT_OUTPUT_DIRS: [&str; 2] = ["artifacts", "cache"];
+
+/// How a project's sources are laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// `foundry.toml`, with sources in `src`.
+    Foundry,
+    /// `hardhat.config.js` or `.ts`, with sources in `contracts`.
+    Hardhat,
+}
+
+impl Layout {
+    fn name(self) -> &'static str {
+        match self {
+            Layout::Foundry => "Foundry",
+            Layout::Hardhat => "Hardhat",
+        }
+    }
+
+    fn sources(self) -> &'static str {
+        match self {
+            Layout::Foundry => "src",
+            Layout::Hardhat => "contracts",
+        }
+    }
+}
+
+/// The project at a search root, which decides the default path to analyze and where
+/// usages are searched for.
+#[derive(Debug)]
+pub struct Project {
+    pub root: PathBuf,
+    /// `None` when the root has neither a `foundry.toml` nor a Hardhat config.
+    pub layout: Option<Layout>,
+    /// Other directories searched for usages, e.g. the packages of a monorepo that use
+    /// this one.
+    pub usage_roots: Vec<PathBuf>,
+    /// Solidity files larger than this many bytes, e.g. flattened builds, are neither
+    /// analyzed nor searched for usages.
+    pub max_file_size: Option<u64>,
+}
+
+impl Project {
+    /// Detects the layout of the project at `root`, unless `layout` is given. When both a
+    /// `foundry.toml` and a Hardhat config are present, `layout` wins, then the layout
+    /// whose sources directory contains `path`, then Foundry, and the choice is printed
+    /// to stderr.
+    pub fn detect(root: &Path, path: Option<&Path>, layout: Option<Layout>) -> Project {
+        let foundry = root.join("foundry.toml").is_file();
+        let hardhat = HARDHAT_CONFIGS
+            .iter()
+            .find(|config| root.join(config).is_file());
+
+        let layout = match (foundry, hardhat) {
+            (true, Some(config)) => {
+                let within = |layout: Layout| {
+                    path.is_some_and(|path| canonical(path).starts_with(canonical(&root.join(layout.sources()))))
+                };
+                let (chosen, reason) = if let Some(layout) = layout {
+                    (layout, "--layout".to_string())
+                } else if let Some(layout) = [Layout::Hardhat, Layout::Foundry].into_iter().find(|&layout| within(layout)) {
+                    (layout, format!("PATH is in {}/", layout.sources()))
+                } else {
+                    (Layout::Foundry, "the default, pass --layout to choose".to_string())
+                };
+                eprintln!(
+                    "Note: {:?} has both foundry.toml and {}; using the {} layout ({}).",
+                    root,
+                    config,
+                    chosen.name(),
+                    reason
+                );
+                Some(chosen)
+            }
+            (true, None) => layout.or(Some(Layout::Foundry)),
+            (false, Some(_)) => layout.or(Some(Layout::Hardhat)),
+            (false, None) => layout,
+        };
+
+        Project {
+            root: root.to_path_buf(),
+            layout,
+            usage_roots: Vec::new(),
+            max_file_size: None,
+        }
+    }
+
+    /// The `foundry.toml` and Hardhat configs that `dir` has, which decide its layout.
+    pub fn config_files(dir: &Path) -> Vec<&'static str> {
+        std::iter::once("foundry.toml")
+            .chain(HARDHAT_CONFIGS)
+            .filter(|config| dir.join(config).is_file())
+            .collect()
+    }
+
+    /// Whether `dir` has a `foundry.toml` or a Hardhat config.
+    pub fn is_project(dir: &Path) -> bool {
+        dir.join("foundry.toml").is_file() || HARDHAT_CONFIGS.iter().any(|config| dir.join(config).is_file())
+    }
+
+    /// The directory analyzed when no path is given.
+    pub fn sources(&self) -> Option<PathBuf> {
+        // Joining to `.` would give `./src`, which wouldn't match baselines recorded with
+        // an explicit `src`.
+        self.layout.map(|layout| {
+            if self.root == Path::new(".") {
+                PathBuf::from(layout.sources())
+            } else {
+                self.root.join(layout.sources())
+            }
+        })
+    }
+
+    /// Collects the Solidity files under `dir`, leaving out Hardhat's build output.
+    pub fn collect(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = collect_sol_files(dir)?;
+        files.retain(|file| self.within_size_limit(file));
+        if self.layout == Some(Layout::Hardhat) {
+            let output: Vec<PathBuf> = HARDHAT_OUTPUT_DIRS
+                .iter()
+                .map(|dir| canonical(&self.root.join(dir)))
+                .collect();
+            files.retain(|file| {
+                let file = canonical(file);
+                !output.iter().any(|dir| file.starts_with(dir))
+            });
+        }
+        Ok(files)
+    }
+
+    /// The Solidity files searched for usages: everything under the root, or only the
+    /// `contracts`, `test` and `scripts` directories of a Hardhat project, which keeps
+    /// `node_modules` out of the search. The files of each usage root are searched the
+    /// same way, as a project of its own.
+    pub fn search_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        if self.layout != Some(Layout::Hardhat) {
+            files.extend(collect_sol_files(&self.root)?);
+            files.retain(|file| self.within_size_limit(file));
+        } else {
+            for dir in HARDHAT_SEARCH_DIRS {
+                let dir = self.root.join(dir);
+                if dir.is_dir() {
+                    files.extend(self.collect(&dir)?);
+                }
+            }
+        }
+        if !self.usage_roots.is_empty() {
+            for root in &self.usage_roots {
+                let mut project = Project::detect(root, None, None);
+                project.max_file_size = self.max_file_size;
+                files.extend(project.search_files()?);
+            }
+        }
+        // A usage root may overlap the project, as may the Hardhat directories through a
+        // symbolic link, and a file counted twice would count its usages twice.
+        dedup_files(&mut files);
+        Ok(files)
+    }
+
+    /// Whether `path` is at most `max_file_size` bytes. Files whose size can't be read are
+    /// kept, and fail to read later like any other.
+    pub fn within_size_limit(&self, path: &Path) -> bool {
+        self.max_file_size
+            .is_none_or(|limit| fs::metadata(path).map_or(true, |metadata| metadata.len() <= limit))
+    }
+
+    /// The Solidity files under the root, the usage roots and `path` that are larger than
+    /// `max_file_size`, with their sizes, to warn about once before they are skipped.
+    pub fn oversized(&self, path: &Path) -> Result<Vec<(PathBuf, u64)>> {
+        let Some(limit) = self.max_file_size else {
+            return Ok(Vec::new());
+        };
+        let mut files = collect_sol_files(&self.root)?;
+        for root in &self.usage_roots {
+            files.extend(collect_sol_files(root)?);
+        }
+        if path.is_dir() {
+            files.extend(collect_sol_files(path)?);
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        }
+        dedup_files(&mut files);
+        Ok(files
+            .into_iter()
+            .filter_map(|file| {
+                let size = fs::metadata(&file).ok()?.len();
+                (size > limit).then_some((file, size))
+            })
+            .collect())
+    }
+}
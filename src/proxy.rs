@@ -0,0 +1,102 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::solidity::{self, ContractKind, Visibility};
+use crate::source::{Context, SourceFile};
+
+fn fallback_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bfallback\s*\(").unwrap())
+}
+
+fn delegatecall_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bdelegatecall\b").unwrap())
+}
+
+/// Signs that a contract is written to sit behind a proxy: an upgradeable or
+/// `Initializable` base, an `initialize` function or the `initializer` modifier.
+fn upgradeable_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\b\w*Upgradeable\b|\bInitializable\b|\bfunction\s+initialize\s*\(|\binitializer\b")
+            .unwrap()
+    })
+}
+
+/// Contracts whose functions are reached through a proxy's `delegatecall` fallback, and
+/// so are never called by name.
+///
+/// A file of the project's own, i.e. not a dependency, is a proxy if it has a `fallback`
+/// function and a `delegatecall`: a vendored OpenZeppelin `Proxy` is one of those, and
+/// would otherwise make every upgradeable contract an implementation. When the project
+/// has a proxy, contracts outside the proxy files are treated as implementations
+/// if they look upgradeable (an `*Upgradeable` or `Initializable` base, an `initialize`
+/// function or the `initializer` modifier) or if a proxy file mentions them by name.
+#[derive(Default)]
+pub struct ProxyHeuristic {
+    pub proxies: Vec<PathBuf>,
+    implementations: HashSet<String>,
+}
+
+impl ProxyHeuristic {
+    pub fn detect<'a>(
+        sources: impl IntoIterator<Item = &'a SourceFile>,
+        is_dependency: impl Fn(&Path) -> bool,
+    ) -> Self {
+        let sources: Vec<&SourceFile> = sources.into_iter().collect();
+        let in_code = |source: &SourceFile, pattern: &Regex| {
+            pattern
+                .find_iter(&source.content)
+                .any(|found| source.context_at(found.start()) == Context::Code)
+        };
+        let (proxies, others): (Vec<&SourceFile>, Vec<&SourceFile>) = sources
+            .into_iter()
+            .partition(|source| {
+                !is_dependency(&source.path)
+                    && in_code(source, fallback_pattern())
+                    && in_code(source, delegatecall_pattern())
+            });
+        if proxies.is_empty() {
+            return ProxyHeuristic::default();
+        }
+
+        let mut implementations = HashSet::new();
+        for source in others {
+            for contract in solidity::parse(source).contracts {
+                if !matches!(contract.kind, ContractKind::Contract | ContractKind::Abstract) {
+                    continue;
+                }
+                let text = &source.content[contract.start..contract.body.1];
+                let named_by_proxy = proxies.iter().any(|proxy| {
+                    Regex::new(&format!(r"\b{}\b", regex::escape(&contract.name)))
+                        .is_ok_and(|name| in_code(proxy, &name))
+                });
+                if named_by_proxy || in_code_within(source, upgradeable_pattern(), contract.start, text) {
+                    implementations.insert(contract.name);
+                }
+            }
+        }
+
+        ProxyHeuristic {
+            proxies: proxies.iter().map(|proxy| proxy.path.clone()).collect(),
+            implementations,
+        }
+    }
+
+    /// Whether a function is reachable through a proxy: an external or public function of
+    /// an implementation contract.
+    pub fn reaches(&self, contract: Option<&str>, visibility: Option<Visibility>) -> bool {
+        visibility.is_some_and(Visibility::is_externally_visible)
+            && contract.is_some_and(|contract| self.implementations.contains(contract))
+    }
+}
+
+/// Whether `pattern` matches code within `text`, which starts at `offset` in `source`.
+fn in_code_within(source: &SourceFile, pattern: &Regex, offset: usize, text: &str) -> bool {
+    pattern
+        .find_iter(text)
+        .any(|found| source.context_at(offset + found.start()) == Context::Code)
+}
@@ -0,0 +1,389 @@
+use aho_corasick::AhoCorasick;
+use regex::Regex;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::source::{Context, SourceFile};
+
+/// A single match of a function name within a source file.
+pub struct Occurrence {
+    pub file: PathBuf,
+    pub offset: usize,
+    pub line: usize,
+    pub snippet: String,
+    pub context: Context,
+    /// The match is the function name in a signature string passed to
+    /// `abi.encodeWithSignature` or `keccak256`, e.g. `"sweep(address)"`.
+    pub in_signature: bool,
+    /// The match is the function passed to `abi.encodeCall`, e.g. `IVault.sweep`, a
+    /// type-checked call by selector.
+    pub in_encode_call: bool,
+    /// For a call qualified by a name, e.g. `FixedPointMath.mulDiv(...)`, the qualifier.
+    pub qualifier: Option<String>,
+    /// For any member access qualified by a name, called or not, e.g. `Constants.MAX_FEE`,
+    /// the qualifier.
+    pub member_qualifier: Option<String>,
+    /// For a match in a string, the functions the string is passed to, innermost first,
+    /// e.g. `string.concat` and `console.log`.
+    pub calls: Vec<String>,
+}
+
+/// Settings controlling which text is read as code, both when extracting declarations
+/// and when counting usages.
+#[derive(Clone, Debug, Default)]
+pub struct CountOptions {
+    /// Count matches inside string literals, not just signature strings.
+    pub count_strings: bool,
+    /// Read commented-out code as code: declarations in comments are extracted and
+    /// matches in comments count as usages.
+    pub include_commented: bool,
+    /// Logging helpers whose message strings never count, besides `console.log`,
+    /// `require` and `revert`.
+    pub log_functions: Vec<String>,
+}
+
+impl CountOptions {
+    /// Whether text in `context` is read as code. Comments and strings are stripped
+    /// unless enabled, so that extraction and counting never disagree about them.
+    pub fn reads(&self, context: Context) -> bool {
+        match context {
+            Context::Code => true,
+            Context::Comment => self.include_commented,
+            Context::String => self.count_strings,
+        }
+    }
+
+    /// Whether the strings passed to `call` are log or revert messages.
+    fn is_message_call(&self, call: &str) -> bool {
+        MESSAGE_CALLS.contains(&call)
+            || LOG_PREFIXES.iter().any(|prefix| call.starts_with(prefix))
+            || self.log_functions.iter().any(|function| function == call)
+    }
+}
+
+impl Occurrence {
+    /// Whether this occurrence counts as a usage of the function. Log and revert messages
+    /// mention functions without calling them, so they never count, even with
+    /// `count_strings`.
+    pub fn counts(&self, options: &CountOptions) -> bool {
+        self.in_signature || self.in_encode_call || (options.reads(self.context) && !self.in_message(options))
+    }
+
+    /// Whether the match is in a log or revert message, e.g. `console.log("rebalance")`.
+    pub fn in_message(&self, options: &CountOptions) -> bool {
+        self.context == Context::String && self.calls.iter().any(|call| options.is_message_call(call))
+    }
+}
+
+/// Calls whose string arguments are messages.
+const MESSAGE_CALLS: [&str; 2] = ["require", "revert"];
+
+/// Prefixes of the forge-std logging functions, e.g. `console.log` or `console2.logUint`.
+const LOG_PREFIXES: [&str; 2] = ["console.log", "console2.log"];
+
+/// How far back from a string the opening parenthesis of its call is looked for.
+const MAX_CALL_DISTANCE: usize = 256;
+
+fn encode_call_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bencodeCall\s*\(\s*(?:[A-Za-z_$][\w$]*\s*\.\s*)*$").unwrap())
+}
+
+fn qualifier_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?:^|[^\w$.])([A-Za-z_$][\w$]*)\s*\.\s*$").unwrap())
+}
+
+fn signature_call_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(?:encodeWithSignature|keccak256)\s*\(\s*$").unwrap())
+}
+
+/// Finds every match of `name` in `source` as a whole word, so that `fee` doesn't match
+/// inside `feeRecipient`.
+///
+/// This is the single definition of what counts as a reference, shared by every
+/// analysis so that they never disagree about what refers to what.
+pub fn find_occurrences(source: &SourceFile, name: &str) -> Vec<Occurrence> {
+    source
+        .content
+        .match_indices(name)
+        .filter(|&(offset, _)| is_whole_word(&source.content, offset, offset + name.len()))
+        .map(|(offset, _)| occurrence(source, offset, name))
+        .collect()
+}
+
+/// Matches many names at once, finding the occurrences of all of them with a single scan
+/// of each file instead of one per name. The matches are those of `find_occurrences`.
+/// The names are interned: each match carries the id of its name, its index in `names`,
+/// so that results can be kept in vectors indexed by id rather than maps keyed by copies
+/// of the names.
+pub struct NameMatcher {
+    automaton: AhoCorasick,
+    names: Vec<String>,
+}
+
+impl NameMatcher {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut names: Vec<String> = names.into_iter().map(String::from).collect();
+        names.sort();
+        names.dedup();
+        let automaton = AhoCorasick::new(&names).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Failed to build the matcher of {} names: {}", names.len(), e),
+            )
+        })?;
+        Ok(NameMatcher { automaton, names })
+    }
+
+    /// The distinct names, sorted, each at the index that is its id.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.names.binary_search_by(|candidate| candidate.as_str().cmp(name)).ok()
+    }
+
+    /// Finds the occurrences of every name in `source`, with the id of the name each is
+    /// of, in the order they end in the file.
+    pub fn find_occurrences(&self, source: &SourceFile) -> Vec<(usize, Occurrence)> {
+        // Names may overlap, e.g. `fee` and `feeRecipient`, so every match is visited,
+        // and only the whole words are kept.
+        self.automaton
+            .find_overlapping_iter(&source.content)
+            .filter(|found| is_whole_word(&source.content, found.start(), found.end()))
+            .map(|found| {
+                let id = found.pattern().as_usize();
+                (id, occurrence(source, found.start(), &self.names[id]))
+            })
+            .collect()
+    }
+}
+
+/// Whether the text from `start` to `end` isn't part of a longer identifier.
+fn is_whole_word(content: &str, start: usize, end: usize) -> bool {
+    let is_identifier = |byte: &u8| byte.is_ascii_alphanumeric() || *byte == b'_' || *byte == b'$';
+    let bytes = content.as_bytes();
+    !start.checked_sub(1).is_some_and(|before| is_identifier(&bytes[before]))
+        && !bytes.get(end).is_some_and(is_identifier)
+}
+
+fn occurrence(source: &SourceFile, offset: usize, name: &str) -> Occurrence {
+    let line = source.line_of(offset);
+    let context = source.context_at(offset);
+    Occurrence {
+        file: source.path.clone(),
+        offset,
+        line,
+        snippet: source.line_text(line).trim().to_string(),
+        context,
+        in_signature: context == Context::String && is_signature(source, offset, name),
+        in_encode_call: context == Context::Code && is_encode_call(source, offset),
+        qualifier: if context == Context::Code {
+            call_qualifier(source, offset, name)
+        } else {
+            None
+        },
+        member_qualifier: if context == Context::Code {
+            member_qualifier(source, offset)
+        } else {
+            None
+        },
+        calls: if context == Context::String {
+            enclosing_calls(source, offset)
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// Whether the string containing `offset` is a signature of `name` passed to
+/// `abi.encodeWithSignature(...)` or `keccak256(...)`.
+fn is_signature(source: &SourceFile, offset: usize, name: &str) -> bool {
+    let content = &source.content;
+    let opens_string = offset
+        .checked_sub(1)
+        .is_some_and(|quote| matches!(content.as_bytes()[quote], b'"' | b'\''));
+    let takes_arguments = content[offset + name.len()..].starts_with('(');
+    if !opens_string || !takes_arguments {
+        return false;
+    }
+
+    let preceding = &content[content.floor_char_boundary(offset.saturating_sub(64))..offset - 1];
+    signature_call_pattern().is_match(preceding)
+}
+
+/// Whether the code at `offset` is the first argument of `abi.encodeCall(...)`, which
+/// names the function called, e.g. `abi.encodeCall(IVault.sweep, (to))`.
+fn is_encode_call(source: &SourceFile, offset: usize) -> bool {
+    let preceding = &source.content[source.content.floor_char_boundary(offset.saturating_sub(128))..offset];
+    encode_call_pattern().is_match(preceding)
+}
+
+/// Returns `Name` when the match at `offset` is called as `Name.name(...)`.
+fn call_qualifier(source: &SourceFile, offset: usize, name: &str) -> Option<String> {
+    if !source.content[offset + name.len()..].trim_start().starts_with('(') {
+        return None;
+    }
+    member_qualifier(source, offset)
+}
+
+/// Returns `Name` when the match at `offset` is accessed as `Name.name`.
+fn member_qualifier(source: &SourceFile, offset: usize) -> Option<String> {
+    let content = &source.content;
+    let preceding = &content[content.floor_char_boundary(offset.saturating_sub(64))..offset];
+    let captures = qualifier_pattern().captures(preceding)?;
+    Some(captures[1].to_string())
+}
+
+/// Returns the functions called with the argument lists containing `offset`, innermost
+/// first, e.g. `console.log` for `console.log("entering rebalance")`. The search stops
+/// at the start of the statement.
+fn enclosing_calls(source: &SourceFile, offset: usize) -> Vec<String> {
+    let content = source.content.as_bytes();
+    let start = offset.saturating_sub(MAX_CALL_DISTANCE);
+    let mut calls = Vec::new();
+    let mut depth = 0usize;
+    let mut index = offset;
+    while index > start {
+        index -= 1;
+        if source.context_at(index) != Context::Code {
+            continue;
+        }
+        match content[index] {
+            b')' => depth += 1,
+            b'(' if depth == 0 => {
+                let before = source.content[..index].trim_end();
+                let callee_start = before
+                    .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$'))
+                    .map_or(0, |index| index + 1);
+                if callee_start < before.len() {
+                    calls.push(before[callee_start..].to_string());
+                }
+            }
+            b'(' => depth -= 1,
+            b';' | b'{' | b'}' => break,
+            _ => {}
+        }
+    }
+    calls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"contract Vault {
+    uint256 fee;
+    address feeRecipient;
+
+    function setFee(uint256 newFee) external {
+        fee = newFee; // the fee, in bps
+        emit FeeSet(fee);
+    }
+
+    function $sweep() external {
+        bytes memory data = abi.encodeWithSignature("sweep(address)", feeRecipient);
+        abi.encodeCall(IVault.sweep, (feeRecipient));
+        FixedPointMath.mulDiv(fee, 1, 2);
+        console.log("sweeping fee", Constants.fee);
+        /* sweep fees */
+    }
+}
+"#;
+
+    /// What an occurrence says about its match, to compare the matches of both searches.
+    fn describe(occurrence: &Occurrence) -> String {
+        format!(
+            "{}:{} {:?} {} {} {:?} {:?} {:?} {}",
+            occurrence.offset,
+            occurrence.line,
+            occurrence.context,
+            occurrence.in_signature,
+            occurrence.in_encode_call,
+            occurrence.qualifier,
+            occurrence.member_qualifier,
+            occurrence.calls,
+            occurrence.snippet
+        )
+    }
+
+    #[test]
+    fn the_matcher_finds_what_each_name_finds() {
+        let source = SourceFile::new(PathBuf::from("Vault.sol"), SOURCE.to_string());
+        let names = ["fee", "feeRecipient", "Fee", "newFee", "setFee", "sweep", "$sweep", "mulDiv", "log", "missing"];
+        let matcher = NameMatcher::new(names).unwrap();
+
+        let mut found: Vec<Vec<String>> = vec![Vec::new(); matcher.names().len()];
+        for (id, occurrence) in matcher.find_occurrences(&source) {
+            found[id].push(describe(&occurrence));
+        }
+        for name in names {
+            let mut expected: Vec<String> = find_occurrences(&source, name).iter().map(describe).collect();
+            let mut matched = found[matcher.id(name).unwrap()].clone();
+            expected.sort();
+            matched.sort();
+            assert_eq!(matched, expected, "occurrences of {}", name);
+        }
+        assert!(!found[matcher.id("fee").unwrap()].is_empty());
+        assert!(found[matcher.id("missing").unwrap()].is_empty());
+    }
+
+    #[test]
+    fn names_are_interned_once() {
+        let matcher = NameMatcher::new(["sweep", "fee", "sweep"]).unwrap();
+        assert_eq!(matcher.names(), ["fee", "sweep"]);
+        assert_eq!(matcher.id("sweep"), Some(1));
+        assert_eq!(matcher.id("missing"), None);
+    }
+
+    /// A contract of `functions` functions named `fn0`, `fn1`, ..., each calling the
+    /// `calls` next ones, and their names.
+    fn synthetic_source(functions: usize, calls: usize) -> (SourceFile, Vec<String>) {
+        let names: Vec<String> = (0..functions).map(|index| format!("fn{}", index)).collect();
+        let mut content = String::from("contract Synthetic {\n");
+        for (index, name) in names.iter().enumerate() {
+            content.push_str(&format!("    function {}(uint256 x) internal pure returns (uint256) {{\n", name));
+            for call in 1..=calls {
+                content.push_str(&format!("        x = {}(x); // calls {}\n", names[(index + call) % functions], call));
+            }
+            content.push_str("        return x;\n    }\n\n");
+        }
+        content.push_str("}\n");
+        (SourceFile::new(PathBuf::from("Synthetic.sol"), content), names)
+    }
+
+    /// Times the matcher against one `find_occurrences` per name on a large file:
+    /// `cargo test --release matcher_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn matcher_benchmark() {
+        let (source, names) = synthetic_source(3_000, 20);
+        let matcher = NameMatcher::new(names.iter().map(String::as_str)).unwrap();
+        let time = |search: &dyn Fn() -> usize| {
+            let mut runs = Vec::new();
+            let mut found = 0;
+            for _ in 0..5 {
+                let start = std::time::Instant::now();
+                found = search();
+                runs.push(start.elapsed());
+            }
+            runs.sort();
+            (found, runs[runs.len() / 2])
+        };
+        let (matched, automaton) = time(&|| matcher.find_occurrences(&source).len());
+        let (found, per_name) = time(&|| names.iter().map(|name| find_occurrences(&source, name).len()).sum());
+        assert_eq!(matched, found);
+        println!(
+            "{} occurrences of {} names in {} bytes: matcher median {:?}, per-name median {:?}",
+            found,
+            names.len(),
+            source.content.len(),
+            automaton,
+            per_name
+        );
+    }
+}
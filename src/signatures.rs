@@ -0,0 +1,169 @@
+use rayon::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Looked up first, for many selectors at once; junk signatures are filtered out.
+const OPENCHAIN_URL: &str = "https://api.openchain.xyz/signature-database/v1/lookup";
+
+/// Looked up for the selectors openchain doesn't know, one per request, several at once.
+const FOURBYTE_URL: &str = "https://www.4byte.directory/api/v1/signatures/";
+
+/// How long a lookup may take before the database is given up on.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many selectors are sent in one openchain request.
+const BATCH_SIZE: usize = 100;
+
+/// Human-readable signatures of function selectors from the openchain and 4byte signature
+/// databases, cached on disk between runs.
+///
+/// Lookups are best effort: when the network or a database fails, the selectors that
+/// couldn't be looked up have no signatures and aren't cached, so a later run retries them.
+pub struct SignatureDatabase {
+    cache: Option<PathBuf>,
+    /// Signatures by `0x`-prefixed selector, including selectors no database knows.
+    known: BTreeMap<String, Vec<String>>,
+}
+
+impl SignatureDatabase {
+    /// Opens the cache at `cache_path`.
+    pub fn open() -> Self {
+        let cache = cache_path();
+        let known = cache
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        SignatureDatabase { cache, known }
+    }
+
+    /// Looks up the selectors that aren't cached yet and saves what was found.
+    pub fn resolve<'a>(&mut self, selectors: impl IntoIterator<Item = &'a String>) {
+        let mut missing: Vec<String> = selectors
+            .into_iter()
+            .filter(|selector| !self.known.contains_key(*selector))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return;
+        }
+
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        let mut found = BTreeMap::new();
+        for batch in missing.chunks(BATCH_SIZE) {
+            match lookup_openchain(&agent, batch) {
+                Some(signatures) => found.extend(signatures),
+                None => break,
+            }
+        }
+        let unknown: Vec<String> = found
+            .iter()
+            .filter(|(_, signatures)| signatures.is_empty())
+            .map(|(selector, _)| selector.clone())
+            .collect();
+        let answers: Vec<(String, Option<Vec<String>>)> = unknown
+            .into_par_iter()
+            .map(|selector| {
+                let signatures = lookup_fourbyte(&agent, &selector);
+                (selector, signatures)
+            })
+            .collect();
+        for (selector, signatures) in answers {
+            match signatures {
+                Some(signatures) => {
+                    found.insert(selector, signatures);
+                }
+                // Without an answer from 4byte, the selector is retried next time.
+                None => {
+                    found.remove(&selector);
+                }
+            }
+        }
+
+        if found.is_empty() {
+            return;
+        }
+        self.known.extend(found);
+        // The cache only saves lookups, so failing to write it is not an error.
+        if let Some(path) = &self.cache {
+            let _ = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, serde_json::to_string(&self.known).unwrap_or_default()));
+        }
+    }
+
+    /// Returns the known signatures of a `0x`-prefixed selector, if it was looked up.
+    pub fn signatures(&self, selector: &str) -> &[String] {
+        self.known.get(selector).map_or(&[], Vec::as_slice)
+    }
+}
+
+fn lookup_openchain(agent: &ureq::Agent, selectors: &[String]) -> Option<BTreeMap<String, Vec<String>>> {
+    let body = agent
+        .get(OPENCHAIN_URL)
+        .query("function", &selectors.join(","))
+        .query("filter", "true")
+        .call()
+        .ok()?
+        .into_string()
+        .ok()?;
+    let value: Value = serde_json::from_str(&body).ok()?;
+    let functions = value.get("result")?.get("function")?;
+
+    Some(
+        selectors
+            .iter()
+            .map(|selector| {
+                let signatures = functions
+                    .get(selector)
+                    .and_then(Value::as_array)
+                    .map(|entries| {
+                        entries
+                            .iter()
+                            .filter_map(|entry| entry.get("name")?.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (selector.clone(), signatures)
+            })
+            .collect(),
+    )
+}
+
+fn lookup_fourbyte(agent: &ureq::Agent, selector: &str) -> Option<Vec<String>> {
+    let body = agent
+        .get(FOURBYTE_URL)
+        .query("hex_signature", selector)
+        .call()
+        .ok()?
+        .into_string()
+        .ok()?;
+    let value: Value = serde_json::from_str(&body).ok()?;
+    let mut results: Vec<(u64, String)> = value
+        .get("results")?
+        .as_array()?
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.get("id").and_then(Value::as_u64).unwrap_or(u64::MAX);
+            Some((id, entry.get("text_signature")?.as_str()?.to_string()))
+        })
+        .collect();
+    // The earliest submissions are the least likely to be spam.
+    results.sort();
+    Some(results.into_iter().map(|(_, signature)| signature).collect())
+}
+
+/// The file signatures are cached in, in `$XDG_CACHE_HOME/wand` or `~/.cache/wand`, or
+/// `None` when neither variable is set.
+pub fn cache_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("wand").join("signatures.json"))
+}
@@ -0,0 +1,168 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::source::SourceFile;
+
+/// Words and builtins of the language, which are never what a misspelled function meant.
+pub const RESERVED: &[&str] = &[
+    "abstract", "addmod", "address", "anonymous", "as", "assembly", "assert", "blockhash", "bool", "break",
+    "calldata", "catch", "constant", "constructor", "continue", "contract", "delete", "do", "ecrecover", "else",
+    "emit", "enum", "error", "event", "external", "fallback", "false", "for", "function", "gasleft", "if",
+    "immutable", "import", "indexed", "interface", "internal", "is", "keccak256", "library", "mapping", "memory",
+    "modifier", "mulmod", "new", "override", "payable", "pragma", "private", "public", "pure", "receive", "require",
+    "return", "returns", "revert", "sha256", "storage", "string", "struct", "true", "try", "type", "unchecked",
+    "using", "view", "virtual", "while",
+];
+
+/// A referenced identifier similar to the name of a function nothing references, which
+/// hints that the function is misspelled, or was renamed without its callers.
+#[derive(Serialize, Clone, Debug)]
+pub struct Suggestion {
+    pub name: String,
+    /// Its calls in code, i.e. occurrences followed by arguments other than in a
+    /// function declaration.
+    pub references: usize,
+}
+
+impl Suggestion {
+    /// e.g. ``did you mean: similarly-named `initialize` is referenced 12 times``.
+    pub fn describe(&self) -> String {
+        let times = if self.references == 1 { "time" } else { "times" };
+        format!(
+            "did you mean: similarly-named `{}` is referenced {} {}",
+            self.name, self.references, times
+        )
+    }
+}
+
+fn identifier_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // The name after `function` is a declaration, not a reference, and only names
+    // followed by arguments are calls, i.e. what a function's callers would write.
+    PATTERN.get_or_init(|| Regex::new(r"(\bfunction\s+)?\b([A-Za-z_$][\w$]*)(\s*\()?").unwrap())
+}
+
+fn elementary_type_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^(?:u?int|bytes|u?fixed)[\dx]*$").unwrap())
+}
+
+/// How often each identifier is called in the code of a set of files.
+#[derive(Default)]
+pub struct IdentifierIndex {
+    references: HashMap<String, usize>,
+}
+
+impl IdentifierIndex {
+    /// Counts the calls in the code of `paths`, leaving out comments, strings and the
+    /// names of function declarations. Unreadable files are skipped.
+    pub fn load(paths: &[PathBuf]) -> Self {
+        let mut references: HashMap<String, usize> = HashMap::new();
+        for path in paths {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            let source = SourceFile::new(path.clone(), content);
+            let code = source.code_only((0, source.content.len()));
+            for captures in identifier_pattern().captures_iter(&code) {
+                if captures.get(1).is_none() && captures.get(3).is_some() {
+                    *references.entry(captures[2].to_string()).or_default() += 1;
+                }
+            }
+        }
+        references.retain(|name, _| !RESERVED.contains(&name.as_str()) && !elementary_type_pattern().is_match(name));
+        IdentifierIndex { references }
+    }
+
+    /// The most called identifier similar to `name`: the same but for case, or within an
+    /// edit distance of a quarter of its length, at most 2. Names shorter than
+    /// `MIN_SUGGESTED_LENGTH` get none, since an edit or two makes another word of them,
+    /// e.g. `foo` and `for`, and a distance of 2 would turn `unused` into `used`.
+    pub fn suggest(&self, name: &str) -> Option<Suggestion> {
+        let length = name.chars().count();
+        if length < MIN_SUGGESTED_LENGTH {
+            return None;
+        }
+        let max_distance = (length / 4).min(2);
+        self.references
+            .iter()
+            .filter(|(candidate, _)| candidate.as_str() != name)
+            .filter(|(candidate, _)| {
+                candidate.eq_ignore_ascii_case(name) || edit_distance(candidate, name) <= max_distance
+            })
+            .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+            .map(|(candidate, &references)| Suggestion {
+                name: candidate.clone(),
+                references,
+            })
+    }
+}
+
+/// The length of the shortest names that are checked for misspellings.
+const MIN_SUGGESTED_LENGTH: usize = 4;
+
+/// The Levenshtein distance between `a` and `b`, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > 2 {
+        // Longer than any distance that's suggested.
+        return a.len().abs_diff(b.len());
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(names: &[(&str, usize)]) -> IdentifierIndex {
+        IdentifierIndex {
+            references: names.iter().map(|&(name, count)| (name.to_string(), count)).collect(),
+        }
+    }
+
+    fn suggested(index: &IdentifierIndex, name: &str) -> Option<String> {
+        index.suggest(name).map(|suggestion| suggestion.name)
+    }
+
+    #[test]
+    fn short_names_get_no_suggestion() {
+        let index = index(&[("b", 3), ("baz", 3), ("Bar", 3), ("mints", 3)]);
+        assert_eq!(suggested(&index, "a"), None);
+        assert_eq!(suggested(&index, "bar"), None);
+        assert_eq!(suggested(&index, "mint"), Some("mints".to_string()));
+    }
+
+    #[test]
+    fn the_distance_grows_with_the_length() {
+        let index = index(&[("used", 5), ("initialize", 12), ("withdrawAll", 2)]);
+        assert_eq!(suggested(&index, "unused"), None);
+        assert_eq!(suggested(&index, "initalize"), Some("initialize".to_string()));
+        assert_eq!(suggested(&index, "withdrawAl"), Some("withdrawAll".to_string()));
+    }
+
+    #[test]
+    fn case_differences_are_suggested() {
+        let index = index(&[("deposit", 1), ("Deposits", 4)]);
+        assert_eq!(suggested(&index, "Deposit"), Some("Deposits".to_string()));
+        assert_eq!(suggested(&index, "DEPOSIT"), Some("deposit".to_string()));
+    }
+
+    #[test]
+    fn the_most_called_similar_name_is_suggested() {
+        let index = index(&[("transfer", 2), ("transfers", 9)]);
+        assert_eq!(suggested(&index, "transfor"), Some("transfers".to_string()));
+    }
+}
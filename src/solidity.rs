@@ -0,0 +1,597 @@
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+use crate::source::{Context, SourceFile};
+
+/// Kind of a top-level Solidity definition that can contain functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractKind {
+    Contract,
+    Abstract,
+    Interface,
+    Library,
+}
+
+/// A contract, interface, or library definition.
+pub struct Contract {
+    pub name: String,
+    pub kind: ContractKind,
+    /// Offset of the first keyword, e.g. `abstract` or `contract`.
+    pub start: usize,
+    pub line: usize,
+    /// Names of the contracts listed after `is`, in order.
+    pub bases: Vec<String>,
+    pub body: (usize, usize),
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    External,
+    Public,
+    Internal,
+    Private,
+}
+
+impl Visibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::External => "external",
+            Visibility::Public => "public",
+            Visibility::Internal => "internal",
+            Visibility::Private => "private",
+        }
+    }
+
+    /// Whether the function is callable from outside the contract.
+    pub fn is_externally_visible(self) -> bool {
+        matches!(self, Visibility::External | Visibility::Public)
+    }
+}
+
+/// A parameter or return variable in a declaration.
+pub struct Param {
+    /// The type as written, e.g. `uint256[]` or `IERC20`.
+    pub ty: String,
+    pub name: Option<String>,
+}
+
+/// A struct definition, used to expand struct types in signatures.
+pub struct Struct {
+    pub name: String,
+    pub fields: Vec<Param>,
+    /// Span from the `struct` keyword to just past the closing `}`.
+    pub span: (usize, usize),
+}
+
+/// A state variable declared directly in a contract.
+pub struct StateVariable {
+    pub name: String,
+    pub contract: String,
+    /// Its type as written, without its visibility and other keywords, e.g.
+    /// `mapping(address => uint256)`.
+    pub ty: String,
+    /// `internal` unless declared otherwise.
+    pub visibility: Visibility,
+    /// Offset of the declaration's first keyword.
+    pub start: usize,
+    /// Offset of the declared name.
+    pub name_offset: usize,
+    pub line: usize,
+}
+
+/// A named declaration such as an event, error or enum.
+pub struct Declaration {
+    pub name: String,
+    /// Span from the keyword to just past the terminating `;` or `}`.
+    pub span: (usize, usize),
+}
+
+/// A function declaration along with the spans needed to reason about it.
+pub struct Function {
+    pub name: String,
+    pub contract: Option<String>,
+    pub visibility: Visibility,
+    pub params: Vec<Param>,
+    pub returns: Vec<Param>,
+    /// Span of the parameter list, without its parentheses.
+    pub params_span: (usize, usize),
+    /// Offset of the `function` keyword.
+    pub start: usize,
+    /// Offset of the `{` or `;` ending the header.
+    pub header_end: usize,
+    /// Offset of the declared name.
+    pub name_offset: usize,
+    pub line: usize,
+    /// Span from the opening `{` to just past the closing `}`, if the function has a body.
+    pub body: Option<(usize, usize)>,
+    /// For a Yul function, the span of the `assembly { }` block defining it, from the `{`
+    /// to just past the `}`. Yul functions can only be called from within their block.
+    pub assembly: Option<(usize, usize)>,
+}
+
+impl Function {
+    /// Returns the name qualified by its containing contract, e.g. `Vault.sweep`.
+    pub fn qualified_name(&self) -> String {
+        match &self.contract {
+            Some(contract) => format!("{}.{}", contract, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Whether this is a `receive` or `fallback` function, which the EVM calls without a
+    /// selector, or a constructor, which only runs on deployment, so that no source ever
+    /// calls it by name. Modern `receive()` and `constructor()` declarations have no
+    /// `function` keyword and aren't parsed at all, but older spellings are: `function
+    /// fallback()`, `function constructor()`, and a function named after its contract,
+    /// which was the constructor before Solidity 0.4.22.
+    pub fn is_special(&self) -> bool {
+        matches!(self.name.as_str(), "receive" | "fallback" | "constructor")
+            || self.contract.as_deref() == Some(self.name.as_str())
+    }
+
+    /// Returns the name and parameter types as written, e.g. `sweep(IERC20,uint)`. See
+    /// `abi::TypeTable::signature` for the canonical form.
+    pub fn written_signature(&self) -> String {
+        let types: Vec<&str> = self.params.iter().map(|param| param.ty.as_str()).collect();
+        format!("{}({})", self.name, types.join(","))
+    }
+
+    /// Returns its code from the keyword to the end of the body, or of the header if it
+    /// has none, without comments and whitespace, so that the same function formatted
+    /// differently gives the same code.
+    pub fn code(&self, source: &SourceFile) -> String {
+        let end = self.body.map_or(self.header_end + 1, |(_, end)| end);
+        let bytes = source.content.as_bytes();
+        let code: Vec<u8> = (self.start..end.min(bytes.len()))
+            .filter(|&offset| !bytes[offset].is_ascii_whitespace() && source.context_at(offset) != Context::Comment)
+            .map(|offset| bytes[offset])
+            .collect();
+        String::from_utf8_lossy(&code).into_owned()
+    }
+}
+
+/// The declarations found in a single source file.
+pub struct SourceUnit {
+    pub contracts: Vec<Contract>,
+    pub functions: Vec<Function>,
+    pub structs: Vec<Struct>,
+    pub enums: Vec<Declaration>,
+    /// User-defined value types and their underlying types, e.g. `type Price is uint256;`.
+    pub value_types: Vec<(String, String)>,
+    pub events: Vec<Declaration>,
+    pub errors: Vec<Declaration>,
+    pub state_variables: Vec<StateVariable>,
+}
+
+fn function_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bfunction\s+([a-zA-Z0-9_]+)\s*\(").unwrap())
+}
+
+fn contract_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\b(?:(abstract)\s+)?(contract|interface|library)\s+([a-zA-Z0-9_$]+)").unwrap()
+    })
+}
+
+fn struct_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bstruct\s+([a-zA-Z0-9_$]+)\s*\{").unwrap())
+}
+
+fn enum_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\benum\s+([a-zA-Z0-9_$]+)\s*\{").unwrap())
+}
+
+fn value_type_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\btype\s+([a-zA-Z0-9_$]+)\s+is\s+([a-zA-Z0-9_$]+)\s*;").unwrap()
+    })
+}
+
+fn event_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bevent\s+([a-zA-Z0-9_$]+)\s*\(").unwrap())
+}
+
+fn error_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\berror\s+([a-zA-Z0-9_$]+)\s*\(").unwrap())
+}
+
+fn assembly_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // e.g. `assembly { }` or `assembly ("memory-safe") { }`.
+    PATTERN.get_or_init(|| Regex::new(r#"\bassembly\s*(?:"[^"]*"\s*)?(?:\([^)]*\)\s*)?\{"#).unwrap())
+}
+
+fn returns_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\breturns\s*\(").unwrap())
+}
+
+/// The keywords of a state variable declaration that aren't part of its type.
+fn variable_keyword_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\b(?:external|public|internal|private|constant|immutable|transient)\b|\boverride\b(?:\s*\([^)]*\))?")
+            .unwrap()
+    })
+}
+
+fn visibility_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(external|public|internal|private)\b").unwrap())
+}
+
+/// Extracts the contracts, functions and other declarations in `source`.
+pub fn parse(source: &SourceFile) -> SourceUnit {
+    let content = &source.content;
+
+    let contracts: Vec<Contract> = contract_pattern()
+        .captures_iter(content)
+        .filter(|cap| source.context_at(cap.get(0).unwrap().start()) == Context::Code)
+        .filter_map(|cap| {
+            let name = cap.get(3).unwrap();
+            let open = find_code_byte(source, name.end(), b'{')?;
+            let close = source.block_end(open)?;
+            let kind = match (&cap[2], cap.get(1).is_some()) {
+                ("interface", _) => ContractKind::Interface,
+                ("library", _) => ContractKind::Library,
+                (_, true) => ContractKind::Abstract,
+                _ => ContractKind::Contract,
+            };
+            Some(Contract {
+                name: name.as_str().to_string(),
+                kind,
+                start: cap.get(0).unwrap().start(),
+                line: source.line_of(name.start()),
+                bases: parse_bases(&source.code_only((name.end(), open))),
+                body: (open, close),
+            })
+        })
+        .collect();
+
+    let assembly_blocks: Vec<(usize, usize)> = assembly_pattern()
+        .find_iter(content)
+        .filter(|found| source.context_at(found.start()) == Context::Code)
+        .filter_map(|found| Some((found.end() - 1, source.block_end(found.end() - 1)?)))
+        .collect();
+
+    let functions = function_pattern()
+        .captures_iter(content)
+        .filter(|cap| source.context_at(cap.get(0).unwrap().start()) == Context::Code)
+        .map(|cap| {
+            let keyword = cap.get(0).unwrap();
+            let name = cap.get(1).unwrap();
+            let container = contracts
+                .iter()
+                .filter(|c| c.body.0 < keyword.start() && keyword.start() < c.body.1)
+                .max_by_key(|c| c.body.0);
+            let assembly = assembly_blocks
+                .iter()
+                .find(|(open, close)| *open < keyword.start() && keyword.start() < *close)
+                .copied();
+
+            let params_start = keyword.end();
+            let params_end = source.group_end(params_start - 1).unwrap_or(content.len());
+            let terminator = (params_end..content.len()).find(|&pos| {
+                matches!(content.as_bytes()[pos], b'{' | b';')
+                    && source.context_at(pos) == Context::Code
+            });
+            let header_end = terminator.unwrap_or(content.len());
+            let body = terminator
+                .filter(|&pos| content.as_bytes()[pos] == b'{')
+                .and_then(|open| Some((open, source.block_end(open)?)));
+
+            // Yul functions have no visibility, and are private to their block.
+            let visibility = visibility_pattern()
+                .captures(&content[params_end..header_end])
+                .filter(|_| assembly.is_none())
+                .map(|vis| match &vis[1] {
+                    "external" => Visibility::External,
+                    "public" => Visibility::Public,
+                    "internal" => Visibility::Internal,
+                    _ => Visibility::Private,
+                })
+                .unwrap_or(match (assembly, container.map(|c| c.kind)) {
+                    (Some(_), _) => Visibility::Private,
+                    (None, Some(ContractKind::Interface)) => Visibility::External,
+                    (None, Some(_)) => Visibility::Public,
+                    (None, None) => Visibility::Internal,
+                });
+
+            let params_span = (params_start, params_end.saturating_sub(1).max(params_start));
+            let returns = returns_pattern()
+                .find(&content[params_end..header_end])
+                .and_then(|found| {
+                    let open = params_end + found.end() - 1;
+                    let close = source.group_end(open)?;
+                    Some(parse_params(&source.code_only((open + 1, close - 1))))
+                })
+                .unwrap_or_default();
+            Function {
+                name: name.as_str().to_string(),
+                contract: container.map(|c| c.name.clone()),
+                visibility,
+                params: parse_params(&source.code_only(params_span)),
+                returns,
+                params_span,
+                start: keyword.start(),
+                header_end,
+                name_offset: name.start(),
+                line: source.line_of(name.start()),
+                body,
+                assembly,
+            }
+        })
+        .collect();
+
+    let code_captures = |pattern: &'static Regex| {
+        pattern
+            .captures_iter(content)
+            .filter(|cap| source.context_at(cap.get(0).unwrap().start()) == Context::Code)
+    };
+
+    let structs = code_captures(struct_pattern())
+        .filter_map(|cap| {
+            let open = cap.get(0).unwrap().end() - 1;
+            let close = source.block_end(open)?;
+            Some(Struct {
+                name: cap[1].to_string(),
+                // Comments would be read as part of the fields' types.
+                fields: source
+                    .code_only((open + 1, close - 1))
+                    .split(';')
+                    .filter(|field| !field.trim().is_empty())
+                    .map(parse_param)
+                    .collect(),
+                span: (cap.get(0).unwrap().start(), close),
+            })
+        })
+        .collect();
+    let enums = code_captures(enum_pattern())
+        .filter_map(|cap| {
+            let start = cap.get(0).unwrap().start();
+            let close = source.block_end(cap.get(0).unwrap().end() - 1)?;
+            Some(Declaration {
+                name: cap[1].to_string(),
+                span: (start, close),
+            })
+        })
+        .collect();
+    let value_types = code_captures(value_type_pattern())
+        .map(|cap| (cap[1].to_string(), cap[2].to_string()))
+        .collect();
+    let statements = |pattern: &'static Regex| {
+        code_captures(pattern)
+            .filter_map(|cap| {
+                let start = cap.get(0).unwrap().start();
+                let end = find_code_byte(source, cap.get(0).unwrap().end(), b';')? + 1;
+                Some(Declaration {
+                    name: cap[1].to_string(),
+                    span: (start, end),
+                })
+            })
+            .collect()
+    };
+    let events = statements(event_pattern());
+    let errors = statements(error_pattern());
+
+    let state_variables = contracts
+        .iter()
+        .flat_map(|contract| parse_state_variables(source, contract))
+        .collect();
+
+    SourceUnit {
+        contracts,
+        functions,
+        structs,
+        enums,
+        value_types,
+        events,
+        errors,
+        state_variables,
+    }
+}
+
+/// Parses the base contracts from the text between a contract's name and its body, e.g.
+/// ` is Ownable, ERC20("Token", "TKN") `.
+fn parse_bases(header: &str) -> Vec<String> {
+    let Some(found) = is_pattern().find(header) else {
+        return Vec::new();
+    };
+    let mut bases = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    for c in header[found.end()..].chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => bases.push(std::mem::take(&mut current)),
+            _ if depth == 0 => current.push(c),
+            _ => {}
+        }
+    }
+    bases.push(current);
+    bases
+        .iter()
+        .map(|base| base.trim().rsplit('.').next().unwrap_or_default().to_string())
+        .filter(|base| !base.is_empty())
+        .collect()
+}
+
+fn is_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\bis\b").unwrap())
+}
+
+fn identifier_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"[a-zA-Z_$][a-zA-Z0-9_$]*").unwrap())
+}
+
+/// Parses the `;`-terminated declarations at the top level of a contract's body that
+/// aren't functions, events, errors or other members, i.e. its state variables.
+fn parse_state_variables(source: &SourceFile, contract: &Contract) -> Vec<StateVariable> {
+    let (open, close) = contract.body;
+    let code = source.code_only((open + 1, close.saturating_sub(1).max(open + 1)));
+    let base = open + 1;
+
+    let mut variables = Vec::new();
+    let mut depth = 0usize;
+    let mut statement_start = 0;
+    for (pos, byte) in code.bytes().enumerate() {
+        match byte {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth = depth.saturating_sub(1);
+                if byte == b'}' && depth == 0 {
+                    statement_start = pos + 1;
+                }
+            }
+            b';' if depth == 0 => {
+                let statement = &code[statement_start..pos];
+                if let Some(variable) = parse_state_variable(statement, base + statement_start, source, contract) {
+                    variables.push(variable);
+                }
+                statement_start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+    variables
+}
+
+fn parse_state_variable(
+    statement: &str,
+    offset: usize,
+    source: &SourceFile,
+    contract: &Contract,
+) -> Option<StateVariable> {
+    let first = identifier_pattern().find(statement)?.as_str();
+    if matches!(
+        first,
+        "function" | "modifier" | "event" | "error" | "using" | "type" | "struct" | "enum"
+    ) {
+        return None;
+    }
+
+    // The declaration ends at the initializer, the first `=` that isn't part of `=>`.
+    let bytes = statement.as_bytes();
+    let mut depth = 0usize;
+    let mut end = statement.len();
+    for (pos, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth = depth.saturating_sub(1),
+            b'=' if depth == 0 && bytes.get(pos + 1) != Some(&b'>') => {
+                end = pos;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let declaration = &statement[..end];
+    let name = identifier_pattern().find_iter(declaration).last()?;
+    if name.start() == identifier_pattern().find(declaration)?.start() {
+        return None;
+    }
+
+    let visibility = visibility_pattern()
+        .captures(declaration)
+        .map_or(Visibility::Internal, |vis| match &vis[1] {
+            "external" => Visibility::External,
+            "public" => Visibility::Public,
+            "private" => Visibility::Private,
+            _ => Visibility::Internal,
+        });
+    let ty = variable_keyword_pattern().replace_all(&declaration[..name.start()], " ");
+    Some(StateVariable {
+        name: name.as_str().to_string(),
+        contract: contract.name.clone(),
+        ty: ty.split_whitespace().collect::<Vec<_>>().join(" "),
+        start: offset + statement.len() - statement.trim_start().len(),
+        visibility,
+        name_offset: offset + name.start(),
+        line: source.line_of(offset + name.start()),
+    })
+}
+
+/// Parses a comma-separated parameter list, without its surrounding parentheses or any
+/// comments, which `SourceFile::code_only` blanks.
+pub fn parse_params(list: &str) -> Vec<Param> {
+    let mut params = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+
+    for (pos, c) in list.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                params.push(&list[start..pos]);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(&list[start..]);
+
+    params
+        .into_iter()
+        .filter(|param| !param.trim().is_empty())
+        .map(parse_param)
+        .collect()
+}
+
+/// Parses a single declaration such as `uint256[] calldata amounts`, keeping its type.
+fn parse_param(param: &str) -> Param {
+    let compact = param.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Keep array brackets and mapping/function type arguments attached to their type.
+    let compact = compact
+        .replace(" [", "[")
+        .replace("[ ", "[")
+        .replace(" ]", "]")
+        .replace(" (", "(");
+
+    let mut depth = 0i32;
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in compact.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        if c == ' ' && depth == 0 {
+            words.push(std::mem::take(&mut word));
+        } else {
+            word.push(c);
+        }
+    }
+    words.push(word);
+
+    let mut words = words.into_iter().filter(|w| !w.is_empty());
+    let ty = words.next().unwrap_or_default();
+    let name = words
+        .next_back()
+        .filter(|word| !matches!(word.as_str(), "memory" | "calldata" | "storage" | "indexed" | "payable"));
+
+    Param { ty, name }
+}
+
+/// Finds the first occurrence of `byte` at or after `from` that is in code.
+fn find_code_byte(source: &SourceFile, from: usize, byte: u8) -> Option<usize> {
+    source.content.as_bytes()[from..]
+        .iter()
+        .enumerate()
+        .filter(|(_, &b)| b == byte)
+        .map(|(i, _)| from + i)
+        .find(|&pos| source.context_at(pos) == Context::Code)
+}
@@ -0,0 +1,709 @@
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{Error, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::exclusions::PathExclusions;
+use crate::timing::{self, Counter};
+
+/// Lexical context of a position within Solidity source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    Code,
+    Comment,
+    String,
+}
+
+impl Context {
+    pub fn label(self) -> &'static str {
+        match self {
+            Context::Code => "code",
+            Context::Comment => "comment",
+            Context::String => "string",
+        }
+    }
+}
+
+/// A source file along with its line boundaries and comment/string spans.
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub content: String,
+    line_starts: Vec<usize>,
+    spans: Vec<(usize, usize, Context)>,
+}
+
+impl SourceFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        timing::count(Counter::FilesRead, 1);
+        Ok(Self::new(path.to_path_buf(), content))
+    }
+
+    pub fn new(path: PathBuf, content: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(pos, _)| pos + 1))
+            .collect();
+        let spans = scan_spans(&content);
+
+        SourceFile {
+            path,
+            content,
+            line_starts,
+            spans,
+        }
+    }
+
+    /// Returns the 1-based line number containing `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        }
+    }
+
+    /// Returns the text of a 1-based line, without its trailing newline.
+    pub fn line_text(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.content.len(), |next| next - 1);
+        self.content[start..end].trim_end_matches('\r')
+    }
+
+    /// Returns whether `offset` falls in code, a comment, or a string literal.
+    pub fn context_at(&self, offset: usize) -> Context {
+        let index = self.spans.partition_point(|(start, _, _)| *start <= offset);
+        match index.checked_sub(1).map(|i| self.spans[i]) {
+            Some((_, end, context)) if offset < end => context,
+            _ => Context::Code,
+        }
+    }
+
+    /// Returns the end of the comment or string containing `offset`, or `offset` itself
+    /// when it is in code.
+    pub fn span_end(&self, offset: usize) -> usize {
+        let index = self.spans.partition_point(|(start, _, _)| *start <= offset);
+        match index.checked_sub(1).map(|i| self.spans[i]) {
+            Some((_, end, _)) if offset < end => end,
+            _ => offset,
+        }
+    }
+
+    /// Returns the text of `span` with every comment and string replaced by spaces, so
+    /// that offsets into it match offsets into the content.
+    pub fn code_only(&self, span: (usize, usize)) -> String {
+        let mut bytes = self.content.as_bytes()[span.0..span.1].to_vec();
+        for &(start, end, _) in &self.spans {
+            for pos in start.max(span.0)..end.min(span.1) {
+                if bytes[pos - span.0] != b'\n' {
+                    bytes[pos - span.0] = b' ';
+                }
+            }
+        }
+        String::from_utf8(bytes).unwrap_or_default()
+    }
+
+    /// Returns a copy of the source with the delimiters of every comment blanked to
+    /// spaces, so that commented-out code reads as code at the same offsets.
+    pub fn uncommented(&self) -> SourceFile {
+        let mut bytes = self.content.as_bytes().to_vec();
+        for &(start, end, context) in &self.spans {
+            if context != Context::Comment {
+                continue;
+            }
+            if bytes[start + 1] == b'/' {
+                for byte in bytes[start..end].iter_mut().take_while(|byte| **byte == b'/') {
+                    *byte = b' ';
+                }
+            } else {
+                bytes[start..start + 2].fill(b' ');
+                if end - start >= 4 && self.content[..end].ends_with("*/") {
+                    bytes[end - 2..end].fill(b' ');
+                }
+            }
+        }
+        SourceFile::new(self.path.clone(), String::from_utf8(bytes).unwrap_or_default())
+    }
+
+    /// Returns the spans of the comments directly preceding `offset`, separated from it
+    /// and from each other only by whitespace, in source order.
+    pub fn preceding_comments(&self, offset: usize) -> Vec<(usize, usize)> {
+        let mut comments = Vec::new();
+        let mut cursor = offset;
+        let index = self.spans.partition_point(|(start, _, _)| *start < offset);
+        for &(start, end, context) in self.spans[..index].iter().rev() {
+            if context != Context::Comment
+                || end > cursor
+                || !self.content[end..cursor].trim().is_empty()
+            {
+                break;
+            }
+            comments.push((start, end));
+            cursor = start;
+        }
+        comments.reverse();
+        comments
+    }
+
+    /// Given the offset of a `{`, returns the offset just past its matching `}`.
+    pub fn block_end(&self, open: usize) -> Option<usize> {
+        self.matching_end(open, b'{', b'}')
+    }
+
+    /// Given the offset of a `(`, returns the offset just past its matching `)`.
+    pub fn group_end(&self, open: usize) -> Option<usize> {
+        self.matching_end(open, b'(', b')')
+    }
+
+    fn matching_end(&self, open: usize, open_byte: u8, close_byte: u8) -> Option<usize> {
+        let mut depth = 0usize;
+        for (pos, &byte) in self.content.as_bytes().iter().enumerate().skip(open) {
+            if (byte == open_byte || byte == close_byte) && self.context_at(pos) == Context::Code {
+                if byte == open_byte {
+                    depth += 1;
+                } else {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(pos + 1);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Resolves `path` to its canonical form, falling back to the path as given.
+pub fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// What makes two paths the same file: its device and inode on Unix, so that hard links
+/// and the other spellings of a path on a case-insensitive filesystem are one file, and
+/// its canonical path elsewhere or when its metadata can't be read.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FileId {
+    Inode(u64, u64),
+    Path(PathBuf),
+}
+
+pub fn file_id(path: &Path) -> FileId {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let Ok(metadata) = fs::metadata(path) {
+            return FileId::Inode(metadata.dev(), metadata.ino());
+        }
+    }
+    FileId::Path(canonical(path))
+}
+
+/// Keeps the first of the paths to each file, leaving the others out.
+pub fn dedup_files(files: &mut Vec<PathBuf>) {
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file_id(file)));
+}
+
+/// Formats a path the same way on every platform, without a leading `./`.
+pub fn portable_path(path: &Path) -> String {
+    let path = path.display().to_string().replace('\\', "/");
+    path.strip_prefix("./").map(str::to_string).unwrap_or(path)
+}
+
+/// `path` without leading `./` components.
+pub fn relative(path: &Path) -> PathBuf {
+    path.components()
+        .skip_while(|component| *component == Component::CurDir)
+        .collect()
+}
+
+/// Parses a path argument, expanding `~` and environment variables as a shell would.
+pub fn expand_path(path: &str) -> std::result::Result<PathBuf, String> {
+    expand(path).map(PathBuf::from)
+}
+
+/// Expands a leading `~` to the home directory, and `$VAR` or `${VAR}` to the value of
+/// the environment variable, since the shell doesn't for arguments from an argument file
+/// or a config file. An unset `$VAR` is kept as written, since `$` is valid in file names,
+/// e.g. `src/$Token.sol`, but an unset `${VAR}` is an error.
+pub fn expand(text: &str) -> std::result::Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = text;
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            let home = env::var("HOME")
+                .or_else(|_| env::var("USERPROFILE"))
+                .map_err(|_| format!("can't expand `~` in {:?}: HOME is not set", text))?;
+            expanded.push_str(&home);
+            rest = after;
+        }
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let braced = after.starts_with('{');
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let close = braced
+                    .find('}')
+                    .ok_or_else(|| format!("unclosed `${{` in {:?}", text))?;
+                (&braced[..close], &braced[close + 1..])
+            }
+            None if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                after.split_at(end)
+            }
+            None => ("", after),
+        };
+        if name.is_empty() {
+            // A `$` that doesn't start a variable name is kept as written.
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        match env::var(name) {
+            Ok(value) => expanded.push_str(&value),
+            Err(_) if braced => return Err(format!("environment variable {} in {:?} is not set", name, text)),
+            Err(_) => {
+                expanded.push('$');
+                expanded.push_str(name);
+            }
+        }
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Replaces the content of `path` in one step, by writing a temporary file next to it and
+/// renaming it over the original, so that anything reading the file meanwhile sees either
+/// the old content or the new, never part of it. A symbolic link is followed, so that its
+/// target is replaced and the link kept, rather than the link replaced by a copy.
+pub fn replace_file(path: &Path, content: &str) -> Result<()> {
+    let (target, temporary) = stage(path, content)?;
+    fs::rename(&temporary, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+/// Replaces the content of several files like `replace_file`, but only once the temporary
+/// files of all of them are written, so that running out of space or permissions on one
+/// file leaves every file as it was, rather than some of them changed.
+pub fn replace_files(files: &[(&Path, &str)]) -> Result<()> {
+    let mut staged = Vec::with_capacity(files.len());
+    for &(path, content) in files {
+        match stage(path, content) {
+            Ok(file) => staged.push(file),
+            Err(e) => {
+                for (_, temporary) in &staged {
+                    let _ = fs::remove_file(temporary);
+                }
+                return Err(Error::new(
+                    e.kind(),
+                    format!("Failed to write {:?}: {}; no file was changed.", path, e),
+                ));
+            }
+        }
+    }
+    for (i, (target, temporary)) in staged.iter().enumerate() {
+        if let Err(e) = fs::rename(temporary, target) {
+            for (_, temporary) in &staged[i..] {
+                let _ = fs::remove_file(temporary);
+            }
+            return Err(Error::new(
+                e.kind(),
+                format!("Failed to replace {:?}: {}; the {} file(s) before it were changed.", target, e, i),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Writes `content` to a temporary file next to what `path` points to, with its
+/// permissions, returning that target and the temporary file to rename over it.
+fn stage(path: &Path, content: &str) -> Result<(PathBuf, PathBuf)> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = target.with_file_name(format!(".{}.wand-tmp", name));
+    fs::write(&temporary, content)?;
+    if let Ok(metadata) = fs::metadata(&target) {
+        fs::set_permissions(&temporary, metadata.permissions()).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })?;
+    }
+    Ok((target, temporary))
+}
+
+/// The extensions of Solidity files, which are matched regardless of case, so that
+/// `Token.SOL` from a case-insensitive checkout is analyzed too.
+pub const SOL_EXTENSIONS: &[&str] = &["sol"];
+
+/// Whether `path` has one of `extensions`, ignoring case.
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|extension| ext.eq_ignore_ascii_case(extension)))
+}
+
+/// Recursively collects every `.sol` file under `dir`, sorted.
+pub fn collect_sol_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    collect_files(dir, SOL_EXTENSIONS)
+}
+
+/// Recursively collects every file under `dir` with one of `extensions`, sorted, each
+/// once. Subdirectories are read in parallel, since on network filesystems and in large
+/// trees the walk is slower than it looks.
+///
+/// Symbolic links to somewhere else under `dir` are skipped, since what they point to is
+/// collected by its own path, which also keeps a link to a parent from looping. So are
+/// the subdirectories that can't be read, see `read_subdir`, but `dir` has to be readable.
+pub fn collect_files(dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    collect_files_where(dir, extensions, &|_| true)
+}
+
+/// Like `collect_files`, for the files searched for usages in other languages: hidden
+/// directories and those named in `skipped_dirs`, i.e. dependencies and build output,
+/// aren't walked, and the paths `excluded` by `--no-match-path`, `--ignore-paths-from`
+/// or a `.wandignore` are left out.
+pub fn collect_searched_files(
+    dir: &Path,
+    extensions: &[&str],
+    skipped_dirs: &[&str],
+    excluded: &PathExclusions,
+) -> Result<Vec<PathBuf>> {
+    collect_files_where(dir, extensions, &|path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let skipped = path.is_dir() && (name.starts_with('.') || skipped_dirs.contains(&name.as_ref()));
+        !skipped && !excluded.excludes(path)
+    })
+}
+
+/// Collects the files under `dir` with one of `extensions`, leaving out the files and
+/// directories that `walked` rejects.
+fn collect_files_where(dir: &Path, extensions: &[&str], walked: &Walked<'_>) -> Result<Vec<PathBuf>> {
+    let mut files = walk_files(read_dir_paths(dir)?, &canonical(dir), extensions, walked)?;
+    files.sort();
+    dedup_files(&mut files);
+    Ok(files)
+}
+
+/// Whether `path` is a symbolic link to somewhere under the canonical `root`, which a walk
+/// of `root` reaches by its own path anyway.
+pub fn is_link_within(path: &Path, root: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink()) && canonical(path).starts_with(root)
+}
+
+/// Whether an unreadable directory fails the run instead of being skipped, set from
+/// `vacuum --strict-fs`.
+static STRICT_FS: AtomicBool = AtomicBool::new(false);
+
+/// The directories skipped so far, in the order they were found.
+static SKIPPED_DIRS: Mutex<Vec<SkippedDir>> = Mutex::new(Vec::new());
+
+/// The files skipped so far, in the order they were found.
+static SKIPPED_FILES: Mutex<Vec<SkippedFile>> = Mutex::new(Vec::new());
+
+/// A directory left out of a walk because it couldn't be read, e.g. a root-owned mount or
+/// a broken network share, so that the files under it are neither analyzed nor searched.
+#[derive(Serialize, Clone, Debug)]
+pub struct SkippedDir {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// A file left out of the analysis or of the search for usages without failing the run,
+/// e.g. one larger than `--max-file-size` or that isn't valid UTF-8.
+#[derive(Serialize, Clone, Debug)]
+pub struct SkippedFile {
+    pub file: PathBuf,
+    pub reason: String,
+}
+
+/// Records that `file` was skipped, once however often it is, returning whether it
+/// wasn't already.
+pub fn skip_file(file: &Path, reason: String) -> bool {
+    let Ok(mut skipped) = SKIPPED_FILES.lock() else {
+        return false;
+    };
+    let id = file_id(file);
+    if skipped.iter().any(|skipped| file_id(&skipped.file) == id) {
+        return false;
+    }
+    skipped.push(SkippedFile {
+        file: relative(file),
+        reason,
+    });
+    true
+}
+
+/// The files skipped so far, each once.
+pub fn skipped_files() -> Vec<SkippedFile> {
+    SKIPPED_FILES.lock().map(|skipped| skipped.clone()).unwrap_or_default()
+}
+
+pub fn set_strict_fs(strict: bool) {
+    STRICT_FS.store(strict, Ordering::Relaxed);
+}
+
+/// The directories skipped so far, each once.
+pub fn skipped_dirs() -> Vec<SkippedDir> {
+    SKIPPED_DIRS.lock().map(|skipped| skipped.clone()).unwrap_or_default()
+}
+
+/// Takes the directories skipped so far, so that whoever reports them doesn't leave them
+/// to be reported again.
+pub fn take_skipped_dirs() -> Vec<SkippedDir> {
+    SKIPPED_DIRS.lock().map(|mut skipped| std::mem::take(&mut *skipped)).unwrap_or_default()
+}
+
+/// Warns about the directories that couldn't be read on stderr, for the reports that
+/// go to other tools and for the commands without a report of their own.
+pub fn warn_skipped_dirs(skipped_dirs: &[SkippedDir]) {
+    for skipped in skipped_dirs {
+        eprintln!("Warning: skipped unreadable directory {:?}: {}", skipped.path, skipped.error);
+    }
+}
+
+fn read_dir_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.path())).collect()
+}
+
+/// The paths of the entries of `dir`, a directory found while walking another. One that
+/// can't be read is recorded and skipped as empty, so that it doesn't end a run that may
+/// have taken minutes, unless `--strict-fs` is set.
+pub fn read_subdir(dir: &Path) -> Result<Vec<PathBuf>> {
+    read_dir_paths(dir).or_else(|e| {
+        if STRICT_FS.load(Ordering::Relaxed) {
+            return Err(Error::new(e.kind(), format!("Failed to read directory {:?}: {}", dir, e)));
+        }
+        if let Ok(mut skipped) = SKIPPED_DIRS.lock() {
+            let id = file_id(dir);
+            if !skipped.iter().any(|skipped| file_id(&skipped.path) == id) {
+                skipped.push(SkippedDir {
+                    path: dir.to_path_buf(),
+                    error: e.to_string(),
+                });
+            }
+        }
+        Ok(Vec::new())
+    })
+}
+
+/// Whether a walk keeps a file or enters a directory.
+type Walked<'a> = dyn Fn(&Path) -> bool + Sync + 'a;
+
+fn walk_files(entries: Vec<PathBuf>, root: &Path, extensions: &[&str], walked: &Walked<'_>) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut subdirs = Vec::new();
+    for path in entries {
+        if is_link_within(&path, root) || !walked(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            subdirs.push(path);
+        } else {
+            timing::count(Counter::FilesWalked, 1);
+            if has_extension(&path, extensions) {
+                files.push(path);
+            }
+        }
+    }
+
+    let nested = subdirs
+        .par_iter()
+        .map(|subdir| walk_files(read_subdir(subdir)?, root, extensions, walked))
+        .collect::<Result<Vec<_>>>()?;
+    files.extend(nested.into_iter().flatten());
+    Ok(files)
+}
+
+/// Finds the byte ranges covered by comments and string literals.
+fn scan_spans(content: &str) -> Vec<(usize, usize, Context)> {
+    let bytes = content.as_bytes();
+    let mut spans = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+                spans.push((start, pos, Context::Comment));
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos += 2;
+                while pos < bytes.len() && !bytes[pos..].starts_with(b"*/") {
+                    pos += 1;
+                }
+                pos = (pos + 2).min(bytes.len());
+                spans.push((start, pos, Context::Comment));
+            }
+            quote @ (b'"' | b'\'') => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != quote && bytes[pos] != b'\n' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+                spans.push((start, pos, Context::String));
+            }
+            _ => pos += 1,
+        }
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_replaces_set_variables() {
+        env::set_var("WAND_TEST_EXPAND_SET", "/work");
+        assert_eq!(expand("$WAND_TEST_EXPAND_SET/src").unwrap(), "/work/src");
+        assert_eq!(expand("${WAND_TEST_EXPAND_SET}src").unwrap(), "/worksrc");
+    }
+
+    #[test]
+    fn expand_keeps_unset_variables_without_braces() {
+        env::remove_var("WAND_TEST_EXPAND_UNSET");
+        assert_eq!(expand("src/$WAND_TEST_EXPAND_UNSET.sol").unwrap(), "src/$WAND_TEST_EXPAND_UNSET.sol");
+        assert_eq!(expand("src/$1/a$").unwrap(), "src/$1/a$");
+        let error = expand("${WAND_TEST_EXPAND_UNSET}/src").unwrap_err();
+        assert!(error.contains("WAND_TEST_EXPAND_UNSET"), "{}", error);
+        assert!(expand("${WAND_TEST_EXPAND_UNSET").is_err());
+    }
+
+    #[test]
+    fn expand_replaces_a_leading_tilde_only() {
+        let home = env::var("HOME").unwrap();
+        assert_eq!(expand("~/src").unwrap(), format!("{}/src", home));
+        assert_eq!(expand("~").unwrap(), home);
+        assert_eq!(expand("~user/src").unwrap(), "~user/src");
+        assert_eq!(expand("src/~").unwrap(), "src/~");
+    }
+
+    #[test]
+    fn replace_file_replaces_the_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("A.sol");
+        fs::write(&path, "old").unwrap();
+        replace_file(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replace_file_keeps_symbolic_links() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("shared")).unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        let target = dir.path().join("shared/A.sol");
+        let link = dir.path().join("src/A.sol");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink("../shared/A.sol", &link).unwrap();
+
+        replace_file(&link, "new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path().join("src")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn replace_files_changes_nothing_when_one_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("A.sol");
+        let second = dir.path().join("B.sol");
+        fs::write(&first, "old").unwrap();
+        fs::write(&second, "old").unwrap();
+        let missing = dir.path().join("missing/C.sol");
+
+        let error = replace_files(&[(&first, "new"), (&missing, "new"), (&second, "new")]).unwrap_err();
+        assert!(error.to_string().contains("no file was changed"), "{}", error);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "old");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        replace_files(&[(&first, "new"), (&second, "new")]).unwrap();
+        assert_eq!(fs::read_to_string(&first).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "new");
+    }
+
+    #[test]
+    fn uncommented_blanks_only_the_comment_delimiters() {
+        let source = SourceFile::new(
+            PathBuf::from("A.sol"),
+            "a(); // b();\n/// c();\n/* d(); */ e(\"// f\");\n/**/".to_string(),
+        );
+        let uncommented = source.uncommented();
+        assert_eq!(uncommented.content, "a();    b();\n    c();\n   d();    e(\"// f\");\n    ");
+        assert_eq!(uncommented.content.len(), source.content.len());
+        assert_eq!(uncommented.context_at(uncommented.content.find("b()").unwrap()), Context::Code);
+        assert_eq!(uncommented.context_at(uncommented.content.find("f").unwrap()), Context::String);
+    }
+
+    /// A tree of `dirs` directories `depth` levels deep below `root`, each with `files` Solidity
+    /// files and a text file, created in an order unrelated to their sorted one.
+    fn synthetic_tree(root: &Path, dirs: usize, depth: usize, files: usize) -> Vec<PathBuf> {
+        let mut expected = Vec::new();
+        for dir in (0..dirs).rev() {
+            let mut path = root.to_path_buf();
+            for level in 0..depth {
+                path.push(format!("d{}_{}", dir, level));
+            }
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("README.md"), "").unwrap();
+            for file in (0..files).rev() {
+                let file = path.join(format!("C{}.sol", file));
+                fs::write(&file, "contract C {}").unwrap();
+                expected.push(file);
+            }
+        }
+        expected.sort();
+        expected
+    }
+
+    #[test]
+    fn collect_sol_files_sorts_a_large_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let expected = synthetic_tree(dir.path(), 64, 3, 16);
+        for _ in 0..3 {
+            assert_eq!(collect_sol_files(dir.path()).unwrap(), expected);
+        }
+    }
+
+    /// Times the walk of a large tree, e.g. to compare the parallel walk with a serial one
+    /// under `RAYON_NUM_THREADS=1`: `cargo test --release walk_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn walk_benchmark() {
+        let dir = tempfile::tempdir().unwrap();
+        let expected = synthetic_tree(dir.path(), 2_000, 4, 25);
+        let mut runs = Vec::new();
+        for _ in 0..5 {
+            let start = std::time::Instant::now();
+            assert_eq!(collect_sol_files(dir.path()).unwrap().len(), expected.len());
+            runs.push(start.elapsed());
+        }
+        runs.sort();
+        println!(
+            "collected {} files: median {:?}, fastest {:?} on {} thread(s)",
+            expected.len(),
+            runs[runs.len() / 2],
+            runs[0],
+            rayon::current_num_threads()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Whether phases are timed and counted, set from `vacuum --timing`.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The parts of a run that `--timing` breaks the time down into.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Walking directories and loading the files that are analyzed or searched.
+    Collection,
+    /// Parsing the declarations of the analyzed files.
+    Extraction,
+    /// Searching every file for occurrences of the declared names.
+    Counting,
+    /// Printing or serializing the report.
+    Reporting,
+    /// Removing functions from the files with `--delete`.
+    Deletion,
+}
+
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Collection => "collection",
+            Phase::Extraction => "extraction",
+            Phase::Counting => "counting",
+            Phase::Reporting => "reporting",
+            Phase::Deletion => "deletion",
+        }
+    }
+}
+
+const PHASES: [Phase; 5] = [
+    Phase::Collection,
+    Phase::Extraction,
+    Phase::Counting,
+    Phase::Reporting,
+    Phase::Deletion,
+];
+
+/// What `--timing` counts besides time.
+#[derive(Clone, Copy)]
+pub enum Counter {
+    /// Files seen while walking directories for `.sol` files.
+    FilesWalked,
+    /// Files read from disk, once per read, so a file searched for each analyzed file
+    /// counts each time.
+    FilesRead,
+    /// Bytes searched for occurrences.
+    BytesScanned,
+    /// Occurrences of declared names found.
+    MatchesFound,
+}
+
+/// The time spent in a phase, summed over the threads that ran it, and its first start
+/// and last end since the start of the run, in nanoseconds.
+struct PhaseClock {
+    busy: AtomicU64,
+    first_start: AtomicU64,
+    last_end: AtomicU64,
+}
+
+struct Clocks {
+    start: Instant,
+    phases: [PhaseClock; 5],
+    counters: [AtomicU64; 4],
+}
+
+fn clocks() -> &'static Clocks {
+    static CLOCKS: OnceLock<Clocks> = OnceLock::new();
+    CLOCKS.get_or_init(|| Clocks {
+        start: Instant::now(),
+        phases: std::array::from_fn(|_| PhaseClock {
+            busy: AtomicU64::new(0),
+            first_start: AtomicU64::new(u64::MAX),
+            last_end: AtomicU64::new(0),
+        }),
+        counters: std::array::from_fn(|_| AtomicU64::new(0)),
+    })
+}
+
+/// Starts the clock of the run, which the phases are measured from.
+pub fn enable() {
+    clocks();
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Times `phase` until the returned guard is dropped, doing nothing unless enabled.
+/// Parallel work is timed on each worker, so that the phase's CPU time is the sum.
+pub fn time(phase: Phase) -> Timer {
+    Timer {
+        running: enabled().then(|| (phase, Instant::now())),
+    }
+}
+
+pub fn count(counter: Counter, amount: usize) {
+    if enabled() {
+        clocks().counters[counter as usize].fetch_add(amount as u64, Ordering::Relaxed);
+    }
+}
+
+pub struct Timer {
+    running: Option<(Phase, Instant)>,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let Some((phase, started)) = self.running else {
+            return;
+        };
+        let clocks = clocks();
+        let nanos = |instant: Instant| instant.saturating_duration_since(clocks.start).as_nanos() as u64;
+        let clock = &clocks.phases[phase as usize];
+        clock.busy.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        clock.first_start.fetch_min(nanos(started), Ordering::Relaxed);
+        clock.last_end.fetch_max(nanos(Instant::now()), Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize)]
+pub struct PhaseTiming {
+    pub phase: Phase,
+    /// From the phase's first start to its last end, which overlaps other phases when
+    /// they run interleaved, e.g. extraction and counting on different files.
+    pub wall_ms: f64,
+    /// The time spent in the phase, summed over every thread that ran it.
+    pub cpu_ms: f64,
+}
+
+/// The times and counters of the run so far, as embedded in JSON reports under `timing`.
+#[derive(Serialize)]
+pub struct Timing {
+    pub phases: Vec<PhaseTiming>,
+    pub total_ms: f64,
+    pub files_walked: u64,
+    pub files_read: u64,
+    pub bytes_scanned: u64,
+    pub matches_found: u64,
+}
+
+fn millis(nanos: u64) -> f64 {
+    Duration::from_nanos(nanos).as_secs_f64() * 1000.0
+}
+
+/// The times and counters so far.
+pub fn snapshot() -> Timing {
+    let clocks = clocks();
+    let counter = |counter: Counter| clocks.counters[counter as usize].load(Ordering::Relaxed);
+    Timing {
+        phases: PHASES
+            .iter()
+            .map(|&phase| {
+                let clock = &clocks.phases[phase as usize];
+                let first_start = clock.first_start.load(Ordering::Relaxed);
+                let last_end = clock.last_end.load(Ordering::Relaxed);
+                PhaseTiming {
+                    phase,
+                    wall_ms: millis(last_end.saturating_sub(first_start)),
+                    cpu_ms: millis(clock.busy.load(Ordering::Relaxed)),
+                }
+            })
+            .collect(),
+        total_ms: clocks.start.elapsed().as_secs_f64() * 1000.0,
+        files_walked: counter(Counter::FilesWalked),
+        files_read: counter(Counter::FilesRead),
+        bytes_scanned: counter(Counter::BytesScanned),
+        matches_found: counter(Counter::MatchesFound),
+    }
+}
+
+impl Timing {
+    /// A table of the phases, followed by the counters.
+    pub fn table(&self) -> String {
+        let mut table = format!("Timing:\n  {:<12} {:>12} {:>12}\n", "phase", "wall ms", "cpu ms");
+        for phase in &self.phases {
+            let _ = writeln!(
+                table,
+                "  {:<12} {:>12.1} {:>12.1}",
+                phase.phase.as_str(),
+                phase.wall_ms,
+                phase.cpu_ms
+            );
+        }
+        let _ = writeln!(table, "  {:<12} {:>12.1}", "total", self.total_ms);
+        let _ = write!(
+            table,
+            "Files walked: {}, files read: {}, bytes scanned: {}, matches found: {}",
+            self.files_walked, self.files_read, self.bytes_scanned, self.matches_found
+        );
+        table
+    }
+}
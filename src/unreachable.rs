@@ -0,0 +1,339 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::solidity::{Function, SourceUnit, Visibility};
+use crate::source::SourceFile;
+
+/// Code inside a function body that can never run.
+#[derive(Serialize, Clone)]
+pub struct UnreachableCode {
+    pub function: String,
+    pub contract: Option<String>,
+    /// The function's name and parameter types as written, e.g. `sweep(address)`.
+    pub signature: String,
+    /// The code of the function, which its finding is fingerprinted with.
+    #[serde(skip)]
+    pub context: String,
+    #[serde(skip)]
+    pub visibility: Visibility,
+    /// The line of the first unreachable statement.
+    pub line: usize,
+    /// How many lines the unreachable code spans.
+    pub lines: usize,
+    /// Why it never runs, e.g. `after return` or `behind condition DEBUG, always false`.
+    pub cause: String,
+}
+
+impl UnreachableCode {
+    /// Its size and cause, e.g. `3 lines after return`.
+    pub fn describe(&self) -> String {
+        let lines = if self.lines == 1 { "line" } else { "lines" };
+        format!("{} {} {}", self.lines, lines, self.cause)
+    }
+}
+
+fn constant_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\bbool\s+(?:(?:public|internal|private)\s+)?constant\s+([A-Za-z_$][\w$]*)\s*=\s*(true|false)\s*;")
+            .unwrap()
+    })
+}
+
+/// Finds the unreachable code in the function bodies of a file: branches behind
+/// conditions that are always false, i.e. boolean literals or `bool constant`s of the
+/// file initialized to one, and statements following a `return` or `revert` in the same
+/// block. Assembly blocks aren't looked into.
+pub fn scan(source: &SourceFile, unit: &SourceUnit) -> Vec<UnreachableCode> {
+    let code = source.code_only((0, source.content.len()));
+    let mut constants: HashMap<&str, Option<bool>> = HashMap::new();
+    for captures in constant_pattern().captures_iter(&code) {
+        let (name, value) = (captures.get(1).map_or("", |name| name.as_str()), &captures[2] == "true");
+        // A constant declared twice with different values, in different contracts, is
+        // left alone.
+        let entry = constants.entry(name).or_insert(Some(value));
+        if *entry != Some(value) {
+            *entry = None;
+        }
+    }
+    let constants: HashMap<&str, bool> = constants
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect();
+
+    let mut found = Vec::new();
+    for function in &unit.functions {
+        let Some((open, close)) = function.body else {
+            continue;
+        };
+        let mut scanner = Scanner {
+            code: &code,
+            constants: &constants,
+            spans: Vec::new(),
+        };
+        scanner.block(open, close - 1);
+        for (start, end, cause) in scanner.spans {
+            found.push(unreachable(source, function, start, end, cause));
+        }
+    }
+    found
+}
+
+fn unreachable(source: &SourceFile, function: &Function, start: usize, end: usize, cause: String) -> UnreachableCode {
+    let line = source.line_of(start);
+    UnreachableCode {
+        function: function.name.clone(),
+        contract: function.contract.clone(),
+        signature: function.written_signature(),
+        context: function.code(source),
+        visibility: function.visibility,
+        line,
+        lines: source.line_of(end) - line + 1,
+        cause,
+    }
+}
+
+/// Walks the statements of function bodies, in code with comments and strings blanked.
+struct Scanner<'a> {
+    code: &'a str,
+    constants: &'a HashMap<&'a str, bool>,
+    /// The unreachable spans found, as the offsets of their first and last characters.
+    spans: Vec<(usize, usize, String)>,
+}
+
+impl Scanner<'_> {
+    /// Scans the block from the `{` at `open` to the `}` at `close`.
+    fn block(&mut self, open: usize, close: usize) {
+        let mut pos = open + 1;
+        while let Some(start) = self.skip_whitespace(pos, close) {
+            let (end, terminator) = self.statement(start, close);
+            match terminator {
+                Some(terminator) => {
+                    if let Some(next) = self.skip_whitespace(end, close) {
+                        let last = self.last_code(next, close);
+                        self.spans.push((next, last, format!("after {}", terminator)));
+                    }
+                    return;
+                }
+                None => pos = end,
+            }
+        }
+    }
+
+    /// Scans the statement at `start`, returning the offset just past it, and the keyword
+    /// ending the block there if it is a `return` or `revert`.
+    fn statement(&mut self, start: usize, limit: usize) -> (usize, Option<&'static str>) {
+        let word = self.word_at(start);
+        match word {
+            "if" => (self.if_statement(start, limit), None),
+            "while" | "for" => {
+                let Some((condition, after)) = self.condition(start + word.len(), limit) else {
+                    return (limit, None);
+                };
+                let dead = condition
+                    .filter(|(value, _)| word == "while" && !value)
+                    .map(|(_, condition)| format!("in a loop on condition {}, always false", condition));
+                (self.branch(after, limit, dead.as_deref()), None)
+            }
+            "do" => {
+                let end = self.branch(start + 2, limit, None);
+                (self.simple_statement(end, limit), None)
+            }
+            "unchecked" => match self.skip_whitespace(start + word.len(), limit) {
+                Some(open) if self.byte(open) == b'{' => (self.branch(open, limit, None), None),
+                _ => (self.simple_statement(start, limit), None),
+            },
+            "assembly" => match self.code[start..limit].find('{') {
+                Some(open) => (self.matching(start + open, limit).map_or(limit, |close| close + 1), None),
+                None => (limit, None),
+            },
+            "try" => (self.try_statement(start, limit), None),
+            _ if self.byte(start) == b'{' => (self.branch(start, limit, None), None),
+            "return" => (self.simple_statement(start, limit), Some("return")),
+            "revert" if self.is_revert(start) => (self.simple_statement(start, limit), Some("revert")),
+            _ => (self.simple_statement(start, limit), None),
+        }
+    }
+
+    /// Scans `if (condition) then [else otherwise]`, recording the branch that never runs
+    /// when the condition is constant.
+    fn if_statement(&mut self, start: usize, limit: usize) -> usize {
+        let Some((condition, after)) = self.condition(start + 2, limit) else {
+            return limit;
+        };
+        let then_dead = condition
+            .as_ref()
+            .filter(|(value, _)| !value)
+            .map(|(_, condition)| format!("behind condition {}, always false", condition));
+        let else_dead = condition
+            .as_ref()
+            .filter(|(value, _)| *value)
+            .map(|(_, condition)| format!("in the else of condition {}, always true", condition));
+        let mut end = self.branch(after, limit, then_dead.as_deref());
+        if let Some(next) = self.skip_whitespace(end, limit) {
+            if self.word_at(next) == "else" {
+                end = self.branch(next + 4, limit, else_dead.as_deref());
+            }
+        }
+        end
+    }
+
+    /// Scans `try call returns (...) { } catch ... { }`, whose blocks end it without a `;`.
+    fn try_statement(&mut self, start: usize, limit: usize) -> usize {
+        let mut pos = start + 3;
+        loop {
+            let Some(open) = self.next_block(pos, limit) else {
+                return limit;
+            };
+            pos = self.branch(open, limit, None);
+            match self.skip_whitespace(pos, limit) {
+                Some(next) if self.word_at(next) == "catch" => pos = next + 5,
+                _ => return pos,
+            }
+        }
+    }
+
+    /// Scans the body of an `if`, `else` or loop at `start`, a block or a single
+    /// statement, recording all of it as unreachable when `dead` gives the cause.
+    fn branch(&mut self, start: usize, limit: usize, dead: Option<&str>) -> usize {
+        let Some(start) = self.skip_whitespace(start, limit) else {
+            return limit;
+        };
+        if self.byte(start) == b'{' {
+            let Some(close) = self.matching(start, limit) else {
+                return limit;
+            };
+            match dead {
+                Some(cause) => {
+                    if let Some(first) = self.skip_whitespace(start + 1, close) {
+                        let last = self.last_code(first, close);
+                        self.spans.push((first, last, cause.to_string()));
+                    }
+                }
+                None => self.block(start, close),
+            }
+            close + 1
+        } else {
+            let spans = self.spans.len();
+            let (end, _) = self.statement(start, limit);
+            if let Some(cause) = dead {
+                self.spans.truncate(spans);
+                self.spans.push((start, self.last_code(start, end), cause.to_string()));
+            }
+            end
+        }
+    }
+
+    /// Parses the parenthesized condition after a keyword, returning its value and text
+    /// when it is constant, and the offset past the `)`.
+    fn condition(&self, after_keyword: usize, limit: usize) -> Option<(Option<(bool, &str)>, usize)> {
+        let open = self.skip_whitespace(after_keyword, limit)?;
+        if self.byte(open) != b'(' {
+            return None;
+        }
+        let close = self.matching(open, limit)?;
+        let condition = self.code[open + 1..close].trim();
+        let constant = self.constant_value(condition).map(|value| (value, condition));
+        Some((constant, close + 1))
+    }
+
+    /// The value of a condition made of boolean literals, known constants and `!`.
+    fn constant_value(&self, condition: &str) -> Option<bool> {
+        let condition = condition.trim();
+        if let Some(inner) = condition.strip_prefix('(').and_then(|rest| rest.strip_suffix(')')) {
+            if self.matching_in(condition, 0) == Some(condition.len() - 1) {
+                return self.constant_value(inner);
+            }
+        }
+        if let Some(negated) = condition.strip_prefix('!') {
+            return self.constant_value(negated).map(|value| !value);
+        }
+        match condition {
+            "true" => Some(true),
+            "false" => Some(false),
+            name => self.constants.get(name).copied(),
+        }
+    }
+
+    /// Skips to the end of a statement without nested statements, just past its `;`.
+    fn simple_statement(&self, start: usize, limit: usize) -> usize {
+        let mut depth = 0usize;
+        for (index, byte) in self.code.as_bytes()[start..limit].iter().enumerate() {
+            match byte {
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+                b';' if depth == 0 => return start + index + 1,
+                _ => {}
+            }
+        }
+        limit
+    }
+
+    /// Whether the `revert` at `start` is a statement, `revert(...)` or `revert Error(...)`,
+    /// rather than an identifier.
+    fn is_revert(&self, start: usize) -> bool {
+        let rest = self.code[start + 6..].trim_start();
+        rest.starts_with('(') || rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+    }
+
+    /// The offset of the next `{` outside parentheses, from `start`.
+    fn next_block(&self, start: usize, limit: usize) -> Option<usize> {
+        let mut depth = 0usize;
+        for (index, byte) in self.code.as_bytes()[start..limit].iter().enumerate() {
+            match byte {
+                b'(' => depth += 1,
+                b')' => depth = depth.saturating_sub(1),
+                b'{' if depth == 0 => return Some(start + index),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Given the `(`, `[` or `{` at `open`, the offset of its matching closing byte.
+    fn matching(&self, open: usize, limit: usize) -> Option<usize> {
+        self.matching_in(&self.code[..limit], open)
+    }
+
+    fn matching_in(&self, text: &str, open: usize) -> Option<usize> {
+        let mut depth = 0usize;
+        for (index, byte) in text.as_bytes().iter().enumerate().skip(open) {
+            match byte {
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// The offset of the first non-whitespace byte from `pos`, if any before `limit`.
+    fn skip_whitespace(&self, pos: usize, limit: usize) -> Option<usize> {
+        let offset = self.code[pos..limit].find(|c: char| !c.is_whitespace())?;
+        Some(pos + offset)
+    }
+
+    /// The offset of the last non-whitespace byte between `start` and `end`.
+    fn last_code(&self, start: usize, end: usize) -> usize {
+        self.code[start..end]
+            .rfind(|c: char| !c.is_whitespace())
+            .map_or(start, |offset| start + offset)
+    }
+
+    fn word_at(&self, pos: usize) -> &str {
+        let rest = &self.code[pos..];
+        let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$')).unwrap_or(rest.len());
+        &rest[..len]
+    }
+
+    fn byte(&self, pos: usize) -> u8 {
+        self.code.as_bytes()[pos]
+    }
+}
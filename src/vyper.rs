@@ -0,0 +1,113 @@
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::abi;
+use crate::exclusions::PathExclusions;
+use crate::source::{collect_searched_files, SourceFile};
+
+/// Directories holding dependencies or build output, whose calls aren't the project's.
+const SKIPPED_DIRS: [&str; 4] = ["lib", "node_modules", "out", "cache"];
+
+/// How a Vyper source refers to a function.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VyperKind {
+    /// An interface call, e.g. `extcall self.vault.sweep(to)` or `staticcall
+    /// IVault(vault).balance()`.
+    ExternalCall,
+    /// The selector as a literal, e.g. `0x01681a62`, or computed with
+    /// `method_id("sweep(address)")`.
+    Selector,
+}
+
+impl VyperKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            VyperKind::ExternalCall => "external call",
+            VyperKind::Selector => "selector",
+        }
+    }
+}
+
+/// A reference to a function from a Vyper contract.
+#[derive(Serialize, Clone)]
+pub struct VyperReference {
+    pub file: PathBuf,
+    pub line: usize,
+    pub snippet: String,
+    pub kind: VyperKind,
+}
+
+/// The `.vy` files under the root, which are searched for calls into the Solidity code
+/// but never analyzed themselves.
+#[derive(Default)]
+pub struct VyperSources {
+    sources: Vec<SourceFile>,
+}
+
+impl VyperSources {
+    /// Reads every `.vy` file under `root` that isn't `excluded`, skipping dependencies and
+    /// build output.
+    pub fn load(root: &Path, excluded: &PathExclusions) -> Result<Self> {
+        let sources = collect_searched_files(root, &["vy"], &SKIPPED_DIRS, excluded)?
+            .into_iter()
+            .map(|path| {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                SourceFile::new(path, content)
+            })
+            .collect();
+        Ok(VyperSources { sources })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Finds the references to the externally visible function `name`, whose canonical
+    /// signature is `signature`. Matches after a `#` on the same line are comments and
+    /// don't count.
+    pub fn references(&self, name: &str, signature: &str) -> Vec<VyperReference> {
+        if self.sources.is_empty() {
+            return Vec::new();
+        }
+
+        let escaped = regex::escape(name);
+        let selector = abi::format_selector(abi::selector(signature));
+        let patterns = [
+            (
+                VyperKind::ExternalCall,
+                format!(r"\b(?:extcall|staticcall)\s+[^\n#]*?\.\s*{}\s*\(", escaped),
+            ),
+            (
+                VyperKind::Selector,
+                format!(r#"\bmethod_id\(\s*['"]{}\s*\("#, escaped),
+            ),
+            (VyperKind::Selector, format!(r"(?i)\b{}\b", regex::escape(&selector))),
+        ]
+        .map(|(kind, pattern)| (kind, Regex::new(&pattern).unwrap()));
+
+        let mut references = Vec::new();
+        for source in &self.sources {
+            for (kind, pattern) in &patterns {
+                for found in pattern.find_iter(&source.content) {
+                    let line_start = source.content[..found.start()].rfind('\n').map_or(0, |index| index + 1);
+                    if source.content[line_start..found.start()].contains('#') {
+                        continue;
+                    }
+                    let line = source.line_of(found.start());
+                    references.push(VyperReference {
+                        file: source.path.clone(),
+                        line,
+                        snippet: source.line_text(line).trim().to_string(),
+                        kind: *kind,
+                    });
+                }
+            }
+        }
+        references.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        references
+    }
+}
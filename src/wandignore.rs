@@ -0,0 +1,261 @@
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::source::{self, canonical, relative};
+
+/// The name of the files listing the paths to exclude, in the syntax of `.gitignore`.
+pub const WANDIGNORE: &str = ".wandignore";
+
+/// Directories never searched for `.wandignore` files: those of git, and the packages of
+/// `node_modules`, whose files aren't the project's.
+const UNSEARCHED_DIRS: [&str; 2] = [".git", "node_modules"];
+
+/// A pattern of a `.wandignore` file.
+#[derive(Debug)]
+struct Rule {
+    /// The directory of the file, which the pattern is relative to, as given and canonical.
+    base: PathBuf,
+    canonical_base: PathBuf,
+    glob: Pattern,
+    /// Written with a leading `!`, re-including what an earlier pattern excluded.
+    negated: bool,
+    /// Written with a trailing `/`, matching directories only.
+    dir_only: bool,
+    /// Written with a `/` before its end, matched against the path from `base`; other
+    /// patterns are matched against the name of a file or directory at any depth.
+    anchored: bool,
+    /// Where the pattern is written, e.g. `src/.wandignore:3: vendor/`.
+    origin: String,
+}
+
+impl Rule {
+    /// Parses the `line` numbered `number` of the file at `path`, in directory `base`,
+    /// or `None` for a blank line or a comment.
+    fn parse(line: &str, path: &Path, number: usize, base: &Path) -> Result<Option<Rule>> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let origin = format!("{}:{}: {}", path.display(), number, line);
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            // `\!` and `\#` start patterns with a literal `!` or `#`.
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return Ok(None);
+        }
+        let glob = Pattern::new(pattern).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid pattern {:?} at {}:{}: {}", pattern, path.display(), number, e),
+            )
+        })?;
+        Ok(Some(Rule {
+            base: base.to_path_buf(),
+            canonical_base: canonical(readable(base)),
+            glob,
+            negated,
+            dir_only,
+            anchored,
+            origin,
+        }))
+    }
+
+    /// Whether the pattern matches `path`, spelled like `base` if `lexical` is set and
+    /// canonical otherwise.
+    fn matches(&self, path: &Path, is_dir: bool, lexical: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let base = if lexical { &self.base } else { &self.canonical_base };
+        let Ok(from_base) = path.strip_prefix(base) else {
+            return false;
+        };
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        if self.anchored {
+            self.glob.matches_path_with(from_base, options)
+        } else {
+            from_base
+                .file_name()
+                .is_some_and(|name| self.glob.matches_path_with(Path::new(name), options))
+        }
+    }
+}
+
+/// The patterns of the `.wandignore` files under the project's roots, which exclude paths
+/// like those of `.gitignore`: each file applies below its own directory, later patterns
+/// and those of deeper files win, `!` re-includes what an earlier pattern excluded, and
+/// nothing under an excluded directory can be re-included.
+#[derive(Default, Debug)]
+pub struct WandIgnore {
+    rules: Vec<Rule>,
+    /// The directories excluded, with the pattern excluding each, in the order found.
+    excluded_dirs: Vec<(PathBuf, usize)>,
+}
+
+impl WandIgnore {
+    /// Reads the `.wandignore` files under `roots`, leaving out those of the directories
+    /// that the files above them exclude.
+    pub fn load(roots: &[PathBuf]) -> Result<Self> {
+        let mut wandignore = WandIgnore::default();
+        for root in roots {
+            if root.is_dir() {
+                wandignore.walk(&relative(root))?;
+            }
+        }
+        Ok(wandignore)
+    }
+
+    fn walk(&mut self, dir: &Path) -> Result<()> {
+        let file = dir.join(WANDIGNORE);
+        if file.is_file() {
+            let content = fs::read_to_string(&file)
+                .map_err(|e| Error::new(e.kind(), format!("Failed to read {:?}: {}", file, e)))?;
+            for (index, line) in content.lines().enumerate() {
+                self.rules.extend(Rule::parse(line, &relative(&file), index + 1, dir)?);
+            }
+        }
+        let mut subdirs: Vec<PathBuf> = source::read_subdir(readable(dir))?
+            .into_iter()
+            .filter(|path| path.is_dir() && !fs::symlink_metadata(path).is_ok_and(|meta| meta.is_symlink()))
+            .filter(|path| {
+                let name = path.file_name().and_then(|name| name.to_str());
+                !name.is_some_and(|name| UNSEARCHED_DIRS.contains(&name))
+            })
+            .collect();
+        subdirs.sort();
+        for subdir in subdirs {
+            let subdir = relative(&subdir);
+            match self.last_match(&subdir, true, true) {
+                Some(index) if !self.rules[index].negated => self.excluded_dirs.push((subdir, index)),
+                _ => self.walk(&subdir)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The index of the last pattern matching `path`, which decides whether it's excluded.
+    fn last_match(&self, path: &Path, is_dir: bool, lexical: bool) -> Option<usize> {
+        self.rules.iter().rposition(|rule| rule.matches(path, is_dir, lexical))
+    }
+
+    /// Where the pattern excluding the file at `path`, or one of its directories, is
+    /// written, or `None` if no pattern excludes it.
+    pub fn excluded_by(&self, path: &Path) -> Option<&str> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        // Paths spelled differently than the roots, e.g. absolute ones, are compared
+        // canonically.
+        let lexical = relative(path);
+        let is_lexical = self.rules.iter().any(|rule| lexical.starts_with(&rule.base));
+        let path = if is_lexical { lexical } else { canonical(path) };
+        let mut prefixes: Vec<&Path> = path.ancestors().collect();
+        prefixes.reverse();
+        let last = prefixes.len() - 1;
+        prefixes.iter().enumerate().find_map(|(depth, prefix)| {
+            let index = self.last_match(prefix, depth < last, is_lexical)?;
+            let rule = &self.rules[index];
+            (!rule.negated).then_some(rule.origin.as_str())
+        })
+    }
+
+    /// The directories the patterns exclude, with where the pattern excluding each is
+    /// written. Those under an excluded directory aren't listed.
+    pub fn excluded_dirs(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.excluded_dirs
+            .iter()
+            .map(|(dir, index)| (dir.as_path(), self.rules[*index].origin.as_str()))
+    }
+}
+
+/// `dir`, or `.` for the empty path that `relative` makes of it.
+fn readable(dir: &Path) -> &Path {
+    if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads the `.wandignore` files of `files`, written under a new directory, and returns
+    /// which of `paths` under it are excluded.
+    fn excluded(files: &[(&str, &str)], paths: &[&str]) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let wandignore = WandIgnore::load(&[dir.path().to_path_buf()]).unwrap();
+        paths
+            .iter()
+            .filter(|path| wandignore.excluded_by(&dir.path().join(path)).is_some())
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn a_negated_pattern_re_includes_after_a_broader_one() {
+        let files = [(".wandignore", "# mocks\n*.sol\n!Keep.sol\n")];
+        assert_eq!(excluded(&files, &["A.sol", "src/B.sol", "src/Keep.sol"]), ["A.sol", "src/B.sol"]);
+        let files = [(".wandignore", "!Keep.sol\n*.sol\n")];
+        assert_eq!(excluded(&files, &["A.sol", "Keep.sol"]), ["A.sol", "Keep.sol"]);
+    }
+
+    #[test]
+    fn a_leading_slash_anchors_to_the_file_directory() {
+        let files = [(".wandignore", "/Mock.sol\n")];
+        assert_eq!(excluded(&files, &["Mock.sol", "src/Mock.sol"]), ["Mock.sol"]);
+        let files = [(".wandignore", "Mock.sol\n")];
+        assert_eq!(excluded(&files, &["Mock.sol", "src/Mock.sol"]), ["Mock.sol", "src/Mock.sol"]);
+        let files = [(".wandignore", "src/*.sol\n")];
+        assert_eq!(excluded(&files, &["src/A.sol", "src/deep/B.sol", "lib/src/C.sol"]), ["src/A.sol"]);
+    }
+
+    #[test]
+    fn a_trailing_slash_only_matches_directories() {
+        let files = [(".wandignore", "generated/\n")];
+        assert_eq!(
+            excluded(&files, &["generated", "generated/A.sol", "src/generated/B.sol"]),
+            ["generated/A.sol", "src/generated/B.sol"]
+        );
+    }
+
+    #[test]
+    fn a_deeper_file_overrides_its_parents() {
+        let files = [(".wandignore", "*.t.sol\n"), ("test/.wandignore", "!Fork.t.sol\n")];
+        assert_eq!(excluded(&files, &["A.t.sol", "Fork.t.sol", "test/Fork.t.sol"]), ["A.t.sol", "Fork.t.sol"]);
+        let files = [(".wandignore", ""), ("src/.wandignore", "Mock.sol\n")];
+        assert_eq!(excluded(&files, &["Mock.sol", "src/Mock.sol"]), ["src/Mock.sol"]);
+    }
+
+    #[test]
+    fn nothing_under_an_excluded_directory_is_re_included() {
+        let files = [(".wandignore", "vendor/\n!vendor/Keep.sol\n"), ("vendor/.wandignore", "!Keep.sol\n")];
+        assert_eq!(excluded(&files, &["vendor/Keep.sol"]), ["vendor/Keep.sol"]);
+    }
+
+    #[test]
+    fn the_origin_names_the_file_line_and_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(WANDIGNORE), "\n# mocks\nMock.sol\n").unwrap();
+        let wandignore = WandIgnore::load(&[dir.path().to_path_buf()]).unwrap();
+        let origin = wandignore.excluded_by(&dir.path().join("Mock.sol")).unwrap();
+        assert!(origin.ends_with(".wandignore:3: Mock.sol"), "{}", origin);
+    }
+}
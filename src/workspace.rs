@@ -0,0 +1,138 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::config::ProjectConfig;
+use crate::exclusions::PathExclusions;
+use crate::project::Project;
+use crate::source::{canonical, read_subdir};
+
+/// How many directory levels below the search directory are searched for projects, e.g.
+/// `packages/<name>/foundry.toml`.
+const MAX_PROJECT_DEPTH: usize = 3;
+
+/// Directories never searched for projects: dependencies and build output.
+const SKIPPED_DIRS: [&str; 6] = ["lib", "node_modules", "out", "cache", "artifacts", "broadcast"];
+
+/// A project of a monorepo, analyzed with its own root.
+#[derive(Debug)]
+pub struct Member {
+    pub name: String,
+    pub root: PathBuf,
+    /// Other directories searched for usages of the project's functions.
+    pub usage_roots: Vec<PathBuf>,
+}
+
+/// Finds the projects of a monorepo: those listed in `wand.toml` at `root`, or else the
+/// projects under `dir` when it isn't a project itself, leaving out the `excluded` ones.
+/// Returns no projects for a single project. `selected` keeps only the projects with these
+/// names or paths.
+pub fn members(
+    root: &Path,
+    dir: &Path,
+    configured: &[ProjectConfig],
+    selected: &[String],
+    excluded: &PathExclusions,
+) -> Result<Vec<Member>> {
+    let mut members = if configured.is_empty() {
+        discover(dir, excluded)?
+    } else {
+        configured
+            .iter()
+            .map(|project| {
+                let member = Member {
+                    name: project.name.clone().unwrap_or_else(|| dir_name(&project.path)),
+                    root: join(root, &project.path),
+                    usage_roots: project.usage_roots.iter().map(|path| join(root, path)).collect(),
+                };
+                for dir in std::iter::once(&member.root).chain(&member.usage_roots) {
+                    if !dir.is_dir() {
+                        return Err(Error::new(
+                            ErrorKind::NotFound,
+                            format!("Project directory {:?} of {:?} does not exist.", dir, member.name),
+                        ));
+                    }
+                }
+                Ok(member)
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    if selected.is_empty() {
+        return Ok(members);
+    }
+    if members.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "--project needs a monorepo, but {:?} is a single project; list projects in wand.toml or point PATH at a directory of projects.",
+                dir
+            ),
+        ));
+    }
+    for name in selected {
+        if !members.iter().any(|member| matches(member, name)) {
+            let names: Vec<&str> = members.iter().map(|member| member.name.as_str()).collect();
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown --project {:?}; expected one of {}.", name, names.join(", ")),
+            ));
+        }
+    }
+    members.retain(|member| selected.iter().any(|name| matches(member, name)));
+    Ok(members)
+}
+
+/// Finds the projects under `dir`, without looking inside a project for nested ones, nor
+/// inside the directories `excluded` by `--no-match-path`, `--ignore-paths-from` or a
+/// `.wandignore`.
+fn discover(dir: &Path, excluded: &PathExclusions) -> Result<Vec<Member>> {
+    if !dir.is_dir() || Project::is_project(dir) {
+        return Ok(Vec::new());
+    }
+
+    let mut members = Vec::new();
+    let mut dirs_to_visit = vec![(dir.to_path_buf(), 0)];
+    while let Some((current_dir, depth)) = dirs_to_visit.pop() {
+        for path in read_subdir(&current_dir)? {
+            // Reading `.` gives `./packages`, which wouldn't match paths given as `packages`.
+            let path = path.strip_prefix(".").map(Path::to_path_buf).unwrap_or(path);
+            let skipped = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.') || SKIPPED_DIRS.contains(&name));
+            if !path.is_dir() || skipped || excluded.excludes(&path) {
+                continue;
+            }
+            if Project::is_project(&path) {
+                members.push(Member {
+                    name: dir_name(&path),
+                    root: path,
+                    usage_roots: Vec::new(),
+                });
+            } else if depth + 1 < MAX_PROJECT_DEPTH {
+                dirs_to_visit.push((path, depth + 1));
+            }
+        }
+    }
+    members.sort_by(|a, b| a.root.cmp(&b.root));
+    Ok(members)
+}
+
+/// Whether `--project <name>` selects a project, by name or by path.
+fn matches(member: &Member, name: &str) -> bool {
+    member.name == name || canonical(Path::new(name)) == canonical(&member.root)
+}
+
+fn dir_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// Joins a configured path to the root, without the `./` prefix of the default root.
+fn join(root: &Path, path: &Path) -> PathBuf {
+    if root == Path::new(".") {
+        path.to_path_buf()
+    } else {
+        root.join(path)
+    }
+}
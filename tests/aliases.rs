@@ -0,0 +1,57 @@
+mod common;
+use common::{names, unused, Fixture};
+
+const FIXED_POINT_MATH: &str = "library FixedPointMath {\n    function mulDiv(uint256 x, uint256 y, uint256 d) internal pure returns (uint256) {\n        return x * y / d;\n    }\n}\n";
+
+const NAIVE_MATH: &str = "library NaiveMath {\n    function mulDiv(uint256 x, uint256 y, uint256 d) internal pure returns (uint256) {\n        return x * y / d;\n    }\n}\n";
+
+const UTILS: &str = "function helper(uint256 x) pure returns (uint256) {\n    return x + 1;\n}\n\nfunction spare(uint256 x) pure returns (uint256) {\n    return x;\n}\n";
+
+/// Renames `FixedPointMath` and `helper` once and re-exports them, for `Vault.sol` to rename again.
+const REEXPORTS: &str = "import {FixedPointMath as FPM} from \"./FixedPointMath.sol\";\nimport {NaiveMath} from \"./NaiveMath.sol\";\nimport {helper as h} from \"./Utils.sol\";\n";
+
+#[test]
+fn a_library_renamed_twice_is_still_attributed() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/FixedPointMath.sol", FIXED_POINT_MATH);
+    fixture.write("src/NaiveMath.sol", NAIVE_MATH);
+    fixture.write("src/Reexports.sol", REEXPORTS);
+    fixture.write(
+        "src/Vault.sol",
+        "import {FPM as Math} from \"./Reexports.sol\";\n\ncontract Vault {\n    function testShare(uint256 x) external pure returns (uint256) {\n        return Math.mulDiv(x, 1, 2);\n    }\n}\n",
+    );
+    let fixed = fixture.wand_ok(&["vacuum", "src/FixedPointMath.sol", "--explain-unused", "mulDiv"]);
+    assert!(fixed.contains("[code, Math.mulDiv]"), "{}", fixed);
+    let naive = fixture.wand_ok(&["vacuum", "src/NaiveMath.sol", "--explain-unused", "mulDiv"]);
+    assert!(naive.contains("[call of Math.mulDiv, excluded]"), "{}", naive);
+}
+
+#[test]
+fn a_free_function_renamed_twice_is_still_used() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Utils.sol", UTILS);
+    fixture.write("src/FixedPointMath.sol", FIXED_POINT_MATH);
+    fixture.write("src/NaiveMath.sol", NAIVE_MATH);
+    fixture.write("src/Reexports.sol", REEXPORTS);
+    fixture.write(
+        "src/Vault.sol",
+        "import {h as bump} from \"./Reexports.sol\";\n\ncontract Vault {\n    function testBump(uint256 x) external pure returns (uint256) {\n        return bump(x);\n    }\n}\n",
+    );
+    let report = fixture.vacuum_json(&["src/Utils.sol"]);
+    assert_eq!(names(&report, "src/Utils.sol", "functions"), ["helper", "spare"]);
+    assert_eq!(unused(&report, "src/Utils.sol"), ["spare"]);
+}
+
+#[test]
+fn an_aliased_library_attached_with_using_for_is_used() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/FixedPointMath.sol", FIXED_POINT_MATH);
+    fixture.write("src/NaiveMath.sol", NAIVE_MATH);
+    fixture.write("src/Reexports.sol", REEXPORTS);
+    fixture.write(
+        "src/Vault.sol",
+        "import {FPM as Math} from \"./Reexports.sol\";\n\ncontract Vault {\n    using Math for uint256;\n\n    function testShare(uint256 x) external pure returns (uint256) {\n        return x.mulDiv(1, 2);\n    }\n}\n",
+    );
+    let explained = fixture.wand_ok(&["vacuum", "src/FixedPointMath.sol", "--explain-unused", "mulDiv"]);
+    assert!(explained.contains("[code, x.mulDiv]"), "{}", explained);
+}
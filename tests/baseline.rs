@@ -0,0 +1,112 @@
+mod common;
+use common::Fixture;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs `wand` with `args` from `dir`.
+fn wand_in(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wand"))
+        .arg("--no-pager")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+/// The functions a run with `--baseline` still reports, i.e. those missing from it.
+fn new_findings(output: &Output) -> Vec<String> {
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!("no JSON report ({}):\n{}", e, String::from_utf8_lossy(&output.stderr))
+    });
+    let mut names: Vec<String> = report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|file| file["unused"].as_array().unwrap().clone())
+        .map(|name| name.as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn entry_files(fixture: &Fixture, baseline: &str) -> Vec<String> {
+    let baseline: serde_json::Value = serde_json::from_str(&fixture.read(baseline)).unwrap();
+    baseline["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["file"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn a_function_moved_to_another_file_stays_baselined() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", "contract A {\n    function legacy() internal {}\n}\n");
+    fixture.wand_ok(&["baseline", "create"]);
+
+    // A refactor moves the contract's code to another file.
+    fixture.write("src/A.sol", "import \"./Legacy.sol\";\n");
+    fixture.write("src/Legacy.sol", "contract A {\n    function legacy() internal {}\n}\n");
+    let output = fixture.wand(&["vacuum", "--format", "json", "--baseline", "wand-baseline.json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(new_findings(&output).is_empty());
+
+    fixture.wand_ok(&["baseline", "trim"]);
+    assert_eq!(entry_files(&fixture, "wand-baseline.json"), ["src/Legacy.sol"]);
+}
+
+#[test]
+fn a_baseline_applies_from_another_directory() {
+    let fixture = Fixture::empty();
+    fixture.write("project/foundry.toml", "[profile.default]\n");
+    fixture.write("project/src/A.sol", "contract A {\n    function legacy() internal {}\n}\n");
+    let output = wand_in(&fixture.path("project"), &["baseline", "create"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let args = ["vacuum", "--format", "json", "--root", "project", "--baseline", "project/wand-baseline.json"];
+    let output = wand_in(fixture.root(), &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(new_findings(&output).is_empty());
+
+    // The entry's file is found under the root, so it isn't taken for a moved function and
+    // recorded again as `project/src/A.sol`.
+    let trim = ["baseline", "trim", "--root", "project", "--baseline", "project/wand-baseline.json"];
+    let output = wand_in(fixture.root(), &trim);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(entry_files(&fixture, "project/wand-baseline.json"), ["src/A.sol"]);
+}
+
+#[test]
+fn entries_recorded_from_another_directory_are_made_relative_to_the_root() {
+    let fixture = Fixture::empty();
+    fixture.write("project/foundry.toml", "[profile.default]\n");
+    fixture.write("project/src/A.sol", "contract A {\n    function legacy() internal {}\n}\n");
+    let baseline = r#"{"version":2,"entries":[{"fingerprint":"0000000000000000","file":"project/src/A.sol","contract":"A","signature":"legacy()","kind":"unused","created":"2026-01-01"}]}"#;
+    fixture.write("project/wand-baseline.json", baseline);
+
+    let trim = ["baseline", "trim", "--root", "project", "--baseline", "project/wand-baseline.json"];
+    let output = wand_in(fixture.root(), &trim);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(entry_files(&fixture, "project/wand-baseline.json"), ["src/A.sol"]);
+
+    let args = ["vacuum", "--format", "json", "--baseline", "wand-baseline.json"];
+    let output = wand_in(&fixture.path("project"), &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn a_function_whose_code_changed_stays_baselined() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", "contract A {\n    function legacy() internal {}\n}\n");
+    fixture.wand_ok(&["baseline", "create"]);
+
+    fixture.write("src/A.sol", "contract A {\n    function legacy() internal {\n        revert();\n    }\n}\n");
+    let output = fixture.wand(&["vacuum", "--format", "json", "--baseline", "wand-baseline.json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(new_findings(&output).is_empty());
+
+    fixture.wand_ok(&["baseline", "trim"]);
+    assert_eq!(entry_files(&fixture, "wand-baseline.json"), ["src/A.sol"]);
+}
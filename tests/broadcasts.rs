@@ -0,0 +1,50 @@
+mod common;
+use common::{unused, Fixture};
+
+const SOURCE: &str = "contract Vault {\n    function pause() external {}\n\n    function sweep(address to) external {}\n\n    function rescue() external {}\n}\n";
+
+/// A broadcast run calling `selector`, e.g. `0x8456cb59` for `pause()`.
+fn run(selector: &str) -> String {
+    format!(
+        "{{\"transactions\":[{{\"transactionType\":\"CALL\",\"transaction\":{{\"input\":\"{}00\"}}}}]}}",
+        selector
+    )
+}
+
+#[test]
+fn run_files_keep_the_functions_they_call() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", SOURCE);
+    // pause()
+    fixture.write("broadcast/Deploy.s.sol/1/run-latest.json", run("0x8456cb59"));
+    // sweep(address)
+    fixture.write("broadcast/Deploy.s.sol/1/run-1700000000.json", run("0x01681a62"));
+
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["rescue"]);
+}
+
+#[test]
+fn other_json_files_and_dry_runs_are_left_out() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", SOURCE);
+    fixture.write("broadcast/Deploy.s.sol/1/dry-run/run-latest.json", run("0x8456cb59"));
+    fixture.write("broadcast/Deploy.s.sol/1/notes.json", run("0x01681a62"));
+
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["pause", "sweep", "rescue"]);
+}
+
+#[test]
+fn a_malformed_run_is_skipped_with_a_warning() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", SOURCE);
+    fixture.write("broadcast/Deploy.s.sol/1/run-latest.json", run("0x8456cb59"));
+    fixture.write("broadcast/Deploy.s.sol/1/run-1700000000.json", "{\"transactions\": [");
+
+    let output = fixture.wand(&["vacuum", "--format", "json"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipping broadcast file"));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(unused(&report, "src/Vault.sol"), ["sweep", "rescue"]);
+}
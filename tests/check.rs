@@ -0,0 +1,119 @@
+mod common;
+use common::Fixture;
+
+const VAULT: &str = r#"/// @title A vault
+contract Vault {
+    uint256 total;
+
+    /// @notice Deposits `amount`.
+    /// @param amount The amount.
+    function deposit(uint256 amount) external {
+        total += amount;
+    }
+
+    function withdraw() external {
+        total = 0;
+    }
+
+    function _legacy() internal {}
+}
+"#;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture
+}
+
+fn check_json(fixture: &Fixture) -> serde_json::Value {
+    let output = fixture.wand(&["check", "--format", "json"]);
+    serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("no JSON report ({}):\n{}", e, String::from_utf8_lossy(&output.stderr)))
+}
+
+/// The `(rule, line, severity)` of every finding of a JSON report.
+fn findings(report: &serde_json::Value) -> Vec<(String, u64, String)> {
+    report["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|rule| {
+            rule["findings"].as_array().unwrap().iter().map(|finding| {
+                (
+                    rule["rule"].as_str().unwrap().to_string(),
+                    finding["line"].as_u64().unwrap(),
+                    finding["severity"].as_str().unwrap().to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn the_default_rules_are_aggregated_into_one_gate() {
+    let fixture = project();
+    let output = fixture.wand(&["check"]);
+    assert!(!output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("unused-function (3):\n"), "{}", report);
+    assert!(report.contains("  src/Vault.sol:15 [warning] internal function `_legacy` is never used.\n"), "{}", report);
+    assert!(report.contains("import-cycle (0):\n  (no findings)\n"), "{}", report);
+    assert!(report.contains("missing-natspec (1):\n  src/Vault.sol:11 [note] external function `withdraw`"), "{}", report);
+    assert!(report.contains("Findings: 4 (0 error, 1 warning, 3 note)\n"), "{}", report);
+    assert!(report.contains("Check failed: 1 finding(s) at warning or above, 0 allowed."), "{}", report);
+}
+
+#[test]
+fn json_reports_each_rule() {
+    let report = check_json(&project());
+    assert_eq!(
+        findings(&report),
+        [
+            ("unused-function".to_string(), 7, "note".to_string()),
+            ("unused-function".to_string(), 11, "note".to_string()),
+            ("unused-function".to_string(), 15, "warning".to_string()),
+            ("missing-natspec".to_string(), 11, "note".to_string()),
+        ]
+    );
+    assert_eq!(report["failing"], 1);
+    assert_eq!(report["passed"], false);
+}
+
+#[test]
+fn the_configured_threshold_and_ignores_apply() {
+    let fixture = project();
+    fixture.write("wand.toml", "[check]\nrules = [\"unused-function\"]\nmax_findings = 1\n");
+    let report = check_json(&fixture);
+    assert_eq!(report["failing"], 1);
+    assert_eq!(report["passed"], true);
+
+    fixture.write("wand.toml", "[check]\nrules = [\"unused-function\"]\nignore = [\"^_\"]\n");
+    let report = check_json(&fixture);
+    assert_eq!(findings(&report).len(), 2);
+    assert_eq!(report["passed"], true);
+}
+
+#[test]
+fn unreachable_code_fails_as_an_error() {
+    let fixture = project();
+    fixture.write("wand.toml", "[check]\nrules = [\"unreachable-code\"]\nfail_on = \"error\"\n");
+    fixture.write(
+        "src/Vault.sol",
+        VAULT.replace("        total = 0;\n", "        total = 0;\n        return;\n        total = 1;\n"),
+    );
+    let report = check_json(&fixture);
+    assert_eq!(findings(&report), [("unreachable-code".to_string(), 14, "error".to_string())]);
+    assert_eq!(report["passed"], false);
+}
+
+#[test]
+fn baselined_findings_pass() {
+    let fixture = project();
+    fixture.write("wand.toml", "[check]\nrules = [\"unused-function\"]\nbaseline = \"wand-baseline.json\"\n");
+    fixture.wand_ok(&["baseline", "create"]);
+    let report = check_json(&fixture);
+    assert!(findings(&report).is_empty(), "{}", report);
+    assert_eq!(report["suppressed_by_baseline"], 3);
+    assert_eq!(report["passed"], true);
+    fixture.wand_ok(&["check"]);
+}
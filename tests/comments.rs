@@ -0,0 +1,42 @@
+mod common;
+use common::{names, unused, Fixture};
+
+const SOURCE: &str = "contract A {
+    function testRun() external { live(); }
+
+    function live() internal {
+        // dead();
+    }
+
+    /// @dev Was called by `live`, see dead().
+    function dead() internal {}
+
+    // function ghost() internal {}
+    /* function ghost2() internal { dead(); } */
+}
+";
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    fixture
+}
+
+#[test]
+fn commented_out_code_is_neither_declared_nor_a_usage() {
+    let report = fixture().vacuum_json(&[]);
+    assert_eq!(names(&report, "src/A.sol", "functions"), ["live", "dead"]);
+    assert_eq!(unused(&report, "src/A.sol"), ["dead"]);
+}
+
+#[test]
+fn include_commented_reads_comments_as_code() {
+    let fixture = fixture();
+    let report = fixture.vacuum_json(&["--include-commented"]);
+    assert_eq!(names(&report, "src/A.sol", "functions"), ["live", "dead", "ghost", "ghost2"]);
+    assert_eq!(unused(&report, "src/A.sol"), ["ghost", "ghost2"]);
+
+    // Declarations in comments are reported, but there is no code to delete.
+    fixture.wand(&["vacuum", "--include-commented", "--delete"]);
+    assert_eq!(fixture.read("src/A.sol"), SOURCE);
+}
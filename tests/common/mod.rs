@@ -0,0 +1,109 @@
+//! A scratch project to run `wand` against, shared by the integration tests.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use tempfile::TempDir;
+
+/// A Foundry project in a temporary directory, removed when dropped.
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    /// An empty Foundry project, with a `foundry.toml` and nothing else.
+    pub fn foundry() -> Self {
+        let fixture = Fixture::empty();
+        fixture.write("foundry.toml", "[profile.default]\n");
+        fixture
+    }
+
+    /// A directory without any project file.
+    pub fn empty() -> Self {
+        Fixture {
+            dir: tempfile::tempdir().expect("failed to create a temporary directory"),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root().join(path)
+    }
+
+    /// Writes `content` to `path` under the root, creating its directories.
+    pub fn write(&self, path: &str, content: impl AsRef<[u8]>) {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.path(path)).unwrap()
+    }
+
+    /// Runs `wand` with `args` from the root.
+    pub fn wand(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_wand"))
+            .args(args)
+            .arg("--no-pager")
+            .current_dir(self.root())
+            .output()
+            .expect("failed to run wand")
+    }
+
+    /// Runs `wand` with `args`, failing the test unless it succeeds, and returns stdout.
+    pub fn wand_ok(&self, args: &[&str]) -> String {
+        let output = self.wand(args);
+        assert!(
+            output.status.success(),
+            "wand {:?} failed:\n{}\n{}",
+            args,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Runs `vacuum --format json` with `args` and parses the report, whatever the exit
+    /// status.
+    pub fn vacuum_json(&self, args: &[&str]) -> serde_json::Value {
+        let mut all = vec!["vacuum", "--format", "json"];
+        all.extend_from_slice(args);
+        let output = self.wand(&all);
+        serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+            panic!(
+                "wand {:?} printed no JSON report ({}):\n{}\n{}",
+                all,
+                e,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        })
+    }
+}
+
+/// The names of the unused functions of the file reported as `file` in a vacuum report.
+pub fn unused(report: &serde_json::Value, file: &str) -> Vec<String> {
+    names(report, file, "unused")
+}
+
+/// The names in the `list` of the file reported as `file` in a vacuum report, e.g. its
+/// `proxy_reachable` functions or, with `functions`, all of them.
+pub fn names(report: &serde_json::Value, file: &str, list: &str) -> Vec<String> {
+    report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|report| report["file"].as_str().is_some_and(|path| path.ends_with(file)))
+        .flat_map(|report| report[list].as_array().cloned().unwrap_or_default())
+        .map(|function| match function.get("name") {
+            Some(name) => name.as_str().unwrap().to_string(),
+            None => function.as_str().unwrap().to_string(),
+        })
+        .collect()
+}
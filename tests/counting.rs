@@ -0,0 +1,131 @@
+mod common;
+use common::{unused, Fixture};
+
+use std::path::Path;
+
+const SOURCE: &str = "contract A {\n    function testRun() external {\n        helper();\n    }\n\n    function helper() internal {}\n\n    function unused() internal {}\n}\n";
+
+/// The occurrences counted for each function of the only file of `report`.
+fn occurrences(report: &serde_json::Value) -> Vec<(String, u64)> {
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1, "{}", report);
+    files[0]["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|function| {
+            (
+                function["name"].as_str().unwrap().to_string(),
+                function["occurrences"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn a_file_under_root_is_counted_once() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    let absolute = fixture.path("src/A.sol");
+    let root = fixture.root().to_str().unwrap().to_string();
+
+    let runs: [&[&str]; 6] = [
+        &[],
+        &["src/A.sol"],
+        &["./src/A.sol"],
+        &[absolute.to_str().unwrap()],
+        &["src/A.sol", "--root", &root],
+        &["src/A.sol", "--root", "src"],
+    ];
+    for args in runs {
+        let report = fixture.vacuum_json(args);
+        assert_eq!(
+            occurrences(&report),
+            [("helper".to_string(), 2), ("unused".to_string(), 1)],
+            "vacuum {:?}",
+            args
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn a_file_reached_through_a_symlink_is_counted_once() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    std::os::unix::fs::symlink(fixture.path("src"), fixture.path("contracts")).unwrap();
+    std::os::unix::fs::symlink(fixture.path("src/A.sol"), fixture.path("Alias.sol")).unwrap();
+
+    for args in [&[][..], &["src"], &["contracts"], &["contracts/A.sol"], &["Alias.sol"]] {
+        let report = fixture.vacuum_json(args);
+        assert_eq!(
+            occurrences(&report),
+            [("helper".to_string(), 2), ("unused".to_string(), 1)],
+            "vacuum {:?}",
+            args
+        );
+        let file = report["files"][0]["file"].as_str().unwrap();
+        assert!(!Path::new(file).is_absolute(), "vacuum {:?} reported {}", args, file);
+    }
+}
+
+#[test]
+fn a_file_named_in_another_case_is_counted_once() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    if !fixture.path("SRC/a.sol").exists() {
+        eprintln!("skipping: the filesystem is case-sensitive");
+        return;
+    }
+    for args in [&["SRC/a.sol"][..], &["Src"], &["src/A.sol", "--root", "SRC"]] {
+        let report = fixture.vacuum_json(args);
+        assert_eq!(
+            occurrences(&report),
+            [("helper".to_string(), 2), ("unused".to_string(), 1)],
+            "vacuum {:?}",
+            args
+        );
+        let file = report["files"][0]["file"].as_str().unwrap();
+        assert!(!Path::new(file).is_absolute(), "vacuum {:?} reported {}", args, file);
+    }
+}
+
+#[test]
+fn encode_call_targets_are_usages() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/IVault.sol", "interface IVault {\n    function sweep(address to) external;\n}\n");
+    fixture.write(
+        "src/Vault.sol",
+        "contract Vault {\n    function sweep(address to) external {}\n\n    function pause() external {}\n}\n",
+    );
+    fixture.write(
+        "src/Keeper.sol",
+        "contract Keeper {\n    function testRun(address vault) external {\n        (bool ok, ) = vault.call(abi.encodeCall(IVault.sweep, (msg.sender)));\n    }\n}\n",
+    );
+    let report = fixture.vacuum_json(&["src/Vault.sol"]);
+    assert_eq!(occurrences(&report), [("sweep".to_string(), 3), ("pause".to_string(), 1)]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["pause"]);
+
+    let explained = fixture.wand_ok(&["vacuum", "src/Vault.sol", "--explain-unused", "sweep"]);
+    assert!(explained.contains("encodeCall"), "{}", explained);
+}
+
+#[test]
+fn library_constants_are_used_through_their_library() {
+    let fixture = Fixture::foundry();
+    let pattern = r"constant\s+(?P<name>\w+)";
+    fixture.write(
+        "src/Constants.sol",
+        "library Constants {\n    uint256 internal constant MAX_FEE = 100;\n    uint256 internal constant MIN_FEE = 1;\n}\n",
+    );
+    fixture.write("src/Fees.sol", "library Fees {\n    uint256 internal constant MAX_FEE = 50;\n}\n");
+    fixture.write(
+        "src/Vault.sol",
+        "import {Constants} from \"./Constants.sol\";\n\ncontract Vault {\n    function testFee() external pure returns (uint256) {\n        return Constants.MAX_FEE;\n    }\n}\n",
+    );
+    let report = fixture.vacuum_json(&["src/Constants.sol", "--pattern", pattern]);
+    assert_eq!(unused(&report, "src/Constants.sol"), ["MIN_FEE"]);
+
+    let explained = fixture.wand_ok(&["vacuum", "src/Fees.sol", "--pattern", pattern, "--explain-unused", "MAX_FEE"]);
+    assert!(explained.contains("[access of Constants.MAX_FEE, excluded]"), "{}", explained);
+}
@@ -0,0 +1,51 @@
+mod common;
+use common::{unused, Fixture};
+
+const VAULT: &str = "contract Vault {\n    function testRun() external {\n        sweep();\n        keep();\n    }\n\n    function sweep() internal {}\n\n    function keep() internal {}\n\n    function pause() internal {}\n\n    function rescue() internal {}\n}\n";
+
+/// Coverage as forge writes it: `FN` lines with end lines, `FNDA` lines before them, and no
+/// `end_of_record` at the end. `rescue` has no record.
+const LCOV: &str = "TN:\nSF:src/Vault.sol\nFNDA:3,Vault.sweep\nFN:7,7,Vault.sweep\nFN:9,9,Vault.keep\nFNDA:0,Vault.keep\nFN:11,11,Vault.pause\nFNDA:0,Vault.pause\nFN:2,5,Vault.testRun\nFNDA:3,Vault.testRun\n";
+
+/// The `coverage_hits` of each function of the only file of `report`.
+fn hits(report: &serde_json::Value) -> Vec<(String, Option<u64>)> {
+    report["files"][0]["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|function| (function["name"].as_str().unwrap().to_string(), function["coverage_hits"].as_u64()))
+        .collect()
+}
+
+#[test]
+fn hits_are_read_from_lcov() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("lcov.info", LCOV);
+    let report = fixture.vacuum_json(&["--coverage", "lcov.info"]);
+    assert_eq!(
+        hits(&report),
+        [
+            ("sweep".to_string(), Some(3)),
+            ("keep".to_string(), Some(0)),
+            ("pause".to_string(), Some(0)),
+            ("rescue".to_string(), None),
+        ]
+    );
+    assert_eq!(unused(&report, "src/Vault.sol"), ["pause", "rescue"]);
+    assert_eq!(report["unused_uncovered"], 1);
+    assert_eq!(report["used_uncovered"], 1);
+}
+
+#[test]
+fn the_prefix_is_removed_from_ci_paths() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("lcov.info", LCOV.replace("SF:src/", "SF:/home/runner/work/protocol/src/"));
+    let report = fixture.vacuum_json(&["--coverage", "lcov.info", "--coverage-prefix", "/home/runner/work/protocol"]);
+    assert_eq!(report["files"][0]["functions"][0]["coverage_hits"], 3, "{}", report);
+
+    let output = fixture.wand(&["vacuum", "--coverage", "lcov.info"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("none of the files covered by \"lcov.info\" is under \"src\""), "{}", stderr);
+}
@@ -0,0 +1,158 @@
+mod common;
+use common::Fixture;
+
+use std::collections::BTreeMap;
+
+const FILES: usize = 24;
+
+/// A project of `FILES` contracts, each calling a helper of the next one and declaring
+/// functions that nothing calls, some of them only called by another unused one.
+fn generated() -> Fixture {
+    let fixture = Fixture::foundry();
+    for i in 0..FILES {
+        let next = (i + 1) % FILES;
+        fixture.write(
+            &format!("src/C{}.sol", i),
+            format!(
+                "import {{C{next}}} from \"./C{next}.sol\";\n\ncontract C{i} {{\n    function testEntry{i}() external {{ C{next}(address(0)).helper{next}(); }}\n\n    function helper{i}() external {{\n        unchecked {{ uint256 x; x++; }}\n    }}\n\n    /// @notice Never called.\n    function unused{i}() internal {{\n        assembly {{ let y := 1 if y {{ y := 2 }} }}\n        chained{i}();\n    }}\n\n    function chained{i}() internal pure returns (string memory) {{\n        return \"}} {{\";\n    }}\n}}\n",
+                i = i,
+                next = next
+            ),
+        );
+    }
+    fixture
+}
+
+/// The content of every source file after `vacuum --delete`.
+fn delete(fixture: &Fixture) -> BTreeMap<String, String> {
+    fixture.wand_ok(&["vacuum", "--delete"]);
+    (0..FILES)
+        .map(|i| {
+            let file = format!("src/C{}.sol", i);
+            let content = fixture.read(&file);
+            (file, content)
+        })
+        .collect()
+}
+
+#[test]
+fn deleting_gives_the_same_files_every_run() {
+    let expected = delete(&generated());
+    for (file, content) in &expected {
+        assert!(!content.contains("function unused"), "{}:\n{}", file, content);
+        assert!(content.contains("function chained"), "{}:\n{}", file, content);
+        assert!(content.contains("function helper"), "{}:\n{}", file, content);
+    }
+    for _ in 0..8 {
+        assert_eq!(delete(&generated()), expected);
+    }
+}
+
+#[test]
+fn a_second_delete_only_removes_what_the_first_left_uncalled() {
+    let fixture = generated();
+    delete(&fixture);
+    let second = delete(&fixture);
+    for (file, content) in &second {
+        assert!(!content.contains("function chained"), "{}:\n{}", file, content);
+    }
+    assert_eq!(delete(&fixture), second);
+}
+
+#[cfg(unix)]
+#[test]
+fn deleting_through_a_symbolic_link_edits_its_target() {
+    let fixture = Fixture::foundry();
+    fixture.write(
+        "shared/A.sol",
+        "contract A {\n    function testRun() external {}\n\n    function gone() internal {}\n}\n",
+    );
+    std::fs::create_dir(fixture.path("src")).unwrap();
+    std::os::unix::fs::symlink("../shared/A.sol", fixture.path("src/A.sol")).unwrap();
+
+    fixture.wand_ok(&["vacuum", "--delete"]);
+    assert!(std::fs::symlink_metadata(fixture.path("src/A.sol")).unwrap().file_type().is_symlink());
+    assert_eq!(fixture.read("shared/A.sol"), "contract A {\n    function testRun() external {}\n\n}\n");
+}
+
+#[test]
+fn transitive_delete_removes_a_dead_chain_in_one_run() {
+    let fixture = Fixture::foundry();
+    fixture.write(
+        "src/A.sol",
+        "contract A {\n    function testRun() external { used(); }\n\n    function used() internal {}\n\n    function f() internal { g(); }\n\n    function g() internal { h(); }\n\n    function h() internal {}\n}\n",
+    );
+
+    fixture.wand_ok(&["vacuum", "--transitive", "--delete"]);
+    assert_eq!(
+        fixture.read("src/A.sol"),
+        "contract A {\n    function testRun() external { used(); }\n\n    function used() internal {}\n\n\n\n}\n"
+    );
+}
+
+#[test]
+fn pattern_symbols_are_kept_with_a_warning() {
+    let fixture = Fixture::foundry();
+    let source = "contract A {\n    uint256 constant FEE_A = 1;\n    uint256 constant FEE_B = 2;\n    // uint256 constant FEE_C = 3;\n    string s = \"uint256 constant FEE_D = 4\";\n    /* FEE_B is mentioned here */\n    string t = \"FEE_B\";\n\n    function testRun() external returns (uint256) { return FEE_A; }\n\n    function gone() internal {}\n}\n";
+    fixture.write("src/A.sol", source);
+    let pattern = r"constant\s+(?P<name>\w+)";
+
+    let report = fixture.vacuum_json(&["--pattern", pattern]);
+    assert_eq!(common::unused(&report, "src/A.sol"), ["gone", "FEE_B"]);
+
+    let output = fixture.wand(&["vacuum", "--pattern", pattern, "--delete"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("1 unused --pattern symbol(s) were left in place, starting with `FEE_B` at src/A.sol:3"));
+    assert_eq!(fixture.read("src/A.sol"), source.replace("    function gone() internal {}\n", ""));
+}
+
+#[test]
+fn removal_ends_at_the_function_closing_brace() {
+    let fixture = Fixture::foundry();
+    fixture.write(
+        "src/Math.sol",
+        r#"contract Math {
+    function testSum() external { sum(1); last(); }
+
+    function sum(uint256 n) internal pure returns (uint256 total) {
+        unchecked { for (uint256 i; i < n; ++i) { if (i % 2 == 0) { total += i; } } }
+    }
+
+    function gone(uint256 n) internal pure returns (bytes32 r) {
+        unchecked {
+            for (uint256 i; i < n; ++i) {
+                if (i > 2) { break; } else { continue; }
+            }
+        }
+        assembly {
+            let s := "} {"
+            r := s // }
+            switch n case 0 { r := 1 } default { r := 2 }
+        }
+    }
+
+    function last() internal pure returns (string memory) {
+        return "{";
+    }
+}
+"#,
+    );
+    fixture.wand_ok(&["vacuum", "--delete"]);
+    assert_eq!(
+        fixture.read("src/Math.sol"),
+        r#"contract Math {
+    function testSum() external { sum(1); last(); }
+
+    function sum(uint256 n) internal pure returns (uint256 total) {
+        unchecked { for (uint256 i; i < n; ++i) { if (i % 2 == 0) { total += i; } } }
+    }
+
+
+    function last() internal pure returns (string memory) {
+        return "{";
+    }
+}
+"#
+    );
+}
@@ -0,0 +1,72 @@
+mod common;
+use common::Fixture;
+
+/// A cycle between `A.sol` and `B.sol`, a file importing a remapped library and a
+/// missing file, and a plain import of `Math.sol`.
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("remappings.txt", "oz/=lib/oz/\n");
+    fixture.write("lib/oz/Owned.sol", "contract Owned {}\n");
+    fixture.write("src/A.sol", "import \"./B.sol\";\ncontract A {}\n");
+    fixture.write("src/B.sol", "import \"./A.sol\";\ncontract B {}\n");
+    fixture.write("src/Math.sol", "library Math {}\n");
+    fixture.write("src/Vault.sol", "import {Math} from \"./Math.sol\";\ncontract Vault {}\n");
+    fixture.write(
+        "src/Token.sol",
+        "import \"oz/Owned.sol\";\nimport \"./Missing.sol\";\ncontract Token is Owned {}\n",
+    );
+    fixture
+}
+
+#[test]
+fn text_reports_cycles_orphans_and_unresolved_imports() {
+    let report = project().wand_ok(&["deps", "src"]);
+    assert!(report.contains("Import cycles:\n- src/A.sol -> src/B.sol -> src/A.sol\n"), "{}", report);
+    assert!(report.contains("Orphans (imported by nothing):\n- src/Token.sol\n- src/Vault.sol\n"), "{}", report);
+    assert!(report.contains("Unresolved imports:\n- src/Token.sol:2 \"./Missing.sol\"\n"), "{}", report);
+    assert!(report.ends_with("Files: 6 (1 external), imports: 4, cycles: 1, orphans: 2\n"), "{}", report);
+}
+
+#[test]
+fn json_lists_files_edges_and_cycles() {
+    let output = project().wand_ok(&["deps", "src", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let owned = report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|file| file["file"] == "lib/oz/Owned.sol")
+        .unwrap();
+    assert_eq!(owned["external"], true);
+    assert_eq!(owned["fan_in"], 1);
+    assert!(report["edges"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!({"from": "src/Token.sol", "to": "lib/oz/Owned.sol"})));
+    assert_eq!(report["cycles"], serde_json::json!([["src/A.sol", "src/B.sol", "src/A.sol"]]));
+    assert_eq!(report["orphans"], serde_json::json!(["src/Token.sol", "src/Vault.sol"]));
+    assert_eq!(
+        report["unresolved"],
+        serde_json::json!([{"file": "src/Token.sol", "line": 2, "import": "./Missing.sol"}])
+    );
+}
+
+#[test]
+fn dot_marks_cycles_and_external_files() {
+    let graph = project().wand_ok(&["deps", "src", "--format", "dot"]);
+    assert!(graph.starts_with("digraph imports {\n"), "{}", graph);
+    assert!(graph.contains("\"src/A.sol\" -> \"src/B.sol\" [color=red];"), "{}", graph);
+    assert!(graph.contains("\"lib/oz/Owned.sol\" [label=\"lib/oz/Owned.sol\" style=dashed];"), "{}", graph);
+    assert!(graph.contains("\"src/Vault.sol\" -> \"src/Math.sol\";"), "{}", graph);
+}
+
+#[test]
+fn cycles_fail_with_fail_on_cycles() {
+    let fixture = project();
+    let output = fixture.wand(&["deps", "src", "--fail-on-cycles"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Found 1 import cycle(s)."));
+
+    fixture.write("src/B.sol", "contract B {}\n");
+    fixture.wand_ok(&["deps", "src", "--fail-on-cycles"]);
+}
@@ -0,0 +1,49 @@
+mod common;
+use common::Fixture;
+
+const BASE: &str = "contract Base {\n    uint256 internal total;\n\n    modifier onlyOwner() {\n        _;\n    }\n\n    function fee(uint256 amount) public view virtual returns (uint256) {\n        return amount * total / 10_000;\n    }\n\n    function cap() public view virtual returns (uint256) {\n        return total * 2;\n    }\n\n    function rate() public virtual returns (uint256) {\n        return total + 1;\n    }\n}\n";
+
+/// Overrides `fee` with the base's body respaced, and `cap` and `rate` with the same bodies
+/// but a modifier added or the mutability changed.
+const VAULT: &str = "import {Base} from \"./Base.sol\";\n\ncontract Vault is Base {\n    /// @notice Same as the base.\n    function fee(uint256 amount) public view override returns (uint256) {\n        return amount  *  total / 10_000;\n    }\n\n    function cap() public view override onlyOwner returns (uint256) {\n        return total * 2;\n    }\n\n    function rate() public view override returns (uint256) {\n        return total + 1;\n    }\n}\n";
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Base.sol", BASE);
+    fixture.write("src/Vault.sol", VAULT);
+    fixture
+}
+
+#[test]
+fn only_exact_overrides_are_redundant() {
+    let report = project().wand_ok(&["dupes", "src", "--inherited", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(
+        report["redundant_overrides"],
+        serde_json::json!([{
+            "function": { "file": "src/Vault.sol", "function": "Vault.fee", "line": 5 },
+            "base": { "file": "src/Base.sol", "function": "Base.fee", "line": 8 },
+            "tokens": 9,
+        }])
+    );
+}
+
+#[test]
+fn delete_removes_redundant_overrides_with_their_natspec() {
+    let fixture = project();
+    fixture.wand_ok(&["dupes", "src", "--inherited"]);
+    assert_eq!(fixture.read("src/Vault.sol"), VAULT, "reporting shouldn't change the files");
+
+    fixture.wand_ok(&["dupes", "src", "--inherited", "--delete"]);
+    let fee = "    /// @notice Same as the base.\n    function fee(uint256 amount) public view override returns (uint256) {\n        return amount  *  total / 10_000;\n    }\n";
+    assert_eq!(fixture.read("src/Vault.sol"), VAULT.replace(fee, ""));
+    assert_eq!(fixture.read("src/Base.sol"), BASE);
+}
+
+#[test]
+fn delete_needs_inherited() {
+    let fixture = project();
+    let output = fixture.wand(&["dupes", "src", "--delete"]);
+    assert!(!output.status.success());
+    assert_eq!(fixture.read("src/Vault.sol"), VAULT);
+}
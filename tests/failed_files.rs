@@ -0,0 +1,79 @@
+mod common;
+
+use common::{unused, Fixture};
+
+const A: &str = "contract A {\n    function unusedA() internal {}\n}\n";
+
+/// A project whose `src/B.sol` isn't valid UTF-8, so that it fails to be analyzed.
+fn corrupt() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", A);
+    fixture.write("src/B.sol", b"contract B {\n    // \xff\xfe\n    function unusedB() internal {}\n}\n");
+    fixture
+}
+
+#[test]
+fn a_corrupt_file_fails_alone() {
+    let fixture = corrupt();
+    let output = fixture.wand(&["vacuum", "src"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("unusedA"), "{}", stdout);
+    assert!(stdout.contains("Failed file(s): 1 could not be analyzed"), "{}", stdout);
+    assert!(stdout.contains("B.sol"), "{}", stdout);
+}
+
+#[test]
+fn a_corrupt_file_is_listed_with_the_errors() {
+    let fixture = corrupt();
+    let report = fixture.vacuum_json(&["src"]);
+    assert_eq!(unused(&report, "A.sol"), ["unusedA"]);
+    let errors = report["errors"].as_array().unwrap();
+    assert!(
+        errors.iter().any(|error| error["file"].as_str().unwrap().ends_with("B.sol")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn nothing_is_deleted_when_a_file_fails() {
+    let fixture = corrupt();
+    let output = fixture.wand(&["vacuum", "src", "--delete"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nothing was deleted"));
+    assert_eq!(fixture.read("src/A.sol"), A);
+}
+
+/// A project whose only usage of `usedByTest` is in a test that isn't valid UTF-8, so
+/// that it can't be searched.
+fn unsearchable() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", "contract A {\n    function usedByTest() public {}\n}\n");
+    fixture.write("test/A.t.sol", b"contract T {\n    // \xff\n    function t(A a) public { a.usedByTest(); }\n}\n");
+    fixture
+}
+
+#[test]
+fn delete_refuses_when_a_searched_file_was_skipped() {
+    let fixture = unsearchable();
+    let original = fixture.read("src/A.sol");
+    let output = fixture.wand(&["vacuum", "src", "--delete"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Pass --force to delete anyway"), "{}", stderr);
+    assert_eq!(fixture.read("src/A.sol"), original);
+
+    let output = fixture.wand(&["vacuum", "src", "--delete", "--force"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!fixture.read("src/A.sol").contains("usedByTest"));
+}
+
+#[test]
+fn delete_refuses_when_a_searched_file_was_skipped_by_transitive() {
+    let fixture = unsearchable();
+    let original = fixture.read("src/A.sol");
+    let output = fixture.wand(&["vacuum", "src", "--delete", "--transitive"]);
+    assert!(!output.status.success());
+    assert_eq!(fixture.read("src/A.sol"), original);
+}
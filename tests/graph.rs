@@ -0,0 +1,110 @@
+mod common;
+use common::Fixture;
+
+const VAULT: &str = r#"import {Math} from "./Math.sol";
+
+contract Vault {
+    uint256 public total;
+
+    function deposit(uint256 amount) external {
+        total = _add(total, amount);
+    }
+
+    function withdraw(uint256 amount) external {
+        total = Math.sub(total, amount);
+    }
+
+    function _add(uint256 a, uint256 b) internal pure returns (uint256) {
+        return Math.add(a, b);
+    }
+
+    function _unused() internal {}
+}
+"#;
+
+const MATH: &str = r#"library Math {
+    function add(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a + b;
+    }
+
+    function sub(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a - b;
+    }
+}
+"#;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("src/Math.sol", MATH);
+    fixture
+}
+
+#[test]
+fn dot_colors_entry_points_and_unreachable_functions() {
+    let graph = project().wand_ok(&["graph", "src"]);
+    assert_eq!(
+        graph,
+        r#"digraph calls {
+    node [shape=box];
+    "Math.add" [label="Math.add"];
+    "Math.sub" [label="Math.sub"];
+    "Vault._add" [label="Vault._add"];
+    "Vault._unused" [label="Vault._unused" color=red fontcolor=red];
+    "Vault.deposit" [label="Vault.deposit" color=green fontcolor=green];
+    "Vault.withdraw" [label="Vault.withdraw" color=green fontcolor=green];
+    "Vault.deposit" -> "Vault._add";
+    "Vault.withdraw" -> "Math.sub";
+    "Vault._add" -> "Math.add";
+}
+"#
+    );
+}
+
+#[test]
+fn json_lists_nodes_and_edges() {
+    let output = project().wand_ok(&["graph", "src", "--format", "json"]);
+    let graph: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let unused = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|node| node["id"] == "Vault._unused")
+        .unwrap();
+    assert_eq!(unused["file"], "src/Vault.sol");
+    assert_eq!(unused["line"], 18);
+    assert_eq!(unused["reachable"], false);
+    assert_eq!(unused["entry_point"], false);
+    let edges: Vec<(&str, &str)> = graph["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| (edge["from"].as_str().unwrap(), edge["to"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        edges,
+        [("Vault.deposit", "Vault._add"), ("Vault.withdraw", "Math.sub"), ("Vault._add", "Math.add")]
+    );
+}
+
+#[test]
+fn mermaid_marks_the_node_classes() {
+    let graph = project().wand_ok(&["graph", "src", "--format", "mermaid"]);
+    assert!(graph.starts_with("graph LR\n"), "{}", graph);
+    assert!(graph.contains("[\"Vault._unused\"]:::unreachable"), "{}", graph);
+    assert!(graph.contains("[\"Vault.deposit\"]:::entry"), "{}", graph);
+}
+
+#[test]
+fn focus_keeps_the_functions_within_the_depth() {
+    let fixture = project();
+    let graph = fixture.wand_ok(&["graph", "src", "--focus", "Vault._add"]);
+    assert!(graph.contains("\"Vault.deposit\" -> \"Vault._add\";"), "{}", graph);
+    assert!(graph.contains("\"Vault._add\" -> \"Math.add\";"), "{}", graph);
+    assert!(!graph.contains("Math.sub"), "{}", graph);
+    assert!(!graph.contains("Vault._unused"), "{}", graph);
+
+    let deeper = fixture.wand_ok(&["graph", "src", "--focus", "Vault.deposit", "--depth", "2"]);
+    assert!(deeper.contains("\"Vault._add\" -> \"Math.add\";"), "{}", deeper);
+    assert!(!deeper.contains("Vault.withdraw"), "{}", deeper);
+}
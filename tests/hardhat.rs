@@ -0,0 +1,71 @@
+mod common;
+use common::{unused, Fixture};
+
+const VAULT: &str = r#"import "@openzeppelin/contracts/access/Ownable.sol";
+
+contract Vault is Ownable {
+    function sweep() external {}
+
+    function init() external {}
+
+    function rescue() external {}
+
+    function unusedVault() internal {}
+}
+"#;
+
+/// A Hardhat project whose sources are in `contracts`, called from `test` and `scripts`,
+/// with OpenZeppelin in `node_modules` and build output in `artifacts` and `cache`.
+fn hardhat() -> Fixture {
+    let fixture = Fixture::empty();
+    fixture.write("hardhat.config.js", "module.exports = { solidity: \"0.8.24\" };\n");
+    fixture.write("contracts/Vault.sol", VAULT);
+    fixture.write("test/Vault.t.sol", "contract VaultTest {\n    function testSweep(Vault v) external {\n        v.sweep();\n    }\n}\n");
+    fixture.write("scripts/Deploy.sol", "contract Deploy {\n    function deploy(Vault v) external {\n        v.init();\n    }\n}\n");
+    fixture.write(
+        "node_modules/@openzeppelin/contracts/access/Ownable.sol",
+        "contract Ownable {\n    function owner(Vault v) external {\n        v.rescue();\n    }\n}\n",
+    );
+    fixture.write("artifacts/Built.sol", "contract Built {\n    function unusedBuilt() internal {}\n}\n");
+    fixture.write("cache/Cached.sol", "contract Cached {\n    function unusedCached() internal {}\n}\n");
+    fixture
+}
+
+#[test]
+fn contracts_are_analyzed_and_searched_with_test_and_scripts() {
+    let report = hardhat().vacuum_json(&[]);
+    let files: Vec<&str> = report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["file"].as_str().unwrap())
+        .collect();
+    assert_eq!(files, ["contracts/Vault.sol"]);
+    // `node_modules` doesn't count as a caller of `rescue`.
+    assert_eq!(unused(&report, "contracts/Vault.sol"), ["rescue", "unusedVault"]);
+}
+
+#[test]
+fn scoped_imports_resolve_through_node_modules() {
+    let output = hardhat().wand_ok(&["deps", "contracts", "--format", "json"]);
+    let graph: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(graph["unresolved"], serde_json::json!([]));
+    assert_eq!(graph["edges"][0]["to"], "node_modules/@openzeppelin/contracts/access/Ownable.sol");
+}
+
+#[test]
+fn a_root_with_both_configs_says_which_layout_it_uses() {
+    let fixture = hardhat();
+    fixture.write("foundry.toml", "[profile.default]\n");
+    fixture.write("src/Other.sol", "contract Other {\n    function unusedOther() internal {}\n}\n");
+
+    let output = fixture.wand(&["vacuum", "contracts", "--format", "json"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("using the Hardhat layout (PATH is in contracts/)"), "{}", stderr);
+
+    let output = fixture.wand(&["vacuum", "--format", "json"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("using the Foundry layout"), "{}", stderr);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(unused(&report, "src/Other.sol"), ["unusedOther"]);
+}
@@ -0,0 +1,59 @@
+mod common;
+use common::Fixture;
+
+/// A project without tests, so that the default `^test` matches nothing.
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", "contract Vault {\n    function sweep() external {}\n}\n");
+    fixture
+}
+
+fn stderr(fixture: &Fixture, args: &[&str]) -> String {
+    String::from_utf8(fixture.wand(args).stderr).unwrap()
+}
+
+#[test]
+fn unmatched_cli_patterns_are_reported_as_from_the_command_line() {
+    let stderr = stderr(&project(), &["vacuum", "--ignore", "^legacy"]);
+    assert!(
+        stderr.contains("Warning: --ignore pattern \"^legacy\" from the command line matched no function."),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn the_default_pattern_is_only_checked_when_passed() {
+    let fixture = project();
+    let default = stderr(&fixture, &["vacuum"]);
+    assert!(!default.contains("matched no function"), "{}", default);
+
+    let explicit = stderr(&fixture, &["vacuum", "--ignore", "^test"]);
+    assert!(explicit.contains("--ignore pattern \"^test\" from the command line matched no function."), "{}", explicit);
+    let strict = fixture.wand(&["vacuum", "--ignore", "^test", "--strict-ignores"]);
+    assert!(!strict.status.success());
+}
+
+#[test]
+fn unmatched_config_patterns_name_their_file_and_key() {
+    let fixture = project();
+    fixture.write("wand.toml", "[check]\nrules = [\"unused-function\"]\nignore = [\n    \"^legacy\",\n]\n");
+    let stderr = stderr(&fixture, &["check"]);
+    assert!(
+        stderr.contains("Warning: ignore pattern \"^legacy\" from wand.toml:4 (check.ignore) matched no function."),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("--ignore"), "{}", stderr);
+}
+
+#[test]
+fn a_configured_default_pattern_is_checked() {
+    let fixture = project();
+    let unconfigured = stderr(&fixture, &["check"]);
+    assert!(!unconfigured.contains("matched no function"), "{}", unconfigured);
+
+    fixture.write("wand.toml", "[check]\nignore = [\"^test\"]\n");
+    let configured = stderr(&fixture, &["check"]);
+    assert!(configured.contains("ignore pattern \"^test\" from wand.toml:2 (check.ignore)"), "{}", configured);
+}
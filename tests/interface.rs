@@ -0,0 +1,137 @@
+mod common;
+use common::Fixture;
+
+use std::process::Command;
+
+const VAULT: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+contract Vault {
+    struct Position {
+        address owner;
+        uint256[] history;
+        uint128 shares;
+        string label;
+    }
+
+    event Deposited(address indexed owner, uint256 amount);
+
+    error Paused();
+
+    /// @notice The account allowed to sweep.
+    address public owner;
+    uint256 public constant MAX_FEE = 1e4;
+    mapping(address => uint256) public balances;
+    mapping(address owner => mapping(uint256 id => Position)) public positions;
+    string[] public names;
+    uint256 internal total;
+
+    /// @notice Deposits `amount` for the sender.
+    function deposit(uint256 amount) external /* returns (bool) */ payable returns (uint256) {
+        emit Deposited(msg.sender, amount);
+        return amount;
+    }
+
+    function position(address account) external view returns (Position memory) {
+        return positions[account][0];
+    }
+}
+"#;
+
+const INTERFACE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+interface IVault {
+    struct Position {
+        address owner;
+        uint256[] history;
+        uint128 shares;
+        string label;
+    }
+
+    event Deposited(address indexed owner, uint256 amount);
+
+    error Paused();
+
+    /// @notice The account allowed to sweep.
+    function owner() external view returns (address);
+
+    function MAX_FEE() external view returns (uint256);
+
+    function balances(address) external view returns (uint256);
+
+    function positions(address, uint256) external view returns (address owner, uint128 shares, string memory label);
+
+    function names(uint256) external view returns (string memory);
+
+    /// @notice Deposits `amount` for the sender.
+    function deposit(uint256 amount) external payable returns (uint256);
+
+    function position(address account) external view returns (Position memory);
+}
+"#;
+
+#[test]
+fn the_interface_lists_functions_and_getters_in_order() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    let interface = fixture.wand_ok(&["interface", "src/Vault.sol", "--contract", "Vault"]);
+    assert_eq!(interface, INTERFACE);
+}
+
+#[test]
+fn excluded_getters_are_left_out() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    let interface = fixture.wand_ok(&["interface", "src/Vault.sol", "--contract", "Vault", "--exclude", "^[A-Z_]+$"]);
+    assert!(!interface.contains("MAX_FEE"), "{}", interface);
+    assert!(interface.contains("function owner() external view returns (address);"), "{}", interface);
+}
+
+/// Removes the `internalType` of every parameter, which names the declaring contract of
+/// structs, e.g. `struct IVault.Position`.
+fn strip_internal_types(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            object.remove("internalType");
+            object.values_mut().for_each(strip_internal_types);
+        }
+        serde_json::Value::Array(array) => array.iter_mut().for_each(strip_internal_types),
+        _ => {}
+    }
+}
+
+/// The functions of the ABI `forge inspect` prints for `contract`, sorted, without their
+/// internal types.
+fn functions(fixture: &Fixture, contract: &str) -> Vec<serde_json::Value> {
+    let output = Command::new("forge")
+        .args(["inspect", contract, "abi", "--json"])
+        .current_dir(fixture.root())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let mut abi: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    strip_internal_types(&mut abi);
+    let mut functions: Vec<serde_json::Value> = abi
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry["type"] == "function")
+        .cloned()
+        .collect();
+    functions.sort_by_key(|function| function["name"].as_str().unwrap().to_string());
+    functions
+}
+
+#[test]
+fn the_interface_compiles_to_the_abi_of_the_contract() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.wand_ok(&["interface", "src/Vault.sol", "--contract", "Vault", "--output", "src/IVault.sol"]);
+    let forge = Command::new("forge").arg("--version").output();
+    if !forge.is_ok_and(|output| output.status.success()) {
+        eprintln!("skipping: forge is not available");
+        return;
+    }
+    assert_eq!(functions(&fixture, "IVault"), functions(&fixture, "Vault"));
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{unused, Fixture};
+
+/// A directory that isn't a project, with its own contracts and a vendored project.
+fn nested() -> Fixture {
+    let fixture = Fixture::empty();
+    fixture.write("contracts/A.sol", "contract A {\n    function unusedA() internal {}\n}\n");
+    fixture.write("vendor/x/foundry.toml", "");
+    fixture.write("vendor/x/src/X.sol", "contract X {\n    function unusedX() internal {}\n}\n");
+    fixture
+}
+
+#[test]
+fn an_explicit_path_is_analyzed_as_given() {
+    let fixture = nested();
+    let report = fixture.vacuum_json(&["."]);
+    assert!(report.get("projects").is_none(), "{}", report);
+    assert_eq!(unused(&report, "contracts/A.sol"), ["unusedA"]);
+    assert_eq!(unused(&report, "vendor/x/src/X.sol"), ["unusedX"]);
+}
+
+#[test]
+fn projects_are_discovered_without_a_path() {
+    let fixture = nested();
+    let report = fixture.vacuum_json(&[]);
+    let projects = report["projects"].as_array().unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0]["name"], "x");
+}
+
+#[test]
+fn project_selects_projects_under_an_explicit_path() {
+    let fixture = nested();
+    let report = fixture.vacuum_json(&["vendor", "--project", "x"]);
+    let projects = report["projects"].as_array().unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(unused(&projects[0], "X.sol"), ["unusedX"]);
+}
+
+#[test]
+fn excluded_directories_are_not_searched_for_projects() {
+    let fixture = nested();
+    fixture.write("packages/y/foundry.toml", "");
+    fixture.write("packages/y/src/Y.sol", "contract Y {\n    function unusedY() internal {}\n}\n");
+    fixture.write(".wandignore", "vendor/\n");
+    let report = fixture.vacuum_json(&[]);
+    let projects = report["projects"].as_array().unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0]["name"], "y");
+}
@@ -0,0 +1,118 @@
+mod common;
+use common::Fixture;
+
+const IVAULT: &str = r#"interface IVault {
+    /// @notice Deposits `amount`.
+    /// @param amount The amount.
+    /// @return shares The shares minted.
+    function deposit(uint256 amount) external returns (uint256 shares);
+
+    function sweep(address to) external;
+}
+"#;
+
+const VAULT: &str = r#"import {IVault} from "./IVault.sol";
+
+contract Vault is IVault {
+    /// @notice Emitted when the vault is swept.
+    event Swept(address to);
+
+    error Paused();
+
+    /// @inheritdoc IVault
+    function deposit(uint256 amount) external returns (uint256 shares) {
+        shares = amount;
+    }
+
+    /// @inheritdoc IVault
+    function sweep(address to) external {
+        emit Swept(to);
+    }
+
+    /// @notice Withdraws `amount` to `to`.
+    /// @param amount The amount.
+    function withdraw(uint256 amount, address to) public returns (uint256, bool) {
+        return (amount, to != address(0));
+    }
+
+    function testWithdraw() external {}
+
+    function _internal() internal {}
+}
+"#;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/IVault.sol", IVAULT);
+    fixture.write("src/Vault.sol", VAULT);
+    fixture
+}
+
+#[test]
+fn text_lists_each_issue() {
+    let report = project().wand_ok(&["natspec", "src"]);
+    assert_eq!(
+        report,
+        "src/IVault.sol:7 IVault.sweep: missing NatSpec\n\
+         src/Vault.sol:15 Vault.sweep: @inheritdoc IVault has no documented function to inherit from\n\
+         src/Vault.sol:21 Vault.withdraw: missing @param for to\n\
+         src/Vault.sol:21 Vault.withdraw: missing @return (0 of 2 documented)\n\
+         \n\
+         Total undocumented declarations: 3\n"
+    );
+}
+
+#[test]
+fn events_and_errors_are_audited_on_request() {
+    let report = project().wand_ok(&["natspec", "src", "--events", "--errors"]);
+    assert!(report.contains("src/Vault.sol:5 Vault.Swept: missing @param for to\n"), "{}", report);
+    assert!(report.contains("src/Vault.sol:7 Vault.Paused: missing NatSpec\n"), "{}", report);
+    assert!(report.ends_with("Total undocumented declarations: 5\n"), "{}", report);
+}
+
+#[test]
+fn json_names_the_issues() {
+    let output = project().wand_ok(&["natspec", "src", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let withdraw = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|declaration| declaration["name"] == "withdraw")
+        .unwrap();
+    assert_eq!(withdraw["file"], "src/Vault.sol");
+    assert_eq!(withdraw["contract"], "Vault");
+    assert_eq!(withdraw["kind"], "function");
+    assert_eq!(
+        withdraw["issues"],
+        serde_json::json!([
+            {"kind": "missing_params", "names": ["to"]},
+            {"kind": "missing_returns", "documented": 0, "expected": 2},
+        ])
+    );
+}
+
+#[test]
+fn check_fails_until_everything_is_documented() {
+    let fixture = project();
+    let output = fixture.wand(&["natspec", "src", "--check"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Found 3 undocumented declaration(s)."));
+
+    fixture.write(
+        "src/IVault.sol",
+        IVAULT.replace(
+            "\n    function sweep",
+            "\n    /// @notice Sweeps the vault.\n    /// @param to The recipient.\n    function sweep",
+        ),
+    );
+    fixture.write(
+        "src/Vault.sol",
+        VAULT.replace(
+            "    /// @param amount The amount.\n    function withdraw",
+            "    /// @param amount The amount.\n    /// @param to The recipient.\n    /// @return The amount.\n    /// @return Whether `to` is set.\n    function withdraw",
+        ),
+    );
+    let report = fixture.wand_ok(&["natspec", "src", "--check"]);
+    assert!(report.contains("Total undocumented declarations: 0"), "{}", report);
+}
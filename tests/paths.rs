@@ -0,0 +1,57 @@
+mod common;
+use common::{names, unused, Fixture};
+
+#[test]
+fn a_dollar_in_a_file_name_is_kept() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/$Token.sol", "contract Token {\n    function unused() internal {}\n}\n");
+    let report = fixture.vacuum_json(&["src/$Token.sol"]);
+    assert_eq!(unused(&report, "src/$Token.sol"), ["unused"]);
+}
+
+#[test]
+fn set_variables_are_expanded() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", "contract A {\n    function unused() internal {}\n}\n");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_wand"))
+        .args(["vacuum", "${WAND_SOURCES}/A.sol", "--format", "json", "--root", "$WAND_ROOT"])
+        .env("WAND_SOURCES", fixture.path("src"))
+        .env("WAND_ROOT", fixture.root())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"unused\":[\"unused\"]"));
+}
+
+#[test]
+fn an_unset_braced_variable_is_an_error() {
+    let output = Fixture::foundry().wand(&["vacuum", "${WAND_TEST_NEVER_SET}/src"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("environment variable WAND_TEST_NEVER_SET"));
+}
+
+#[test]
+fn an_uppercase_extension_is_analyzed() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Token.SOL", "contract Token {\n    function unusedToken() internal {}\n}\n");
+    fixture.write("src/Vault.sol", "contract Vault {\n    function unusedVault() internal {}\n}\n");
+
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Token.SOL"), ["unusedToken"]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["unusedVault"]);
+
+    let output = fixture.wand(&["vacuum", "src/Token.SOL", "--format", "json"]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("does not have a .sol extension"));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(unused(&report, "src/Token.SOL"), ["unusedToken"]);
+}
+
+#[test]
+fn an_uppercase_extension_is_searched_for_usages() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", "contract Vault {\n    function sweep() external {}\n}\n");
+    fixture.write("test/Vault.t.SOL", "contract VaultTest {\n    function testSweep(Vault v) external {\n        v.sweep();\n    }\n}\n");
+    let report = fixture.vacuum_json(&["src"]);
+    assert_eq!(names(&report, "src/Vault.sol", "functions"), ["sweep"]);
+    assert!(unused(&report, "src/Vault.sol").is_empty(), "{}", report);
+}
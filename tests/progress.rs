@@ -0,0 +1,38 @@
+mod common;
+use common::Fixture;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    for i in 0..8 {
+        fixture.write(
+            &format!("src/C{}.sol", i),
+            format!("contract C{i} {{\n    function used{i}() internal {{}}\n    function testRun() external {{ used{i}(); }}\n    function unused{i}() internal {{}}\n}}\n", i = i),
+        );
+    }
+    fixture
+}
+
+#[test]
+fn the_report_is_the_same_with_any_number_of_threads() {
+    let fixture = project();
+    let default = fixture.wand_ok(&["vacuum", "--format", "json"]);
+    assert!(default.contains("unused7"));
+    for threads in ["1", "3"] {
+        assert_eq!(fixture.wand_ok(&["vacuum", "--format", "json", "--threads", threads]), default);
+    }
+}
+
+#[test]
+fn zero_threads_is_refused() {
+    let output = project().wand(&["vacuum", "--threads", "0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--threads"));
+}
+
+#[test]
+fn no_progress_is_drawn_when_stderr_is_not_a_terminal() {
+    let output = project().wand(&["vacuum", "--threads", "2"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unused0"));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
@@ -0,0 +1,25 @@
+mod common;
+use common::{names, unused, Fixture};
+
+const PROXY: &str = "contract Proxy {\n    fallback() external payable {\n        (bool ok, ) = impl().delegatecall(msg.data);\n    }\n}\n";
+
+const VAULT: &str = "contract Vault is Initializable {\n    function initialize() external initializer {}\n\n    function sweep() external {}\n}\n";
+
+#[test]
+fn a_project_proxy_keeps_upgradeable_contracts() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Proxy.sol", PROXY);
+    fixture.write("src/Vault.sol", VAULT);
+    let report = fixture.vacuum_json(&[]);
+    assert!(unused(&report, "src/Vault.sol").is_empty(), "{}", report);
+    assert_eq!(names(&report, "src/Vault.sol", "proxy_reachable"), ["initialize", "sweep"]);
+}
+
+#[test]
+fn a_vendored_proxy_keeps_nothing() {
+    let fixture = Fixture::foundry();
+    fixture.write("lib/openzeppelin-contracts/contracts/proxy/Proxy.sol", PROXY);
+    fixture.write("src/Vault.sol", VAULT);
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["initialize", "sweep"]);
+}
@@ -0,0 +1,67 @@
+mod common;
+use common::Fixture;
+
+#[test]
+fn only_the_targeted_overload_is_renamed() {
+    let fixture = Fixture::foundry();
+    fixture.write(
+        "src/A.sol",
+        "contract A {\n    function f(uint256 a) public {}\n    function f(uint256 a, uint256 b) public {}\n\n    function g() public {\n        f(1);\n        f(1, 2);\n    }\n}\n",
+    );
+
+    fixture.wand_ok(&["rename", "f", "h", "--signature", "f(uint256)", "--write"]);
+    assert_eq!(
+        fixture.read("src/A.sol"),
+        "contract A {\n    function h(uint256 a) public {}\n    function f(uint256 a, uint256 b) public {}\n\n    function g() public {\n        h(1);\n        f(1, 2);\n    }\n}\n"
+    );
+}
+
+#[test]
+fn overrides_are_renamed_across_the_inheritance_chain() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/I.sol", "interface I {\n    function f() external;\n}\n");
+    fixture.write(
+        "src/B.sol",
+        "import {I} from \"./I.sol\";\n\ncontract B is I {\n    function f() public virtual override {}\n}\n",
+    );
+    fixture.write(
+        "src/C.sol",
+        "import {B} from \"./B.sol\";\n\ncontract C is B {\n    function f() public override {\n        super.f();\n    }\n}\n",
+    );
+    fixture.write(
+        "src/User.sol",
+        "import {I} from \"./I.sol\";\n\ncontract User {\n    function g(I i) external {\n        i.f();\n    }\n}\n",
+    );
+
+    fixture.wand_ok(&["rename", "f", "h", "--write"]);
+    assert_eq!(fixture.read("src/I.sol"), "interface I {\n    function h() external;\n}\n");
+    assert!(fixture.read("src/B.sol").contains("function h() public virtual override {}"));
+    assert!(fixture.read("src/C.sol").contains("function h() public override {\n        super.h();"));
+    assert!(fixture.read("src/User.sol").contains("i.h();"));
+}
+
+#[test]
+fn the_diff_is_printed_without_writing() {
+    let fixture = Fixture::foundry();
+    let source = "contract A {\n    function f() public {}\n\n    function g() public {\n        f();\n    }\n}\n";
+    fixture.write("src/A.sol", source);
+
+    let diff = fixture.wand_ok(&["rename", "f", "h"]);
+    assert!(diff.contains("-    function f() public {}\n+    function h() public {}"), "{}", diff);
+    assert_eq!(fixture.read("src/A.sol"), source);
+}
+
+#[test]
+fn a_colliding_name_is_refused() {
+    let fixture = Fixture::foundry();
+    let base = "contract Base {\n    function h() public {}\n}\n";
+    let derived = "import {Base} from \"./Base.sol\";\n\ncontract A is Base {\n    function f() public {}\n}\n";
+    fixture.write("src/Base.sol", base);
+    fixture.write("src/A.sol", derived);
+
+    let output = fixture.wand(&["rename", "f", "h", "--write"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`h` is already used at src/Base.sol:2"));
+    assert_eq!(fixture.read("src/Base.sol"), base);
+    assert_eq!(fixture.read("src/A.sol"), derived);
+}
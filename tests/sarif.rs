@@ -0,0 +1,84 @@
+mod common;
+use common::Fixture;
+
+use std::process::Command;
+
+const SOURCE: &str = "contract A {\n    function testRun() external {}\n\n    function unused() internal {}\n}\n";
+
+fn sarif(fixture: &Fixture, args: &[&str]) -> serde_json::Value {
+    let mut all = vec!["vacuum", "--format", "sarif"];
+    all.extend_from_slice(args);
+    let output = fixture.wand(&all);
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|e| panic!("wand {:?} printed no SARIF ({})", all, e))
+}
+
+/// The location of the first result, and the `SRCROOT` base it's relative to.
+fn location(report: &serde_json::Value) -> (&serde_json::Value, &str) {
+    let run = &report["runs"][0];
+    let location = &run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"];
+    (location, run["originalUriBaseIds"]["SRCROOT"]["uri"].as_str().unwrap())
+}
+
+#[test]
+fn uris_are_relative_to_the_repository_root() {
+    let fixture = Fixture::empty();
+    fixture.write("packages/core/foundry.toml", "[profile.default]\n");
+    fixture.write("packages/core/src/nested/A.sol", SOURCE);
+    let git = Command::new("git").args(["init", "-q"]).current_dir(fixture.root()).status();
+    if !git.is_ok_and(|status| status.success()) {
+        eprintln!("skipping: git is not available");
+        return;
+    }
+    let report = sarif(&fixture, &["--root", "packages/core"]);
+    let (location, base) = location(&report);
+    assert_eq!(location["uri"], "packages/core/src/nested/A.sol", "{}", report);
+    assert_eq!(location["uriBaseId"], "SRCROOT");
+    assert!(base.starts_with("file://") && base.ends_with('/'), "{}", base);
+    assert!(!location["uri"].as_str().unwrap().contains('\\'));
+}
+
+#[test]
+fn uris_are_relative_to_the_given_directory() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/nested/A.sol", SOURCE);
+    let report = sarif(&fixture, &["--relative-to", "src"]);
+    let (location, base) = location(&report);
+    assert_eq!(location["uri"], "nested/A.sol", "{}", report);
+    assert_eq!(location["uriBaseId"], "SRCROOT");
+    assert!(base.ends_with("/src/"), "{}", base);
+}
+
+#[test]
+fn fingerprints_stay_when_the_function_moves_to_another_file() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    let before = sarif(&fixture, &[]);
+    fixture.write("src/A.sol", "import \"./legacy/A.sol\";\n");
+    fixture.write("src/legacy/A.sol", SOURCE.replace("\n\n", "\n\n    // Moved here.\n"));
+    let after = sarif(&fixture, &[]);
+
+    let fingerprint = |report: &serde_json::Value| {
+        report["runs"][0]["results"][0]["partialFingerprints"]["wand/v1"].clone()
+    };
+    assert!(fingerprint(&before).is_string(), "{}", before);
+    assert_eq!(fingerprint(&before), fingerprint(&after));
+    assert_eq!(location(&after).0["uri"], "src/legacy/A.sol", "{}", after);
+}
+
+#[test]
+fn the_run_names_the_version_and_invocation() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    let report = sarif(&fixture, &[]);
+    let run = &report["runs"][0];
+    assert_eq!(run["tool"]["driver"]["version"], env!("CARGO_PKG_VERSION"));
+
+    let invocations = run["invocations"].as_array().unwrap();
+    assert_eq!(invocations.len(), 1, "{}", report);
+    let invocation = &invocations[0];
+    assert_eq!(invocation["arguments"], serde_json::json!(["vacuum", "--format", "sarif", "--no-pager"]));
+    assert!(invocation["commandLine"].as_str().unwrap().ends_with("wand vacuum --format sarif --no-pager"));
+    assert_eq!(invocation["executionSuccessful"], true);
+    let working_directory = invocation["workingDirectory"]["uri"].as_str().unwrap();
+    assert!(working_directory.starts_with("file://") && working_directory.ends_with('/'), "{}", working_directory);
+}
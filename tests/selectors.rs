@@ -0,0 +1,44 @@
+mod common;
+
+use common::Fixture;
+
+/// The signatures `wand selectors` computes for the functions of `source`.
+fn signatures(source: &str) -> Vec<String> {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", source);
+    let output = fixture.wand_ok(&["selectors", "src", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    report["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|function| function["signature"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn comments_in_struct_fields_are_not_types() {
+    let source = "contract A {
+    struct Order {
+        // fee in bps
+        uint16 fee;
+        /* the recipient */ address to; // of the fill
+    }
+    function fill(Order calldata order) external {}
+}
+";
+    assert_eq!(signatures(source), ["fill((uint16,address))"]);
+}
+
+#[test]
+fn comments_in_parameters_are_not_types() {
+    let source = "contract A {
+    function g(/* amount */ uint256 a) external {}
+    function h(
+        address to, // the recipient
+        uint256 amount /* in wei */
+    ) external returns (/* shares */ uint256) {}
+}
+";
+    assert_eq!(signatures(source), ["g(uint256)", "h(address,uint256)"]);
+}
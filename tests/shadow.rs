@@ -0,0 +1,78 @@
+mod common;
+use common::Fixture;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Base.sol", "contract Base {\n    uint256 public owner;\n}\n");
+    fixture.write(
+        "src/Vault.sol",
+        "import {Base} from \"./Base.sol\";\n\ncontract Vault is Base {\n    uint256 total;\n\n    function deposit(uint256 owner) external {\n        uint256 total = owner;\n    }\n}\n",
+    );
+    fixture
+}
+
+#[test]
+fn text_reports_the_default_severities() {
+    let report = project().wand_ok(&["shadow", "src"]);
+    assert!(report.contains("[error] src/Vault.sol:7 Local `total` in Vault.deposit shadows state variable Vault.total (src/Vault.sol:4)"), "{}", report);
+    assert!(report.contains("[warning] src/Vault.sol:6 Parameter `owner` of Vault.deposit shadows the getter"), "{}", report);
+}
+
+#[test]
+fn json_reports_the_default_severities() {
+    let output = project().wand_ok(&["shadow", "src", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let severities: Vec<(&str, &str)> = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|shadowing| (shadowing["kind"].as_str().unwrap(), shadowing["severity"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        severities,
+        [("local_shadows_state", "error"), ("param_shadows_inherited_getter", "warning")]
+    );
+}
+
+#[test]
+fn the_severity_policy_applies() {
+    let fixture = project();
+    fixture.write("wand.toml", "[severity]\nshadowing = \"note\"\n");
+    let report = fixture.wand_ok(&["shadow", "src"]);
+    assert!(report.contains("[note] src/Vault.sol:7"), "{}", report);
+    let annotations = fixture.wand_ok(&["shadow", "src", "--format", "github"]);
+    assert!(annotations.contains("::notice file=src/Vault.sol,line=7,title=Shadowed declaration::Local `total`"), "{}", annotations);
+    assert!(annotations.contains("::warning file=src/Vault.sol,line=6,title=Shadowed getter::Parameter `owner`"), "{}", annotations);
+}
+
+#[test]
+fn sarif_names_the_rules() {
+    let output = project().wand_ok(&["shadow", "src", "--format", "sarif"]);
+    let log: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let rules: Vec<&str> = log["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["ruleId"].as_str().unwrap())
+        .collect();
+    assert_eq!(rules, ["shadowed-declaration", "shadowed-getter"]);
+}
+
+#[test]
+fn jsonl_writes_one_record_per_finding() {
+    let output = project().wand_ok(&["shadow", "src", "--format", "jsonl"]);
+    let records: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let findings: Vec<&str> = records
+        .iter()
+        .filter(|record| record["type"] == "finding")
+        .map(|record| record["severity"].as_str().unwrap())
+        .collect();
+    assert_eq!(findings, ["error", "warning"]);
+}
+
+#[test]
+fn check_fails_on_any_shadowing() {
+    let output = project().wand(&["shadow", "src", "--check", "--format", "junit"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("<failure type=\"error\""));
+}
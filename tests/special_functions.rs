@@ -0,0 +1,74 @@
+mod common;
+use common::{names, unused, Fixture};
+
+const SOURCE: &str = "contract Modern {
+    receive() external payable {}
+
+    fallback() external payable {}
+
+    function receiveTokens() external {}
+}
+
+contract Legacy {
+    function receive() external payable {}
+
+    function fallback() external payable {}
+
+    function () external payable {}
+}
+";
+
+#[test]
+fn receive_and_fallback_are_never_reported() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(names(&report, "src/A.sol", "functions"), ["receiveTokens"]);
+    assert_eq!(unused(&report, "src/A.sol"), ["receiveTokens"]);
+}
+
+#[test]
+fn receive_and_fallback_are_never_deleted() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", SOURCE);
+    fixture.wand_ok(&["vacuum", "--delete"]);
+    let content = fixture.read("src/A.sol");
+    assert_eq!(content, SOURCE.replace("    function receiveTokens() external {}\n", ""));
+}
+
+const CONSTRUCTORS: &str = "contract Modern {
+    uint256 x;
+
+    constructor(uint256 x_) {
+        x = x_;
+        init();
+    }
+
+    function init() internal {}
+}
+
+contract Legacy {
+    function constructor() public {}
+
+    function Legacy() public {}
+}
+";
+
+#[test]
+fn constructors_are_never_reported_nor_counted() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", CONSTRUCTORS);
+    let report = fixture.vacuum_json(&[]);
+    // Old-style constructors, named after their contract, too.
+    assert_eq!(names(&report, "src/A.sol", "functions"), ["init"]);
+    // The constructor's call keeps `init` used.
+    assert!(unused(&report, "src/A.sol").is_empty(), "{}", report);
+}
+
+#[test]
+fn constructors_are_never_deleted() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/A.sol", CONSTRUCTORS);
+    fixture.wand_ok(&["vacuum", "--delete"]);
+    assert_eq!(fixture.read("src/A.sol"), CONSTRUCTORS);
+}
@@ -0,0 +1,114 @@
+mod common;
+use common::Fixture;
+
+const VAULT: &str = r#"import {Math} from "./Math.sol";
+
+/// @title A vault
+contract Vault {
+    uint256 public total;
+
+    function deposit(uint256 amount) external {
+        total = Math.add(total, amount);
+    }
+
+    function withdraw(uint256 amount) external returns (uint256) {
+        // Reverts on underflow.
+        total = Math.sub(total, amount);
+        return total;
+    }
+}
+"#;
+
+const MATH: &str = r#"library Math {
+    function add(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a + b;
+    }
+
+    function sub(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a - b;
+    }
+}
+"#;
+
+const BASE: &str = "abstract contract Base {\n    function _hook() internal virtual;\n\n    function _check() private pure {}\n}\n";
+
+const IVAULT: &str = "interface IVault {\n    event Deposited(uint256 amount);\n\n    error Paused();\n\n    function deposit(uint256 amount) external;\n}\n";
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("src/Math.sol", MATH);
+    fixture.write("src/Base.sol", BASE);
+    fixture.write("src/interfaces/IVault.sol", IVAULT);
+    fixture
+}
+
+fn stats_json(fixture: &Fixture, args: &[&str]) -> serde_json::Value {
+    let mut all = vec!["stats", "src", "--format", "json"];
+    all.extend_from_slice(args);
+    serde_json::from_str(&fixture.wand_ok(&all)).unwrap()
+}
+
+#[test]
+fn totals_count_declarations_and_lines() {
+    let report = stats_json(&project(), &[]);
+    let total = &report["total"];
+    assert_eq!(total["files"], 4);
+    assert_eq!(
+        [&total["contracts"], &total["abstract_contracts"], &total["interfaces"], &total["libraries"]],
+        [1, 1, 1, 1]
+    );
+    assert_eq!(total["functions"], serde_json::json!({"external": 3, "public": 0, "internal": 3, "private": 1}));
+    assert_eq!([&total["events"], &total["errors"]], [1, 1]);
+    assert_eq!(total["lines"], 37);
+    assert_eq!([&total["code_lines"], &total["comment_lines"], &total["blank_lines"]], [28, 2, 7]);
+    // The body-less `_hook` and `IVault.deposit` have no length.
+    assert_eq!(total["implemented_functions"], 5);
+}
+
+#[test]
+fn the_largest_functions_and_contracts_are_listed() {
+    let report = stats_json(&project(), &["--top", "2"]);
+    let largest: Vec<(&str, &str, u64, u64)> = report["largest_functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|function| {
+            (
+                function["name"].as_str().unwrap(),
+                function["file"].as_str().unwrap(),
+                function["line"].as_u64().unwrap(),
+                function["lines"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(largest, [("Vault.withdraw", "src/Vault.sol", 11, 5), ("Math.add", "src/Math.sol", 2, 3)]);
+    assert_eq!(report["largest_contracts"][0]["name"], "Vault");
+    assert_eq!(report["largest_contracts"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn by_dir_breaks_the_metrics_down() {
+    let report = stats_json(&project(), &["--by-dir"]);
+    let directories: Vec<(&str, u64)> = report["directories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|directory| (directory["directory"].as_str().unwrap(), directory["files"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(directories, [("src", 3), ("src/interfaces", 1)]);
+    assert_eq!(report["directories"][1]["interfaces"], 1);
+}
+
+#[test]
+fn text_lists_the_metrics() {
+    let report = project().wand_ok(&["stats", "src", "--top", "1"]);
+    assert!(report.starts_with("\nStatistics for src:\n  Files                             4\n"), "{}", report);
+    let private: Vec<Vec<&str>> = report
+        .lines()
+        .filter(|line| line.trim_start().starts_with("private"))
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(private, [["private", "1"]], "{}", report);
+    assert!(report.contains("Largest functions:\n      5 lines  Vault.withdraw (src/Vault.sol:11)\n"), "{}", report);
+}
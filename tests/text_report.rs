@@ -0,0 +1,71 @@
+mod common;
+use common::Fixture;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write(
+        "src/Clean.sol",
+        "contract Clean {\n    function testRun() external { used(); }\n\n    function used() internal {}\n}\n",
+    );
+    fixture.write("src/Dirty.sol", "contract Dirty {\n    function unused() internal {}\n}\n");
+    fixture
+}
+
+#[test]
+fn clean_files_are_only_counted() {
+    let report = project().wand_ok(&["vacuum", "--no-legend"]);
+    assert!(report.contains("Function Usage Report for \"src/Dirty.sol\""), "{}", report);
+    assert!(!report.contains("src/Clean.sol"), "{}", report);
+    assert!(report.contains("Files without findings, not shown (see --verbose): 1"), "{}", report);
+}
+
+#[test]
+fn verbose_prints_clean_files_too() {
+    let report = project().wand_ok(&["vacuum", "--no-legend", "--verbose"]);
+    assert!(report.contains("src/Dirty.sol"), "{}", report);
+    assert!(report.contains("No unused functions found in \"src/Clean.sol\"."), "{}", report);
+    assert!(!report.contains("not shown"), "{}", report);
+}
+
+#[test]
+fn nothing_is_counted_as_hidden_when_every_file_has_findings() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Dirty.sol", "contract Dirty {\n    function unused() internal {}\n}\n");
+    let report = fixture.wand_ok(&["vacuum"]);
+    assert!(!report.contains("not shown"), "{}", report);
+}
+
+#[test]
+fn the_removed_option_is_refused() {
+    let output = project().wand(&["vacuum", "--report-summary-only-on-findings"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn colors_follow_the_color_mode() {
+    let fixture = project();
+    for args in [&["vacuum", "--color", "always"][..], &["--color", "always", "vacuum"]] {
+        let report = fixture.wand_ok(args);
+        assert!(report.contains("\x1b["), "wand {:?}:\n{}", args, report);
+        assert!(report.contains("Occurrences are colored "), "wand {:?}:\n{}", args, report);
+    }
+    // The tests' stdout is a pipe, so `auto` leaves the colors out like `never`.
+    for args in [&["vacuum", "--color", "never"][..], &["vacuum", "--color", "auto"], &["vacuum"]] {
+        let report = fixture.wand_ok(args);
+        assert!(!report.contains('\x1b'), "wand {:?}:\n{}", args, report);
+        assert!(!report.contains("Occurrences are colored"), "wand {:?}:\n{}", args, report);
+        assert!(report.contains("Function Usage Report for \"src/Dirty.sol\""), "wand {:?}:\n{}", args, report);
+    }
+}
+
+#[test]
+fn pretty_leaves_text_and_jsonl_alone() {
+    let fixture = project();
+    assert_eq!(fixture.wand_ok(&["vacuum", "--pretty"]), fixture.wand_ok(&["vacuum"]));
+
+    let jsonl = fixture.wand_ok(&["vacuum", "--format", "jsonl", "--pretty"]);
+    assert!(jsonl.lines().count() > 1, "{}", jsonl);
+    for line in jsonl.lines() {
+        assert!(serde_json::from_str::<serde_json::Value>(line).is_ok(), "{}", jsonl);
+    }
+}
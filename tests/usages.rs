@@ -0,0 +1,87 @@
+mod common;
+use common::Fixture;
+
+const MATH: &str = r#"library Math {
+    function add(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a + b;
+    }
+
+    function add(uint256 a, uint256 b, uint256 c) internal pure returns (uint256) {
+        return add(add(a, b), c);
+    }
+}
+"#;
+
+const VAULT: &str = r#"import {Math} from "./Math.sol";
+
+contract Vault {
+    uint256 total;
+
+    function deposit(uint256 amount) external {
+        total = Math.add(total, amount);
+    }
+}
+"#;
+
+const TEST: &str = r#"import {Math} from "../src/Math.sol";
+
+contract MathTest {
+    function testAdd() external pure {
+        Math.add(1, 2);
+        // Math.add(3, 4);
+    }
+}
+"#;
+
+fn project() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Math.sol", MATH);
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("test/Math.t.sol", TEST);
+    fixture
+}
+
+#[test]
+fn text_lists_the_references_by_file() {
+    let report = project().wand_ok(&["usages", "add", "--signature", "add(uint256,uint256)"]);
+    assert!(report.starts_with("Declarations of `add`:\n  src/Math.sol:2 Math.add(uint256,uint256)\n"), "{}", report);
+    assert!(!report.contains("Math.sol:6 Math.add"), "{}", report);
+    assert!(report.contains("src/Vault.sol:\n  7 [call] total = Math.add(total, amount);\n"), "{}", report);
+    assert!(
+        report.contains("test/Math.t.sol:\n  5 [test] Math.add(1, 2);\n  6 [comment, not counted] // Math.add(3, 4);\n"),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn json_lists_the_declarations_and_counted_references() {
+    let output = project().wand_ok(&["usages", "add", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["name"], "add");
+    let signatures: Vec<&str> = report["declarations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|declaration| declaration["signature"].as_str().unwrap())
+        .collect();
+    assert_eq!(signatures, ["add(uint256,uint256)", "add(uint256,uint256,uint256)"]);
+    let comment = report["references"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|reference| reference["kind"] == "comment")
+        .unwrap();
+    assert_eq!(comment["file"], "test/Math.t.sol");
+    assert_eq!(comment["counts"], false);
+    let counted = report["references"].as_array().unwrap().iter().filter(|reference| reference["counts"] == true);
+    assert_eq!(report["counted"], counted.count());
+}
+
+#[test]
+fn an_undeclared_function_fails() {
+    let output = project().wand(&["usages", "missing"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No function `missing` is declared in"), "{}", stderr);
+}
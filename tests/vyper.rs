@@ -0,0 +1,53 @@
+mod common;
+use common::{unused, Fixture};
+
+const VAULT: &str = "contract Vault {\n    function sweep(address to) external {}\n\n    function rescue() external {}\n}\n";
+
+const KEEPER: &str = "# @version ^0.3.10\ninterface IVault:\n    def sweep(to: address): nonpayable\n\nvault: IVault\n\n@external\ndef run(to: address):\n    extcall self.vault.sweep(to)\n    # extcall self.vault.rescue()\n";
+
+/// A Solidity vault and a Vyper keeper calling one of its functions.
+fn mixed() -> Fixture {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("vyper/Keeper.vy", KEEPER);
+    fixture
+}
+
+#[test]
+fn a_function_called_from_vyper_is_used() {
+    let report = mixed().vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["rescue"]);
+}
+
+#[test]
+fn vyper_files_under_dependencies_are_skipped() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("lib/keeper/Keeper.vy", KEEPER);
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["sweep", "rescue"]);
+}
+
+#[test]
+fn excluded_vyper_files_are_not_searched() {
+    let fixture = mixed();
+    let report = fixture.vacuum_json(&["--no-match-path", "vyper"]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["sweep", "rescue"]);
+
+    fixture.write(".wandignore", "*.vy\n");
+    let report = fixture.vacuum_json(&[]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["sweep", "rescue"]);
+}
+
+#[test]
+fn excluded_text_files_are_not_searched() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/Vault.sol", VAULT);
+    fixture.write("ops/keeper.yaml", "tasks:\n  - call: sweep\n");
+    let report = fixture.vacuum_json(&["--search-ext", "yaml"]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["rescue"]);
+
+    fixture.write(".wandignore", "ops/\n");
+    let report = fixture.vacuum_json(&["--search-ext", "yaml"]);
+    assert_eq!(unused(&report, "src/Vault.sol"), ["sweep", "rescue"]);
+}
@@ -0,0 +1,110 @@
+mod common;
+use common::{names, unused, Fixture};
+
+/// The rounding division of Solady's `FixedPointMathLib`, with a helper that outlived its caller.
+const MATH: &str = r#"library FixedPointMathLib {
+    function mulDivUp(uint256 x, uint256 y, uint256 d) internal pure returns (uint256 z) {
+        assembly {
+            function div_up(a, b) -> c {
+                c := add(iszero(iszero(mod(a, b))), div(a, b))
+            }
+            function round_up(a, b) -> c {
+                c := mul(div(add(a, sub(b, 1)), b), b)
+            }
+            if iszero(mul(d, iszero(mul(y, gt(x, div(not(0), y)))))) {
+                mstore(0x00, 0xad251c27)
+                revert(0x1c, 0x04)
+            }
+            z := div_up(mul(x, y), d)
+        }
+    }
+
+    function testMulDivUp() external pure returns (uint256) {
+        return mulDivUp(3, 5, 2);
+    }
+}
+"#;
+
+/// An EIP-1967 proxy forwarding calls to its implementation, whose reading and writing of
+/// the slot each define their own `slot()`.
+const PROXY: &str = r#"contract ERC1967Proxy {
+    function _implementation() internal view returns (address impl) {
+        assembly {
+            function slot() -> s {
+                s := 0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc
+            }
+            impl := sload(slot())
+        }
+    }
+
+    function _setImplementation(address impl) internal {
+        assembly {
+            function slot() -> s {
+                s := 0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc
+            }
+            function bubble() {
+                returndatacopy(0, 0, returndatasize())
+                revert(0, returndatasize())
+            }
+            sstore(0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc, impl)
+        }
+    }
+
+    fallback() external payable {
+        address impl = _implementation();
+        assembly {
+            function forward(target) {
+                calldatacopy(0, 0, calldatasize())
+                let ok := delegatecall(gas(), target, 0, calldatasize(), 0, 0)
+                returndatacopy(0, 0, returndatasize())
+                switch ok
+                case 0 { revert(0, returndatasize()) }
+                default { return(0, returndatasize()) }
+            }
+            forward(impl)
+        }
+    }
+
+    function testUpgrade(address impl) external {
+        _setImplementation(impl);
+    }
+}
+"#;
+
+#[test]
+fn unused_yul_helpers_of_math_assembly_are_reported() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/FixedPointMathLib.sol", MATH);
+    let report = fixture.vacuum_json(&[]);
+    let file = "src/FixedPointMathLib.sol";
+    assert_eq!(names(&report, file, "functions"), ["mulDivUp", "div_up", "round_up"]);
+    assert_eq!(unused(&report, file), ["round_up"]);
+}
+
+#[test]
+fn yul_helpers_are_counted_in_their_own_block() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/ERC1967Proxy.sol", PROXY);
+    let report = fixture.vacuum_json(&[]);
+    let file = "src/ERC1967Proxy.sol";
+    assert_eq!(
+        names(&report, file, "functions"),
+        ["_implementation", "slot", "_setImplementation", "slot", "bubble", "forward"]
+    );
+    assert_eq!(unused(&report, file), ["slot", "bubble"]);
+}
+
+#[test]
+fn deleting_yul_helpers_keeps_the_assembly_block() {
+    let fixture = Fixture::foundry();
+    fixture.write("src/FixedPointMathLib.sol", MATH);
+    fixture.write("src/ERC1967Proxy.sol", PROXY);
+    fixture.wand_ok(&["vacuum", "--delete"]);
+
+    let round_up = "            function round_up(a, b) -> c {\n                c := mul(div(add(a, sub(b, 1)), b), b)\n            }\n";
+    assert_eq!(fixture.read("src/FixedPointMathLib.sol"), MATH.replace(round_up, ""));
+
+    let unused_slot = "            function slot() -> s {\n                s := 0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc\n            }\n            function bubble() {\n                returndatacopy(0, 0, returndatasize())\n                revert(0, returndatasize())\n            }\n            sstore";
+    let proxy = fixture.read("src/ERC1967Proxy.sol");
+    assert_eq!(proxy, PROXY.replace(unused_slot, "            sstore"), "{}", proxy);
+}